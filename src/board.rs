@@ -2,7 +2,10 @@
 
 use core::{fmt, fmt::Write, iter::FusedIterator};
 
-use crate::{attacks, Bitboard, ByColor, ByRole, Color, File, Piece, Rank, Role, Square};
+use crate::{
+    attacks, chess960, material::MaterialKey, Bitboard, ByColor, ByRole, Color, File, Piece, Rank,
+    Role, Square,
+};
 
 /// [`Piece`] positions on a board.
 ///
@@ -315,6 +318,11 @@ impl Board {
         ByColor::new_with(|color| self.material_side(color))
     }
 
+    /// Gets the material signature of the board, like `KQPvKR`.
+    pub fn material_key(&self) -> MaterialKey {
+        MaterialKey::new(self.material()).expect("at most 64 pieces")
+    }
+
     fn transform<F>(&mut self, f: F)
     where
         F: Fn(Bitboard) -> Bitboard,
@@ -385,6 +393,118 @@ impl Board {
         self
     }
 
+    /// Creates the board of [Chess960](crate::chess960) starting position
+    /// `n`. Returns `None` if `n >= 960`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use shakmaty::{chess960, Board};
+    ///
+    /// assert_eq!(Board::chess960(chess960::STANDARD), Some(Board::new()));
+    /// ```
+    pub fn chess960(n: u32) -> Option<Board> {
+        Board::double_chess960(ByColor { white: n, black: n })
+    }
+
+    /// Creates the board of a Double Fischer Random starting position, where
+    /// each side has an independently numbered [Chess960](crate::chess960)
+    /// back rank. Returns `None` if any number is `>= 960`.
+    pub fn double_chess960(numbers: ByColor<u32>) -> Option<Board> {
+        let mut board = Board::empty();
+        for color in Color::ALL {
+            let back_rank = chess960::back_rank(*numbers.get(color))?;
+            for (file, role) in File::ALL.into_iter().zip(back_rank) {
+                board.set_piece_at(Square::from_coords(file, color.backrank()), role.of(color));
+                board.set_piece_at(
+                    Square::from_coords(file, color.relative_rank(Rank::Second)),
+                    color.pawn(),
+                );
+            }
+        }
+        Some(board)
+    }
+
+    /// Identifies the board as [Chess960](crate::chess960) starting position
+    /// number `n`. Returns `None` if this is not the starting position of a
+    /// Chess960 game, in particular if the back ranks of both sides are not
+    /// symmetrical.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use shakmaty::Board;
+    ///
+    /// let board: Board = "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR".parse()?;
+    /// assert_eq!(board.chess960_number(), Some(0));
+    /// # Ok::<_, shakmaty::fen::ParseFenError>(())
+    /// ```
+    pub fn chess960_number(&self) -> Option<u32> {
+        self.double_chess960_numbers()
+            .filter(|numbers| numbers.white == numbers.black)
+            .map(|numbers| numbers.white)
+    }
+
+    /// Identifies the board as a Double Fischer Random starting position,
+    /// returning the [Chess960](crate::chess960) number of each side. Returns
+    /// `None` if this is not the starting position of such a game.
+    pub fn double_chess960_numbers(&self) -> Option<ByColor<u32>> {
+        let number = |color: Color| {
+            chess960::number(&File::ALL.map(|file| {
+                self.role_at(Square::from_coords(file, color.backrank()))
+                    .unwrap_or(Role::Pawn)
+            }))
+        };
+        let numbers = ByColor {
+            white: number(Color::White)?,
+            black: number(Color::Black)?,
+        };
+        (Board::double_chess960(numbers).as_ref() == Some(self)).then_some(numbers)
+    }
+
+    /// Renders the board as a text diagram. See [`Diagram`] for options.
+    pub const fn diagram(&self) -> Diagram<'_> {
+        Diagram::new(self)
+    }
+
+    /// Parses a text diagram, as rendered by [`Board::diagram()`].
+    ///
+    /// Each row lists the squares of a rank, using FEN piece letters or
+    /// Unicode chess symbols, and `.`, `-` or `·` for empty squares.
+    /// Whitespace, highlight brackets, and a final `white to move` or
+    /// `black to move` line are ignored. The orientation is determined from
+    /// the rank and file labels, if any, and is otherwise assumed to be
+    /// from white's point of view.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseDiagramError`] if the diagram does not have exactly
+    /// 8 rows of 8 squares, or if the labels are inconsistent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use shakmaty::{Board, Color::White, Square};
+    ///
+    /// let board = Board::from_diagram("
+    ///     8 . . . . k . . .
+    ///     7 - - - - - - - -
+    ///     6 . . . . . . . .
+    ///     5 . . . . . . . .
+    ///     4 . . . . . . . .
+    ///     3 . . . . . . . .
+    ///     2 . . . . P . . .
+    ///     1 . . . . K . . .
+    ///       a b c d e f g h
+    /// ")?;
+    ///
+    /// assert_eq!(board.piece_at(Square::E2), Some(White.pawn()));
+    /// # Ok::<_, shakmaty::board::ParseDiagramError>(())
+    /// ```
+    pub fn from_diagram(diagram: &str) -> Result<Board, ParseDiagramError> {
+        parse_diagram(diagram).map(|(board, _)| board)
+    }
+
     pub fn pop_front(&mut self) -> Option<(Square, Piece)> {
         self.occupied
             .first()
@@ -406,18 +526,318 @@ impl Default for Board {
 
 impl fmt::Debug for Board {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for rank in Rank::ALL.into_iter().rev() {
-            for file in File::ALL {
+        fmt::Display::fmt(&self.diagram(), f)
+    }
+}
+
+/// Glyphs used to render a [`Diagram`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Glyphs {
+    /// Piece letters like in FEN, e.g. `K` and `p`, and `.` for empty
+    /// squares.
+    #[default]
+    Ascii,
+    /// Chess symbols, e.g. `♔` and `♟`, and `·` for empty squares.
+    Unicode,
+}
+
+impl Glyphs {
+    const fn piece_char(self, piece: Piece) -> char {
+        match self {
+            Glyphs::Ascii => match piece.color {
+                Color::White => piece.role.upper_char(),
+                Color::Black => piece.role.char(),
+            },
+            Glyphs::Unicode => match (piece.color, piece.role) {
+                (Color::White, Role::King) => '♔',
+                (Color::White, Role::Queen) => '♕',
+                (Color::White, Role::Rook) => '♖',
+                (Color::White, Role::Bishop) => '♗',
+                (Color::White, Role::Knight) => '♘',
+                (Color::White, Role::Pawn) => '♙',
+                (Color::Black, Role::King) => '♚',
+                (Color::Black, Role::Queen) => '♛',
+                (Color::Black, Role::Rook) => '♜',
+                (Color::Black, Role::Bishop) => '♝',
+                (Color::Black, Role::Knight) => '♞',
+                (Color::Black, Role::Pawn) => '♟',
+            },
+        }
+    }
+
+    const fn empty_char(self) -> char {
+        match self {
+            Glyphs::Ascii => '.',
+            Glyphs::Unicode => '·',
+        }
+    }
+
+    fn parse_square(ch: char) -> Option<Option<Piece>> {
+        Some(match ch {
+            '.' | '-' | '·' => None,
+            '♔' => Some(Color::White.king()),
+            '♕' => Some(Color::White.queen()),
+            '♖' => Some(Color::White.rook()),
+            '♗' => Some(Color::White.bishop()),
+            '♘' => Some(Color::White.knight()),
+            '♙' => Some(Color::White.pawn()),
+            '♚' => Some(Color::Black.king()),
+            '♛' => Some(Color::Black.queen()),
+            '♜' => Some(Color::Black.rook()),
+            '♝' => Some(Color::Black.bishop()),
+            '♞' => Some(Color::Black.knight()),
+            '♟' => Some(Color::Black.pawn()),
+            _ => Some(Piece::from_char(ch)?),
+        })
+    }
+}
+
+/// A configurable text diagram of a [`Board`]. See [`Board::diagram()`].
+///
+/// Highlighted squares are enclosed in brackets, e.g. `[P]`. The side to
+/// move is rendered as a final line, e.g. `white to move`.
+///
+/// # Examples
+///
+/// ```
+/// use shakmaty::{Bitboard, Board, Color, Square, board::Glyphs};
+///
+/// let board = Board::new();
+/// let diagram = board
+///     .diagram()
+///     .glyphs(Glyphs::Unicode)
+///     .coordinates(true)
+///     .orientation(Color::Black)
+///     .highlight(Bitboard::from(Square::E2) | Bitboard::from(Square::E4));
+///
+/// assert_eq!(diagram.to_string(), concat!(
+///     "1 ♖ ♘ ♗ ♔ ♕ ♗ ♘ ♖\n",
+///     "2 ♙ ♙ ♙[♙]♙ ♙ ♙ ♙\n",
+///     "3 · · · · · · · ·\n",
+///     "4 · · ·[·]· · · ·\n",
+///     "5 · · · · · · · ·\n",
+///     "6 · · · · · · · ·\n",
+///     "7 ♟ ♟ ♟ ♟ ♟ ♟ ♟ ♟\n",
+///     "8 ♜ ♞ ♝ ♚ ♛ ♝ ♞ ♜\n",
+///     "  h g f e d c b a\n",
+/// ));
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct Diagram<'a> {
+    board: &'a Board,
+    glyphs: Glyphs,
+    coordinates: bool,
+    orientation: Color,
+    highlight: Bitboard,
+    turn: Option<Color>,
+}
+
+impl<'a> Diagram<'a> {
+    /// Creates a plain ASCII diagram, from white's point of view, without
+    /// coordinates. This is equivalent to the [`Debug`](fmt::Debug)
+    /// representation of the board.
+    pub const fn new(board: &'a Board) -> Diagram<'a> {
+        Diagram {
+            board,
+            glyphs: Glyphs::Ascii,
+            coordinates: false,
+            orientation: Color::White,
+            highlight: Bitboard::EMPTY,
+            turn: None,
+        }
+    }
+
+    /// Sets the glyphs used for pieces and empty squares.
+    pub const fn glyphs(mut self, glyphs: Glyphs) -> Diagram<'a> {
+        self.glyphs = glyphs;
+        self
+    }
+
+    /// Enables or disables rank and file labels.
+    pub const fn coordinates(mut self, coordinates: bool) -> Diagram<'a> {
+        self.coordinates = coordinates;
+        self
+    }
+
+    /// Sets the side that is at the bottom of the diagram.
+    pub const fn orientation(mut self, orientation: Color) -> Diagram<'a> {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Sets the squares to highlight.
+    pub const fn highlight(mut self, highlight: Bitboard) -> Diagram<'a> {
+        self.highlight = highlight;
+        self
+    }
+
+    /// Sets the side to move, to be rendered below the board, or `None`.
+    pub const fn turn(mut self, turn: Option<Color>) -> Diagram<'a> {
+        self.turn = turn;
+        self
+    }
+}
+
+impl fmt::Display for Diagram<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (ranks, files) = match self.orientation {
+            Color::White => (Rank::ALL.map(Rank::flip_vertical), File::ALL),
+            Color::Black => (Rank::ALL, File::ALL.map(File::flip_horizontal)),
+        };
+
+        for rank in ranks {
+            if self.coordinates {
+                f.write_char(rank.char())?;
+            }
+            let mut left = false;
+            for (i, file) in files.into_iter().enumerate() {
                 let square = Square::from_coords(file, rank);
-                f.write_char(self.piece_at(square).map_or('.', Piece::char))?;
-                f.write_char(if file < File::H { ' ' } else { '\n' })?;
+                let right = self.highlight.contains(square);
+                if i > 0 || self.coordinates || self.highlight.any() {
+                    f.write_char(match (left, right) {
+                        (false, false) => ' ',
+                        (false, true) => '[',
+                        (true, false) => ']',
+                        (true, true) => '|',
+                    })?;
+                }
+                f.write_char(
+                    self.board
+                        .piece_at(square)
+                        .map_or(self.glyphs.empty_char(), |piece| {
+                            self.glyphs.piece_char(piece)
+                        }),
+                )?;
+                left = right;
+            }
+            if left {
+                f.write_char(']')?;
             }
+            f.write_char('\n')?;
+        }
+
+        if self.coordinates {
+            f.write_char(' ')?;
+            for file in files {
+                f.write_char(' ')?;
+                f.write_char(file.char())?;
+            }
+            f.write_char('\n')?;
+        }
+
+        if let Some(turn) = self.turn {
+            f.write_str(turn.fold_wb("white to move\n", "black to move\n"))?;
         }
 
         Ok(())
     }
 }
 
+/// Error when parsing an invalid board diagram.
+#[derive(Clone, Debug)]
+pub struct ParseDiagramError;
+
+impl fmt::Display for ParseDiagramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid board diagram")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseDiagramError {}
+
+pub(crate) fn parse_diagram(diagram: &str) -> Result<(Board, Option<Color>), ParseDiagramError> {
+    let mut rows: [[Option<Piece>; 8]; 8] = [[None; 8]; 8];
+    let mut num_rows = 0;
+    let mut rank_labels = [None; 8];
+    let mut file_labels = None;
+    let mut turn = None;
+
+    for line in diagram.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        } else if line == "white to move" || line == "black to move" {
+            if turn.is_some() {
+                return Err(ParseDiagramError);
+            }
+            turn = Some(Color::from_white(line == "white to move"));
+            continue;
+        }
+
+        if line
+            .chars()
+            .all(|ch| ch.is_ascii_whitespace() || File::from_char(ch).is_some())
+        {
+            if file_labels.is_some() {
+                return Err(ParseDiagramError);
+            }
+            let mut labels = line.chars().filter(|ch| !ch.is_ascii_whitespace());
+            let mut files = [File::A; 8];
+            for file in &mut files {
+                *file = labels
+                    .next()
+                    .and_then(File::from_char)
+                    .ok_or(ParseDiagramError)?;
+            }
+            if labels.next().is_some() {
+                return Err(ParseDiagramError);
+            }
+            file_labels = Some(files);
+            continue;
+        }
+
+        let row = rows.get_mut(num_rows).ok_or(ParseDiagramError)?;
+        let mut num_squares = 0;
+        for ch in line.chars() {
+            if ch.is_whitespace() || matches!(ch, '[' | ']' | '|') {
+                continue;
+            } else if let Some(rank) = Rank::from_char(ch) {
+                if rank_labels[num_rows].replace(rank).is_some() {
+                    return Err(ParseDiagramError);
+                }
+            } else {
+                *row.get_mut(num_squares).ok_or(ParseDiagramError)? =
+                    Glyphs::parse_square(ch).ok_or(ParseDiagramError)?;
+                num_squares += 1;
+            }
+        }
+        if num_squares != 8 {
+            return Err(ParseDiagramError);
+        }
+        num_rows += 1;
+    }
+
+    if num_rows != 8 {
+        return Err(ParseDiagramError);
+    }
+
+    let orientation = match (rank_labels[0], file_labels) {
+        (Some(Rank::Eighth), _) | (None, Some([File::A, ..]) | None) => Color::White,
+        (Some(Rank::First), _) | (None, Some(_)) => Color::Black,
+        (Some(_), _) => return Err(ParseDiagramError),
+    };
+    let (ranks, files) = match orientation {
+        Color::White => (Rank::ALL.map(Rank::flip_vertical), File::ALL),
+        Color::Black => (Rank::ALL, File::ALL.map(File::flip_horizontal)),
+    };
+    if (rank_labels.iter().any(Option::is_some) && rank_labels != ranks.map(Some))
+        || file_labels.is_some_and(|labels| labels != files)
+    {
+        return Err(ParseDiagramError);
+    }
+
+    let mut board = Board::empty();
+    for (row, rank) in rows.into_iter().zip(ranks) {
+        for (piece, file) in row.into_iter().zip(files) {
+            if let Some(piece) = piece {
+                board.set_piece_at(Square::from_coords(file, rank), piece);
+            }
+        }
+    }
+    Ok((board, turn))
+}
+
 impl Extend<(Square, Piece)> for Board {
     fn extend<T: IntoIterator<Item = (Square, Piece)>>(&mut self, iter: T) {
         for (sq, piece) in iter {
@@ -551,6 +971,97 @@ mod tests {
         compare_trans(&Board::rotate_270, "8/8/7N/1np3B1/b2K1R2/r3Q3/qkPNBRQK/8");
     }
 
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_diagram() {
+        use alloc::string::ToString as _;
+
+        let board = Board::new();
+        assert_eq!(board.diagram().to_string(), alloc::format!("{board:?}"));
+
+        let diagram = board
+            .diagram()
+            .coordinates(true)
+            .highlight(Bitboard::from(Square::A8) | Square::B8 | Square::H1)
+            .turn(Some(White))
+            .to_string();
+        assert_eq!(
+            diagram,
+            concat!(
+                "8[r|n]b q k b n r\n",
+                "7 p p p p p p p p\n",
+                "6 . . . . . . . .\n",
+                "5 . . . . . . . .\n",
+                "4 . . . . . . . .\n",
+                "3 . . . . . . . .\n",
+                "2 P P P P P P P P\n",
+                "1 R N B Q K B N[R]\n",
+                "  a b c d e f g h\n",
+                "white to move\n",
+            )
+        );
+        assert_eq!(Board::from_diagram(&diagram).expect("valid diagram"), board);
+
+        let board: Board = "r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR"
+            .parse()
+            .expect("valid fen");
+        for glyphs in [Glyphs::Ascii, Glyphs::Unicode] {
+            for orientation in [White, Black] {
+                for coordinates in [false, true] {
+                    let diagram = board
+                        .diagram()
+                        .glyphs(glyphs)
+                        .orientation(orientation)
+                        .coordinates(coordinates)
+                        .to_string();
+                    let parsed = Board::from_diagram(&diagram);
+                    if orientation == White || coordinates {
+                        assert_eq!(parsed.expect("valid diagram"), board, "{diagram}");
+                    } else {
+                        assert_ne!(parsed.expect("valid diagram"), board, "{diagram}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_diagram() {
+        let board = Board::from_diagram(
+            "
+            1 - - - - - - ♔ -
+            2 - - - - - ♙ ♙ ♙
+            3 - - - - - - - -
+            4 - - - - - - - -
+            5 - - - - - - - -
+            6 - - - - - - - -
+            7 ♟ ♟ ♟ - - - - -
+            8 - ♚ - - - - - -
+            ",
+        )
+        .expect("valid diagram");
+        assert_eq!(board.piece_at(Square::B1), Some(White.king()));
+        assert_eq!(board.piece_at(Square::G8), Some(Black.king()));
+        assert_eq!(board.occupied().count(), 8);
+
+        for invalid in [
+            "",
+            "8 . . . . . . . .",
+            // Too many squares.
+            ". . . . . . . . .\n. . . . . . . .\n. . . . . . . .\n. . . . . . . .\n\
+             . . . . . . . .\n. . . . . . . .\n. . . . . . . .\n. . . . . . . .",
+            // Inconsistent labels.
+            "8 . . . . . . . .\n7 . . . . . . . .\n6 . . . . . . . .\n5 . . . . . . . .\n\
+             4 . . . . . . . .\n3 . . . . . . . .\n2 . . . . . . . .\n1 . . . . . . . .\n\
+             h g f e d c b a",
+            // Invalid piece.
+            "x . . . . . . .\n. . . . . . . .\n. . . . . . . .\n. . . . . . . .\n\
+             . . . . . . . .\n. . . . . . . .\n. . . . . . . .\n. . . . . . . .",
+        ] {
+            assert!(Board::from_diagram(invalid).is_err(), "{invalid}");
+        }
+    }
+
     #[test]
     fn test_from_bitboards() {
         let (by_role, by_color) = Board::default().into_bitboards();
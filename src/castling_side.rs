@@ -101,6 +101,7 @@ impl ops::BitXorAssign<bool> for CastlingSide {
 
 /// Container with values for each [`CastlingSide`].
 #[derive(Copy, Clone, Default, Eq, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct ByCastlingSide<T> {
     pub king_side: T,
@@ -0,0 +1,232 @@
+//! Numbered Chess960 starting positions.
+//!
+//! The 960 starting positions are numbered from 0 to 959 according to
+//! Reinhard Scharnagl's scheme, where position 518 is the standard starting
+//! position.
+//!
+//! See [`Setup::chess960()`](crate::Setup::chess960) and
+//! [`Board::chess960_number()`](crate::Board::chess960_number) to build and
+//! identify starting positions.
+//!
+//! # Examples
+//!
+//! ```
+//! use shakmaty::{chess960, Role};
+//!
+//! assert_eq!(chess960::back_rank(518), Some([
+//!     Role::Rook, Role::Knight, Role::Bishop, Role::Queen,
+//!     Role::King, Role::Bishop, Role::Knight, Role::Rook,
+//! ]));
+//!
+//! let back_rank = chess960::back_rank(0).expect("valid number");
+//! assert_eq!(chess960::number(&back_rank), Some(0));
+//! ```
+
+use crate::Role;
+
+/// The number of the standard starting position.
+pub const STANDARD: u32 = 518;
+
+/// Positions of the two knights among the 5 squares remaining after placing
+/// the bishops and the queen.
+const KNIGHTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+/// Gets the pieces on the back rank of starting position `n`, from the
+/// a-file to the h-file. Returns `None` if `n >= 960`.
+pub fn back_rank(n: u32) -> Option<[Role; 8]> {
+    if n >= 960 {
+        return None;
+    }
+
+    let mut back_rank = [None; 8];
+    let n = n as usize;
+
+    // Light-squared and dark-squared bishop.
+    back_rank[(n % 4) * 2 + 1] = Some(Role::Bishop);
+    back_rank[((n / 4) % 4) * 2] = Some(Role::Bishop);
+
+    // Queen on one of the remaining 6 squares.
+    let mut empty = [0; 6];
+    for (slot, file) in empty
+        .iter_mut()
+        .zip((0..8).filter(|&file| back_rank[file].is_none()))
+    {
+        *slot = file;
+    }
+    back_rank[empty[(n / 16) % 6]] = Some(Role::Queen);
+
+    // Knights on two of the remaining 5 squares.
+    let mut empty = [0; 5];
+    for (slot, file) in empty
+        .iter_mut()
+        .zip((0..8).filter(|&file| back_rank[file].is_none()))
+    {
+        *slot = file;
+    }
+    let (left, right) = KNIGHTS[n / 96];
+    back_rank[empty[left]] = Some(Role::Knight);
+    back_rank[empty[right]] = Some(Role::Knight);
+
+    // Rook, king and rook on the remaining 3 squares.
+    let mut remaining = [Role::Rook, Role::King, Role::Rook].into_iter();
+    Some(back_rank.map(|role| role.or_else(|| remaining.next()).expect("8 squares")))
+}
+
+/// Gets the number of a starting position from the pieces on its back rank,
+/// from the a-file to the h-file. Returns `None` if the back rank does not
+/// belong to a Chess960 starting position.
+pub fn number(pieces: &[Role; 8]) -> Option<u32> {
+    let mut bishops = pieces
+        .iter()
+        .enumerate()
+        .filter(|&(_, &role)| role == Role::Bishop)
+        .map(|(file, _)| file);
+    let (light, dark) = match (bishops.next(), bishops.next(), bishops.next()) {
+        (Some(a), Some(b), None) if a % 2 != b % 2 => {
+            if a % 2 == 1 {
+                (a, b)
+            } else {
+                (b, a)
+            }
+        }
+        _ => return None,
+    };
+
+    let queen = pieces
+        .iter()
+        .filter(|&&role| role != Role::Bishop)
+        .position(|&role| role == Role::Queen)?;
+
+    let mut knights = pieces
+        .iter()
+        .filter(|&&role| role != Role::Bishop && role != Role::Queen)
+        .enumerate()
+        .filter(|&(_, &role)| role == Role::Knight)
+        .map(|(i, _)| i);
+    let knights = (knights.next()?, knights.next()?);
+    let knights = KNIGHTS.iter().position(|&k| k == knights)?;
+
+    let n = (light / 2 + (dark / 2) * 4 + queen * 16 + knights * 96) as u32;
+    (back_rank(n).as_ref() == Some(pieces)).then_some(n)
+}
+
+/// Chooses a starting position number in `0..960`, uniformly and
+/// deterministically, based on `seed`.
+///
+/// This uses a simple pseudo-random number generator (SplitMix64) that is
+/// good enough to pick positions for games and tournaments, but not suitable
+/// for cryptographic purposes.
+///
+/// # Examples
+///
+/// ```
+/// use shakmaty::chess960;
+///
+/// let n = chess960::random_number(2024);
+/// assert!(n < 960);
+/// assert_eq!(chess960::random_number(2024), n);
+/// ```
+pub fn random_number(seed: u64) -> u32 {
+    // Reject values from the incomplete last block, to avoid bias.
+    const LIMIT: u64 = u64::MAX - u64::MAX % 960;
+
+    let mut state = seed;
+    loop {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        if z < LIMIT {
+            return (z % 960) as u32;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Board, ByColor, CastlingMode, Chess, Position, Setup, Square};
+
+    #[test]
+    fn test_back_rank() {
+        use Role::*;
+
+        assert_eq!(
+            back_rank(0),
+            Some([Bishop, Bishop, Queen, Knight, Knight, Rook, King, Rook])
+        );
+        assert_eq!(
+            back_rank(STANDARD),
+            Some([Rook, Knight, Bishop, Queen, King, Bishop, Knight, Rook])
+        );
+        assert_eq!(
+            back_rank(959),
+            Some([Rook, King, Rook, Knight, Knight, Queen, Bishop, Bishop])
+        );
+        assert_eq!(back_rank(960), None);
+
+        for n in 0..960 {
+            assert_eq!(number(&back_rank(n).expect("valid number")), Some(n));
+        }
+
+        assert_eq!(
+            number(&[Rook, Knight, Bishop, Queen, King, Knight, Bishop, Rook]),
+            None
+        );
+        assert_eq!(
+            number(&[King, Knight, Bishop, Queen, Rook, Bishop, Knight, Rook]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_setup() {
+        for n in 0..960 {
+            let setup = Setup::chess960(n).expect("valid number");
+            let pos: Chess = setup
+                .clone()
+                .position(CastlingMode::Chess960)
+                .expect("legal position");
+            assert_eq!(pos.castles().castling_rights(), setup.castling_rights);
+            assert_eq!(pos.board().chess960_number(), Some(n));
+        }
+        assert_eq!(Setup::chess960(960), None);
+
+        let numbers = ByColor {
+            white: 100,
+            black: 200,
+        };
+        let board = Board::double_chess960(numbers).expect("valid numbers");
+        assert_eq!(board.chess960_number(), None);
+        assert_eq!(board.double_chess960_numbers(), Some(numbers));
+        let _: Chess = Setup::double_chess960(numbers)
+            .expect("valid numbers")
+            .position(CastlingMode::Chess960)
+            .expect("legal position");
+
+        let mut board = Board::new();
+        board.discard_piece_at(Square::E2);
+        assert_eq!(board.chess960_number(), None);
+    }
+
+    #[test]
+    fn test_random_number() {
+        let mut seen = [false; 960];
+        for seed in 0..20_000 {
+            seen[random_number(seed) as usize] = true;
+        }
+        assert!(seen.iter().all(|&seen| seen));
+    }
+}
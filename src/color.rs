@@ -211,8 +211,35 @@ macro_rules! try_color_from_int_impl {
 
 try_color_from_int_impl! { u8 i8 u16 i16 u32 i32 u64 i64 u128 i128 usize isize }
 
+/// Serializes as `white` or `black` in human-readable formats, and as
+/// `1` or `0` otherwise.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Color {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(self.name())
+        } else {
+            serializer.serialize_u8(u8::from(*self))
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Color {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        if deserializer.is_human_readable() {
+            crate::util::deserialize_ascii(deserializer, "color name", |s| {
+                Color::from_name(core::str::from_utf8(s).ok()?)
+            })
+        } else {
+            crate::util::deserialize_u8(deserializer, "color index", |n| Color::try_from(n).ok())
+        }
+    }
+}
+
 /// Container with values for each [`Color`].
 #[derive(Copy, Clone, Default, Eq, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ByColor<T> {
     pub black: T,
     pub white: T,
@@ -0,0 +1,999 @@
+//! Read and write descriptive notation, as found in historical games.
+//!
+//! Descriptive notation names files after the pieces that start on them
+//! (`QR`, `QN`, `QB`, `Q`, `K`, `KB`, `KN`, `KR` for the a-file to the
+//! h-file) and counts ranks from the point of view of the moving side.
+//! Moves like `P-K4` or `N-KB3` name the destination square, while captures
+//! like `QxP` name the captured piece. Abbreviated forms like `N-B3` are
+//! resolved in the context of a position.
+//!
+//! English and Spanish notation are supported (see [`Language`]).
+//!
+//! # Examples
+//!
+//! ```
+//! use shakmaty::{Chess, Position, descriptive::{Descriptive, Language}};
+//!
+//! let mut pos = Chess::default();
+//!
+//! for (english, spanish) in [
+//!     ("P-K4", "P4R"),
+//!     ("P-K4", "P4R"),
+//!     ("N-KB3", "C3AR"),
+//!     ("N-QB3", "C3AD"),
+//! ] {
+//!     let descriptive: Descriptive = english.parse()?;
+//!     let m = descriptive.to_move(&pos)?;
+//!
+//!     let rendered = Descriptive::from_move(&pos, &m).expect("not a drop");
+//!     assert_eq!(rendered.display_in(Language::Spanish).to_string(), spanish);
+//!
+//!     pos.play_unchecked(&m);
+//! }
+//!
+//! // Abbreviated squares are resolved in the context of the position.
+//! let m = "B-B4".parse::<Descriptive>()?.to_move(&pos)?;
+//! assert_eq!(m.to(), shakmaty::Square::C4);
+//!
+//! # #[derive(Debug)] struct CommonError;
+//! # impl From<shakmaty::descriptive::ParseDescriptiveError> for CommonError { fn from(_: shakmaty::descriptive::ParseDescriptiveError) -> Self { Self } }
+//! # impl From<shakmaty::descriptive::DescriptiveError> for CommonError { fn from(_: shakmaty::descriptive::DescriptiveError) -> Self { Self } }
+//! # Ok::<_, CommonError>(())
+//! ```
+
+use core::{fmt, str::FromStr};
+
+use crate::{
+    util::AppendAscii, Bitboard, CastlingSide, Color, File, Move, MoveList, Position, Rank, Role,
+    Square,
+};
+
+/// Error when parsing syntactically invalid descriptive notation.
+#[derive(Clone, Debug)]
+pub struct ParseDescriptiveError;
+
+impl fmt::Display for ParseDescriptiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid descriptive notation")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseDescriptiveError {}
+
+/// `IllegalDescriptive` or `AmbiguousDescriptive`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DescriptiveError {
+    /// Descriptive notation does not match a legal move.
+    IllegalDescriptive,
+    /// Descriptive notation matches multiple legal moves.
+    AmbiguousDescriptive,
+}
+
+impl fmt::Display for DescriptiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match *self {
+            DescriptiveError::IllegalDescriptive => "illegal descriptive notation",
+            DescriptiveError::AmbiguousDescriptive => "ambiguous descriptive notation",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DescriptiveError {}
+
+/// Language of descriptive notation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Language {
+    /// English notation, e.g. `N-KB3` or `QxP`. Knights may also be written
+    /// as `Kt` when parsing.
+    #[default]
+    English,
+    /// Spanish notation, e.g. `C3AR` or `DxP`. Pieces are `P`, `C`, `A`,
+    /// `T`, `D` and `R`, the king and queen side are `R` and `D`, and
+    /// squares are written with the rank first.
+    Spanish,
+}
+
+impl Language {
+    const fn role_char(self, role: Role) -> char {
+        match self {
+            Language::English => role.upper_char(),
+            Language::Spanish => match role {
+                Role::Pawn => 'P',
+                Role::Knight => 'C',
+                Role::Bishop => 'A',
+                Role::Rook => 'T',
+                Role::Queen => 'D',
+                Role::King => 'R',
+            },
+        }
+    }
+
+    const fn wing_char(self, wing: CastlingSide) -> char {
+        match (self, wing) {
+            (Language::English, CastlingSide::KingSide) => 'K',
+            (Language::English, CastlingSide::QueenSide) => 'Q',
+            (Language::Spanish, CastlingSide::KingSide) => 'R',
+            (Language::Spanish, CastlingSide::QueenSide) => 'D',
+        }
+    }
+
+    fn role_prefix(self, s: &[u8]) -> Option<(Role, &[u8])> {
+        if self == Language::English {
+            if let Some(rest) = s.strip_prefix(b"Kt") {
+                return Some((Role::Knight, rest));
+            }
+        }
+        let (&ch, rest) = s.split_first()?;
+        Role::ALL
+            .into_iter()
+            .find(|&role| self.role_char(role) as u8 == ch)
+            .map(|role| (role, rest))
+    }
+
+    fn wing_prefix(self, s: &[u8]) -> Option<(CastlingSide, &[u8])> {
+        let (&ch, rest) = s.split_first()?;
+        CastlingSide::ALL
+            .into_iter()
+            .find(|&wing| self.wing_char(wing) as u8 == ch)
+            .map(|wing| (wing, rest))
+    }
+}
+
+const QUEEN_SIDE: Bitboard = Bitboard(0x0f0f_0f0f_0f0f_0f0f);
+const KING_SIDE: Bitboard = Bitboard(0xf0f0_f0f0_f0f0_f0f0);
+
+const fn wing_of(square: Square) -> CastlingSide {
+    CastlingSide::from_queen_side((square as u8 & 7) < 4)
+}
+
+/// A file named after the piece that starts on it, like `QB` for the
+/// c-file, or `B` for either bishop file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FileName {
+    /// The king or queen side, if given. Always `None` for the king and
+    /// queen files.
+    pub wing: Option<CastlingSide>,
+    /// The piece, which is never a pawn.
+    pub role: Role,
+}
+
+impl FileName {
+    /// Gets the full name of a file, e.g. `KN` for the g-file.
+    pub const fn from_file(file: File) -> FileName {
+        let (wing, role) = match file {
+            File::A => (Some(CastlingSide::QueenSide), Role::Rook),
+            File::B => (Some(CastlingSide::QueenSide), Role::Knight),
+            File::C => (Some(CastlingSide::QueenSide), Role::Bishop),
+            File::D => (None, Role::Queen),
+            File::E => (None, Role::King),
+            File::F => (Some(CastlingSide::KingSide), Role::Bishop),
+            File::G => (Some(CastlingSide::KingSide), Role::Knight),
+            File::H => (Some(CastlingSide::KingSide), Role::Rook),
+        };
+        FileName { wing, role }
+    }
+
+    /// Gets the files matching the name.
+    pub fn files(self) -> Bitboard {
+        let (queen_side, king_side) = match self.role {
+            Role::Pawn => return Bitboard::EMPTY,
+            Role::Rook => (File::A, File::H),
+            Role::Knight => (File::B, File::G),
+            Role::Bishop => (File::C, File::F),
+            Role::Queen => return Bitboard::from(File::D),
+            Role::King => return Bitboard::from(File::E),
+        };
+        match self.wing {
+            Some(CastlingSide::QueenSide) => Bitboard::from(queen_side),
+            Some(CastlingSide::KingSide) => Bitboard::from(king_side),
+            None => Bitboard::from(queen_side) | Bitboard::from(king_side),
+        }
+    }
+
+    fn parse(s: &[u8], language: Language) -> Option<(FileName, &[u8])> {
+        let (wing, role, rest) = match language {
+            Language::English => match language.wing_prefix(s) {
+                Some((wing, rest)) => match language.role_prefix(rest) {
+                    Some((role @ (Role::Rook | Role::Knight | Role::Bishop), rest)) => {
+                        (Some(wing), role, rest)
+                    }
+                    _ => {
+                        let (role, rest) = language.role_prefix(s)?;
+                        (None, role, rest)
+                    }
+                },
+                None => {
+                    let (role, rest) = language.role_prefix(s)?;
+                    (None, role, rest)
+                }
+            },
+            Language::Spanish => {
+                let (role, rest) = language.role_prefix(s)?;
+                match (role, language.wing_prefix(rest)) {
+                    (Role::Rook | Role::Knight | Role::Bishop, Some((wing, rest))) => {
+                        (Some(wing), role, rest)
+                    }
+                    _ => (None, role, rest),
+                }
+            }
+        };
+        match role {
+            Role::Pawn => None,
+            _ => Some((FileName { wing, role }, rest)),
+        }
+    }
+
+    fn append_to<W: AppendAscii>(self, f: &mut W, language: Language) -> Result<(), W::Error> {
+        let wing = self
+            .wing
+            .filter(|_| matches!(self.role, Role::Rook | Role::Knight | Role::Bishop));
+        if let (Language::English, Some(wing)) = (language, wing) {
+            f.append_ascii(language.wing_char(wing))?;
+        }
+        f.append_ascii(language.role_char(self.role))?;
+        if let (Language::Spanish, Some(wing)) = (language, wing) {
+            f.append_ascii(language.wing_char(wing))?;
+        }
+        Ok(())
+    }
+}
+
+/// A square like `KB3`, with the rank counted from the point of view of
+/// the moving side.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DescriptiveSquare {
+    pub file: FileName,
+    /// Rank from the point of view of the moving side.
+    pub rank: Rank,
+}
+
+impl DescriptiveSquare {
+    /// Gets the full name of a square, from the point of view of `turn`.
+    pub fn from_square(square: Square, turn: Color) -> DescriptiveSquare {
+        DescriptiveSquare {
+            file: FileName::from_file(square.file()),
+            rank: turn.relative_rank(square.rank()),
+        }
+    }
+
+    /// Gets the squares matching the name, from the point of view of
+    /// `turn`.
+    pub fn squares(self, turn: Color) -> Bitboard {
+        self.file.files() & Bitboard::from(turn.relative_rank(self.rank))
+    }
+
+    fn parse(s: &[u8], language: Language) -> Option<(DescriptiveSquare, &[u8])> {
+        match language {
+            Language::English => {
+                let (file, rest) = FileName::parse(s, language)?;
+                let (rank, rest) = parse_rank(rest)?;
+                Some((DescriptiveSquare { file, rank }, rest))
+            }
+            Language::Spanish => {
+                let (rank, rest) = parse_rank(s)?;
+                let (file, rest) = FileName::parse(rest, language)?;
+                Some((DescriptiveSquare { file, rank }, rest))
+            }
+        }
+    }
+
+    fn append_to<W: AppendAscii>(self, f: &mut W, language: Language) -> Result<(), W::Error> {
+        if language == Language::Spanish {
+            f.append_ascii(self.rank.char())?;
+        }
+        self.file.append_to(f, language)?;
+        if language == Language::English {
+            f.append_ascii(self.rank.char())?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_rank(s: &[u8]) -> Option<(Rank, &[u8])> {
+    let (&ch, rest) = s.split_first()?;
+    Some((Rank::from_char(char::from(ch))?, rest))
+}
+
+/// Narrows down a [`Designation`], e.g. `(1)` or `/Q2`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Qualifier {
+    /// Rank from the point of view of the moving side.
+    Rank(Rank),
+    Square(DescriptiveSquare),
+}
+
+impl Qualifier {
+    fn squares(self, turn: Color) -> Bitboard {
+        match self {
+            Qualifier::Rank(rank) => Bitboard::from(turn.relative_rank(rank)),
+            Qualifier::Square(square) => square.squares(turn),
+        }
+    }
+
+    fn parse(s: &[u8], language: Language) -> Option<(Qualifier, &[u8])> {
+        let (close, s) = match s.split_first() {
+            Some((b'/', rest)) => (None, rest),
+            Some((b'(', rest)) => (Some(b')'), rest),
+            _ => return None,
+        };
+        let (qualifier, rest) = match DescriptiveSquare::parse(s, language) {
+            Some((square, rest)) => (Qualifier::Square(square), rest),
+            None => {
+                let (rank, rest) = parse_rank(s)?;
+                (Qualifier::Rank(rank), rest)
+            }
+        };
+        match close {
+            Some(close) => Some((qualifier, rest.strip_prefix(&[close])?)),
+            None => Some((qualifier, rest)),
+        }
+    }
+}
+
+/// Designates a piece, like `P`, `KBP`, `QN` or `R(1)`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Designation {
+    pub role: Role,
+    /// For pawns, the file of the pawn, e.g. `KB` in `KBP`. For other pieces,
+    /// only the wing is significant, e.g. `Q` in `QN`. It is interpreted as
+    /// the half of the board the piece is on, because the position does not
+    /// record where pieces started.
+    pub file: Option<FileName>,
+    pub qualifier: Option<Qualifier>,
+}
+
+impl Designation {
+    const fn new(role: Role) -> Designation {
+        Designation {
+            role,
+            file: None,
+            qualifier: None,
+        }
+    }
+
+    fn squares(self, turn: Color) -> Bitboard {
+        let mut squares = Bitboard::FULL;
+        if let Some(file) = self.file {
+            squares &= match (self.role, file.wing) {
+                (Role::Pawn, _) => file.files(),
+                (_, Some(CastlingSide::KingSide)) => KING_SIDE,
+                (_, Some(CastlingSide::QueenSide)) => QUEEN_SIDE,
+                (_, None) => Bitboard::FULL,
+            };
+        }
+        if let Some(qualifier) = self.qualifier {
+            squares &= qualifier.squares(turn);
+        }
+        squares
+    }
+
+    fn matches(self, role: Role, square: Square, turn: Color) -> bool {
+        self.role == role && self.squares(turn).contains(square)
+    }
+
+    fn parse(s: &[u8], language: Language) -> Option<(Designation, &[u8])> {
+        let pawn = language.role_char(Role::Pawn) as u8;
+        let (mut designation, rest) = match language {
+            Language::English => {
+                if let Some(rest) = s.strip_prefix(&[pawn]) {
+                    (Designation::new(Role::Pawn), rest)
+                } else if let Some(rest) = FileName::parse(s, language)
+                    .and_then(|(file, rest)| Some((file, rest.strip_prefix(&[pawn])?)))
+                    .map(|(file, rest)| {
+                        (
+                            Designation {
+                                file: Some(file),
+                                ..Designation::new(Role::Pawn)
+                            },
+                            rest,
+                        )
+                    })
+                {
+                    rest
+                } else {
+                    Designation::parse_piece(s, language)?
+                }
+            }
+            Language::Spanish => {
+                if let Some(rest) = s.strip_prefix(&[pawn]) {
+                    match FileName::parse(rest, language) {
+                        Some((file, rest)) => (
+                            Designation {
+                                file: Some(file),
+                                ..Designation::new(Role::Pawn)
+                            },
+                            rest,
+                        ),
+                        None => (Designation::new(Role::Pawn), rest),
+                    }
+                } else {
+                    Designation::parse_piece(s, language)?
+                }
+            }
+        };
+        let rest = match Qualifier::parse(rest, language) {
+            Some((qualifier, rest)) => {
+                designation.qualifier = Some(qualifier);
+                rest
+            }
+            None => rest,
+        };
+        Some((designation, rest))
+    }
+
+    fn parse_piece(s: &[u8], language: Language) -> Option<(Designation, &[u8])> {
+        let (file, rest) = FileName::parse(s, language)?;
+        Some(match file.wing {
+            Some(_) => (
+                Designation {
+                    file: Some(file),
+                    ..Designation::new(file.role)
+                },
+                rest,
+            ),
+            None => (Designation::new(file.role), rest),
+        })
+    }
+
+    fn append_to<W: AppendAscii>(self, f: &mut W, language: Language) -> Result<(), W::Error> {
+        if self.role == Role::Pawn {
+            if language == Language::Spanish {
+                f.append_ascii(language.role_char(Role::Pawn))?;
+            }
+            if let Some(file) = self.file {
+                file.append_to(f, language)?;
+            }
+            if language == Language::English {
+                f.append_ascii(language.role_char(Role::Pawn))?;
+            }
+        } else {
+            FileName {
+                wing: self.file.and_then(|file| file.wing),
+                role: self.role,
+            }
+            .append_to(f, language)?;
+        }
+        match self.qualifier {
+            Some(Qualifier::Rank(rank)) => {
+                f.append_ascii('(')?;
+                f.append_ascii(rank.char())?;
+                f.append_ascii(')')?;
+            }
+            Some(Qualifier::Square(square)) => {
+                f.append_ascii('(')?;
+                square.append_to(f, language)?;
+                f.append_ascii(')')?;
+            }
+            None => (),
+        }
+        Ok(())
+    }
+
+    /// Designations of the piece on `square`, from least to most specific.
+    fn candidates(role: Role, square: Square, turn: Color) -> [Designation; 4] {
+        let full = FileName::from_file(square.file());
+        let file = match role {
+            // Bishop pawn, knight pawn or rook pawn.
+            Role::Pawn => Some(FileName { wing: None, ..full }),
+            Role::Knight | Role::Bishop | Role::Rook => Some(FileName {
+                wing: Some(wing_of(square)),
+                role,
+            }),
+            Role::Queen | Role::King => None,
+        };
+        let narrow = if role == Role::Pawn {
+            Designation {
+                file: Some(full),
+                ..Designation::new(role)
+            }
+        } else {
+            Designation {
+                qualifier: Some(Qualifier::Rank(turn.relative_rank(square.rank()))),
+                ..Designation::new(role)
+            }
+        };
+        [
+            Designation::new(role),
+            Designation {
+                file,
+                ..Designation::new(role)
+            },
+            narrow,
+            Designation {
+                qualifier: Some(Qualifier::Square(DescriptiveSquare::from_square(
+                    square, turn,
+                ))),
+                ..Designation::new(role)
+            },
+        ]
+    }
+}
+
+/// A move in descriptive notation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Descriptive {
+    /// A move to a square, e.g. `N-KB3` or `P-Q8(Q)`.
+    Normal {
+        piece: Designation,
+        to: DescriptiveSquare,
+        promotion: Option<Role>,
+    },
+    /// A capture, e.g. `QxP` or `PxP e.p.`, naming the captured piece.
+    Capture {
+        piece: Designation,
+        target: Designation,
+        promotion: Option<Role>,
+    },
+    Castle(CastlingSide),
+}
+
+const SUFFIXES: [&[u8]; 19] = [
+    b"dis ch", b"dbl ch", b"ch", b"jaque", b"mate", b"e.p.", b"ep", b"a.p.", b"++", b"+", b"#",
+    b"!!", b"??", b"!?", b"?!", b"!", b"?", b"dis", b"dbl",
+];
+
+impl Descriptive {
+    /// Parses descriptive notation. Ignores possible check, checkmate,
+    /// en passant and annotation suffixes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseDescriptiveError`] if `descriptive` is not
+    /// syntactically valid.
+    pub fn from_ascii(
+        descriptive: &[u8],
+        language: Language,
+    ) -> Result<Descriptive, ParseDescriptiveError> {
+        let (descriptive, rest) =
+            Descriptive::parse(trim_start(descriptive), language).ok_or(ParseDescriptiveError)?;
+
+        let mut rest = trim_start(rest);
+        while !rest.is_empty() {
+            rest = trim_start(
+                SUFFIXES
+                    .iter()
+                    .find_map(|suffix| rest.strip_prefix(*suffix))
+                    .ok_or(ParseDescriptiveError)?,
+            );
+        }
+
+        Ok(descriptive)
+    }
+
+    fn parse(s: &[u8], language: Language) -> Option<(Descriptive, &[u8])> {
+        for (castle, side) in [
+            (&b"O-O-O"[..], CastlingSide::QueenSide),
+            (b"0-0-0", CastlingSide::QueenSide),
+            (b"O-O", CastlingSide::KingSide),
+            (b"0-0", CastlingSide::KingSide),
+        ] {
+            if let Some(rest) = s.strip_prefix(castle) {
+                return Some((Descriptive::Castle(side), rest));
+            }
+        }
+
+        let (piece, rest) = Designation::parse(s, language)?;
+        let (descriptive, rest) =
+            if let Some(rest) = rest.strip_prefix(b"x").or_else(|| rest.strip_prefix(b":")) {
+                let (target, rest) = Designation::parse(rest, language)?;
+                let (promotion, rest) = parse_promotion(rest, language);
+                (
+                    Descriptive::Capture {
+                        piece,
+                        target,
+                        promotion,
+                    },
+                    rest,
+                )
+            } else {
+                let rest = match (language, rest.strip_prefix(b"-")) {
+                    (_, Some(rest)) => rest,
+                    (Language::Spanish, None) => rest,
+                    (Language::English, None) => return None,
+                };
+                let (to, rest) = DescriptiveSquare::parse(rest, language)?;
+                let (promotion, rest) = parse_promotion(rest, language);
+                (
+                    Descriptive::Normal {
+                        piece,
+                        to,
+                        promotion,
+                    },
+                    rest,
+                )
+            };
+
+        Some((descriptive, rest))
+    }
+
+    /// Tests if the descriptive notation matches the move, given the side
+    /// to move.
+    pub fn matches(&self, turn: Color, m: &Move) -> bool {
+        match *self {
+            Descriptive::Normal {
+                piece,
+                to,
+                promotion,
+            } => match *m {
+                Move::Normal {
+                    role,
+                    from,
+                    capture: None,
+                    to: t,
+                    promotion: p,
+                } => {
+                    piece.matches(role, from, turn)
+                        && to.squares(turn).contains(t)
+                        && promotion == p
+                }
+                _ => false,
+            },
+            Descriptive::Capture {
+                piece,
+                target,
+                promotion,
+            } => match *m {
+                Move::Normal {
+                    role,
+                    from,
+                    capture: Some(capture),
+                    to,
+                    promotion: p,
+                } => {
+                    piece.matches(role, from, turn)
+                        && target.matches(capture, to, turn)
+                        && promotion == p
+                }
+                Move::EnPassant { from, to } => {
+                    piece.matches(Role::Pawn, from, turn)
+                        && target.matches(
+                            Role::Pawn,
+                            Square::from_coords(to.file(), from.rank()),
+                            turn,
+                        )
+                        && promotion.is_none()
+                }
+                _ => false,
+            },
+            Descriptive::Castle(side) => m.castling_side() == Some(side),
+        }
+    }
+
+    /// Tries to convert the descriptive notation to a legal move in the
+    /// context of a position.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DescriptiveError`] if there is no unique matching legal
+    /// move.
+    pub fn to_move<P: Position>(&self, pos: &P) -> Result<Move, DescriptiveError> {
+        let mut legals = pos.legal_moves();
+        legals.retain(|m| self.matches(pos.turn(), m));
+        match *legals {
+            [] => Err(DescriptiveError::IllegalDescriptive),
+            [ref m] => Ok(m.clone()),
+            _ => Err(DescriptiveError::AmbiguousDescriptive),
+        }
+    }
+
+    /// Converts a legal move to the shortest unambiguous descriptive notation
+    /// in the context of a position. Returns `None` for piece drops, which
+    /// descriptive notation cannot represent.
+    ///
+    /// Squares are written in full (`N-KB3` rather than `N-B3`), unless the
+    /// abbreviated form is unambiguous.
+    pub fn from_move<P: Position>(pos: &P, m: &Move) -> Option<Descriptive> {
+        let legals = pos.legal_moves();
+        Descriptive::disambiguate(pos.turn(), m, &legals)
+    }
+
+    /// Finds the shortest descriptive notation that uniquely identifies the
+    /// move `m` among `moves`. Returns `None` for piece drops.
+    pub fn disambiguate(turn: Color, m: &Move, moves: &MoveList) -> Option<Descriptive> {
+        let is_unique = |candidate: &Descriptive| {
+            moves.iter().filter(|m| candidate.matches(turn, m)).count() <= 1
+        };
+
+        let (role, from, promotion) = match *m {
+            Move::Normal {
+                role,
+                from,
+                promotion,
+                ..
+            } => (role, from, promotion),
+            Move::EnPassant { from, .. } => (Role::Pawn, from, None),
+            Move::Castle { king, rook } => {
+                return Some(Descriptive::Castle(CastlingSide::from_king_side(
+                    king < rook,
+                )))
+            }
+            Move::Put { .. } => return None,
+        };
+        let pieces = Designation::candidates(role, from, turn);
+
+        let targets = match *m {
+            Move::Normal {
+                capture: Some(capture),
+                to,
+                ..
+            } => Designation::candidates(capture, to, turn),
+            Move::EnPassant { from, to } => Designation::candidates(
+                Role::Pawn,
+                Square::from_coords(to.file(), from.rank()),
+                turn,
+            ),
+            _ => {
+                let to = DescriptiveSquare::from_square(m.to(), turn);
+                let abbreviated = DescriptiveSquare {
+                    file: FileName {
+                        wing: None,
+                        ..to.file
+                    },
+                    ..to
+                };
+                let squares = [abbreviated, to];
+                let mut fallback = None;
+                for sum in 0..(pieces.len() + squares.len() - 1) {
+                    for (i, &piece) in pieces.iter().enumerate() {
+                        let Some(&to) = sum.checked_sub(i).and_then(|j| squares.get(j)) else {
+                            continue;
+                        };
+                        let candidate = Descriptive::Normal {
+                            piece,
+                            to,
+                            promotion,
+                        };
+                        if is_unique(&candidate) {
+                            return Some(candidate);
+                        }
+                        fallback = Some(candidate);
+                    }
+                }
+                return fallback;
+            }
+        };
+
+        let mut fallback = None;
+        for sum in 0..(pieces.len() + targets.len() - 1) {
+            for (i, &piece) in pieces.iter().enumerate() {
+                let Some(&target) = sum.checked_sub(i).and_then(|j| targets.get(j)) else {
+                    continue;
+                };
+                let candidate = Descriptive::Capture {
+                    piece,
+                    target,
+                    promotion,
+                };
+                if is_unique(&candidate) {
+                    return Some(candidate);
+                }
+                fallback = Some(candidate);
+            }
+        }
+        fallback
+    }
+
+    /// Displays the move in the given language.
+    pub fn display_in(&self, language: Language) -> DisplayDescriptive<'_> {
+        DisplayDescriptive {
+            descriptive: self,
+            language,
+        }
+    }
+
+    fn append_to<W: AppendAscii>(&self, f: &mut W, language: Language) -> Result<(), W::Error> {
+        let promotion = match *self {
+            Descriptive::Normal {
+                piece,
+                to,
+                promotion,
+            } => {
+                piece.append_to(f, language)?;
+                if language == Language::English || piece.qualifier.is_some() {
+                    f.append_ascii('-')?;
+                }
+                to.append_to(f, language)?;
+                promotion
+            }
+            Descriptive::Capture {
+                piece,
+                target,
+                promotion,
+            } => {
+                piece.append_to(f, language)?;
+                f.append_ascii('x')?;
+                target.append_to(f, language)?;
+                promotion
+            }
+            Descriptive::Castle(CastlingSide::KingSide) => {
+                f.append_ascii('O')?;
+                f.append_ascii('-')?;
+                f.append_ascii('O')?;
+                None
+            }
+            Descriptive::Castle(CastlingSide::QueenSide) => {
+                f.append_ascii('O')?;
+                f.append_ascii('-')?;
+                f.append_ascii('O')?;
+                f.append_ascii('-')?;
+                f.append_ascii('O')?;
+                None
+            }
+        };
+        if let Some(promotion) = promotion {
+            f.append_ascii('(')?;
+            f.append_ascii(language.role_char(promotion))?;
+            f.append_ascii(')')?;
+        }
+        Ok(())
+    }
+}
+
+fn trim_start(mut s: &[u8]) -> &[u8] {
+    while let [b' ', rest @ ..] = s {
+        s = rest;
+    }
+    s
+}
+
+fn parse_promotion(s: &[u8], language: Language) -> (Option<Role>, &[u8]) {
+    let promotion = match s {
+        [b'(', rest @ ..] => language
+            .role_prefix(rest)
+            .and_then(|(role, rest)| Some((role, rest.strip_prefix(b")")?))),
+        [b'=' | b'/', rest @ ..] => language.role_prefix(rest),
+        _ => None,
+    };
+    match promotion {
+        Some((role, rest)) if role != Role::Pawn => (Some(role), rest),
+        _ => (None, s),
+    }
+}
+
+impl FromStr for Descriptive {
+    type Err = ParseDescriptiveError;
+
+    /// Parses English descriptive notation.
+    fn from_str(descriptive: &str) -> Result<Descriptive, ParseDescriptiveError> {
+        Descriptive::from_ascii(descriptive.as_bytes(), Language::English)
+    }
+}
+
+impl fmt::Display for Descriptive {
+    /// Writes English descriptive notation.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.append_to(f, Language::English)
+    }
+}
+
+/// Displays [`Descriptive`] in a given [`Language`]. See
+/// [`Descriptive::display_in()`].
+#[derive(Debug, Clone)]
+pub struct DisplayDescriptive<'a> {
+    descriptive: &'a Descriptive,
+    language: Language,
+}
+
+impl fmt::Display for DisplayDescriptive<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.descriptive.append_to(f, self.language)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "alloc")]
+    use alloc::string::ToString as _;
+
+    use super::*;
+    use crate::{fen::Fen, san::San, CastlingMode, Chess};
+
+    fn setup_fen(fen: &str) -> Chess {
+        fen.parse::<Fen>()
+            .expect("valid fen")
+            .into_position(CastlingMode::Standard)
+            .expect("legal fen")
+    }
+
+    fn assert_resolves(pos: &Chess, descriptive: &str, language: Language, san: &str) {
+        let m = Descriptive::from_ascii(descriptive.as_bytes(), language)
+            .expect("valid descriptive")
+            .to_move(pos);
+        let expected = san.parse::<San>().expect("valid san").to_move(pos);
+        assert_eq!(m.ok(), expected.ok(), "{descriptive}");
+    }
+
+    #[test]
+    fn test_to_move() {
+        let pos = Chess::default();
+        assert_resolves(&pos, "P-K4", Language::English, "e4");
+        assert_resolves(&pos, "N-KB3", Language::English, "Nf3");
+        assert_resolves(&pos, "Kt-QB3", Language::English, "Nc3");
+        assert_resolves(&pos, "QRP-R3", Language::English, "a3");
+        assert_resolves(&pos, "P4R", Language::Spanish, "e4");
+        assert_resolves(&pos, "C3AR", Language::Spanish, "Nf3");
+        assert_resolves(&pos, "PTD-3T", Language::Spanish, "a3");
+        assert_eq!(
+            "N-B3".parse::<Descriptive>().expect("valid").to_move(&pos),
+            Err(DescriptiveError::AmbiguousDescriptive)
+        );
+        assert_eq!(
+            "P-K5".parse::<Descriptive>().expect("valid").to_move(&pos),
+            Err(DescriptiveError::IllegalDescriptive)
+        );
+
+        // Ranks are counted from black's side.
+        let pos = setup_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq -");
+        assert_resolves(&pos, "P-K4", Language::English, "e5");
+        assert_resolves(&pos, "QN-B3 ch!", Language::English, "Nc6");
+
+        let pos = setup_fen("4k3/1P6/8/2pPp3/6b1/8/4Q3/R3K2R w KQ c6");
+        assert_resolves(&pos, "P-Kt8(Q)", Language::English, "b8=Q");
+        assert_resolves(&pos, "P-N8=N", Language::English, "b8=N");
+        assert_resolves(&pos, "PxP e.p.", Language::English, "dxc6");
+        assert_resolves(&pos, "QxB", Language::English, "Qxg4");
+        assert_resolves(&pos, "QxP ch", Language::English, "Qxe5");
+        assert_resolves(&pos, "O-O", Language::English, "O-O");
+        assert_resolves(&pos, "O-O-O", Language::English, "O-O-O");
+        assert_resolves(&pos, "DxA", Language::Spanish, "Qxg4");
+        assert_resolves(&pos, "P8CD(D)", Language::Spanish, "b8=Q");
+        assert_resolves(&pos, "R(1)-Q1", Language::English, "Rd1");
+        assert_resolves(&pos, "QR-Q1", Language::English, "Rd1");
+        assert_resolves(&pos, "R-Q1", Language::English, "Rd1");
+        assert_eq!(
+            "KR-Q1".parse::<Descriptive>().expect("valid").to_move(&pos),
+            Err(DescriptiveError::IllegalDescriptive)
+        );
+
+        for invalid in ["P-K9", "PK4", "X-K4", "P-K4 foo", "N-", "QxZ"] {
+            assert!(invalid.parse::<Descriptive>().is_err(), "{invalid}");
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_from_move() {
+        for (fen, expected) in [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+                &["P-QR3", "N-KB3", "N-QB3", "P-K4"][..],
+            ),
+            (
+                "r1bqkbnr/pppp1ppp/2n5/4p3/3PP3/5N2/PPP2PPP/RNBQKB1R b KQkq -",
+                &["PxP", "NxP"],
+            ),
+            ("4k3/8/8/3p1p2/4P3/8/8/4K3 w - -", &["PxQP", "PxBP"]),
+            (
+                "4k3/8/8/8/8/8/8/R3K2R w - -",
+                &["R-Q1", "R-KB1", "R-QR8 ch"],
+            ),
+            ("4k3/8/3p4/2P1P3/8/8/8/4K3 w - -", &["BPxP", "KPxP"]),
+            (
+                "4k3/8/8/1N3N2/8/1N3N2/8/4K3 w - -",
+                &["N(QN5)-Q4", "N(QN3)-Q4"],
+            ),
+        ] {
+            let pos = setup_fen(fen);
+            for descriptive in expected {
+                let parsed: Descriptive = descriptive.parse().expect("valid descriptive");
+                let m = parsed.to_move(&pos).expect("legal descriptive");
+                let rendered = Descriptive::from_move(&pos, &m).expect("no drop");
+                assert_eq!(
+                    rendered.to_string(),
+                    descriptive.trim_end_matches(" ch"),
+                    "{fen}"
+                );
+                let spanish = rendered.display_in(Language::Spanish).to_string();
+                assert_eq!(
+                    Descriptive::from_ascii(spanish.as_bytes(), Language::Spanish)
+                        .expect("valid spanish")
+                        .to_move(&pos),
+                    Ok(m),
+                    "{spanish}"
+                );
+            }
+        }
+    }
+}
@@ -16,6 +16,8 @@
 //! * Accepts multiple spaces and underscores (`_`) as separators between
 //!   FEN fields.
 //! * Accepts `0` as fullmove number and uses `1` instead.
+//! * Parses [`Epd`] operations like `bm Nf3; id "test";` (with the `alloc`
+//!   feature). Unknown opcodes are kept as `EpdOperation::Other`.
 //!
 //! [`Fen`] and [`Epd`] implement [`FromStr`]:
 //!
@@ -56,6 +58,14 @@ use core::{
     str::FromStr,
 };
 
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+
+#[cfg(feature = "alloc")]
+use crate::{
+    san::{SanError, SanPlus},
+    Move,
+};
 use crate::{
     util::AppendAscii, Bitboard, Board, ByColor, ByRole, CastlingMode, Color, EnPassantMode, File,
     FromSetup, Piece, Position, PositionError, Rank, RemainingChecks, Role, Setup, Square,
@@ -77,9 +87,9 @@ fn append_castling<W: AppendAscii>(
 
         for rook in (castling_rights & color.backrank()).into_iter().rev() {
             f.append_ascii(
-                if Some(rook) == candidates.first() && king.map_or(false, |k| rook < k) {
+                if Some(rook) == candidates.first() && king.is_some_and(|k| rook < k) {
                     color.fold_wb('Q', 'q')
-                } else if Some(rook) == candidates.last() && king.map_or(false, |k| k < rook) {
+                } else if Some(rook) == candidates.last() && king.is_some_and(|k| k < rook) {
                     color.fold_wb('K', 'k')
                 } else {
                     let file = rook.file();
@@ -135,9 +145,9 @@ fn append_epd<W: AppendAscii>(f: &mut W, setup: &Setup) -> Result<(), W::Error>
     Ok(())
 }
 
-/// Errors that can occur when parsing a FEN.
-#[derive(Clone, Eq, PartialEq, Debug)]
-pub enum ParseFenError {
+/// The part of a FEN in which a [`ParseFenError`] occurred.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ParseFenErrorKind {
     InvalidFen,
     InvalidBoard,
     InvalidPocket,
@@ -147,71 +157,256 @@ pub enum ParseFenError {
     InvalidRemainingChecks,
     InvalidHalfmoveClock,
     InvalidFullmoves,
+    InvalidOperation,
 }
 
-impl Display for ParseFenError {
+impl Display for ParseFenErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(match *self {
-            ParseFenError::InvalidFen => "invalid fen",
-            ParseFenError::InvalidBoard => "invalid board part in fen",
-            ParseFenError::InvalidPocket => "invalid pocket in fen",
-            ParseFenError::InvalidTurn => "invalid turn part in fen",
-            ParseFenError::InvalidCastling => "invalid castling part in fen",
-            ParseFenError::InvalidEpSquare => "invalid ep square in fen",
-            ParseFenError::InvalidRemainingChecks => "invalid remaining checks in fen",
-            ParseFenError::InvalidHalfmoveClock => "invalid halfmove clock in fen",
-            ParseFenError::InvalidFullmoves => "invalid fullmove part in fen",
+            ParseFenErrorKind::InvalidFen => "invalid fen",
+            ParseFenErrorKind::InvalidBoard => "invalid board part in fen",
+            ParseFenErrorKind::InvalidPocket => "invalid pocket in fen",
+            ParseFenErrorKind::InvalidTurn => "invalid turn part in fen",
+            ParseFenErrorKind::InvalidCastling => "invalid castling part in fen",
+            ParseFenErrorKind::InvalidEpSquare => "invalid ep square in fen",
+            ParseFenErrorKind::InvalidRemainingChecks => "invalid remaining checks in fen",
+            ParseFenErrorKind::InvalidHalfmoveClock => "invalid halfmove clock in fen",
+            ParseFenErrorKind::InvalidFullmoves => "invalid fullmove part in fen",
+            ParseFenErrorKind::InvalidOperation => "invalid operation in epd",
         })
     }
 }
 
+/// The specific problem that caused a [`ParseFenError`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ParseFenErrorReason {
+    /// The input does not even contain a board part.
+    MissingBoard,
+    /// A byte that is not allowed at this point.
+    UnexpectedByte(u8),
+    /// A letter that does not denote a piece.
+    UnknownPieceLetter(char),
+    /// A rank that does not describe exactly 8 squares. If the rank is
+    /// too long, `squares` is the count up to and including the offending
+    /// character.
+    RankSquares { rank: Rank, squares: u32 },
+    /// More than 8 ranks.
+    TooManyRanks,
+    /// Fewer than 8 ranks.
+    TooFewRanks { ranks: u32 },
+    /// A pocket closed with `]` without being opened with `[`.
+    UnopenedPocket,
+    /// More than 64 pieces in the pockets.
+    TooManyPocketPieces,
+    /// A king in the pockets.
+    PocketKing,
+    /// A turn other than `w` or `b`.
+    ExpectedTurn,
+    /// More than 2 castling rights for one side.
+    TooManyCastlingRights(Color),
+    /// An en passant square other than a square or `-`.
+    ExpectedSquare,
+    /// A halfmove clock or fullmove number that is not a number.
+    ExpectedNumber,
+    /// An additional part after the fullmove number.
+    UnexpectedPart,
+    /// An EPD opcode that does not start with a letter or contains
+    /// characters other than letters, digits and `_`.
+    InvalidOpcode,
+    /// An EPD string operand without closing quote.
+    UnterminatedString,
+    /// EPD operands that do not fit the opcode.
+    InvalidOperands,
+}
+
+impl Display for ParseFenErrorReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ParseFenErrorReason::MissingBoard => f.write_str("missing board part"),
+            ParseFenErrorReason::UnexpectedByte(byte) if byte.is_ascii_graphic() => {
+                write!(f, "unexpected character '{}'", char::from(byte))
+            }
+            ParseFenErrorReason::UnexpectedByte(byte) => write!(f, "unexpected byte 0x{byte:02x}"),
+            ParseFenErrorReason::UnknownPieceLetter(ch) => {
+                write!(f, "unknown piece letter '{ch}'")
+            }
+            ParseFenErrorReason::RankSquares { rank, squares } => {
+                write!(f, "rank {rank} has {squares} squares")
+            }
+            ParseFenErrorReason::TooManyRanks => f.write_str("more than 8 ranks"),
+            ParseFenErrorReason::TooFewRanks { ranks } => write!(f, "only {ranks} ranks"),
+            ParseFenErrorReason::UnopenedPocket => f.write_str("pocket closed without '['"),
+            ParseFenErrorReason::TooManyPocketPieces => {
+                f.write_str("pocket contains more than 64 pieces")
+            }
+            ParseFenErrorReason::PocketKing => f.write_str("pocket contains a king"),
+            ParseFenErrorReason::ExpectedTurn => f.write_str("expected 'w' or 'b'"),
+            ParseFenErrorReason::TooManyCastlingRights(color) => write!(
+                f,
+                "more than 2 castling rights for {}",
+                color.fold_wb("white", "black")
+            ),
+            ParseFenErrorReason::ExpectedSquare => f.write_str("expected square or '-'"),
+            ParseFenErrorReason::ExpectedNumber => f.write_str("expected number"),
+            ParseFenErrorReason::UnexpectedPart => f.write_str("unexpected part"),
+            ParseFenErrorReason::InvalidOpcode => f.write_str("invalid opcode"),
+            ParseFenErrorReason::UnterminatedString => f.write_str("unterminated string"),
+            ParseFenErrorReason::InvalidOperands => f.write_str("invalid operands for opcode"),
+        }
+    }
+}
+
+/// Errors that can occur when parsing a FEN.
+///
+/// Carries the [kind](ParseFenError::kind) of the error (the part of the
+/// FEN that is invalid), the specific [reason](ParseFenError::reason), and
+/// the [byte offset](ParseFenError::offset) in the input where the problem
+/// was detected.
+///
+/// # Example
+///
+/// ```
+/// use shakmaty::fen::{Fen, ParseFenErrorKind};
+///
+/// let err = "rnbqkbnr/pppppppp/8/8/5P3/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+///     .parse::<Fen>()
+///     .unwrap_err();
+/// assert_eq!(err.kind(), ParseFenErrorKind::InvalidBoard);
+/// assert_eq!(err.offset(), 24);
+/// assert_eq!(err.to_string(), "invalid board part in fen at byte 24: rank 4 has 9 squares");
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ParseFenError {
+    kind: ParseFenErrorKind,
+    reason: ParseFenErrorReason,
+    offset: usize,
+}
+
+impl ParseFenError {
+    const fn new(
+        kind: ParseFenErrorKind,
+        reason: ParseFenErrorReason,
+        offset: usize,
+    ) -> ParseFenError {
+        ParseFenError {
+            kind,
+            reason,
+            offset,
+        }
+    }
+
+    const fn shifted(self, base: usize) -> ParseFenError {
+        ParseFenError {
+            offset: base + self.offset,
+            ..self
+        }
+    }
+
+    /// The part of the FEN that is invalid.
+    pub const fn kind(&self) -> ParseFenErrorKind {
+        self.kind
+    }
+
+    /// The specific problem.
+    pub const fn reason(&self) -> ParseFenErrorReason {
+        self.reason
+    }
+
+    /// The byte offset in the input where the problem was detected.
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl Display for ParseFenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}: {}", self.kind, self.offset, self.reason)
+    }
+}
+
 #[cfg(feature = "std")]
 impl std::error::Error for ParseFenError {}
 
 fn parse_board_fen(board_fen: &[u8]) -> Result<(Board, Bitboard), ParseFenError> {
+    let err = |reason, offset| ParseFenError::new(ParseFenErrorKind::InvalidBoard, reason, offset);
+
     let mut promoted = Bitboard(0);
     let mut board = Board::empty();
 
-    let mut rank = 7i8;
-    let mut file = 0i8;
-
-    let mut iter = board_fen.iter().copied().peekable();
-
-    while let Some(ch) = iter.next() {
-        if ch == b'/' && file == 8 {
-            file = 0;
-            rank -= 1;
-            if rank < 0 {
-                return Err(ParseFenError::InvalidBoard);
+    let mut rank = Rank::Eighth;
+    let mut file = 0u32;
+
+    let mut iter = board_fen.iter().copied().enumerate().peekable();
+
+    while let Some((offset, ch)) = iter.next() {
+        if ch == b'/' {
+            if file != 8 {
+                return Err(err(
+                    ParseFenErrorReason::RankSquares {
+                        rank,
+                        squares: file,
+                    },
+                    offset,
+                ));
             }
+            rank = rank
+                .offset(-1)
+                .ok_or(err(ParseFenErrorReason::TooManyRanks, offset))?;
+            file = 0;
         } else if (b'1'..=b'8').contains(&ch) {
-            file += (ch - b'0') as i8;
+            file += u32::from(ch - b'0');
             if file > 8 {
-                return Err(ParseFenError::InvalidBoard);
+                return Err(err(
+                    ParseFenErrorReason::RankSquares {
+                        rank,
+                        squares: file,
+                    },
+                    offset,
+                ));
             }
         } else if let Some(piece) = Piece::from_char(char::from(ch)) {
-            match (File::try_from(file), Rank::try_from(rank)) {
-                (Ok(f), Ok(r)) => {
-                    let sq = Square::from_coords(f, r);
-                    let is_promoted = iter.peek() == Some(&b'~');
-                    if is_promoted {
-                        promoted.add(sq);
-                        iter.next();
-                    }
-                    board.set_piece_at(sq, piece);
-                }
-                _ => return Err(ParseFenError::InvalidBoard),
+            if file >= 8 {
+                return Err(err(
+                    ParseFenErrorReason::RankSquares {
+                        rank,
+                        squares: file + 1,
+                    },
+                    offset,
+                ));
+            }
+            let sq = Square::from_coords(File::new(file), rank);
+            if iter.next_if(|&(_, ch)| ch == b'~').is_some() {
+                promoted.add(sq);
             }
+            board.set_piece_at(sq, piece);
             file += 1;
+        } else if ch.is_ascii_alphabetic() {
+            return Err(err(
+                ParseFenErrorReason::UnknownPieceLetter(char::from(ch)),
+                offset,
+            ));
         } else {
-            return Err(ParseFenError::InvalidBoard);
+            return Err(err(ParseFenErrorReason::UnexpectedByte(ch), offset));
         }
     }
 
-    if rank == 0 && file == 8 {
-        Ok((board, promoted))
+    if file != 8 {
+        Err(err(
+            ParseFenErrorReason::RankSquares {
+                rank,
+                squares: file,
+            },
+            board_fen.len(),
+        ))
+    } else if rank != Rank::First {
+        Err(err(
+            ParseFenErrorReason::TooFewRanks {
+                ranks: 8 - u32::from(rank),
+            },
+            board_fen.len(),
+        ))
     } else {
-        Err(ParseFenError::InvalidBoard)
+        Ok((board, promoted))
     }
 }
 
@@ -236,15 +431,41 @@ fn parse_remaining_checks(s: &[u8]) -> Option<ByColor<RemainingChecks>> {
     })
 }
 
-fn parse_pockets(s: &[u8]) -> Option<ByColor<ByRole<u8>>> {
+fn parse_pockets(s: &[u8]) -> Result<ByColor<ByRole<u8>>, ParseFenError> {
+    let err = |reason, offset| ParseFenError::new(ParseFenErrorKind::InvalidPocket, reason, offset);
+
     if s.len() > 64 {
-        return None;
+        return Err(err(ParseFenErrorReason::TooManyPocketPieces, 64));
     }
     let mut result = ByColor::<ByRole<u8>>::default();
-    for ch in s {
-        *result.piece_mut(Piece::from_char(char::from(*ch))?) += 1;
+    for (offset, &ch) in s.iter().enumerate() {
+        let piece = Piece::from_char(char::from(ch)).ok_or(err(
+            if ch.is_ascii_alphabetic() {
+                ParseFenErrorReason::UnknownPieceLetter(char::from(ch))
+            } else {
+                ParseFenErrorReason::UnexpectedByte(ch)
+            },
+            offset,
+        ))?;
+        if piece.role == Role::King {
+            return Err(err(ParseFenErrorReason::PocketKing, offset));
+        }
+        *result.piece_mut(piece) += 1;
     }
-    Some(result)
+    Ok(result)
+}
+
+/// Splits a FEN into its non-empty parts, separated by spaces or
+/// underscores, along with their byte offsets.
+fn split_parts(fen: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut offset = 0;
+    fen.split(|ch| *ch == b' ' || *ch == b'_')
+        .map(move |part| {
+            let start = offset;
+            offset += part.len() + 1;
+            (start, part)
+        })
+        .filter(|(_, part)| !part.is_empty())
 }
 
 impl Board {
@@ -362,7 +583,8 @@ impl Fen {
     ///
     /// # Errors
     ///
-    /// Returns [`ParseFenError`] if any part is syntactically invalid.
+    /// Returns [`ParseFenError`] if any part is syntactically invalid. The
+    /// error points to the offending byte and says what is wrong with it.
     ///
     /// # Example
     ///
@@ -375,20 +597,30 @@ impl Fen {
     /// ```
     pub fn from_ascii(fen: &[u8]) -> Result<Fen, ParseFenError> {
         let mut result = Setup::empty();
-        let mut parts = fen
-            .split(|ch| *ch == b' ' || *ch == b'_')
-            .filter(|s| !s.is_empty());
+        let mut parts = split_parts(fen);
 
-        let board_part = parts.next().ok_or(ParseFenError::InvalidFen)?;
+        let (board_offset, board_part) = parts.next().ok_or(ParseFenError::new(
+            ParseFenErrorKind::InvalidFen,
+            ParseFenErrorReason::MissingBoard,
+            fen.len(),
+        ))?;
 
         let (board_part, pocket_part) = if board_part.ends_with(b"]") {
             // format: ...[pocket]
-            let split_point = board_part
-                .iter()
-                .position(|ch| *ch == b'[')
-                .ok_or(ParseFenError::InvalidBoard)?;
+            let split_point =
+                board_part
+                    .iter()
+                    .position(|ch| *ch == b'[')
+                    .ok_or(ParseFenError::new(
+                        ParseFenErrorKind::InvalidBoard,
+                        ParseFenErrorReason::UnopenedPocket,
+                        board_offset + board_part.len() - 1,
+                    ))?;
             let pocket_part = &board_part[(split_point + 1)..(board_part.len() - 1)];
-            (&board_part[..split_point], Some(pocket_part))
+            (
+                &board_part[..split_point],
+                Some((split_point + 1, pocket_part)),
+            )
         } else if let Some(split_point) = board_part
             .iter()
             .enumerate()
@@ -398,32 +630,41 @@ impl Fen {
             // format: .../pocket
             (
                 &board_part[..split_point],
-                Some(&board_part[(split_point + 1)..]),
+                Some((split_point + 1, &board_part[(split_point + 1)..])),
             )
         } else {
             (board_part, None)
         };
 
-        let (board, promoted) = parse_board_fen(board_part)?;
+        let (board, promoted) = parse_board_fen(board_part).map_err(|e| e.shifted(board_offset))?;
         result.board = board;
         result.promoted = promoted;
 
-        if let Some(pocket_part) = pocket_part {
-            result.pockets = Some(parse_pockets(pocket_part).ok_or(ParseFenError::InvalidPocket)?);
+        if let Some((pocket_offset, pocket_part)) = pocket_part {
+            result.pockets = Some(
+                parse_pockets(pocket_part).map_err(|e| e.shifted(board_offset + pocket_offset))?,
+            );
         }
 
         result.turn = match parts.next() {
-            Some(b"w") | None => Color::White,
-            Some(b"b") => Color::Black,
-            Some(_) => return Err(ParseFenError::InvalidTurn),
+            Some((_, b"w")) | None => Color::White,
+            Some((_, b"b")) => Color::Black,
+            Some((offset, _)) => {
+                return Err(ParseFenError::new(
+                    ParseFenErrorKind::InvalidTurn,
+                    ParseFenErrorReason::ExpectedTurn,
+                    offset,
+                ))
+            }
         };
 
         match parts.next() {
-            Some(b"-") | None => (),
-            Some(castling_part) => {
+            Some((_, b"-")) | None => (),
+            Some((castling_offset, castling_part)) => {
                 result.castling_rights = castling_part
                     .iter()
-                    .map(|ch| {
+                    .enumerate()
+                    .map(|(offset, ch)| {
                         let color = Color::from_white(ch.is_ascii_uppercase());
                         let rooks_and_kings = result.board.by_color(color)
                             & (result.board.rooks() | result.board.kings())
@@ -438,8 +679,11 @@ impl Fen {
                                 .filter(|sq| result.board.rooks().contains(*sq))
                                 .unwrap_or_else(|| Square::from_coords(File::A, color.backrank())),
                             file => Square::from_coords(
-                                File::from_char(char::from(file))
-                                    .ok_or(ParseFenError::InvalidCastling)?,
+                                File::from_char(char::from(file)).ok_or(ParseFenError::new(
+                                    ParseFenErrorKind::InvalidCastling,
+                                    ParseFenErrorReason::UnexpectedByte(*ch),
+                                    castling_offset + offset,
+                                ))?,
                                 color.backrank(),
                             ),
                         })
@@ -448,57 +692,80 @@ impl Fen {
 
                 for color in Color::ALL {
                     if (result.castling_rights & color.backrank()).count() > 2 {
-                        return Err(ParseFenError::InvalidCastling);
+                        return Err(ParseFenError::new(
+                            ParseFenErrorKind::InvalidCastling,
+                            ParseFenErrorReason::TooManyCastlingRights(color),
+                            castling_offset,
+                        ));
                     }
                 }
             }
         }
 
         match parts.next() {
-            Some(b"-") | None => (),
-            Some(ep_part) => {
-                result.ep_square =
-                    Some(Square::from_ascii(ep_part).map_err(|_| ParseFenError::InvalidEpSquare)?);
+            Some((_, b"-")) | None => (),
+            Some((ep_offset, ep_part)) => {
+                result.ep_square = Some(Square::from_ascii(ep_part).map_err(|_| {
+                    ParseFenError::new(
+                        ParseFenErrorKind::InvalidEpSquare,
+                        ParseFenErrorReason::ExpectedSquare,
+                        ep_offset,
+                    )
+                })?);
             }
         }
 
-        let halfmoves_part = if let Some(checks_part) = parts.next() {
+        let halfmoves_part = if let Some((checks_offset, checks_part)) = parts.next() {
             if let Some(remaining_checks) = parse_remaining_checks(checks_part) {
                 result.remaining_checks = Some(remaining_checks);
                 parts.next()
             } else {
-                Some(checks_part)
+                Some((checks_offset, checks_part))
             }
         } else {
             None
         };
 
-        if let Some(halfmoves_part) = halfmoves_part {
-            result.halfmoves = btoi::btou_saturating(halfmoves_part)
-                .map_err(|_| ParseFenError::InvalidHalfmoveClock)?;
+        if let Some((halfmoves_offset, halfmoves_part)) = halfmoves_part {
+            result.halfmoves = btoi::btou_saturating(halfmoves_part).map_err(|_| {
+                ParseFenError::new(
+                    ParseFenErrorKind::InvalidHalfmoveClock,
+                    ParseFenErrorReason::ExpectedNumber,
+                    halfmoves_offset,
+                )
+            })?;
         }
 
-        if let Some(fullmoves_part) = parts.next() {
-            let fullmoves = btoi::btou_saturating(fullmoves_part)
-                .map_err(|_| ParseFenError::InvalidFullmoves)?;
+        if let Some((fullmoves_offset, fullmoves_part)) = parts.next() {
+            let fullmoves = btoi::btou_saturating(fullmoves_part).map_err(|_| {
+                ParseFenError::new(
+                    ParseFenErrorKind::InvalidFullmoves,
+                    ParseFenErrorReason::ExpectedNumber,
+                    fullmoves_offset,
+                )
+            })?;
             result.fullmoves = NonZeroU32::new(fullmoves).unwrap_or(NonZeroU32::MIN);
         }
 
-        let last_part = if let Some(checks_part) = parts.next() {
+        let last_part = if let Some((checks_offset, checks_part)) = parts.next() {
             if result.remaining_checks.is_some() {
-                Some(checks_part) // got checks earlier
+                Some((checks_offset, checks_part)) // got checks earlier
             } else if let Some(remaining_checks) = parse_remaining_checks(checks_part) {
                 result.remaining_checks = Some(remaining_checks);
                 parts.next()
             } else {
-                Some(checks_part)
+                Some((checks_offset, checks_part))
             }
         } else {
             None
         };
 
-        if last_part.is_some() {
-            Err(ParseFenError::InvalidFen)
+        if let Some((offset, _)) = last_part {
+            Err(ParseFenError::new(
+                ParseFenErrorKind::InvalidFen,
+                ParseFenErrorReason::UnexpectedPart,
+                offset,
+            ))
         } else {
             Ok(Fen(result))
         }
@@ -580,17 +847,66 @@ impl Display for Fen {
     }
 }
 
-/// An EPD like `rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Fen {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Fen {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Fen, D::Error> {
+        crate::util::deserialize_ascii(deserializer, "FEN", |s| Fen::from_ascii(s).ok())
+    }
+}
+
+/// An EPD like `rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm e4;`.
+///
+/// Halfmove clock and fullmove number are not part of EPDs. Instead, an EPD
+/// may carry a list of operations (`EpdOperation`, requires the `alloc`
+/// feature, otherwise operations are skipped when parsing).
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
-pub struct Epd(Setup);
+pub struct Epd {
+    setup: Setup,
+    #[cfg(feature = "alloc")]
+    operations: Vec<EpdOperation>,
+}
 
 impl Epd {
     pub const fn empty() -> Epd {
-        Epd(Setup::empty())
+        Epd::from_setup(Setup::empty())
     }
 
+    /// Parses an EPD, including operations like `bm Nf3; id "test";`.
+    ///
+    /// Like [`Fen::from_ascii()`], this is lenient and also accepts
+    /// halfmove clock and fullmove number, but ignores them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseFenError`] if any part is syntactically invalid.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")] {
+    /// use shakmaty::fen::{Epd, EpdOperation};
+    ///
+    /// let epd = Epd::from_ascii(b"8/8/8/8/8/8/8/k6K w - - bm Kg2; id \"test\";")?;
+    /// assert_eq!(epd.operation("id"), Some(&EpdOperation::Id("test".to_owned())));
+    /// # }
+    /// # Ok::<_, shakmaty::fen::ParseFenError>(())
+    /// ```
     pub fn from_ascii(epd: &[u8]) -> Result<Epd, ParseFenError> {
-        Ok(Epd::from_setup(Fen::from_ascii(epd)?.into_setup()))
+        let (fen, operations) = split_operations(epd);
+        #[cfg(not(feature = "alloc"))]
+        let _ = operations;
+        Ok(Epd {
+            #[cfg(feature = "alloc")]
+            operations: parse_operations(operations).map_err(|e| e.shifted(fen.len()))?,
+            ..Epd::from_setup(Fen::from_ascii(fen)?.into_setup())
+        })
     }
 
     pub const fn from_setup(mut setup: Setup) -> Epd {
@@ -599,7 +915,11 @@ impl Epd {
             Some(num) => num,
             _ => unreachable!(),
         };
-        Epd(setup)
+        Epd {
+            setup,
+            #[cfg(feature = "alloc")]
+            operations: Vec::new(),
+        }
     }
 
     pub fn from_position<P: Position>(pos: P, mode: EnPassantMode) -> Epd {
@@ -607,19 +927,109 @@ impl Epd {
     }
 
     pub const fn as_setup(&self) -> &Setup {
-        &self.0
+        &self.setup
     }
 
+    /// Converts to a setup.
+    #[cfg(not(feature = "alloc"))]
     pub const fn into_setup(self) -> Setup {
-        self.0
+        self.setup
+    }
+
+    /// Converts to a setup, discarding the operations.
+    ///
+    /// Not `const` with the `alloc` feature, because the operations have to
+    /// be dropped.
+    #[cfg(feature = "alloc")]
+    pub fn into_setup(self) -> Setup {
+        self.setup
     }
 
     pub fn into_position<P: FromSetup>(self, mode: CastlingMode) -> Result<P, PositionError<P>> {
         P::from_setup(self.into_setup(), mode)
     }
 
+    /// The operations, in order.
+    #[cfg(feature = "alloc")]
+    pub fn operations(&self) -> &[EpdOperation] {
+        &self.operations
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn operations_mut(&mut self) -> &mut Vec<EpdOperation> {
+        &mut self.operations
+    }
+
+    /// The first operation with the given opcode, if any.
+    #[cfg(feature = "alloc")]
+    pub fn operation(&self, opcode: &str) -> Option<&EpdOperation> {
+        self.operations.iter().find(|op| op.opcode() == opcode)
+    }
+
+    /// Resolves the moves of the `bm` (best move) operation against `pos`,
+    /// which should be the position of this EPD. Returns an empty list if
+    /// there is no such operation.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SanError`] if any of the moves is illegal or ambiguous.
+    #[cfg(feature = "alloc")]
+    pub fn best_moves<P: Position>(&self, pos: &P) -> Result<Vec<Move>, SanError> {
+        match self.operation("bm") {
+            Some(EpdOperation::BestMoves(sans)) => {
+                sans.iter().map(|san| san.san.to_move(pos)).collect()
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Resolves the moves of the `am` (avoid move) operation against `pos`,
+    /// which should be the position of this EPD. Returns an empty list if
+    /// there is no such operation.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SanError`] if any of the moves is illegal or ambiguous.
+    #[cfg(feature = "alloc")]
+    pub fn avoid_moves<P: Position>(&self, pos: &P) -> Result<Vec<Move>, SanError> {
+        match self.operation("am") {
+            Some(EpdOperation::AvoidMoves(sans)) => {
+                sans.iter().map(|san| san.san.to_move(pos)).collect()
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Resolves the moves of the `pv` (predicted variation) operation,
+    /// playing them one after another, starting from `pos`, which should be
+    /// the position of this EPD. Returns an empty list if there is no such
+    /// operation.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SanError`] if any of the moves is illegal or ambiguous.
+    #[cfg(feature = "alloc")]
+    pub fn predicted_variation<P: Position + Clone>(&self, pos: &P) -> Result<Vec<Move>, SanError> {
+        let mut moves = Vec::new();
+        if let Some(EpdOperation::PredictedVariation(sans)) = self.operation("pv") {
+            let mut pos = pos.clone();
+            for san in sans {
+                let m = san.san.to_move(&pos)?;
+                pos.play_unchecked(&m);
+                moves.push(m);
+            }
+        }
+        Ok(moves)
+    }
+
     fn append_to<W: AppendAscii>(&self, f: &mut W) -> Result<(), W::Error> {
-        append_epd(f, &self.0)
+        append_epd(f, &self.setup)?;
+        #[cfg(feature = "alloc")]
+        for op in &self.operations {
+            f.append_ascii(' ')?;
+            op.append_to(f)?;
+        }
+        Ok(())
     }
 
     #[cfg(feature = "alloc")]
@@ -664,6 +1074,349 @@ impl Display for Epd {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Epd {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Epd {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Epd, D::Error> {
+        crate::util::deserialize_ascii(deserializer, "EPD", |s| Epd::from_ascii(s).ok())
+    }
+}
+
+/// Splits an EPD into the FEN-like fields and the operations, which start
+/// with the first field beginning with a letter after the first four
+/// fields.
+fn split_operations(epd: &[u8]) -> (&[u8], &[u8]) {
+    let is_separator = |ch: u8| ch == b' ' || ch == b'_';
+    let mut field = 0;
+    let mut i = 0;
+    loop {
+        while epd.get(i).copied().is_some_and(is_separator) {
+            i += 1;
+        }
+        match epd.get(i) {
+            None => return (epd, b""),
+            Some(ch) if field >= 4 && ch.is_ascii_alphabetic() => return (&epd[..i], &epd[i..]),
+            Some(_) => (),
+        }
+        while epd.get(i).is_some_and(|&ch| !is_separator(ch)) {
+            i += 1;
+        }
+        field += 1;
+    }
+}
+
+/// Index of an EPD comment operation, `c0` to `c9`.
+///
+/// # Examples
+///
+/// ```
+/// use shakmaty::fen::CommentIndex;
+///
+/// assert_eq!(u8::from(CommentIndex::new(7)), 7);
+/// assert!(CommentIndex::try_from(10).is_err());
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash)]
+pub struct CommentIndex(u8);
+
+#[cfg(feature = "alloc")]
+impl CommentIndex {
+    /// Constructs a new [`CommentIndex`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > 9`.
+    #[track_caller]
+    pub const fn new(index: u8) -> CommentIndex {
+        assert!(index <= 9);
+        CommentIndex(index)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<CommentIndex> for u8 {
+    fn from(CommentIndex(index): CommentIndex) -> u8 {
+        index
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<CommentIndex> for usize {
+    fn from(CommentIndex(index): CommentIndex) -> usize {
+        usize::from(index)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl TryFrom<u8> for CommentIndex {
+    type Error = core::num::TryFromIntError;
+
+    fn try_from(index: u8) -> Result<CommentIndex, Self::Error> {
+        if index <= 9 {
+            Ok(CommentIndex(index))
+        } else {
+            Err(crate::util::out_of_range_error())
+        }
+    }
+}
+
+/// An operation of an [`Epd`], like `bm Nf3;`.
+///
+/// String operands can not contain double quotes.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum EpdOperation {
+    /// `bm`: Best moves.
+    BestMoves(Vec<SanPlus>),
+    /// `am`: Moves to avoid.
+    AvoidMoves(Vec<SanPlus>),
+    /// `id`: Position identifier.
+    Id(String),
+    /// `c0` to `c9`: Comments.
+    Comment { index: CommentIndex, text: String },
+    /// `hmvc`: Halfmove clock.
+    HalfmoveClock(u32),
+    /// `fmvn`: Fullmove number.
+    FullmoveNumber(u32),
+    /// `pv`: Predicted variation.
+    PredictedVariation(Vec<SanPlus>),
+    /// `acd`: Analysis count depth.
+    AnalysisCountDepth(u32),
+    /// `ce`: Centipawn evaluation, from the point of view of the side to
+    /// move.
+    CentipawnEvaluation(i32),
+    /// Any other operation, with operands kept as they are (including
+    /// quotes).
+    Other {
+        opcode: String,
+        operands: Vec<String>,
+    },
+}
+
+#[cfg(feature = "alloc")]
+impl EpdOperation {
+    pub fn opcode(&self) -> &str {
+        match *self {
+            EpdOperation::BestMoves(_) => "bm",
+            EpdOperation::AvoidMoves(_) => "am",
+            EpdOperation::Id(_) => "id",
+            EpdOperation::Comment { index, .. } => {
+                ["c0", "c1", "c2", "c3", "c4", "c5", "c6", "c7", "c8", "c9"][usize::from(index)]
+            }
+            EpdOperation::HalfmoveClock(_) => "hmvc",
+            EpdOperation::FullmoveNumber(_) => "fmvn",
+            EpdOperation::PredictedVariation(_) => "pv",
+            EpdOperation::AnalysisCountDepth(_) => "acd",
+            EpdOperation::CentipawnEvaluation(_) => "ce",
+            EpdOperation::Other { ref opcode, .. } => opcode,
+        }
+    }
+
+    fn from_opcode_and_operands(opcode: &[u8], operands: &[(&[u8], bool)]) -> Option<EpdOperation> {
+        fn sans(operands: &[(&[u8], bool)]) -> Option<Vec<SanPlus>> {
+            if operands.is_empty() {
+                return None;
+            }
+            operands
+                .iter()
+                .map(|&(operand, quoted)| {
+                    if quoted {
+                        None
+                    } else {
+                        SanPlus::from_ascii(operand).ok()
+                    }
+                })
+                .collect()
+        }
+
+        fn single<'a>(operands: &[(&'a [u8], bool)]) -> Option<&'a [u8]> {
+            match *operands {
+                [(operand, _)] => Some(operand),
+                _ => None,
+            }
+        }
+
+        fn string(operands: &[(&[u8], bool)]) -> Option<String> {
+            core::str::from_utf8(single(operands)?)
+                .map(String::from)
+                .ok()
+        }
+
+        fn unsigned(operands: &[(&[u8], bool)]) -> Option<u32> {
+            btoi::btou(single(operands)?).ok()
+        }
+
+        Some(match opcode {
+            b"bm" => EpdOperation::BestMoves(sans(operands)?),
+            b"am" => EpdOperation::AvoidMoves(sans(operands)?),
+            b"pv" => EpdOperation::PredictedVariation(sans(operands)?),
+            b"id" => EpdOperation::Id(string(operands)?),
+            &[b'c', digit] if digit.is_ascii_digit() => EpdOperation::Comment {
+                index: CommentIndex::new(digit - b'0'),
+                text: string(operands)?,
+            },
+            b"hmvc" => EpdOperation::HalfmoveClock(unsigned(operands)?),
+            b"fmvn" => EpdOperation::FullmoveNumber(unsigned(operands)?),
+            b"acd" => EpdOperation::AnalysisCountDepth(unsigned(operands)?),
+            b"ce" => EpdOperation::CentipawnEvaluation(btoi::btoi(single(operands)?).ok()?),
+            _ => EpdOperation::Other {
+                opcode: core::str::from_utf8(opcode).ok()?.into(),
+                operands: operands
+                    .iter()
+                    .map(|&(operand, quoted)| {
+                        let operand = core::str::from_utf8(operand).ok()?;
+                        Some(if quoted {
+                            alloc::format!("\"{operand}\"")
+                        } else {
+                            operand.into()
+                        })
+                    })
+                    .collect::<Option<_>>()?,
+            },
+        })
+    }
+
+    fn append_to<W: AppendAscii>(&self, f: &mut W) -> Result<(), W::Error> {
+        fn append_quoted<W: AppendAscii>(f: &mut W, s: &str) -> Result<(), W::Error> {
+            f.append_ascii(' ')?;
+            f.append_ascii('"')?;
+            f.append_str(s)?;
+            f.append_ascii('"')
+        }
+
+        f.append_str(self.opcode())?;
+        match *self {
+            EpdOperation::BestMoves(ref sans)
+            | EpdOperation::AvoidMoves(ref sans)
+            | EpdOperation::PredictedVariation(ref sans) => {
+                for san in sans {
+                    f.append_ascii(' ')?;
+                    san.append_to(f)?;
+                }
+            }
+            EpdOperation::Id(ref text) | EpdOperation::Comment { ref text, .. } => {
+                append_quoted(f, text)?;
+            }
+            EpdOperation::HalfmoveClock(n)
+            | EpdOperation::FullmoveNumber(n)
+            | EpdOperation::AnalysisCountDepth(n) => {
+                f.append_ascii(' ')?;
+                f.append_u32(n)?;
+            }
+            EpdOperation::CentipawnEvaluation(cp) => {
+                f.append_ascii(' ')?;
+                if cp < 0 {
+                    f.append_ascii('-')?;
+                }
+                f.append_u32(cp.unsigned_abs())?;
+            }
+            EpdOperation::Other { ref operands, .. } => {
+                for operand in operands {
+                    f.append_ascii(' ')?;
+                    f.append_str(operand)?;
+                }
+            }
+        }
+        f.append_ascii(';')
+    }
+
+    pub fn append_to_string(&self, s: &mut alloc::string::String) {
+        let _ = self.append_to(s);
+    }
+
+    pub fn append_ascii_to(&self, buf: &mut alloc::vec::Vec<u8>) {
+        let _ = self.append_to(buf);
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_ascii_to<W: std::io::Write>(&self, w: W) -> std::io::Result<()> {
+        self.append_to(&mut crate::util::WriteAscii(w))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Display for EpdOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.append_to(f)
+    }
+}
+
+#[cfg(feature = "alloc")]
+fn parse_operations(input: &[u8]) -> Result<Vec<EpdOperation>, ParseFenError> {
+    fn trim_start(s: &[u8]) -> &[u8] {
+        let n = s.iter().take_while(|ch| ch.is_ascii_whitespace()).count();
+        &s[n..]
+    }
+
+    let err =
+        |reason, offset| ParseFenError::new(ParseFenErrorKind::InvalidOperation, reason, offset);
+    let offset = |s: &[u8]| input.len() - s.len();
+
+    let mut s = input;
+    let mut operations = Vec::new();
+    loop {
+        s = trim_start(s);
+        if s.is_empty() {
+            break;
+        }
+
+        let opcode_offset = offset(s);
+        let opcode_len = s
+            .iter()
+            .position(|&ch| ch.is_ascii_whitespace() || ch == b';')
+            .unwrap_or(s.len());
+        let opcode = &s[..opcode_len];
+        if !opcode.first().is_some_and(u8::is_ascii_alphabetic)
+            || !opcode
+                .iter()
+                .all(|&ch| ch.is_ascii_alphanumeric() || ch == b'_')
+        {
+            return Err(err(ParseFenErrorReason::InvalidOpcode, opcode_offset));
+        }
+        s = &s[opcode_len..];
+
+        let mut operands = Vec::new();
+        loop {
+            s = trim_start(s);
+            match s.first() {
+                None => break, // Be lenient about missing final semicolon
+                Some(b';') => {
+                    s = &s[1..];
+                    break;
+                }
+                Some(b'"') => {
+                    let len = s[1..]
+                        .iter()
+                        .position(|&ch| ch == b'"')
+                        .ok_or(err(ParseFenErrorReason::UnterminatedString, offset(s)))?;
+                    operands.push((&s[1..1 + len], true));
+                    s = &s[len + 2..];
+                }
+                Some(_) => {
+                    let len = s
+                        .iter()
+                        .position(|&ch| ch.is_ascii_whitespace() || ch == b';')
+                        .unwrap_or(s.len());
+                    operands.push((&s[..len], false));
+                    s = &s[len..];
+                }
+            }
+        }
+
+        operations.push(
+            EpdOperation::from_opcode_and_operands(opcode, &operands)
+                .ok_or(err(ParseFenErrorReason::InvalidOperands, opcode_offset))?,
+        );
+    }
+    Ok(operations)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -696,34 +1449,186 @@ mod tests {
 
     #[test]
     fn test_invalid_fen() {
-        assert_eq!("".parse::<Fen>().unwrap_err(), ParseFenError::InvalidFen);
+        assert_eq!(
+            "".parse::<Fen>().unwrap_err().kind(),
+            ParseFenErrorKind::InvalidFen
+        );
 
         assert_eq!(
             "8/8/8/8/8/8/8/8 w · - 0 1" // not ascii
                 .parse::<Fen>()
-                .unwrap_err(),
-            ParseFenError::InvalidCastling
+                .unwrap_err()
+                .kind(),
+            ParseFenErrorKind::InvalidCastling
         );
 
         assert_eq!(
             "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQQKBNR w cq - 0P1" // syntax
                 .parse::<Fen>()
-                .unwrap_err(),
-            ParseFenError::InvalidCastling
+                .unwrap_err()
+                .kind(),
+            ParseFenErrorKind::InvalidCastling
         );
 
         assert_eq!(
             "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w  - 0 1" // double space
                 .parse::<Fen>()
-                .unwrap_err(),
-            ParseFenError::InvalidEpSquare
+                .unwrap_err()
+                .kind(),
+            ParseFenErrorKind::InvalidEpSquare
         );
 
         assert_eq!(
             "4k2r/8/8/8/8/8/8/RR2K2R w KBQk - 0 1" // triple castling rights
                 .parse::<Fen>()
-                .unwrap_err(),
-            ParseFenError::InvalidCastling
+                .unwrap_err()
+                .kind(),
+            ParseFenErrorKind::InvalidCastling
+        );
+    }
+
+    #[test]
+    fn test_fen_error_details() {
+        for (fen, kind, reason, offset) in [
+            (
+                "  ",
+                ParseFenErrorKind::InvalidFen,
+                ParseFenErrorReason::MissingBoard,
+                2,
+            ),
+            (
+                "8/8/8/8/8/ppppppppp/8/8",
+                ParseFenErrorKind::InvalidBoard,
+                ParseFenErrorReason::RankSquares {
+                    rank: Rank::Third,
+                    squares: 9,
+                },
+                18,
+            ),
+            (
+                "8/8/8/8/8/7/8/8",
+                ParseFenErrorKind::InvalidBoard,
+                ParseFenErrorReason::RankSquares {
+                    rank: Rank::Third,
+                    squares: 7,
+                },
+                11,
+            ),
+            (
+                "8/8/8",
+                ParseFenErrorKind::InvalidBoard,
+                ParseFenErrorReason::TooFewRanks { ranks: 3 },
+                5,
+            ),
+            (
+                "8/8/8/8/3X4/8/8/8",
+                ParseFenErrorKind::InvalidBoard,
+                ParseFenErrorReason::UnknownPieceLetter('X'),
+                9,
+            ),
+            (
+                "8/8/8/8/8/8/8/7~",
+                ParseFenErrorKind::InvalidBoard,
+                ParseFenErrorReason::UnexpectedByte(b'~'),
+                15,
+            ),
+            (
+                "8/8/8/8/8/8/8/8Q]",
+                ParseFenErrorKind::InvalidBoard,
+                ParseFenErrorReason::UnopenedPocket,
+                16,
+            ),
+            (
+                "8/8/8/8/8/8/8/8[QqK]",
+                ParseFenErrorKind::InvalidPocket,
+                ParseFenErrorReason::PocketKing,
+                18,
+            ),
+            (
+                "8/8/8/8/8/8/8/8/Qx",
+                ParseFenErrorKind::InvalidPocket,
+                ParseFenErrorReason::UnknownPieceLetter('x'),
+                17,
+            ),
+            (
+                "8/8/8/8/8/8/8/8 x",
+                ParseFenErrorKind::InvalidTurn,
+                ParseFenErrorReason::ExpectedTurn,
+                16,
+            ),
+            (
+                "8/8/8/8/8/8/8/8 w KQ1",
+                ParseFenErrorKind::InvalidCastling,
+                ParseFenErrorReason::UnexpectedByte(b'1'),
+                20,
+            ),
+            (
+                "4k2r/8/8/8/8/8/8/RR2K2R w KBQk",
+                ParseFenErrorKind::InvalidCastling,
+                ParseFenErrorReason::TooManyCastlingRights(Color::White),
+                26,
+            ),
+            (
+                "8/8/8/8/8/8/8/8 w - e9",
+                ParseFenErrorKind::InvalidEpSquare,
+                ParseFenErrorReason::ExpectedSquare,
+                20,
+            ),
+            (
+                "8/8/8/8/8/8/8/8 w - - x 1",
+                ParseFenErrorKind::InvalidHalfmoveClock,
+                ParseFenErrorReason::ExpectedNumber,
+                22,
+            ),
+            (
+                "8/8/8/8/8/8/8/8_w_-_-_0_x",
+                ParseFenErrorKind::InvalidFullmoves,
+                ParseFenErrorReason::ExpectedNumber,
+                24,
+            ),
+            (
+                "8/8/8/8/8/8/8/8 w - - 0 1 x",
+                ParseFenErrorKind::InvalidFen,
+                ParseFenErrorReason::UnexpectedPart,
+                26,
+            ),
+        ] {
+            let err = fen.parse::<Fen>().unwrap_err();
+            assert_eq!(err.kind(), kind, "{fen}");
+            assert_eq!(err.reason(), reason, "{fen}");
+            assert_eq!(err.offset(), offset, "{fen}");
+        }
+
+        let err = "8/8/8/8/8/8/8/8/8".parse::<Board>().unwrap_err();
+        assert_eq!(err.reason(), ParseFenErrorReason::TooManyRanks);
+        assert_eq!(err.offset(), 15);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_fen_error_display() {
+        use alloc::string::ToString as _;
+
+        assert_eq!(
+            "8/8/8/8/8/ppppppppp/8/8 w - - 0 1"
+                .parse::<Fen>()
+                .unwrap_err()
+                .to_string(),
+            "invalid board part in fen at byte 18: rank 3 has 9 squares"
+        );
+        assert_eq!(
+            "8/8/8/8/8/8/8/8[K] w - - 0 1"
+                .parse::<Fen>()
+                .unwrap_err()
+                .to_string(),
+            "invalid pocket in fen at byte 16: pocket contains a king"
+        );
+        assert_eq!(
+            "8/8/8/8/8/8/8/8 w · - 0 1"
+                .parse::<Fen>()
+                .unwrap_err()
+                .to_string(),
+            "invalid castling part in fen at byte 18: unexpected byte 0xc2"
         );
     }
 
@@ -810,4 +1715,109 @@ mod tests {
             "rRpppppp/8/8/8/8/8/PPPPPPBN/PPRQKBNR w KA - 0 1"
         );
     }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_epd_operations() {
+        use alloc::{string::ToString as _, vec};
+
+        use crate::{san::San, Chess};
+
+        let wac = "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\"; c0 \"a; b\"; hmvc 0; fmvn 1; acd 12; ce -30; pv Qg6 fxg6; foo bar \"baz qux\";";
+        let epd: Epd = wac.parse().expect("valid epd");
+        assert_eq!(epd.to_string(), wac);
+        assert_eq!(
+            epd.operations()[1..7],
+            [
+                EpdOperation::Id("WAC.001".into()),
+                EpdOperation::Comment {
+                    index: CommentIndex::new(0),
+                    text: "a; b".into()
+                },
+                EpdOperation::HalfmoveClock(0),
+                EpdOperation::FullmoveNumber(1),
+                EpdOperation::AnalysisCountDepth(12),
+                EpdOperation::CentipawnEvaluation(-30),
+            ]
+        );
+        assert_eq!(
+            epd.operation("foo"),
+            Some(&EpdOperation::Other {
+                opcode: "foo".into(),
+                operands: vec!["bar".into(), "\"baz qux\"".into()],
+            })
+        );
+
+        let pos: Chess = epd
+            .clone()
+            .into_position(CastlingMode::Standard)
+            .expect("legal position");
+        let best_moves = epd.best_moves(&pos).expect("legal moves");
+        assert_eq!(best_moves.len(), 1);
+        assert_eq!(San::from_move(&pos, &best_moves[0]).to_string(), "Qg6");
+        assert_eq!(epd.avoid_moves(&pos), Ok(vec![]));
+        assert_eq!(epd.predicted_variation(&pos).expect("legal pv").len(), 2);
+
+        let bad: Epd = "8/8/8/8/8/8/8/k6K w - - bm Qg6;"
+            .parse()
+            .expect("valid epd");
+        let pos: Chess = bad
+            .clone()
+            .into_position(CastlingMode::Standard)
+            .expect("legal position");
+        assert_eq!(bad.best_moves(&pos), Err(SanError::IllegalSan));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_invalid_epd_operations() {
+        for epd in [
+            "8/8/8/8/8/8/8/k6K w - - bm;",
+            "8/8/8/8/8/8/8/k6K w - - bm \"Kg2\";",
+            "8/8/8/8/8/8/8/k6K w - - id \"unterminated;",
+            "8/8/8/8/8/8/8/k6K w - - hmvc x;",
+            "8/8/8/8/8/8/8/k6K w - - ce 1 2;",
+        ] {
+            assert_eq!(
+                epd.parse::<Epd>().map_err(|err| err.kind()),
+                Err(ParseFenErrorKind::InvalidOperation),
+                "{epd}"
+            );
+        }
+
+        let err = "8/8/8/8/8/8/8/k6K w - - id \"unterminated;"
+            .parse::<Epd>()
+            .unwrap_err();
+        assert_eq!(err.reason(), ParseFenErrorReason::UnterminatedString);
+        assert_eq!(err.offset(), 27);
+    }
+
+    #[test]
+    fn test_epd_with_counters() {
+        let epd: Epd = "8/8/8/8/8/8/8/k6K b - - 12 34 bm Ka2;"
+            .parse()
+            .expect("valid epd");
+        assert_eq!(epd.as_setup().turn, Color::Black);
+        assert_eq!(epd.as_setup().halfmoves, 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        use serde_test::{assert_de_tokens_error, assert_tokens, Configure as _, Token};
+
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+        assert_tokens(&fen.parse::<Fen>().expect("valid fen"), &[Token::Str(fen)]);
+        assert_tokens(
+            &fen.parse::<Fen>()
+                .expect("valid fen")
+                .into_setup()
+                .readable(),
+            &[Token::Str(fen)],
+        );
+        assert_de_tokens_error::<Fen>(
+            &[Token::Str("8/8/8 w - -")],
+            "invalid value: string \"8/8/8 w - -\", expected FEN",
+        );
+    }
 }
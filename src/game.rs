@@ -0,0 +1,456 @@
+//! Game records with repetition detection and draw claims.
+//!
+//! A [`Game`] holds a starting position and the moves played from it. It
+//! tracks the history needed for the rules that depend on more than the
+//! current position:
+//!
+//! * Threefold repetition and the fifty-move rule allow a player to
+//!   [claim a draw](Game::draw_claim()), either in the current position or
+//!   [by announcing the move](Game::draw_claim_with()) that leads to it
+//!   (FIDE Laws of Chess, articles 9.2 and 9.3).
+//! * Fivefold repetition and the seventy-five-move rule end the game
+//!   [automatically](Game::outcome()) (article 9.6).
+//!
+//! Positions are compared by their [`Zobrist64`] hash, with
+//! [`EnPassantMode::Legal`], so that positions are the same in the sense of
+//! article 9.2.2. History before the most recent
+//! [irreversible](Position::is_irreversible()) move is never searched.
+//!
+//! # Examples
+//!
+//! ```
+//! use shakmaty::{game::{DrawClaim, Game}, san::{ParseSanError, San, SanError}, Chess};
+//!
+//! let mut game = Game::new(Chess::default());
+//! for san in ["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1"] {
+//!     let m = san.parse::<San>()?.to_move(game.position())?;
+//!     game.play_unchecked(&m);
+//! }
+//!
+//! // The starting position has occurred twice. Black can claim a draw
+//! // by announcing the move that repeats it a third time.
+//! assert_eq!(game.draw_claim(), None);
+//! let ng8 = "Ng8".parse::<San>()?.to_move(game.position())?;
+//! assert_eq!(game.draw_claim_with(&ng8), Some(DrawClaim::ThreefoldRepetition));
+//! assert!(game.can_claim_draw());
+//!
+//! game.play_unchecked(&ng8);
+//! assert_eq!(game.repetitions(), 3);
+//! assert_eq!(game.draw_claim(), Some(DrawClaim::ThreefoldRepetition));
+//! # #[derive(Debug)] struct CommonError;
+//! # impl From<ParseSanError> for CommonError { fn from(_: ParseSanError) -> Self { Self } }
+//! # impl From<SanError> for CommonError { fn from(_: SanError) -> Self { Self } }
+//! # Ok::<_, CommonError>(())
+//! ```
+
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    zobrist::{Zobrist, Zobrist64},
+    EnPassantMode, Move, Outcome, PlayError, Position,
+};
+
+/// Reason for a draw that a player can claim.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum DrawClaim {
+    /// The same position occurs for at least the third time.
+    ThreefoldRepetition,
+    /// The last 50 moves of each player were made without a pawn move or
+    /// capture.
+    FiftyMoves,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Ply {
+    zobrist: Zobrist64,
+    /// First ply since the last irreversible move.
+    reversible_since: usize,
+}
+
+/// A starting position and the moves played from it.
+#[derive(Clone, Debug)]
+pub struct Game<P> {
+    initial: P,
+    moves: Vec<Move>,
+    current: Zobrist<P, Zobrist64>,
+    plies: Vec<Ply>,
+}
+
+impl<P: Position + Clone> Game<P> {
+    /// Starts a game from the given position.
+    ///
+    /// Repetitions and the fifty-move rule only consider the moves played in
+    /// this game. The halfmove clock of the starting position is taken into
+    /// account.
+    pub fn new(pos: P) -> Game<P> {
+        let current = Zobrist::new(pos.clone(), EnPassantMode::Legal);
+        Game {
+            initial: pos,
+            moves: Vec::new(),
+            plies: vec![Ply {
+                zobrist: current.zobrist(),
+                reversible_since: 0,
+            }],
+            current,
+        }
+    }
+
+    /// The starting position.
+    pub fn initial(&self) -> &P {
+        &self.initial
+    }
+
+    /// The moves played so far.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// The current position.
+    pub fn position(&self) -> &P {
+        self.current.inner()
+    }
+
+    /// Consumes the game, returning the current position.
+    pub fn into_position(self) -> P {
+        self.current.into_inner()
+    }
+
+    /// The position after `ply` half-moves, or `None` if fewer moves have
+    /// been played. The position at ply `0` is the starting position.
+    ///
+    /// Replays the moves from the starting position.
+    pub fn position_at(&self, ply: usize) -> Option<P> {
+        let moves = self.moves.get(..ply)?;
+        let mut pos = self.initial.clone();
+        for m in moves {
+            pos.play_unchecked(m);
+        }
+        Some(pos)
+    }
+
+    /// Plays a move.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`PlayError`] with the unchanged game if the move is not
+    /// legal. You can use [`Game::play_unchecked()`] if you can guarantee
+    /// legality.
+    pub fn play(mut self, m: &Move) -> Result<Game<P>, PlayError<Game<P>>> {
+        if self.position().is_legal(m) {
+            self.play_unchecked(m);
+            Ok(self)
+        } else {
+            Err(PlayError {
+                m: m.clone(),
+                position: self,
+            })
+        }
+    }
+
+    /// Plays a move. It is the callers responsibility to ensure the move is
+    /// legal.
+    ///
+    /// # Panics
+    ///
+    /// Illegal moves can corrupt the state of the game and may
+    /// (or may not) panic or cause panics on future calls. Consider using
+    /// [`Game::play()`] if you cannot guarantee legality.
+    pub fn play_unchecked(&mut self, m: &Move) {
+        let reversible_since = if self.current.is_irreversible(m) {
+            self.plies.len()
+        } else {
+            self.last_ply().reversible_since
+        };
+        self.current.play_unchecked(m);
+        self.moves.push(m.clone());
+        self.plies.push(Ply {
+            zobrist: self.current.zobrist(),
+            reversible_since,
+        });
+    }
+
+    /// Number of times the current position has occurred, including the
+    /// current occurrence.
+    pub fn repetitions(&self) -> usize {
+        let last = self.last_ply();
+        1 + self.earlier_occurrences(last.zobrist, last.reversible_since, self.moves.len())
+    }
+
+    /// Tests if the current position has occurred at least three times.
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetitions() >= 3
+    }
+
+    /// Tests if the current position has occurred at least five times.
+    /// The game is drawn automatically.
+    pub fn is_fivefold_repetition(&self) -> bool {
+        self.repetitions() >= 5
+    }
+
+    /// Tests if at least 50 moves of each player were made without a pawn
+    /// move or capture.
+    pub fn is_fifty_moves(&self) -> bool {
+        self.position().halfmoves() >= 100
+    }
+
+    /// Tests if at least 75 moves of each player were made without a pawn
+    /// move or capture. Unless the last move was checkmate, the game is drawn
+    /// automatically.
+    pub fn is_seventy_five_moves(&self) -> bool {
+        self.position().halfmoves() >= 150
+    }
+
+    /// The outcome of the game, or `None` if the game is not over.
+    ///
+    /// In addition to [`Position::outcome()`], the game is drawn by
+    /// fivefold repetition and the seventy-five-move rule.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.position().outcome().or_else(|| {
+            if self.is_fivefold_repetition() || self.is_seventy_five_moves() {
+                Some(Outcome::Draw)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// A draw the player to move can claim in the current position, or
+    /// `None` if the game is over.
+    ///
+    /// Threefold repetition takes precedence over the fifty-move rule.
+    pub fn draw_claim(&self) -> Option<DrawClaim> {
+        if self.outcome().is_some() {
+            None
+        } else if self.is_threefold_repetition() {
+            Some(DrawClaim::ThreefoldRepetition)
+        } else if self.is_fifty_moves() {
+            Some(DrawClaim::FiftyMoves)
+        } else {
+            None
+        }
+    }
+
+    /// A draw the player to move can claim by announcing the legal move `m`,
+    /// if the position after `m` would allow the claim. Returns `None` if `m`
+    /// ends the game, for example by checkmate.
+    ///
+    /// This does not include draws that could already be claimed in the
+    /// current position. See [`Game::draw_claim()`].
+    pub fn draw_claim_with(&self, m: &Move) -> Option<DrawClaim> {
+        let mut child = self.current.clone();
+        child.play_unchecked(m);
+
+        let ply = self.moves.len() + 1;
+        let reversible_since = if self.current.is_irreversible(m) {
+            ply
+        } else {
+            self.last_ply().reversible_since
+        };
+
+        let repetitions = 1 + self.earlier_occurrences(child.zobrist(), reversible_since, ply);
+        if child.outcome().is_some() || repetitions >= 5 || child.halfmoves() >= 150 {
+            None
+        } else if repetitions >= 3 {
+            Some(DrawClaim::ThreefoldRepetition)
+        } else if child.halfmoves() >= 100 {
+            Some(DrawClaim::FiftyMoves)
+        } else {
+            None
+        }
+    }
+
+    /// Tests if the player to move can claim a draw, either in the current
+    /// position or by announcing any legal move.
+    pub fn can_claim_draw(&self) -> bool {
+        self.draw_claim().is_some()
+            || (self.outcome().is_none()
+                && self
+                    .position()
+                    .legal_moves()
+                    .iter()
+                    .any(|m| self.draw_claim_with(m).is_some()))
+    }
+
+    fn last_ply(&self) -> Ply {
+        self.plies[self.moves.len()]
+    }
+
+    /// Counts occurrences of `zobrist` before `ply`, at plies with the same
+    /// side to move, back to `reversible_since`.
+    fn earlier_occurrences(
+        &self,
+        zobrist: Zobrist64,
+        reversible_since: usize,
+        ply: usize,
+    ) -> usize {
+        (reversible_since..ply)
+            .rev()
+            .skip(1)
+            .step_by(2)
+            .filter(|&i| self.plies[i].zobrist == zobrist)
+            .count()
+    }
+}
+
+impl<P: Position + Clone + Default> Default for Game<P> {
+    fn default() -> Game<P> {
+        Game::new(P::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fen::Fen, san::San, CastlingMode, Chess};
+
+    fn setup_fen(fen: &str) -> Chess {
+        fen.parse::<Fen>()
+            .expect("valid fen")
+            .into_position(CastlingMode::Standard)
+            .expect("legal position")
+    }
+
+    fn play_sans(game: &mut Game<Chess>, sans: &[&str]) {
+        for san in sans {
+            let m = san
+                .parse::<San>()
+                .expect("valid san")
+                .to_move(game.position())
+                .expect("legal move");
+            game.play_unchecked(&m);
+        }
+    }
+
+    fn san_move(game: &Game<Chess>, san: &str) -> Move {
+        san.parse::<San>()
+            .expect("valid san")
+            .to_move(game.position())
+            .expect("legal move")
+    }
+
+    #[test]
+    fn test_repetitions() {
+        let mut game = Game::<Chess>::default();
+        assert_eq!(game.repetitions(), 1);
+
+        play_sans(&mut game, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        assert_eq!(game.repetitions(), 2);
+        assert!(!game.is_threefold_repetition());
+
+        play_sans(&mut game, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        assert_eq!(game.repetitions(), 3);
+        assert!(game.is_threefold_repetition());
+        assert_eq!(game.draw_claim(), Some(DrawClaim::ThreefoldRepetition));
+        assert_eq!(game.outcome(), None);
+
+        play_sans(&mut game, &["Nf3"]);
+        assert_eq!(game.repetitions(), 3);
+        assert_eq!(game.draw_claim(), Some(DrawClaim::ThreefoldRepetition));
+
+        play_sans(
+            &mut game,
+            &["Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1", "Ng8"],
+        );
+        assert_eq!(game.repetitions(), 5);
+        assert!(game.is_fivefold_repetition());
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+        assert_eq!(game.draw_claim(), None);
+    }
+
+    #[test]
+    fn test_irreversible_move_cuts_history() {
+        let mut game = Game::<Chess>::default();
+        play_sans(&mut game, &["Nf3", "Nf6", "Ng1", "Ng8", "e4"]);
+        assert_eq!(game.repetitions(), 1);
+
+        // The position right after the irreversible move can repeat. The en
+        // passant square after e4 is irrelevant, because no capture is
+        // possible.
+        play_sans(&mut game, &["Nf6", "Nf3", "Ng8", "Ng1"]);
+        assert_eq!(game.repetitions(), 2);
+
+        let nf6 = san_move(&game, "Nf6");
+        assert_eq!(game.draw_claim_with(&nf6), None);
+        play_sans(&mut game, &["Nf6", "Nf3", "Ng8"]);
+
+        let ng1 = san_move(&game, "Ng1");
+        assert_eq!(
+            game.draw_claim_with(&ng1),
+            Some(DrawClaim::ThreefoldRepetition)
+        );
+        assert!(game.can_claim_draw());
+        let e5 = san_move(&game, "e5");
+        assert_eq!(game.draw_claim_with(&e5), None);
+
+        play_sans(&mut game, &["Ng1"]);
+        assert_eq!(game.repetitions(), 3);
+    }
+
+    #[test]
+    fn test_fifty_moves() {
+        let game = Game::new(setup_fen("7k/8/6K1/8/8/8/8/R7 w - - 99 80"));
+        assert_eq!(game.draw_claim(), None);
+        assert!(game.can_claim_draw());
+
+        let ra2 = san_move(&game, "Ra2");
+        assert_eq!(game.draw_claim_with(&ra2), Some(DrawClaim::FiftyMoves));
+
+        // Checkmate takes precedence.
+        let ra8 = san_move(&game, "Ra8#");
+        assert_eq!(game.draw_claim_with(&ra8), None);
+
+        let game = game.play(&ra2).expect("legal move");
+        assert!(game.is_fifty_moves());
+        assert_eq!(game.draw_claim(), Some(DrawClaim::FiftyMoves));
+        assert_eq!(game.outcome(), None);
+    }
+
+    #[test]
+    fn test_seventy_five_moves() {
+        let mut game = Game::new(setup_fen("7k/8/6K1/8/8/8/8/R7 w - - 149 80"));
+        assert_eq!(game.outcome(), None);
+
+        play_sans(&mut game, &["Ra8#"]);
+        assert!(game.is_seventy_five_moves());
+        assert_eq!(
+            game.outcome(),
+            Some(Outcome::Decisive {
+                winner: crate::Color::White
+            })
+        );
+
+        let mut game = Game::new(setup_fen("7k/8/6K1/8/8/8/8/R7 w - - 149 80"));
+        play_sans(&mut game, &["Ra2"]);
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+        assert_eq!(game.draw_claim(), None);
+    }
+
+    #[test]
+    fn test_position_at() {
+        let mut game = Game::<Chess>::default();
+        play_sans(&mut game, &["e4", "e5", "Nf3"]);
+        assert_eq!(game.moves().len(), 3);
+
+        assert_eq!(game.position_at(0).as_ref(), Some(game.initial()));
+        assert_eq!(game.position_at(3).as_ref(), Some(game.position()));
+        assert_eq!(
+            game.position_at(2),
+            Some(setup_fen(
+                "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2"
+            ))
+        );
+        assert_eq!(game.position_at(4), None);
+    }
+
+    #[test]
+    fn test_play_illegal() {
+        let game = Game::<Chess>::default();
+        let m = Move::Put {
+            role: crate::Role::Pawn,
+            to: crate::Square::E4,
+        };
+        let err = game.play(&m).expect_err("illegal move");
+        assert_eq!(err.m, m);
+        assert!(err.position.moves().is_empty());
+    }
+}
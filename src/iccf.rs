@@ -0,0 +1,410 @@
+//! Parse and write moves in ICCF numeric notation.
+//!
+//! Squares are written as two digits, file and rank, so that `5254` is the
+//! move from e2 to e4. Promotions append a digit for the piece type
+//! (`1` queen, `2` rook, `3` bishop, `4` knight), e.g., `17181` for a7a8=Q.
+//! Castling is written as a move of the king to its destination square.
+//!
+//! # Examples
+//!
+//! Parsing ICCF moves:
+//!
+//! ```
+//! use shakmaty::{Square, iccf::IccfMove};
+//!
+//! let iccf: IccfMove = "7163".parse()?;
+//!
+//! assert_eq!(iccf, IccfMove {
+//!     from: Square::G1,
+//!     to: Square::F3,
+//!     promotion: None,
+//! });
+//!
+//! # Ok::<_, shakmaty::iccf::ParseIccfMoveError>(())
+//! ```
+//!
+//! Converting to a legal move in the context of a position, and back:
+//!
+//! ```
+//! # use shakmaty::iccf::{IccfMove, IllegalIccfMoveError, ParseIccfMoveError};
+//! use shakmaty::{Chess, Position, Square, Color::White};
+//!
+//! let mut pos = Chess::default();
+//! let m = "7163".parse::<IccfMove>()?.to_move(&pos)?;
+//!
+//! assert_eq!(IccfMove::from_move(&m).map(|iccf| iccf.to_string()), Some("7163".to_owned()));
+//!
+//! pos.play_unchecked(&m);
+//! assert_eq!(pos.board().piece_at(Square::F3), Some(White.knight()));
+//!
+//! # #[derive(Debug)] struct CommonError;
+//! # impl From<IllegalIccfMoveError> for CommonError { fn from(_: IllegalIccfMoveError) -> Self { Self } }
+//! # impl From<ParseIccfMoveError> for CommonError { fn from(_: ParseIccfMoveError) -> Self { Self } }
+//! # Ok::<_, CommonError>(())
+//! ```
+
+use core::{fmt, str::FromStr};
+
+use crate::{util::AppendAscii, CastlingSide, File, Move, Position, Rank, Role, Square};
+
+/// Error when parsing an invalid ICCF move.
+#[derive(Clone, Debug)]
+pub struct ParseIccfMoveError;
+
+impl fmt::Display for ParseIccfMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid iccf")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseIccfMoveError {}
+
+/// Error when ICCF move is illegal.
+#[derive(Clone, Debug)]
+pub struct IllegalIccfMoveError;
+
+impl fmt::Display for IllegalIccfMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("illegal iccf")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IllegalIccfMoveError {}
+
+/// A move in ICCF numeric notation, e.g. `5254` or `17181`.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub struct IccfMove {
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<Role>,
+}
+
+impl FromStr for IccfMove {
+    type Err = ParseIccfMoveError;
+
+    fn from_str(iccf: &str) -> Result<IccfMove, ParseIccfMoveError> {
+        IccfMove::from_ascii(iccf.as_bytes())
+    }
+}
+
+impl fmt::Display for IccfMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.append_to(f)
+    }
+}
+
+fn square_from_digits(file: u8, rank: u8) -> Option<Square> {
+    match (file, rank) {
+        (b'1'..=b'8', b'1'..=b'8') => Some(Square::from_coords(
+            File::new(u32::from(file - b'1')),
+            Rank::new(u32::from(rank - b'1')),
+        )),
+        _ => None,
+    }
+}
+
+const fn promotion_from_digit(digit: u8) -> Option<Role> {
+    match digit {
+        b'1' => Some(Role::Queen),
+        b'2' => Some(Role::Rook),
+        b'3' => Some(Role::Bishop),
+        b'4' => Some(Role::Knight),
+        _ => None,
+    }
+}
+
+const fn promotion_digit(role: Role) -> Option<char> {
+    match role {
+        Role::Queen => Some('1'),
+        Role::Rook => Some('2'),
+        Role::Bishop => Some('3'),
+        Role::Knight => Some('4'),
+        Role::Pawn | Role::King => None,
+    }
+}
+
+impl IccfMove {
+    /// Parses a move in ICCF numeric notation.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseIccfMoveError`] if `iccf` is not syntactically valid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use shakmaty::{Role, Square, iccf::IccfMove};
+    ///
+    /// let iccf = IccfMove::from_ascii(b"17181")?;
+    ///
+    /// assert_eq!(iccf, IccfMove {
+    ///     from: Square::A7,
+    ///     to: Square::A8,
+    ///     promotion: Some(Role::Queen),
+    /// });
+    ///
+    /// # Ok::<_, shakmaty::iccf::ParseIccfMoveError>(())
+    /// ```
+    pub fn from_ascii(iccf: &[u8]) -> Result<IccfMove, ParseIccfMoveError> {
+        let (squares, promotion) = match *iccf {
+            [a, b, c, d] => ([a, b, c, d], None),
+            [a, b, c, d, p] => (
+                [a, b, c, d],
+                Some(promotion_from_digit(p).ok_or(ParseIccfMoveError)?),
+            ),
+            _ => return Err(ParseIccfMoveError),
+        };
+
+        Ok(IccfMove {
+            from: square_from_digits(squares[0], squares[1]).ok_or(ParseIccfMoveError)?,
+            to: square_from_digits(squares[2], squares[3]).ok_or(ParseIccfMoveError)?,
+            promotion,
+        })
+    }
+
+    /// Converts a move to ICCF numeric notation. Castling moves are
+    /// represented as a move of the king to its destination square.
+    ///
+    /// Returns `None` for moves that cannot be represented, i.e., piece
+    /// drops and promotions to a king.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use shakmaty::{Move, Square, iccf::IccfMove};
+    ///
+    /// let m = Move::Castle {
+    ///     king: Square::E8,
+    ///     rook: Square::A8,
+    /// };
+    ///
+    /// let iccf = IccfMove::from_move(&m).expect("castling move");
+    /// assert_eq!(iccf.to_string(), "5838");
+    /// ```
+    pub fn from_move(m: &Move) -> Option<IccfMove> {
+        match *m {
+            Move::Normal {
+                from,
+                to,
+                promotion,
+                ..
+            } => {
+                if let Some(promotion) = promotion {
+                    promotion_digit(promotion)?;
+                }
+                Some(IccfMove {
+                    from,
+                    to,
+                    promotion,
+                })
+            }
+            Move::EnPassant { from, to } => Some(IccfMove {
+                from,
+                to,
+                promotion: None,
+            }),
+            Move::Castle { king, rook } => {
+                let side = CastlingSide::from_king_side(king < rook);
+                Some(IccfMove {
+                    from: king,
+                    to: Square::from_coords(side.king_to_file(), king.rank()),
+                    promotion: None,
+                })
+            }
+            Move::Put { .. } => None,
+        }
+    }
+
+    /// Tries to convert the `IccfMove` to a legal [`Move`] in the context of
+    /// a position.
+    ///
+    /// A king move to the destination square of a legal castling move is
+    /// interpreted as castling, unless it is also a legal normal king move
+    /// (which can happen in Chess960).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IllegalIccfMoveError`] if the move is not legal.
+    pub fn to_move<P: Position>(&self, pos: &P) -> Result<Move, IllegalIccfMoveError> {
+        let IccfMove {
+            from,
+            to,
+            promotion,
+        } = *self;
+
+        let role = pos.board().role_at(from).ok_or(IllegalIccfMoveError)?;
+
+        if promotion.is_some() && role != Role::Pawn {
+            return Err(IllegalIccfMoveError);
+        }
+
+        let candidate = if role == Role::Pawn
+            && from.file() != to.file()
+            && !pos.board().occupied().contains(to)
+        {
+            Move::EnPassant { from, to }
+        } else {
+            Move::Normal {
+                role,
+                from,
+                capture: pos.board().role_at(to),
+                to,
+                promotion,
+            }
+        };
+
+        if pos.is_legal(&candidate) {
+            return Ok(candidate);
+        }
+
+        if role == Role::King {
+            for side in CastlingSide::ALL {
+                if to != Square::from_coords(side.king_to_file(), from.rank()) {
+                    continue;
+                }
+                if let Some(m) = pos
+                    .castling_moves(side)
+                    .into_iter()
+                    .find(|m| m.from() == Some(from))
+                {
+                    return Ok(m);
+                }
+            }
+        }
+
+        Err(IllegalIccfMoveError)
+    }
+
+    fn append_to<W: AppendAscii>(&self, f: &mut W) -> Result<(), W::Error> {
+        for sq in [self.from, self.to] {
+            f.append_ascii(char::from(b'1' + u8::from(sq.file())))?;
+            f.append_ascii(sq.rank().char())?;
+        }
+        if let Some(digit) = self.promotion.and_then(promotion_digit) {
+            f.append_ascii(digit)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn append_to_string(&self, s: &mut alloc::string::String) {
+        let _ = self.append_to(s);
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn append_ascii_to(&self, buf: &mut alloc::vec::Vec<u8>) {
+        let _ = self.append_to(buf);
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_ascii_to<W: std::io::Write>(&self, w: W) -> std::io::Result<()> {
+        self.append_to(&mut crate::util::WriteAscii(w))
+    }
+}
+
+impl Move {
+    /// See [`IccfMove::from_move()`].
+    pub fn to_iccf(&self) -> Option<IccfMove> {
+        IccfMove::from_move(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "alloc")]
+    use alloc::string::ToString as _;
+
+    use super::*;
+    use crate::{fen::Fen, CastlingMode, Chess};
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_read_write() {
+        for iccf in ["5254", "7163", "17181", "28174", "5171"] {
+            let result = iccf.parse::<IccfMove>().expect("valid iccf").to_string();
+            assert_eq!(iccf, result);
+        }
+
+        for invalid in ["525", "5294", "52540", "17185", "e2e4", "525411"] {
+            assert!(invalid.parse::<IccfMove>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_to_move() {
+        let pos: Chess = "r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6"
+            .parse::<Fen>()
+            .expect("valid fen")
+            .into_position(CastlingMode::Standard)
+            .expect("legal fen");
+
+        for (iccf, expected) in [
+            (
+                "5171",
+                Some(Move::Castle {
+                    king: Square::E1,
+                    rook: Square::H1,
+                }),
+            ),
+            (
+                "5131",
+                Some(Move::Castle {
+                    king: Square::E1,
+                    rook: Square::A1,
+                }),
+            ),
+            (
+                "5546",
+                Some(Move::EnPassant {
+                    from: Square::E5,
+                    to: Square::D6,
+                }),
+            ),
+            (
+                "27184",
+                Some(Move::Normal {
+                    role: Role::Pawn,
+                    from: Square::B7,
+                    capture: Some(Role::Rook),
+                    to: Square::A8,
+                    promotion: Some(Role::Knight),
+                }),
+            ),
+            ("2718", None),
+            ("5181", None),
+            ("11181", None),
+        ] {
+            let iccf: IccfMove = iccf.parse().expect("valid iccf");
+            let m = iccf.to_move(&pos).ok();
+            assert_eq!(m, expected, "{iccf:?}");
+            if let Some(m) = m {
+                assert_eq!(IccfMove::from_move(&m), Some(iccf));
+            }
+        }
+    }
+
+    #[test]
+    fn test_chess960_castling() {
+        // King on f1 may castle king side to g1, which is also a normal
+        // king move. The normal move is preferred.
+        let pos: Chess = "4k3/8/8/8/8/8/8/1R3K1R w HB -"
+            .parse::<Fen>()
+            .expect("valid fen")
+            .into_position(CastlingMode::Chess960)
+            .expect("legal fen");
+
+        let iccf: IccfMove = "6171".parse().expect("valid iccf");
+        assert!(matches!(iccf.to_move(&pos), Ok(Move::Normal { .. })));
+
+        let iccf: IccfMove = "6131".parse().expect("valid iccf");
+        assert_eq!(
+            iccf.to_move(&pos).ok(),
+            Some(Move::Castle {
+                king: Square::F1,
+                rook: Square::B1,
+            })
+        );
+    }
+}
@@ -0,0 +1,697 @@
+//! Read and write Long Algebraic Notation.
+//!
+//! Long algebraic notation always names the origin square of a move, e.g.
+//! `Ng1-f3`, `e7xd8=Q` or `N@f3`. Pawn moves omit the piece letter.
+//! The parser also accepts reversible algebraic notation (Smith notation),
+//! which omits piece letters and separators and names the captured piece
+//! instead, e.g. `e2e4`, `e5d4p` or `e1g1c`.
+//!
+//! # Examples
+//!
+//! Parse and write LANs:
+//!
+//! ```
+//! use shakmaty::lan::Lan;
+//!
+//! let lan: Lan = "Ng1-f3".parse()?;
+//! assert_eq!(lan.to_string(), "Ng1-f3");
+//! # Ok::<_, shakmaty::lan::ParseLanError>(())
+//! ```
+//!
+//! Converting to a move and back:
+//!
+//! ```
+//! # use shakmaty::lan::{IllegalLanError, Lan, ParseLanError};
+//! use shakmaty::{Chess, Move, Position, Role, Square};
+//!
+//! let pos = Chess::default();
+//! let lan: Lan = "g1f3".parse()?;
+//! let m = lan.to_move(&pos)?;
+//!
+//! assert_eq!(m, Move::Normal {
+//!     role: Role::Knight,
+//!     from: Square::G1,
+//!     capture: None,
+//!     to: Square::F3,
+//!     promotion: None,
+//! });
+//!
+//! assert_eq!(Lan::from_move(&m).to_string(), "Ng1-f3");
+//!
+//! # #[derive(Debug)] struct CommonError;
+//! # impl From<ParseLanError> for CommonError { fn from(_: ParseLanError) -> Self { Self } }
+//! # impl From<IllegalLanError> for CommonError { fn from(_: IllegalLanError) -> Self { Self } }
+//! # Ok::<_, CommonError>(())
+//! ```
+
+use core::{fmt, str::FromStr};
+
+use crate::{
+    san::{San, SanError, Suffix},
+    uci::{IllegalUciMoveError, UciMove},
+    util::AppendAscii,
+    CastlingSide, Move, Position, Role, Square,
+};
+
+/// Error when parsing a syntactically invalid LAN.
+#[derive(Clone, Debug)]
+pub struct ParseLanError;
+
+impl fmt::Display for ParseLanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid lan")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseLanError {}
+
+/// Error when a LAN does not match a legal move.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IllegalLanError;
+
+impl fmt::Display for IllegalLanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("illegal lan")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IllegalLanError {}
+
+/// A move in Long Algebraic Notation.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum Lan {
+    Normal {
+        /// The piece letter, if given. Pawn moves and moves in Smith
+        /// notation omit it, so that `None` matches any piece on `from`.
+        role: Option<Role>,
+        from: Square,
+        capture: bool,
+        to: Square,
+        promotion: Option<Role>,
+    },
+    Castle(CastlingSide),
+    Put {
+        role: Role,
+        to: Square,
+    },
+    Null,
+}
+
+impl Lan {
+    /// Parses a LAN or a move in Smith notation. Ignores a possible check or
+    /// checkmate suffix.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseLanError`] if `lan` is not syntactically valid.
+    pub fn from_ascii(mut lan: &[u8]) -> Result<Lan, ParseLanError> {
+        if lan.ends_with(b"#") || lan.ends_with(b"+") {
+            lan = &lan[0..(lan.len() - 1)];
+        }
+
+        if lan == b"--" {
+            return Ok(Lan::Null);
+        } else if lan == b"O-O" {
+            return Ok(Lan::Castle(CastlingSide::KingSide));
+        } else if lan == b"O-O-O" {
+            return Ok(Lan::Castle(CastlingSide::QueenSide));
+        } else if lan.len() == 3 && lan[0] == b'@' {
+            return Ok(Lan::Put {
+                role: Role::Pawn,
+                to: Square::from_ascii(&lan[1..]).map_err(|_| ParseLanError)?,
+            });
+        } else if lan.len() == 4 && lan[1] == b'@' {
+            return Ok(Lan::Put {
+                role: Role::from_char(char::from(lan[0])).ok_or(ParseLanError)?,
+                to: Square::from_ascii(&lan[2..]).map_err(|_| ParseLanError)?,
+            });
+        }
+
+        let role = match lan.first() {
+            Some(&ch) if ch.is_ascii_uppercase() => {
+                lan = &lan[1..];
+                Some(Role::from_char(char::from(ch)).ok_or(ParseLanError)?)
+            }
+            _ => None,
+        };
+
+        let from =
+            Square::from_ascii(lan.get(0..2).ok_or(ParseLanError)?).map_err(|_| ParseLanError)?;
+        lan = &lan[2..];
+
+        let separator = match lan.first() {
+            Some(&ch @ (b'-' | b'x')) => {
+                lan = &lan[1..];
+                Some(ch)
+            }
+            _ => None,
+        };
+
+        let to =
+            Square::from_ascii(lan.get(0..2).ok_or(ParseLanError)?).map_err(|_| ParseLanError)?;
+        lan = &lan[2..];
+
+        let (capture, promotion) = if let Some(separator) = separator {
+            let promotion = match *lan {
+                [] => None,
+                [b'=', ch] if ch.is_ascii_uppercase() => {
+                    Some(Role::from_char(char::from(ch)).ok_or(ParseLanError)?)
+                }
+                _ => return Err(ParseLanError),
+            };
+            (separator == b'x', promotion)
+        } else {
+            // Smith notation: Captured piece, then en passant or castling
+            // marker, or promotion.
+            let capture = match lan.first() {
+                Some(&ch) if ch.is_ascii_lowercase() && ch != b'c' => {
+                    lan = &lan[1..];
+                    Role::from_char(char::from(ch)).ok_or(ParseLanError)?;
+                    true
+                }
+                _ => false,
+            };
+            match *lan {
+                [] => (capture, None),
+                [b'E'] => (true, None),
+                [b'c'] if !capture && role.map_or(true, |r| r == Role::King) => {
+                    return Ok(Lan::Castle(CastlingSide::KingSide))
+                }
+                [b'C'] if !capture && role.map_or(true, |r| r == Role::King) => {
+                    return Ok(Lan::Castle(CastlingSide::QueenSide))
+                }
+                [ch] if ch.is_ascii_uppercase() => (
+                    capture,
+                    Some(Role::from_char(char::from(ch)).ok_or(ParseLanError)?),
+                ),
+                _ => return Err(ParseLanError),
+            }
+        };
+
+        Ok(Lan::Normal {
+            role,
+            from,
+            capture,
+            to,
+            promotion,
+        })
+    }
+
+    /// Converts a move to Long Algebraic Notation. Unlike SAN, this does not
+    /// require the context of a position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use shakmaty::{Move, Square, lan::Lan};
+    ///
+    /// let m = Move::EnPassant {
+    ///     from: Square::E5,
+    ///     to: Square::D6,
+    /// };
+    ///
+    /// assert_eq!(Lan::from_move(&m).to_string(), "e5xd6");
+    /// ```
+    pub fn from_move(m: &Move) -> Lan {
+        match *m {
+            Move::Normal {
+                role,
+                from,
+                capture,
+                to,
+                promotion,
+            } => Lan::Normal {
+                role: (role != Role::Pawn).then_some(role),
+                from,
+                capture: capture.is_some(),
+                to,
+                promotion,
+            },
+            Move::EnPassant { from, to } => Lan::Normal {
+                role: None,
+                from,
+                capture: true,
+                to,
+                promotion: None,
+            },
+            Move::Castle { king, rook } => Lan::Castle(CastlingSide::from_king_side(king < rook)),
+            Move::Put { role, to } => Lan::Put { role, to },
+        }
+    }
+
+    /// Tries to convert the `Lan` to a legal move in the context of a
+    /// position.
+    ///
+    /// Castling moves may also be given as a king move to the castling
+    /// target square or onto the rook.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IllegalLanError`] if there is no matching legal move.
+    pub fn to_move<P: Position>(&self, pos: &P) -> Result<Move, IllegalLanError> {
+        match *self {
+            Lan::Normal { from, to, .. } => {
+                let role = pos.board().role_at(from).ok_or(IllegalLanError)?;
+                let mut candidates = pos.san_candidates(role, to);
+                if role == Role::King {
+                    for side in CastlingSide::ALL {
+                        candidates.extend(pos.castling_moves(side));
+                    }
+                }
+                candidates
+                    .into_iter()
+                    .find(|m| self.matches(m))
+                    .ok_or(IllegalLanError)
+            }
+            Lan::Castle(side) => pos
+                .castling_moves(side)
+                .first()
+                .cloned()
+                .ok_or(IllegalLanError),
+            Lan::Put { role, to } => {
+                let m = Move::Put { role, to };
+                if pos.is_legal(&m) {
+                    Ok(m)
+                } else {
+                    Err(IllegalLanError)
+                }
+            }
+            Lan::Null => Err(IllegalLanError),
+        }
+    }
+
+    /// Test if the `Lan` can match the `Move` (in any position).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use shakmaty::{Square, Role, Move, lan::Lan};
+    ///
+    /// let m = Move::Normal {
+    ///     role: Role::Knight,
+    ///     from: Square::G1,
+    ///     to: Square::F3,
+    ///     capture: None,
+    ///     promotion: None,
+    /// };
+    ///
+    /// assert!(Lan::from_ascii(b"Ng1-f3")?.matches(&m));
+    /// assert!(Lan::from_ascii(b"g1f3")?.matches(&m));
+    ///
+    /// // capture does not match
+    /// assert!(!Lan::from_ascii(b"Ng1xf3")?.matches(&m));
+    ///
+    /// // other piece does not match
+    /// assert!(!Lan::from_ascii(b"Bg1-f3")?.matches(&m));
+    ///
+    /// # Ok::<_, shakmaty::lan::ParseLanError>(())
+    /// ```
+    pub fn matches(&self, m: &Move) -> bool {
+        match *self {
+            Lan::Normal {
+                role,
+                from,
+                capture,
+                to,
+                promotion,
+            } => match *m {
+                Move::Normal {
+                    role: r,
+                    from: f,
+                    capture: c,
+                    to: t,
+                    promotion: p,
+                } => {
+                    role.map_or(true, |role| role == r)
+                        && from == f
+                        && capture == c.is_some()
+                        && to == t
+                        && promotion == p
+                }
+                Move::EnPassant { from: f, to: t } => {
+                    role.map_or(true, |role| role == Role::Pawn)
+                        && from == f
+                        && capture
+                        && to == t
+                        && promotion.is_none()
+                }
+                Move::Castle { king, rook } => {
+                    let side = CastlingSide::from_king_side(king < rook);
+                    role.map_or(true, |role| role == Role::King)
+                        && from == king
+                        && !capture
+                        && (to == rook
+                            || to == Square::from_coords(side.king_to_file(), king.rank()))
+                        && promotion.is_none()
+                }
+                Move::Put { .. } => false,
+            },
+            Lan::Castle(side) => m.castling_side() == Some(side),
+            Lan::Put { role, to } => match *m {
+                Move::Put { role: r, to: t } => r == role && to == t,
+                _ => false,
+            },
+            Lan::Null => false,
+        }
+    }
+
+    /// Converts a SAN to Long Algebraic Notation in the context of a
+    /// position.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SanError`] if there is no unique matching legal move.
+    pub fn from_san<P: Position>(pos: &P, san: &San) -> Result<Lan, SanError> {
+        san.to_move(pos).map(|m| Lan::from_move(&m))
+    }
+
+    /// Converts the `Lan` to a (minimally disambiguated) SAN in the context
+    /// of a position.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IllegalLanError`] if there is no matching legal move.
+    pub fn to_san<P: Position>(&self, pos: &P) -> Result<San, IllegalLanError> {
+        self.to_move(pos).map(|m| San::from_move(pos, &m))
+    }
+
+    /// Converts a UCI move to Long Algebraic Notation in the context of a
+    /// position.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IllegalUciMoveError`] if the move is not legal.
+    pub fn from_uci<P: Position>(pos: &P, uci: &UciMove) -> Result<Lan, IllegalUciMoveError> {
+        uci.to_move(pos).map(|m| Lan::from_move(&m))
+    }
+
+    /// Converts the `Lan` to a UCI move in the context of a position,
+    /// using the castling mode of the position.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IllegalLanError`] if there is no matching legal move.
+    pub fn to_uci<P: Position>(&self, pos: &P) -> Result<UciMove, IllegalLanError> {
+        self.to_move(pos).map(|m| m.to_uci(pos.castles().mode()))
+    }
+
+    pub(crate) fn append_to<W: AppendAscii>(&self, f: &mut W) -> Result<(), W::Error> {
+        match *self {
+            Lan::Normal {
+                role,
+                from,
+                capture,
+                to,
+                promotion,
+            } => {
+                if let Some(role) = role {
+                    f.append_ascii(role.upper_char())?;
+                }
+                from.append_to(f)?;
+                f.append_ascii(if capture { 'x' } else { '-' })?;
+                to.append_to(f)?;
+                if let Some(promotion) = promotion {
+                    f.append_ascii('=')?;
+                    f.append_ascii(promotion.upper_char())?;
+                }
+            }
+            Lan::Castle(CastlingSide::KingSide) => {
+                f.append_ascii('O')?;
+                f.append_ascii('-')?;
+                f.append_ascii('O')?;
+            }
+            Lan::Castle(CastlingSide::QueenSide) => {
+                f.append_ascii('O')?;
+                f.append_ascii('-')?;
+                f.append_ascii('O')?;
+                f.append_ascii('-')?;
+                f.append_ascii('O')?;
+            }
+            Lan::Put { role, to } => {
+                if role != Role::Pawn {
+                    f.append_ascii(role.upper_char())?;
+                }
+                f.append_ascii('@')?;
+                to.append_to(f)?;
+            }
+            Lan::Null => {
+                f.append_ascii('-')?;
+                f.append_ascii('-')?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn append_to_string(&self, s: &mut alloc::string::String) {
+        let _ = self.append_to(s);
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn append_ascii_to(&self, buf: &mut alloc::vec::Vec<u8>) {
+        let _ = self.append_to(buf);
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_ascii_to<W: std::io::Write>(&self, w: W) -> std::io::Result<()> {
+        self.append_to(&mut crate::util::WriteAscii(w))
+    }
+}
+
+impl From<&Move> for Lan {
+    fn from(m: &Move) -> Lan {
+        Lan::from_move(m)
+    }
+}
+
+impl FromStr for Lan {
+    type Err = ParseLanError;
+
+    fn from_str(lan: &str) -> Result<Lan, ParseLanError> {
+        Lan::from_ascii(lan.as_bytes())
+    }
+}
+
+impl fmt::Display for Lan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.append_to(f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Lan {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Lan {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Lan, D::Error> {
+        crate::util::deserialize_ascii(deserializer, "LAN", |s| Lan::from_ascii(s).ok())
+    }
+}
+
+/// A [`Lan`] and possible check and checkmate suffixes.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct LanPlus {
+    pub lan: Lan,
+    pub suffix: Option<Suffix>,
+}
+
+impl LanPlus {
+    /// Parses a LAN and possible check and checkmate suffix.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseLanError`] if `lan` is not syntactically valid.
+    pub fn from_ascii(lan: &[u8]) -> Result<LanPlus, ParseLanError> {
+        Lan::from_ascii(lan).map(|result| LanPlus {
+            lan: result,
+            suffix: lan
+                .last()
+                .copied()
+                .and_then(|ch| Suffix::from_char(char::from(ch))),
+        })
+    }
+
+    /// Converts a move to Long Algebraic Notation including possible
+    /// check and checkmate suffixes. Also plays the move.
+    ///
+    /// It is the callers responsibility to ensure the move is legal.
+    ///
+    /// # Panics
+    ///
+    /// Illegal moves can corrupt the state of the position and may
+    /// (or may not) panic or cause panics on future calls.
+    pub fn from_move_and_play_unchecked<P: Position>(pos: &mut P, m: &Move) -> LanPlus {
+        pos.play_unchecked(m);
+        LanPlus {
+            lan: Lan::from_move(m),
+            suffix: Suffix::from_position(pos),
+        }
+    }
+
+    pub fn from_move<P: Position>(mut pos: P, m: &Move) -> LanPlus {
+        LanPlus {
+            lan: Lan::from_move(m),
+            suffix: if pos.is_legal(m) {
+                pos.play_unchecked(m);
+                Suffix::from_position(&pos)
+            } else {
+                None
+            },
+        }
+    }
+
+    pub(crate) fn append_to<W: AppendAscii>(&self, f: &mut W) -> Result<(), W::Error> {
+        self.lan.append_to(f)?;
+        if let Some(suffix) = self.suffix {
+            f.append_ascii(suffix.char())?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn append_to_string(&self, s: &mut alloc::string::String) {
+        let _ = self.append_to(s);
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn append_ascii_to(&self, buf: &mut alloc::vec::Vec<u8>) {
+        let _ = self.append_to(buf);
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_ascii_to<W: std::io::Write>(&self, w: W) -> std::io::Result<()> {
+        self.append_to(&mut crate::util::WriteAscii(w))
+    }
+}
+
+impl FromStr for LanPlus {
+    type Err = ParseLanError;
+
+    fn from_str(lan: &str) -> Result<LanPlus, ParseLanError> {
+        LanPlus::from_ascii(lan.as_bytes())
+    }
+}
+
+impl fmt::Display for LanPlus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.append_to(f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for LanPlus {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for LanPlus {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<LanPlus, D::Error> {
+        crate::util::deserialize_ascii(deserializer, "LAN", |s| LanPlus::from_ascii(s).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "alloc")]
+    use alloc::string::ToString as _;
+
+    use super::*;
+    use crate::{fen::Fen, CastlingMode, Chess};
+
+    fn setup_fen(fen: &str) -> Chess {
+        fen.parse::<Fen>()
+            .expect("valid fen")
+            .into_position(CastlingMode::Standard)
+            .expect("legal fen")
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_read_write() {
+        for lan in [
+            "e2-e4", "Ng1-f3", "Bd3xh7+", "e7xd8=Q#", "O-O", "O-O-O+", "N@f3", "@e4", "--",
+            "Ke1-g1",
+        ] {
+            let result = lan.parse::<LanPlus>().expect("valid lan").to_string();
+            assert_eq!(lan, result, "read {lan} write {result}");
+        }
+    }
+
+    #[test]
+    fn test_smith() {
+        let pos = setup_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6");
+
+        for (smith, lan) in [
+            ("e5d6E", "e5xd6"),
+            ("b7a8rQ", "b7xa8=Q"),
+            ("b7b8N", "b7-b8=N"),
+            ("e1g1c", "O-O"),
+            ("e1c1C", "O-O-O"),
+            ("a1a8r", "Ra1xa8"),
+            ("e1e2", "Ke1-e2"),
+        ] {
+            let m = Lan::from_ascii(smith.as_bytes())
+                .expect("valid smith")
+                .to_move(&pos)
+                .expect("legal smith");
+            assert_eq!(Lan::from_move(&m), lan.parse().expect("valid lan"));
+        }
+
+        assert!(Lan::from_ascii(b"e1g1rc").is_err());
+        assert!(Lan::from_ascii(b"e2e4x").is_err());
+    }
+
+    #[test]
+    fn test_to_move() {
+        let pos = setup_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6");
+
+        for (lan, legal) in [
+            ("e5-d6", false),
+            ("e5xd6", true),
+            ("Ke1-g1", true),
+            ("Ke1-h1", true),
+            ("Ke1xh1", false),
+            ("b7xa8", false),
+            ("Qb7xa8=Q", false),
+            ("Ra1-a7", true),
+            ("Ra1xa8", true),
+            ("Ra1-a8", false),
+            ("Na1-a7", false),
+            ("@a3", false),
+        ] {
+            let lan: Lan = lan.parse().expect("valid lan");
+            assert_eq!(lan.to_move(&pos).is_ok(), legal, "{lan:?}");
+        }
+    }
+
+    #[test]
+    fn test_conversions() {
+        let pos = setup_fen("4k3/8/8/8/8/8/4K3/R6R w - -");
+        let lan: Lan = "Ra1-d1".parse().expect("valid lan");
+        let san: San = "Rad1".parse().expect("valid san");
+        assert_eq!(lan.to_san(&pos), Ok(san.clone()));
+        assert_eq!(Lan::from_san(&pos, &san), Ok(lan));
+
+        let pos = setup_fen("4k3/8/8/8/8/8/8/R3K2R w KQ -");
+
+        let lan = Lan::Castle(CastlingSide::KingSide);
+        let uci: UciMove = "e1g1".parse().expect("valid uci");
+        assert_eq!(lan.to_uci(&pos), Ok(uci.clone()));
+        assert_eq!(Lan::from_uci(&pos, &uci).ok(), Some(lan));
+
+        let m = "Ra1-a8"
+            .parse::<Lan>()
+            .expect("valid lan")
+            .to_move(&pos)
+            .expect("legal");
+        assert_eq!(LanPlus::from_move(pos, &m).suffix, Some(Suffix::Check));
+    }
+}
@@ -42,8 +42,14 @@
 //! assert_eq!(pos.outcome(), None); // no winner yet
 //! ```
 //!
-//! Also supports [FEN](fen), [SAN](san) and
-//! [UCI](uci) formats for positions and moves.
+//! Also supports [FEN](fen), [SAN](san), [LAN](lan), [UCI](uci) and
+//! [ICCF](iccf) formats for positions and moves, and reading and writing
+//! games in [PGN](pgn). Historical [descriptive notation](descriptive) can
+//! be resolved to moves as well. Boards can be rendered as text
+//! [diagrams](board::Diagram) or [SVG](svg) images. Endgame positions can be
+//! looked up in Syzygy tablebases (`syzygy`, with the `std` feature). Engines
+//! and GUIs can talk the [UCI](uci#protocol) and XBoard (`xboard`, with the
+//! `alloc` feature) protocols.
 //!
 //! # Feature flags
 //!
@@ -60,6 +66,14 @@
 //! * `nohash-hasher`: Implements
 //!   [`nohash_hasher::IsEnabled`](https://docs.rs/nohash-hasher/0.2/nohash_hasher/trait.IsEnabled.html)
 //!   for sensible types.
+//! * `serde`: Implements [`serde::Serialize`](https://docs.rs/serde/1/serde/trait.Serialize.html)
+//!   and [`serde::Deserialize`](https://docs.rs/serde/1/serde/trait.Deserialize.html)
+//!   for squares, roles, colors, moves, notations, variants, outcomes,
+//!   bitboards, containers, castling rights, and setups. Human-readable
+//!   formats use the usual notation (e.g. `e4` for a square, FEN for a
+//!   setup), while compact formats use compact representations (e.g. the
+//!   square index, [`PackedSetup`](packed::PackedSetup)). Works with
+//!   `no_std`.
 
 #![no_std]
 #![doc(html_root_url = "https://docs.rs/shakmaty/0.27.2")]
@@ -80,7 +94,6 @@ mod castling_side;
 mod color;
 mod magics;
 mod movelist;
-mod perft;
 mod position;
 mod role;
 mod setup;
@@ -90,9 +103,25 @@ mod types;
 pub mod attacks;
 pub mod bitboard;
 pub mod board;
+pub mod chess960;
+pub mod descriptive;
 pub mod fen;
+#[cfg(feature = "alloc")]
+pub mod game;
+pub mod iccf;
+pub mod lan;
+pub mod material;
+pub mod packed;
+pub mod perft;
+pub mod pgn;
+pub mod polyglot;
 pub mod san;
+pub mod svg;
+#[cfg(feature = "std")]
+pub mod syzygy;
 pub mod uci;
+#[cfg(feature = "alloc")]
+pub mod xboard;
 pub mod zobrist;
 
 #[cfg(feature = "variant")]
@@ -106,7 +135,7 @@ pub use movelist::MoveList;
 pub use perft::perft;
 pub use position::{
     Chess, FromSetup, Outcome, ParseOutcomeError, PlayError, Position, PositionError,
-    PositionErrorKinds,
+    PositionErrorKinds, Undo,
 };
 pub use role::{ByRole, Role};
 pub use setup::{Castles, Setup};
@@ -0,0 +1,515 @@
+//! Material signatures like `KQPvKR`.
+//!
+//! A [`MaterialKey`] counts the pieces of each role and color, without
+//! regard to where they stand. It is the usual way to name endgames and
+//! endgame tablebase files.
+//!
+//! # Examples
+//!
+//! ```
+//! use shakmaty::{Chess, Color, Position, material::MaterialKey};
+//!
+//! let key: MaterialKey = "KRvKQP".parse()?;
+//! assert_eq!(key.to_string(), "KRvKQP");
+//! assert_eq!(key.count(), 5);
+//!
+//! // Stronger side first.
+//! assert_eq!(key.into_normalized().to_string(), "KQPvKR");
+//!
+//! // Dense index, suitable for array lookup.
+//! let index = key.index();
+//! assert!(index < MaterialKey::index_bound(5));
+//! assert_eq!(MaterialKey::from_index(index), Some(key));
+//!
+//! // Material of a position.
+//! let pos = Chess::default();
+//! assert_eq!(pos.board().material_key().to_string(), "KQRRBBNNPPPPPPPPvKQRRBBNNPPPPPPPP");
+//! # Ok::<_, shakmaty::material::ParseMaterialKeyError>(())
+//! ```
+
+use core::{cmp::Ordering, fmt, iter::FusedIterator, str::FromStr};
+
+use crate::{util::AppendAscii, ByColor, ByRole, Color, Role};
+
+/// Roles in the order of material signatures.
+const ROLES: [Role; 6] = [
+    Role::King,
+    Role::Queen,
+    Role::Rook,
+    Role::Bishop,
+    Role::Knight,
+    Role::Pawn,
+];
+
+/// Error when parsing an invalid material signature.
+#[derive(Clone, Debug)]
+pub struct ParseMaterialKeyError;
+
+impl fmt::Display for ParseMaterialKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid material key")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseMaterialKeyError {}
+
+/// The number of pieces of each color and role, like `KQPvKR`.
+///
+/// Keys are ordered by their [index](MaterialKey::index()), so keys with
+/// fewer pieces come first.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct MaterialKey {
+    material: ByColor<ByRole<u8>>,
+}
+
+impl MaterialKey {
+    /// The maximum number of pieces of a key, as many as there are squares.
+    pub const MAX_PIECES: usize = 64;
+
+    /// Creates a key with the given number of pieces of each color and
+    /// role, or `None` if there are more than [`MaterialKey::MAX_PIECES`].
+    pub fn new(material: ByColor<ByRole<u8>>) -> Option<MaterialKey> {
+        let key = MaterialKey { material };
+        (key.count() <= MaterialKey::MAX_PIECES).then_some(key)
+    }
+
+    /// The number of pieces of each color and role.
+    pub const fn material(&self) -> ByColor<ByRole<u8>> {
+        self.material
+    }
+
+    /// The number of pieces of each role of one side.
+    pub const fn side(&self, color: Color) -> ByRole<u8> {
+        *self.material.get(color)
+    }
+
+    /// The total number of pieces.
+    pub fn count(&self) -> usize {
+        self.material
+            .iter()
+            .flat_map(|side| side.iter())
+            .map(|&n| usize::from(n))
+            .sum()
+    }
+
+    /// Checks if there are any pawns.
+    pub const fn has_pawns(&self) -> bool {
+        self.material.white.pawn > 0 || self.material.black.pawn > 0
+    }
+
+    /// Checks if both sides have the same material.
+    pub fn is_symmetric(&self) -> bool {
+        self.material.white == self.material.black
+    }
+
+    /// Swaps the material of both sides.
+    pub fn mirror(&mut self) {
+        self.material.swap();
+    }
+
+    /// Swaps the material of both sides.
+    #[must_use]
+    pub fn into_mirrored(mut self) -> MaterialKey {
+        self.mirror();
+        self
+    }
+
+    /// Roles of one side, in signature order.
+    fn roles(&self, color: Color) -> impl Iterator<Item = Role> + '_ {
+        ROLES
+            .into_iter()
+            .flat_map(move |role| (0..*self.material.get(color).get(role)).map(move |_| role))
+    }
+
+    /// Gets the stronger side: the side with more pieces, or, if both have
+    /// the same number of pieces, the side with the more valuable pieces
+    /// (in the order `KQRBNP`). White if both sides have the same material.
+    ///
+    /// This is the convention of Syzygy table names.
+    pub fn stronger_side(&self) -> Color {
+        let rank = |role: Role| ROLES.iter().position(|&r| r == role);
+        let white = self.roles(Color::White).count();
+        let black = self.roles(Color::Black).count();
+        let weaker_black = white.cmp(&black).then_with(|| {
+            self.roles(Color::Black)
+                .map(rank)
+                .cmp(self.roles(Color::White).map(rank))
+        });
+        Color::from_white(weaker_black != Ordering::Less)
+    }
+
+    /// Checks if the stronger side is white.
+    pub fn is_normalized(&self) -> bool {
+        self.stronger_side() == Color::White
+    }
+
+    /// Mirrors the key if necessary, so that the stronger side is white.
+    #[must_use]
+    pub fn into_normalized(self) -> MaterialKey {
+        if self.is_normalized() {
+            self
+        } else {
+            self.into_mirrored()
+        }
+    }
+
+    /// Slot counts in signature order, white first.
+    fn slots(&self) -> impl Iterator<Item = u8> + '_ {
+        Color::ALL.into_iter().flat_map(move |color| {
+            ROLES
+                .into_iter()
+                .map(move |role| *self.material.get(color).get(role))
+        })
+    }
+
+    /// Gets a dense index of the key. Keys with fewer pieces have lower
+    /// indexes, and the keys with at most `n` pieces have exactly the
+    /// indexes below [`MaterialKey::index_bound(n)`](MaterialKey::index_bound()).
+    pub fn index(&self) -> u64 {
+        // Number of keys with fewer pieces, then the rank of the multiset
+        // of slots among those with the same number of pieces.
+        let n = self.count() as u64;
+        let mut index = if n == 0 { 0 } else { binomial(n + 11, 12) };
+        let mut i = 0;
+        for (slot, count) in self.slots().enumerate() {
+            for _ in 0..count {
+                i += 1;
+                index += binomial(slot as u64 + i - 1, i);
+            }
+        }
+        index
+    }
+
+    /// Gets the key with the given [index](MaterialKey::index()), or `None`
+    /// if it would have more than [`MaterialKey::MAX_PIECES`].
+    pub fn from_index(index: u64) -> Option<MaterialKey> {
+        let n = (0..=MaterialKey::MAX_PIECES as u64).find(|&n| index < binomial(n + 12, 12))?;
+        let mut rank = index - if n == 0 { 0 } else { binomial(n + 11, 12) };
+
+        let mut slots = [0; 12];
+        for i in (1..=n).rev() {
+            // Largest b with C(b, i) <= rank.
+            let mut b = i - 1;
+            while binomial(b + 1, i) <= rank {
+                b += 1;
+            }
+            rank -= binomial(b, i);
+            slots[(b + 1 - i) as usize] += 1;
+        }
+
+        let mut material = ByColor::<ByRole<u8>>::default();
+        for (slot, count) in slots.into_iter().enumerate() {
+            let color = if slot < 6 { Color::White } else { Color::Black };
+            *material.get_mut(color).get_mut(ROLES[slot % 6]) = count;
+        }
+        Some(MaterialKey { material })
+    }
+
+    /// The number of keys with at most `max_pieces` pieces, i.e., the size
+    /// of an array indexed by their [indexes](MaterialKey::index()).
+    pub fn index_bound(max_pieces: usize) -> u64 {
+        binomial(max_pieces.min(MaterialKey::MAX_PIECES) as u64 + 12, 12)
+    }
+
+    /// Iterates over all other keys that can be reached by captures and
+    /// promotions, following the rules of standard chess: kings are never
+    /// captured, and pawns promote to queens, rooks, bishops or knights.
+    ///
+    /// The number of such keys grows quickly with the number of pieces.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use shakmaty::material::MaterialKey;
+    ///
+    /// let key: MaterialKey = "KPvKN".parse()?;
+    /// let reachable: Vec<String> = key.reachable().map(|key| key.to_string()).collect();
+    /// assert!(reachable.contains(&"KvK".to_owned()));
+    /// assert!(reachable.contains(&"KQvKN".to_owned()));
+    /// assert!(reachable.contains(&"KRvK".to_owned()));
+    /// assert!(!reachable.contains(&"KPvKN".to_owned()));
+    /// assert!(!reachable.contains(&"KQvKQ".to_owned()));
+    /// # Ok::<_, shakmaty::material::ParseMaterialKeyError>(())
+    /// ```
+    pub fn reachable(&self) -> Reachable {
+        Reachable {
+            key: *self,
+            next: Some(ByColor::new_with(|color| ByRole {
+                king: self.material.get(color).king,
+                ..ByRole::default()
+            })),
+        }
+    }
+
+    /// Parses a material signature like `KQPvKR`. Pieces of each side can be
+    /// given in any order.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the signature is not composed of uppercase piece letters
+    /// and exactly one `v`, or has more than [`MaterialKey::MAX_PIECES`].
+    pub fn from_ascii(s: &[u8]) -> Result<MaterialKey, ParseMaterialKeyError> {
+        let mut material = ByColor::<ByRole<u8>>::default();
+        let mut color = Color::White;
+        let mut count = 0;
+        for &ch in s {
+            if ch == b'v' && color == Color::White {
+                color = Color::Black;
+                continue;
+            }
+            if !ch.is_ascii_uppercase() {
+                return Err(ParseMaterialKeyError);
+            }
+            let role = Role::from_char(char::from(ch)).ok_or(ParseMaterialKeyError)?;
+            count += 1;
+            if count > MaterialKey::MAX_PIECES {
+                return Err(ParseMaterialKeyError);
+            }
+            *material.get_mut(color).get_mut(role) += 1;
+        }
+        if color == Color::White {
+            return Err(ParseMaterialKeyError);
+        }
+        Ok(MaterialKey { material })
+    }
+
+    fn append_to<W: AppendAscii>(&self, f: &mut W) -> Result<(), W::Error> {
+        for role in self.roles(Color::White) {
+            f.append_ascii(role.upper_char())?;
+        }
+        f.append_ascii('v')?;
+        for role in self.roles(Color::Black) {
+            f.append_ascii(role.upper_char())?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn append_to_string(&self, s: &mut alloc::string::String) {
+        let _ = self.append_to(s);
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn append_ascii_to(&self, buf: &mut alloc::vec::Vec<u8>) {
+        let _ = self.append_to(buf);
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_ascii_to<W: std::io::Write>(&self, w: W) -> std::io::Result<()> {
+        self.append_to(&mut crate::util::WriteAscii(w))
+    }
+}
+
+const fn binomial(n: u64, k: u64) -> u64 {
+    if k > n {
+        return 0;
+    }
+    // Use the smaller of k and n - k to keep intermediate results small.
+    let k = if k > n - k { n - k } else { k };
+    let mut result = 1;
+    let mut i = 0;
+    while i < k {
+        result = result * (n - i) / (i + 1);
+        i += 1;
+    }
+    result
+}
+
+impl Ord for MaterialKey {
+    fn cmp(&self, other: &MaterialKey) -> Ordering {
+        self.index().cmp(&other.index())
+    }
+}
+
+impl PartialOrd for MaterialKey {
+    fn partial_cmp(&self, other: &MaterialKey) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for MaterialKey {
+    type Err = ParseMaterialKeyError;
+
+    fn from_str(s: &str) -> Result<MaterialKey, ParseMaterialKeyError> {
+        MaterialKey::from_ascii(s.as_bytes())
+    }
+}
+
+impl fmt::Display for MaterialKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.append_to(f)
+    }
+}
+
+impl fmt::Debug for MaterialKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MaterialKey({self})")
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for MaterialKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for MaterialKey {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<MaterialKey, D::Error> {
+        crate::util::deserialize_ascii(deserializer, "material key", |s| {
+            MaterialKey::from_ascii(s).ok()
+        })
+    }
+}
+
+/// Iterator over keys reachable by captures and promotions. See
+/// [`MaterialKey::reachable()`].
+#[derive(Debug, Clone)]
+pub struct Reachable {
+    key: MaterialKey,
+    next: Option<ByColor<ByRole<u8>>>,
+}
+
+impl Reachable {
+    /// Checks if one side can get from `from` to `to`.
+    fn is_reachable(from: &ByRole<u8>, to: &ByRole<u8>) -> bool {
+        let promoted: u8 = [Role::Queen, Role::Rook, Role::Bishop, Role::Knight]
+            .into_iter()
+            .map(|role| to.get(role).saturating_sub(*from.get(role)))
+            .sum();
+        to.king == from.king && to.pawn <= from.pawn && promoted <= from.pawn - to.pawn
+    }
+
+    /// Advances the counts of one side, returning `false` after the last
+    /// combination.
+    fn advance(from: &ByRole<u8>, side: &mut ByRole<u8>) -> bool {
+        for role in [
+            Role::Pawn,
+            Role::Knight,
+            Role::Bishop,
+            Role::Rook,
+            Role::Queen,
+        ] {
+            let max = if role == Role::Pawn {
+                from.pawn
+            } else {
+                from.get(role) + from.pawn
+            };
+            let count = side.get_mut(role);
+            if *count < max {
+                *count += 1;
+                return true;
+            }
+            *count = 0;
+        }
+        false
+    }
+}
+
+impl Iterator for Reachable {
+    type Item = MaterialKey;
+
+    fn next(&mut self) -> Option<MaterialKey> {
+        loop {
+            let candidate = self.next?;
+
+            let from = self.key.material;
+            let mut next = candidate;
+            self.next = (Reachable::advance(&from.black, &mut next.black)
+                || Reachable::advance(&from.white, &mut next.white))
+            .then_some(next);
+
+            if candidate != from
+                && Reachable::is_reachable(&from.white, &candidate.white)
+                && Reachable::is_reachable(&from.black, &candidate.black)
+            {
+                return Some(MaterialKey {
+                    material: candidate,
+                });
+            }
+        }
+    }
+}
+
+impl FusedIterator for Reachable {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index() {
+        for index in 0..MaterialKey::index_bound(4) {
+            let key = MaterialKey::from_index(index).expect("valid index");
+            assert_eq!(key.index(), index);
+            assert!(key.count() <= 4);
+        }
+        assert_eq!(MaterialKey::default().index(), 0);
+        assert_eq!(MaterialKey::index_bound(0), 1);
+
+        let key = crate::Board::new().material_key();
+        assert_eq!(MaterialKey::from_index(key.index()), Some(key));
+
+        let key = MaterialKey::new(ByColor::new_with(|_| ByRole {
+            pawn: 32,
+            ..ByRole::default()
+        }))
+        .expect("64 pieces");
+        assert_eq!(MaterialKey::from_index(key.index()), Some(key));
+        assert!(key.index() < MaterialKey::index_bound(64));
+        assert_eq!(MaterialKey::from_index(MaterialKey::index_bound(64)), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_parse() {
+        use alloc::string::ToString as _;
+
+        let key: MaterialKey = "KPQvRK".parse().expect("valid key");
+        assert_eq!(key.side(Color::White).queen, 1);
+        assert_eq!(key.side(Color::Black).rook, 1);
+        assert_eq!(key.into_mirrored().to_string(), "KRvKQP");
+
+        assert_eq!(
+            "v".parse::<MaterialKey>().ok(),
+            Some(MaterialKey::default())
+        );
+        assert!("KQK".parse::<MaterialKey>().is_err());
+        assert!("KvKvK".parse::<MaterialKey>().is_err());
+        assert!("KQvk".parse::<MaterialKey>().is_err());
+        assert!("KXvK".parse::<MaterialKey>().is_err());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_normalized() {
+        use alloc::string::ToString as _;
+
+        for (key, normalized) in [
+            ("KvKQ", "KQvK"),
+            ("KRvKNN", "KNNvKR"),
+            ("KRvKB", "KRvKB"),
+            ("KBvKR", "KRvKB"),
+            ("KRvKR", "KRvKR"),
+            ("KPvKQ", "KQvKP"),
+        ] {
+            let key: MaterialKey = key.parse().expect("valid key");
+            assert_eq!(key.into_normalized().to_string(), normalized);
+            assert!(key.into_normalized().is_normalized());
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_reachable() {
+        let key: MaterialKey = "KPvK".parse().expect("valid key");
+        let mut reachable: alloc::vec::Vec<_> = key.reachable().collect();
+        reachable.sort();
+        assert_eq!(
+            reachable,
+            ["KvK", "KQvK", "KRvK", "KBvK", "KNvK"].map(|key| key.parse().expect("valid key"))
+        );
+    }
+}
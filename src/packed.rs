@@ -0,0 +1,1221 @@
+//! Compact binary encodings.
+//!
+//! [`PackedMove`] encodes a [`Move`] into 16 bits.
+//!
+//! [`PackedSetup`] encodes a [`Setup`] (and therefore any [`Position`]) into
+//! at most [`PackedSetup::MAX_LEN`] bytes. A standard chess position takes
+//! 8 to 24 bytes for the board, and usually at most 3 more bytes for the move
+//! counters.
+//!
+//! `PackedGame` encodes a starting position and a sequence of moves
+//! (with the `alloc` feature). Each move takes only as many bits as needed
+//! to select it from the legal moves, typically 5 or 6 bits.
+//!
+//! # Move layout
+//!
+//! | Bits    | Content                                                   |
+//! | ------- | --------------------------------------------------------- |
+//! | 0 - 5   | Destination square (rook square for castling moves)       |
+//! | 6 - 11  | Origin square (king square for castling moves, 0 for drops) |
+//! | 12 - 15 | Kind: 0 normal, 1 to 5 promotion to knight, bishop, rook, queen or king, 6 en passant, 7 castling, 8 to 13 drop of pawn, knight, bishop, rook, queen or king |
+//!
+//! # Setup layout
+//!
+//! 1. The occupied squares as a big-endian `u64` [`Bitboard`] (8 bytes).
+//! 2. A 4-bit code for each occupied square, from A1 to H8. Two codes are
+//!    packed into each byte, low nibble first. The last high nibble is
+//!    `0` if there is an odd number of pieces.
+//!
+//!    | Code | Piece                                                    |
+//!    | ---- | -------------------------------------------------------- |
+//!    | 0    | White pawn                                               |
+//!    | 1    | Black pawn                                               |
+//!    | 2    | White knight                                             |
+//!    | 3    | Black knight                                             |
+//!    | 4    | White bishop                                             |
+//!    | 5    | Black bishop                                             |
+//!    | 6    | White rook                                               |
+//!    | 7    | Black rook                                               |
+//!    | 8    | White queen                                              |
+//!    | 9    | Black queen                                              |
+//!    | 10   | White king                                               |
+//!    | 11   | Black king                                               |
+//!    | 12   | Pawn on the fourth (white) or fifth (black) rank, with the en passant square behind it |
+//!    | 13   | White rook with castling right                           |
+//!    | 14   | Black rook with castling right                           |
+//!    | 15   | Black king, and black to move                            |
+//!
+//!    Code 15 is used for the first black king if black is to move.
+//!
+//! 3. Only if any of the following sections is not at its default value:
+//!    the halfmove clock as an unsigned LEB128 number, followed by the ply
+//!    `2 * (fullmoves - 1) + (1 if black to move else 0)`, also as an
+//!    unsigned LEB128 number. If present, the ply determines the side to
+//!    move. It must be present if black is to move, but there is no black
+//!    king.
+//! 4. Only if any of the following sections is present: a flags byte,
+//!    followed by the sections indicated by its bits, in this order:
+//!
+//!    | Bit    | Section                                                                                      |
+//!    | ------ | -------------------------------------------------------------------------------------------- |
+//!    | `0x01` | Remaining checks, one byte: white in the low nibble, black in the high nibble                |
+//!    | `0x02` | Pockets, 12 bytes: white pawns, knights, bishops, rooks, queens, kings, then the same for black |
+//!    | `0x04` | Promoted pieces, as a big-endian `u64` bitboard                                              |
+//!    | `0x08` | Castling rights as a big-endian `u64` bitboard, only if not all castling rights are on rooks (replacing codes 13 and 14) |
+//!    | `0x10` | En passant square, as one byte from 0 (A1) to 63 (H8), only if it can not be expressed with code 12 |
+//!
+//! The encoding is canonical: Each [`Setup`] has exactly one encoding,
+//! and only canonical encodings are accepted when decoding.
+//!
+//! # Game layout
+//!
+//! 1. The number of moves as an unsigned LEB128 number.
+//! 2. The length of the encoded starting setup, as one byte, followed by
+//!    the encoded setup as described above. The length is `0` (and the
+//!    setup is omitted) if and only if the game starts from
+//!    [`Setup::initial()`], the standard starting position.
+//! 3. The moves. Each move is stored as its index into the legal moves of
+//!    the position before it, using the smallest number of bits that can
+//!    represent all indexes (no bits at all if there is only one legal
+//!    move). The indexes are concatenated, most significant bit first, and
+//!    padded with zero bits to a whole number of bytes.
+//!
+//! The legal moves are ordered by their [`PackedMove`] encoding, as an
+//! unsigned 16-bit number. This order does not depend on move generation
+//! and is well-defined for every variant.
+//!
+//! The variant and castling mode are not part of the encoding, and the
+//! en passant square of the starting position is kept only if it is
+//! legal.
+//!
+//! # Stability
+//!
+//! All layouts are guaranteed to be stable. Changing them is considered a
+//! semver breaking change and will be noted in the changelog.
+//!
+//! # Examples
+//!
+//! ```
+//! use shakmaty::{packed::PackedMove, Chess, Move, Position, Role, Square};
+//!
+//! let pos = Chess::default();
+//! let m = Move::Normal {
+//!     role: Role::Knight,
+//!     from: Square::G1,
+//!     capture: None,
+//!     to: Square::F3,
+//!     promotion: None,
+//! };
+//!
+//! let packed = PackedMove::from(&m);
+//! assert_eq!(packed.to_move(&pos)?, m);
+//! # Ok::<_, shakmaty::packed::IllegalPackedMoveError>(())
+//! ```
+//!
+//! ```
+//! use shakmaty::{packed::PackedSetup, CastlingMode, Chess, EnPassantMode, Position};
+//!
+//! let pos = Chess::default();
+//! let packed = PackedSetup::from_position(pos.clone(), EnPassantMode::Legal);
+//! assert_eq!(packed.as_bytes().len(), 24);
+//!
+//! let decoded = PackedSetup::from_bytes(packed.as_bytes())?;
+//! assert_eq!(decoded.position::<Chess>(CastlingMode::Standard)?, pos);
+//!
+//! # use shakmaty::{packed::ParsePackedSetupError, PositionError};
+//! # #[derive(Debug)] struct CommonError;
+//! # impl From<ParsePackedSetupError> for CommonError { fn from(_: ParsePackedSetupError) -> Self { Self } }
+//! # impl<P> From<PositionError<P>> for CommonError { fn from(_: PositionError<P>) -> Self { Self } }
+//! # Ok::<_, CommonError>(())
+//! ```
+//!
+//! ```
+//! # #[cfg(feature = "alloc")] {
+//! use shakmaty::{packed::PackedGame, uci::UciMove, CastlingMode, Chess, Position};
+//!
+//! let mut pos = Chess::default();
+//! let mut moves = Vec::new();
+//! for uci in ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6"] {
+//!     let m = uci.parse::<UciMove>()?.to_move(&pos)?;
+//!     pos.play_unchecked(&m);
+//!     moves.push(m);
+//! }
+//!
+//! let packed = PackedGame::encode(&Chess::default(), &moves)?;
+//! assert_eq!(packed.as_bytes().len(), 2 + 4); // Header and 6 moves
+//!
+//! let (start, decoded) = packed.decode::<Chess>(CastlingMode::Standard)?;
+//! assert_eq!(start, Chess::default());
+//! assert_eq!(decoded, moves);
+//! # }
+//!
+//! # use shakmaty::{uci::{IllegalUciMoveError, ParseUciMoveError}, Chess, PlayError};
+//! # #[derive(Debug)] struct CommonError;
+//! # impl From<ParseUciMoveError> for CommonError { fn from(_: ParseUciMoveError) -> Self { Self } }
+//! # impl From<IllegalUciMoveError> for CommonError { fn from(_: IllegalUciMoveError) -> Self { Self } }
+//! # impl From<PlayError<Chess>> for CommonError { fn from(_: PlayError<Chess>) -> Self { Self } }
+//! # #[cfg(feature = "alloc")]
+//! # impl From<shakmaty::packed::ParsePackedGameError> for CommonError { fn from(_: shakmaty::packed::ParsePackedGameError) -> Self { Self } }
+//! # Ok::<_, CommonError>(())
+//! ```
+
+use core::{fmt, num::NonZeroU32};
+
+use arrayvec::ArrayVec;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(all(feature = "alloc", feature = "variant"))]
+use crate::variant::{Variant, VariantPosition};
+use crate::{
+    Bitboard, Board, ByColor, ByRole, CastlingMode, Color, EnPassantMode, FromSetup, Move, Piece,
+    Position, PositionError, Rank, RemainingChecks, Role, Setup, Square,
+};
+#[cfg(feature = "alloc")]
+use crate::{MoveList, PlayError};
+
+/// Error when a [`PackedMove`] is not legal in a position.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IllegalPackedMoveError;
+
+impl fmt::Display for IllegalPackedMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("illegal packed move")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IllegalPackedMoveError {}
+
+const KIND_NORMAL: u16 = 0;
+const KIND_EN_PASSANT: u16 = 6;
+const KIND_CASTLE: u16 = 7;
+const KIND_PUT: u16 = 7; // Plus role
+
+/// A [`Move`] packed into 16 bits, as described in the
+/// [module documentation](self).
+///
+/// The moving role and captured role are not stored, but restored from
+/// the position when [unpacking](PackedMove::to_move()).
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct PackedMove(pub u16);
+
+impl PackedMove {
+    pub const fn from_move(m: &Move) -> PackedMove {
+        let (from, to, kind) = match *m {
+            Move::Normal {
+                from,
+                to,
+                promotion,
+                ..
+            } => (
+                from,
+                to,
+                match promotion {
+                    Some(role) => role as u16 - 1,
+                    None => KIND_NORMAL,
+                },
+            ),
+            Move::EnPassant { from, to } => (from, to, KIND_EN_PASSANT),
+            Move::Castle { king, rook } => (king, rook, KIND_CASTLE),
+            Move::Put { role, to } => (Square::A1, to, KIND_PUT + role as u16),
+        };
+        PackedMove(to as u16 | ((from as u16) << 6) | (kind << 12))
+    }
+
+    /// Restores the full move in the context of `pos`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IllegalPackedMoveError`] if the move is not legal.
+    pub fn to_move<P: Position>(self, pos: &P) -> Result<Move, IllegalPackedMoveError> {
+        let to = Square::new(u32::from(self.0 & 0x3f));
+        let from = Square::new(u32::from((self.0 >> 6) & 0x3f));
+        let candidate = match self.0 >> 12 {
+            kind @ (KIND_NORMAL..=5) => Move::Normal {
+                role: pos.board().role_at(from).ok_or(IllegalPackedMoveError)?,
+                from,
+                capture: pos.board().role_at(to),
+                to,
+                promotion: match kind {
+                    KIND_NORMAL => None,
+                    _ => Some(Role::try_from(kind + 1).map_err(|_| IllegalPackedMoveError)?),
+                },
+            },
+            KIND_EN_PASSANT => Move::EnPassant { from, to },
+            KIND_CASTLE => Move::Castle {
+                king: from,
+                rook: to,
+            },
+            kind => {
+                if from != Square::A1 {
+                    return Err(IllegalPackedMoveError);
+                }
+                Move::Put {
+                    role: Role::try_from(kind - KIND_PUT).map_err(|_| IllegalPackedMoveError)?,
+                    to,
+                }
+            }
+        };
+
+        if pos.is_legal(&candidate) {
+            Ok(candidate)
+        } else {
+            Err(IllegalPackedMoveError)
+        }
+    }
+}
+
+impl From<&Move> for PackedMove {
+    fn from(m: &Move) -> PackedMove {
+        PackedMove::from_move(m)
+    }
+}
+
+impl From<Move> for PackedMove {
+    fn from(m: Move) -> PackedMove {
+        PackedMove::from_move(&m)
+    }
+}
+
+impl From<u16> for PackedMove {
+    fn from(packed: u16) -> PackedMove {
+        PackedMove(packed)
+    }
+}
+
+impl From<PackedMove> for u16 {
+    fn from(PackedMove(packed): PackedMove) -> u16 {
+        packed
+    }
+}
+
+/// Error when decoding an invalid or non-canonical [`PackedSetup`].
+#[derive(Clone, Debug)]
+pub struct ParsePackedSetupError;
+
+impl fmt::Display for ParsePackedSetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid packed setup")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParsePackedSetupError {}
+
+const FLAG_REMAINING_CHECKS: u8 = 0x01;
+const FLAG_POCKETS: u8 = 0x02;
+const FLAG_PROMOTED: u8 = 0x04;
+const FLAG_CASTLING_RIGHTS: u8 = 0x08;
+const FLAG_EP_SQUARE: u8 = 0x10;
+
+const CODE_EP_PAWN: u8 = 12;
+const CODE_WHITE_CASTLING_ROOK: u8 = 13;
+const CODE_BLACK_CASTLING_ROOK: u8 = 14;
+const CODE_BLACK_KING_TO_MOVE: u8 = 15;
+
+/// A [`Setup`] in the compact binary encoding described in the
+/// [module documentation](self).
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct PackedSetup {
+    bytes: ArrayVec<u8, { PackedSetup::MAX_LEN }>,
+}
+
+impl PackedSetup {
+    /// Maximum length of an encoded setup.
+    pub const MAX_LEN: usize = 8 // occupied squares
+        + 32 // piece codes, two per byte
+        + 5 // halfmove clock
+        + 5 // ply
+        + 1 // flags
+        + 1 // remaining checks
+        + 12 // pockets
+        + 8 // promoted pieces
+        + 8 // castling rights
+        + 1; // en passant square
+
+    pub fn from_setup(setup: &Setup) -> PackedSetup {
+        let mut bytes = ArrayVec::new();
+        bytes.extend(setup.board.occupied().0.to_be_bytes());
+
+        let ep_pawn = setup.ep_square.and_then(|sq| ep_pawn(&setup.board, sq));
+        let black_king_to_move = if setup.turn.is_black() {
+            setup.board.by_piece(Color::Black.king()).first()
+        } else {
+            None
+        };
+        let regular_castling_rights = setup.castling_rights.is_subset(setup.board.rooks());
+
+        let mut nibbles = setup.board.occupied().into_iter().map(|sq| {
+            let piece = setup.board.piece_at(sq).expect("occupied");
+            if Some(sq) == ep_pawn {
+                CODE_EP_PAWN
+            } else if Some(sq) == black_king_to_move {
+                CODE_BLACK_KING_TO_MOVE
+            } else if regular_castling_rights && setup.castling_rights.contains(sq) {
+                piece
+                    .color
+                    .fold_wb(CODE_WHITE_CASTLING_ROOK, CODE_BLACK_CASTLING_ROOK)
+            } else {
+                piece_code(piece)
+            }
+        });
+        while let Some(lo) = nibbles.next() {
+            bytes.push(lo | (nibbles.next().unwrap_or(0) << 4));
+        }
+
+        let mut flags = 0;
+        if setup.remaining_checks.is_some() {
+            flags |= FLAG_REMAINING_CHECKS;
+        }
+        if setup.pockets.is_some() {
+            flags |= FLAG_POCKETS;
+        }
+        if setup.promoted.any() {
+            flags |= FLAG_PROMOTED;
+        }
+        if !regular_castling_rights {
+            flags |= FLAG_CASTLING_RIGHTS;
+        }
+        if setup.ep_square.is_some() && ep_pawn.is_none() {
+            flags |= FLAG_EP_SQUARE;
+        }
+
+        let ply = 2 * (u64::from(setup.fullmoves.get()) - 1) + u64::from(setup.turn.is_black());
+        if setup.halfmoves != 0
+            || ply > 1
+            || (setup.turn.is_black() && black_king_to_move.is_none())
+            || flags != 0
+        {
+            push_leb128(&mut bytes, u64::from(setup.halfmoves));
+            push_leb128(&mut bytes, ply);
+        }
+
+        if flags != 0 {
+            bytes.push(flags);
+        }
+        if let Some(remaining_checks) = setup.remaining_checks {
+            bytes.push(u8::from(remaining_checks.white) | (u8::from(remaining_checks.black) << 4));
+        }
+        if let Some(pockets) = setup.pockets {
+            for side in pockets {
+                bytes.extend(side);
+            }
+        }
+        if setup.promoted.any() {
+            bytes.extend(setup.promoted.0.to_be_bytes());
+        }
+        if !regular_castling_rights {
+            bytes.extend(setup.castling_rights.0.to_be_bytes());
+        }
+        if let (Some(ep_square), None) = (setup.ep_square, ep_pawn) {
+            bytes.push(u8::from(ep_square));
+        }
+
+        PackedSetup { bytes }
+    }
+
+    pub fn from_position<P: Position>(pos: P, mode: EnPassantMode) -> PackedSetup {
+        PackedSetup::from_setup(&pos.into_setup(mode))
+    }
+
+    /// Decodes and validates an encoded setup.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParsePackedSetupError`] if `bytes` is not a valid
+    /// canonical encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<PackedSetup, ParsePackedSetupError> {
+        let setup = decode(bytes).ok_or(ParsePackedSetupError)?;
+        let packed = PackedSetup::from_setup(&setup);
+        if packed.as_bytes() == bytes {
+            Ok(packed)
+        } else {
+            Err(ParsePackedSetupError)
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn to_setup(&self) -> Setup {
+        decode(&self.bytes).expect("valid packed setup")
+    }
+
+    /// Set up a [`Position`]. See [`FromSetup`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PositionError`] if the setup does not meet basic validity
+    /// requirements, exactly like [`Setup::position()`].
+    pub fn position<P: FromSetup>(&self, mode: CastlingMode) -> Result<P, PositionError<P>> {
+        self.to_setup().position(mode)
+    }
+}
+
+impl From<&Setup> for PackedSetup {
+    fn from(setup: &Setup) -> PackedSetup {
+        PackedSetup::from_setup(setup)
+    }
+}
+
+impl From<PackedSetup> for Setup {
+    fn from(packed: PackedSetup) -> Setup {
+        packed.to_setup()
+    }
+}
+
+impl TryFrom<&[u8]> for PackedSetup {
+    type Error = ParsePackedSetupError;
+
+    fn try_from(bytes: &[u8]) -> Result<PackedSetup, ParsePackedSetupError> {
+        PackedSetup::from_bytes(bytes)
+    }
+}
+
+impl AsRef<[u8]> for PackedSetup {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+/// Error when decoding an invalid or non-canonical [`PackedGame`].
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+pub struct ParsePackedGameError;
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for ParsePackedGameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid packed game")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParsePackedGameError {}
+
+/// A starting position and a sequence of moves in the compact binary
+/// encoding described in the [module documentation](self).
+///
+/// The variant and castling mode are not stored. They must be known when
+/// [decoding](PackedGame::decode()).
+#[cfg(feature = "alloc")]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct PackedGame {
+    bytes: Vec<u8>,
+}
+
+#[cfg(feature = "alloc")]
+impl PackedGame {
+    /// Encodes the moves played from `pos`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PlayError`] with the first illegal move and the position
+    /// in which it was to be played.
+    pub fn encode<P: Position + Clone>(
+        pos: &P,
+        moves: &[Move],
+    ) -> Result<PackedGame, PlayError<P>> {
+        let mut bytes = Vec::new();
+        push_leb128(&mut bytes, moves.len() as u64);
+
+        let setup = pos.clone().into_setup(EnPassantMode::Legal);
+        if setup == Setup::initial() {
+            bytes.push(0);
+        } else {
+            let packed = PackedSetup::from_setup(&setup);
+            bytes.push(packed.as_bytes().len() as u8);
+            bytes.extend_from_slice(packed.as_bytes());
+        }
+
+        let mut pos = pos.clone();
+        let mut acc = 0u64;
+        let mut acc_bits = 0;
+        for m in moves {
+            let legals = ordered_legal_moves(&pos);
+            let Some(index) = legals.iter().position(|legal| legal == m) else {
+                return Err(PlayError {
+                    m: m.clone(),
+                    position: pos,
+                });
+            };
+            let width = index_width(legals.len());
+            acc = (acc << width) | index as u64;
+            acc_bits += width;
+            while acc_bits >= 8 {
+                acc_bits -= 8;
+                bytes.push((acc >> acc_bits) as u8);
+            }
+            pos.play_unchecked(m);
+        }
+        if acc_bits > 0 {
+            bytes.push((acc << (8 - acc_bits)) as u8);
+        }
+
+        Ok(PackedGame { bytes })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Decodes the starting position and replays the moves.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParsePackedGameError`] if the bytes are not a valid
+    /// canonical encoding, if the starting position is not valid for `P`
+    /// and `mode`, if any move index is out of range, or if there are more
+    /// than 1024 consecutive forced moves.
+    pub fn decode<P: FromSetup + Position + Clone>(
+        &self,
+        mode: CastlingMode,
+    ) -> Result<(P, Vec<Move>), ParsePackedGameError> {
+        let (count, setup, moves) = self.header().ok_or(ParsePackedGameError)?;
+        let pos: P = setup.position(mode).map_err(|_| ParsePackedGameError)?;
+        let moves = decode_moves(pos.clone(), count, moves).ok_or(ParsePackedGameError)?;
+        Ok((pos, moves))
+    }
+
+    /// Like [`PackedGame::decode()`], but for a variant that is only known
+    /// at runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParsePackedGameError`] if the bytes are not a valid
+    /// canonical encoding, if the starting position is not valid for
+    /// `variant` and `mode`, if any move index is out of range, or if there
+    /// are more than 1024 consecutive forced moves.
+    #[cfg(feature = "variant")]
+    pub fn decode_variant(
+        &self,
+        variant: Variant,
+        mode: CastlingMode,
+    ) -> Result<(VariantPosition, Vec<Move>), ParsePackedGameError> {
+        let (count, setup, moves) = self.header().ok_or(ParsePackedGameError)?;
+        let pos =
+            VariantPosition::from_setup(variant, setup, mode).map_err(|_| ParsePackedGameError)?;
+        let moves = decode_moves(pos.clone(), count, moves).ok_or(ParsePackedGameError)?;
+        Ok((pos, moves))
+    }
+
+    fn header(&self) -> Option<(u64, Setup, &[u8])> {
+        let mut cursor = Cursor { bytes: &self.bytes };
+        let count = cursor.leb128(5)?;
+        let mut canonical_count = ArrayVec::<u8, 5>::new();
+        push_leb128(&mut canonical_count, count);
+        if u32::try_from(count).is_err() || !self.bytes.starts_with(&canonical_count) {
+            return None;
+        }
+        let len = usize::from(cursor.byte()?);
+        let setup = if len == 0 {
+            Setup::initial()
+        } else {
+            let setup = PackedSetup::from_bytes(cursor.bytes.get(..len)?)
+                .ok()?
+                .to_setup();
+            if setup == Setup::initial() {
+                return None;
+            }
+            cursor.bytes = &cursor.bytes[len..];
+            setup
+        };
+        Some((count, setup, cursor.bytes))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<Vec<u8>> for PackedGame {
+    fn from(bytes: Vec<u8>) -> PackedGame {
+        PackedGame { bytes }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<PackedGame> for Vec<u8> {
+    fn from(packed: PackedGame) -> Vec<u8> {
+        packed.into_bytes()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AsRef<[u8]> for PackedGame {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+/// Legal moves in the order used by [`PackedGame`]: by their
+/// [`PackedMove`] encoding.
+#[cfg(feature = "alloc")]
+fn ordered_legal_moves<P: Position>(pos: &P) -> MoveList {
+    let mut legals = pos.legal_moves();
+    legals.sort_unstable_by_key(PackedMove::from_move);
+    legals
+}
+
+/// Number of bits needed to store an index into `len` moves.
+#[cfg(feature = "alloc")]
+fn index_width(len: usize) -> u32 {
+    usize::BITS - len.saturating_sub(1).leading_zeros()
+}
+
+/// Maximum number of consecutive forced moves when decoding a
+/// [`PackedGame`].
+///
+/// Forced moves take no space in the encoding, so without a limit a few
+/// bytes could claim billions of moves. A longer run of forced moves would
+/// repeat a position five times, which ends the game anyway.
+#[cfg(feature = "alloc")]
+const MAX_FORCED_PLIES: u32 = 1024;
+
+#[cfg(feature = "alloc")]
+fn decode_moves<P: Position>(mut pos: P, count: u64, bytes: &[u8]) -> Option<Vec<Move>> {
+    let mut reader = BitReader { bytes, bits: 0 };
+    let mut moves = Vec::new();
+    let mut forced = 0;
+    for _ in 0..count {
+        let mut legals = ordered_legal_moves(&pos);
+        let width = index_width(legals.len());
+        if width == 0 {
+            forced += 1;
+            if forced > MAX_FORCED_PLIES {
+                return None;
+            }
+        } else {
+            forced = 0;
+        }
+        let index = reader.read(width)?;
+        if index >= legals.len() {
+            return None;
+        }
+        let m = legals.swap_remove(index);
+        pos.play_unchecked(&m);
+        moves.push(m);
+    }
+    reader.is_padding().then_some(moves)
+}
+
+#[cfg(feature = "alloc")]
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    bits: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl BitReader<'_> {
+    fn read(&mut self, width: u32) -> Option<usize> {
+        let mut n = 0;
+        for _ in 0..width {
+            let byte = self.bytes.get(self.bits / 8)?;
+            n = (n << 1) | usize::from((byte >> (7 - self.bits % 8)) & 1);
+            self.bits += 1;
+        }
+        Some(n)
+    }
+
+    /// Checks that only zero bits are left to complete the last byte.
+    fn is_padding(&mut self) -> bool {
+        let padding = (8 - self.bits % 8) % 8;
+        self.read(padding as u32) == Some(0) && self.bits / 8 == self.bytes.len()
+    }
+}
+
+fn piece_code(piece: Piece) -> u8 {
+    (u8::from(piece.role) - 1) * 2 + u8::from(piece.color.is_black())
+}
+
+fn ep_pawn(board: &Board, ep_square: Square) -> Option<Square> {
+    let (pawn, color) = match ep_square.rank() {
+        Rank::Third => (ep_square.offset(8)?, Color::White),
+        Rank::Sixth => (ep_square.offset(-8)?, Color::Black),
+        _ => return None,
+    };
+    (board.piece_at(pawn) == Some(color.pawn())).then_some(pawn)
+}
+
+fn push_leb128<E: Extend<u8>>(bytes: &mut E, mut n: u64) {
+    while n > 0x7f {
+        bytes.extend([(n & 0x7f) as u8 | 0x80]);
+        n >>= 7;
+    }
+    bytes.extend([n as u8]);
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+}
+
+impl Cursor<'_> {
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let (&first, rest) = self.bytes.split_first()?;
+        self.bytes = rest;
+        Some(first)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        let head = self.bytes.get(..8)?;
+        self.bytes = &self.bytes[8..];
+        Some(u64::from_be_bytes(head.try_into().ok()?))
+    }
+
+    fn leb128(&mut self, max_bytes: u32) -> Option<u64> {
+        let mut n = 0;
+        for i in 0..max_bytes {
+            let byte = self.byte()?;
+            n |= u64::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Some(n);
+            }
+        }
+        None
+    }
+}
+
+fn decode(bytes: &[u8]) -> Option<Setup> {
+    let mut cursor = Cursor { bytes };
+    let mut setup = Setup::empty();
+
+    let occupied = Bitboard(cursor.u64()?);
+    let mut codes = ArrayVec::<u8, 64>::new();
+    for _ in 0..occupied.count().div_ceil(2) {
+        let byte = cursor.byte()?;
+        codes.push(byte & 0xf);
+        codes.push(byte >> 4);
+    }
+
+    let mut black_to_move = false;
+    for (sq, code) in occupied.into_iter().zip(codes) {
+        let piece = match code {
+            CODE_EP_PAWN => {
+                let (color, ep_square) = match sq.rank() {
+                    Rank::Fourth => (Color::White, sq.offset(-8)?),
+                    Rank::Fifth => (Color::Black, sq.offset(8)?),
+                    _ => return None,
+                };
+                if setup.ep_square.replace(ep_square).is_some() {
+                    return None;
+                }
+                color.pawn()
+            }
+            CODE_WHITE_CASTLING_ROOK => {
+                setup.castling_rights.add(sq);
+                Color::White.rook()
+            }
+            CODE_BLACK_CASTLING_ROOK => {
+                setup.castling_rights.add(sq);
+                Color::Black.rook()
+            }
+            CODE_BLACK_KING_TO_MOVE => {
+                black_to_move = true;
+                Color::Black.king()
+            }
+            _ => Piece {
+                color: Color::from_black(code & 1 != 0),
+                role: Role::try_from(code / 2 + 1).ok()?,
+            },
+        };
+        setup.board.set_piece_at(sq, piece);
+    }
+    setup.turn = Color::from_black(black_to_move);
+
+    if !cursor.is_empty() {
+        setup.halfmoves = u32::try_from(cursor.leb128(5)?).ok()?;
+        let ply = cursor.leb128(5)?;
+        setup.turn = Color::from_black(ply % 2 == 1);
+        setup.fullmoves = NonZeroU32::new(u32::try_from(ply / 2 + 1).ok()?)?;
+    }
+
+    if !cursor.is_empty() {
+        let flags = cursor.byte()?;
+        if flags & !0x1f != 0 {
+            return None;
+        }
+        if flags & FLAG_REMAINING_CHECKS != 0 {
+            let byte = cursor.byte()?;
+            setup.remaining_checks = Some(ByColor {
+                white: RemainingChecks::try_from(byte & 0xf).ok()?,
+                black: RemainingChecks::try_from(byte >> 4).ok()?,
+            });
+        }
+        if flags & FLAG_POCKETS != 0 {
+            let mut pockets = ByColor::<ByRole<u8>>::default();
+            for color in Color::ALL {
+                for role in Role::ALL {
+                    *pockets.piece_mut(role.of(color)) = cursor.byte()?;
+                }
+            }
+            setup.pockets = Some(pockets);
+        }
+        if flags & FLAG_PROMOTED != 0 {
+            setup.promoted = Bitboard(cursor.u64()?);
+        }
+        if flags & FLAG_CASTLING_RIGHTS != 0 {
+            setup.castling_rights = Bitboard(cursor.u64()?);
+        }
+        if flags & FLAG_EP_SQUARE != 0 {
+            setup.ep_square = Some(Square::try_from(cursor.byte()?).ok()?);
+        }
+    }
+
+    cursor.is_empty().then_some(setup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fen::Fen, Chess};
+
+    fn assert_packed_moves_round_trip<P: Position>(pos: &P) {
+        for m in pos.legal_moves() {
+            let packed = PackedMove::from(&m);
+            assert_eq!(packed.to_move(pos), Ok(m));
+        }
+    }
+
+    #[test]
+    fn test_packed_move() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "8/8/8/2k5/3pP3/8/8/4K3 b - e3 0 1",
+        ] {
+            let pos: Chess = Fen::from_ascii(fen.as_bytes())
+                .expect("valid fen")
+                .into_position(CastlingMode::Standard)
+                .expect("legal position");
+            assert_packed_moves_round_trip(&pos);
+        }
+
+        assert_eq!(
+            PackedMove(0).to_move(&Chess::default()),
+            Err(IllegalPackedMoveError)
+        );
+        assert_eq!(
+            PackedMove(0xffff).to_move(&Chess::default()),
+            Err(IllegalPackedMoveError)
+        );
+    }
+
+    #[cfg(feature = "variant")]
+    #[test]
+    fn test_packed_move_variants() {
+        use crate::variant::{Antichess, Crazyhouse};
+
+        let pos: Crazyhouse = Fen::from_ascii(
+            b"r1bqk2r/pppp1ppp/2n5/4p3/1b2P3/2N5/PPPP1PPP/R1BQKB1R/Nn w KQkq - 0 1",
+        )
+        .expect("valid fen")
+        .into_position(CastlingMode::Standard)
+        .expect("legal position");
+        assert_packed_moves_round_trip(&pos);
+
+        let pos: Antichess = Fen::from_ascii(b"8/1P6/8/8/8/8/8/8 w - - 0 1")
+            .expect("valid fen")
+            .into_position(CastlingMode::Standard)
+            .expect("legal position");
+        assert_packed_moves_round_trip(&pos);
+    }
+
+    fn round_trip(fen: &str) -> usize {
+        let setup = Fen::from_ascii(fen.as_bytes())
+            .expect("valid fen")
+            .into_setup();
+        let packed = PackedSetup::from_setup(&setup);
+        assert_eq!(packed.to_setup(), setup, "{fen}");
+        assert_eq!(
+            PackedSetup::from_bytes(packed.as_bytes()).expect("canonical"),
+            packed
+        );
+        packed.as_bytes().len()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        assert_eq!(
+            round_trip("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            24
+        );
+        assert_eq!(
+            round_trip("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            24
+        );
+        assert_eq!(
+            round_trip("r3k2r/8/8/8/8/8/8/R3K2R w Kq - 17 123"),
+            8 + 3 + 1 + 2
+        );
+        round_trip("4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+        round_trip("8/8/8/8/8/8/8/8 b - - 0 1");
+        round_trip("8/8/8/8/8/8/8/8 w - - 4294967295 4294967295");
+        round_trip("rRpppppp/8/8/8/8/8/PPPPPPBN/PPRQKBNR w KA - 0 1");
+        round_trip("4k3/8/8/8/8/8/8/4K3 w - e6 0 1");
+        round_trip("rnbqk1nQ~/ppppp3/8/5p2/8/5N2/PPPPPPP1/RNBQKB1R/PPBRq b KQq - 0 6");
+        round_trip("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+1 0 1");
+        round_trip("8/8/8/8/8/8/8/8[RRrQqp] w - - 0 1");
+    }
+
+    #[test]
+    fn test_max_len() {
+        // Full board, all optional parts present, and move counters that
+        // take the longest encoding.
+        let mut setup = Setup::empty();
+        for sq in Square::ALL {
+            setup.board.set_piece_at(sq, Color::White.pawn());
+        }
+        setup.promoted = Bitboard::FULL;
+        setup.pockets = Some(ByColor::new_with(|_| ByRole {
+            pawn: 16,
+            knight: 2,
+            bishop: 2,
+            rook: 2,
+            queen: 1,
+            king: 0,
+        }));
+        setup.turn = Color::Black;
+        setup.castling_rights = Bitboard::CORNERS;
+        setup.ep_square = Some(Square::E4);
+        setup.remaining_checks = Some(ByColor::default());
+        setup.halfmoves = u32::MAX;
+        setup.fullmoves = NonZeroU32::MAX;
+
+        let packed = PackedSetup::from_setup(&setup);
+        assert_eq!(packed.as_bytes().len(), PackedSetup::MAX_LEN);
+        assert_eq!(packed.to_setup(), setup);
+    }
+
+    #[test]
+    fn test_position() {
+        let packed = PackedSetup::from_position(Chess::default(), EnPassantMode::Legal);
+        assert_eq!(
+            packed.position::<Chess>(CastlingMode::Standard).ok(),
+            Some(Chess::default())
+        );
+
+        let empty = PackedSetup::from_setup(&Setup::empty());
+        assert_eq!(
+            empty
+                .position::<Chess>(CastlingMode::Standard)
+                .expect_err("empty board")
+                .kinds(),
+            Setup::empty()
+                .position::<Chess>(CastlingMode::Standard)
+                .expect_err("empty board")
+                .kinds()
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let packed = PackedSetup::from_setup(&Setup::initial());
+        let bytes = packed.as_bytes();
+
+        // Truncated.
+        assert!(PackedSetup::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        // Trailing garbage.
+        let mut long = ArrayVec::<u8, 64>::new();
+        long.extend(bytes.iter().copied());
+        long.push(0);
+        assert!(PackedSetup::from_bytes(&long).is_err());
+        // Non-canonical default counters.
+        long.pop();
+        long.push(0);
+        long.push(0);
+        assert!(PackedSetup::from_bytes(&long).is_err());
+        // Ep pawn on wrong rank.
+        let mut ep = ArrayVec::<u8, 64>::new();
+        ep.extend(bytes.iter().copied());
+        ep[8] = CODE_EP_PAWN | (ep[8] & 0xf0);
+        assert!(PackedSetup::from_bytes(&ep).is_err());
+    }
+
+    #[cfg(feature = "alloc")]
+    fn uci_moves<P: Position + Clone>(pos: &P, ucis: &[&str]) -> Vec<Move> {
+        use crate::uci::UciMove;
+
+        let mut pos = pos.clone();
+        ucis.iter()
+            .map(|uci| {
+                let m = uci
+                    .parse::<UciMove>()
+                    .expect("valid uci")
+                    .to_move(&pos)
+                    .expect("legal move");
+                pos.play_unchecked(&m);
+                m
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_packed_game() {
+        // En passant, promotion with capture.
+        let moves = uci_moves(
+            &Chess::default(),
+            &[
+                "e2e4", "d7d5", "e4e5", "f7f5", "e5f6", "g7g6", "f6f7", "e8d7", "f7g8q",
+            ],
+        );
+        let packed = PackedGame::encode(&Chess::default(), &moves).expect("legal moves");
+        assert_eq!(packed.as_bytes()[..2], [9, 0]);
+        assert!(packed.as_bytes().len() <= 2 + moves.len());
+        assert_eq!(
+            packed
+                .decode::<Chess>(CastlingMode::Standard)
+                .expect("valid"),
+            (Chess::default(), moves)
+        );
+
+        // Custom starting position, castling.
+        let pos: Chess = Fen::from_ascii(b"r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1")
+            .expect("valid fen")
+            .into_position(CastlingMode::Standard)
+            .expect("legal position");
+        let moves = uci_moves(&pos, &["e1h1", "e8a8"]);
+        let packed = PackedGame::encode(&pos, &moves).expect("legal moves");
+        assert_eq!(
+            packed
+                .decode::<Chess>(CastlingMode::Standard)
+                .expect("valid"),
+            (pos, moves)
+        );
+
+        // Empty game.
+        let packed = PackedGame::encode(&Chess::default(), &[]).expect("no moves");
+        assert_eq!(packed.as_bytes(), [0, 0]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_packed_game_illegal_move() {
+        let moves = uci_moves(&Chess::default(), &["e2e4", "e7e5"]);
+        let err = PackedGame::encode(&Chess::default(), &[moves[1].clone(), moves[0].clone()])
+            .expect_err("illegal move");
+        assert_eq!(err.m, moves[1]);
+        assert_eq!(err.position, Chess::default());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_packed_game_invalid() {
+        let decode = |bytes: &[u8]| {
+            PackedGame::from(bytes.to_vec())
+                .decode::<Chess>(CastlingMode::Standard)
+                .is_ok()
+        };
+
+        let moves = uci_moves(&Chess::default(), &["e2e4", "e7e5", "g1f3"]);
+        let packed = PackedGame::encode(&Chess::default(), &moves).expect("legal moves");
+        let bytes = packed.as_bytes();
+        assert!(decode(bytes));
+
+        // Truncated.
+        assert!(!decode(&bytes[..bytes.len() - 1]));
+        assert!(!decode(&[]));
+        // Trailing garbage.
+        let mut long = bytes.to_vec();
+        long.push(0);
+        assert!(!decode(&long));
+        // Non-zero padding.
+        let mut padded = bytes.to_vec();
+        *padded.last_mut().expect("moves") |= 1;
+        assert!(!decode(&padded));
+        // Index out of range: 20 legal moves, but index 31.
+        assert!(!decode(&[1, 0, 0xf8]));
+        // Non-canonical move count.
+        assert!(!decode(&[0x80, 0x00, 0]));
+        // Non-canonical explicit starting position.
+        let initial = PackedSetup::from_setup(&Setup::initial());
+        let mut explicit = alloc::vec![0, initial.as_bytes().len() as u8];
+        explicit.extend_from_slice(initial.as_bytes());
+        assert!(!decode(&explicit));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_packed_game_forced_moves() {
+        // Both kings shuttle back and forth. Everything else is blocked.
+        let pos: Chess = "k1b5/1p1p4/1P1P4/8/8/4p1p1/4P1P1/5B1K w - - 0 1"
+            .parse::<Fen>()
+            .expect("valid fen")
+            .into_position(CastlingMode::Standard)
+            .expect("legal position");
+        let mut moves = Vec::new();
+        let mut p = pos.clone();
+        for _ in 0..=MAX_FORCED_PLIES {
+            let legals = p.legal_moves();
+            assert_eq!(legals.len(), 1);
+            p.play_unchecked(&legals[0]);
+            moves.push(legals[0].clone());
+        }
+
+        let decode = |moves: &[Move]| {
+            PackedGame::encode(&pos, moves)
+                .expect("legal moves")
+                .decode::<Chess>(CastlingMode::Standard)
+        };
+        assert_eq!(
+            decode(&moves[..MAX_FORCED_PLIES as usize])
+                .expect("valid")
+                .1,
+            &moves[..MAX_FORCED_PLIES as usize]
+        );
+        assert!(decode(&moves).is_err());
+
+        // Huge move count, but no bits required for the moves.
+        let mut bytes = Vec::new();
+        push_leb128(&mut bytes, u64::from(u32::MAX));
+        let header = PackedGame::encode(&pos, &[])
+            .expect("no moves")
+            .into_bytes();
+        bytes.extend_from_slice(&header[1..]);
+        assert!(PackedGame::from(bytes)
+            .decode::<Chess>(CastlingMode::Standard)
+            .is_err());
+    }
+
+    #[cfg(all(feature = "alloc", feature = "variant"))]
+    #[test]
+    fn test_packed_game_variant() {
+        use crate::variant::{Variant, VariantPosition};
+
+        let pos = VariantPosition::new(Variant::Crazyhouse);
+        let moves = uci_moves(
+            &pos,
+            &["e2e4", "d7d5", "e4d5", "d8d5", "b1c3", "d5a5", "P@d4"],
+        );
+        let packed = PackedGame::encode(&pos, &moves).expect("legal moves");
+        assert_eq!(
+            packed
+                .decode_variant(Variant::Crazyhouse, CastlingMode::Standard)
+                .expect("valid"),
+            (pos, moves)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_setup() {
+        use serde_test::{assert_tokens, Configure as _, Token};
+
+        assert_tokens(
+            &Setup::initial().compact(),
+            &[Token::Bytes(&[
+                0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0x2d, 0x84, 0x4a, 0xd2, 0x00, 0x00,
+                0x00, 0x00, 0x11, 0x11, 0x11, 0x11, 0x3e, 0x95, 0x5b, 0xe3,
+            ])],
+        );
+    }
+}
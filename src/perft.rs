@@ -1,4 +1,52 @@
-use crate::position::Position;
+//! Count legal move paths to test move generation.
+//!
+//! [`perft()`] counts the leaf nodes of the tree of legal moves.
+//! [`perft_stats()`] additionally breaks down the moves leading to the
+//! leaf nodes into the usual categories: captures, en passant, castles,
+//! promotions, drops, checks, discovered checks, double checks and
+//! checkmates.
+//!
+//! When the counts disagree with a reference implementation,
+//! [`divide()`] and [`divide_stats()`] help to find the faulty branch, by
+//! reporting the results for each legal move of the root position
+//! separately.
+//!
+//! For deep verification runs, `HashedPerft` (with the `alloc` feature)
+//! caches subtree counts in a transposition table, and can split the work
+//! at the root across multiple threads (with the `std` feature).
+//!
+//! # Examples
+//!
+//! ```
+//! use shakmaty::{perft::divide, CastlingMode, Chess};
+//!
+//! let pos = Chess::default();
+//! for (m, nodes) in divide(&pos, 3) {
+//!     println!("{}: {}", m.to_uci(CastlingMode::Standard), nodes);
+//! }
+//!
+//! assert_eq!(divide(&pos, 3).map(|(_, nodes)| nodes).sum::<u64>(), 8902);
+//! ```
+
+use core::{
+    iter::Sum,
+    ops::{Add, AddAssign},
+};
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::{
+    num::NonZeroUsize,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{position::Position, Move};
+#[cfg(feature = "alloc")]
+use crate::{
+    zobrist::{Zobrist64, ZobristHash as _},
+    EnPassantMode,
+};
 
 /// Counts legal move paths of a given length.
 ///
@@ -46,10 +94,326 @@ pub fn perft<P: Position + Clone>(pos: &P, depth: u32) -> u64 {
     }
 }
 
+/// Leaf node count of a perft run, with a breakdown of the moves leading
+/// to the leaf nodes.
+///
+/// The categories are not exclusive. For example, an en passant capture
+/// that gives check is counted as a capture, an en passant move and a
+/// check.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct PerftStats {
+    /// Number of leaf nodes, the same as [`perft()`].
+    pub nodes: u64,
+    /// Captures, including en passant.
+    pub captures: u64,
+    pub en_passants: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    /// Piece drops, like in Crazyhouse.
+    pub drops: u64,
+    /// Moves that give check, including discovered and double checks.
+    pub checks: u64,
+    /// Moves that give check with a piece other than the moved piece.
+    pub discovered_checks: u64,
+    /// Moves that give check with more than one piece.
+    pub double_checks: u64,
+    pub checkmates: u64,
+}
+
+impl PerftStats {
+    fn leaf<P: Position>(pos: &P, m: &Move, child: &P) -> PerftStats {
+        let checkers = child.checkers();
+        let moved = match m.castling_side() {
+            Some(side) => side.rook_to(pos.turn()),
+            None => m.to(),
+        };
+        PerftStats {
+            nodes: 1,
+            captures: u64::from(m.is_capture()),
+            en_passants: u64::from(m.is_en_passant()),
+            castles: u64::from(m.is_castle()),
+            promotions: u64::from(m.is_promotion()),
+            drops: u64::from(matches!(*m, Move::Put { .. })),
+            checks: u64::from(checkers.any()),
+            discovered_checks: u64::from(checkers.without(moved).any()),
+            double_checks: u64::from(checkers.more_than_one()),
+            checkmates: u64::from(checkers.any() && child.legal_moves().is_empty()),
+        }
+    }
+}
+
+impl AddAssign for PerftStats {
+    fn add_assign(&mut self, other: PerftStats) {
+        self.nodes += other.nodes;
+        self.captures += other.captures;
+        self.en_passants += other.en_passants;
+        self.castles += other.castles;
+        self.promotions += other.promotions;
+        self.drops += other.drops;
+        self.checks += other.checks;
+        self.discovered_checks += other.discovered_checks;
+        self.double_checks += other.double_checks;
+        self.checkmates += other.checkmates;
+    }
+}
+
+impl Add for PerftStats {
+    type Output = PerftStats;
+
+    fn add(mut self, other: PerftStats) -> PerftStats {
+        self += other;
+        self
+    }
+}
+
+impl Sum for PerftStats {
+    fn sum<I: Iterator<Item = PerftStats>>(iter: I) -> PerftStats {
+        iter.fold(PerftStats::default(), Add::add)
+    }
+}
+
+/// Like [`perft()`], but also breaks down the moves leading to the leaf
+/// nodes into categories. See [`PerftStats`].
+///
+/// This is slower than [`perft()`], because it needs to play the last
+/// move of each path.
+///
+/// # Examples
+///
+/// ```
+/// use shakmaty::{perft::perft_stats, Chess};
+///
+/// let stats = perft_stats(&Chess::default(), 4);
+/// assert_eq!(stats.nodes, 197_281);
+/// assert_eq!(stats.captures, 1576);
+/// assert_eq!(stats.checks, 469);
+/// assert_eq!(stats.checkmates, 8);
+/// ```
+pub fn perft_stats<P: Position + Clone>(pos: &P, depth: u32) -> PerftStats {
+    if depth < 1 {
+        PerftStats {
+            nodes: 1,
+            ..PerftStats::default()
+        }
+    } else {
+        pos.legal_moves()
+            .iter()
+            .map(|m| {
+                let mut child = pos.clone();
+                child.play_unchecked(m);
+                if depth == 1 {
+                    PerftStats::leaf(pos, m, &child)
+                } else {
+                    perft_stats(&child, depth - 1)
+                }
+            })
+            .sum()
+    }
+}
+
+/// Runs [`perft()`] for each legal move of `pos`, yielding the move and the
+/// number of leaf nodes below it, in the order of
+/// [`Position::legal_moves()`].
+///
+/// Returns nothing at depth 0.
+pub fn divide<P: Position + Clone>(pos: &P, depth: u32) -> impl Iterator<Item = (Move, u64)> + '_ {
+    divide_with(pos, depth, perft)
+}
+
+/// Runs [`perft_stats()`] for each legal move of `pos`, yielding the move
+/// and the statistics below it, in the order of
+/// [`Position::legal_moves()`].
+///
+/// Returns nothing at depth 0.
+pub fn divide_stats<P: Position + Clone>(
+    pos: &P,
+    depth: u32,
+) -> impl Iterator<Item = (Move, PerftStats)> + '_ {
+    divide_with(pos, depth, perft_stats)
+}
+
+fn divide_with<'a, P: Position + Clone, T: 'a>(
+    pos: &'a P,
+    depth: u32,
+    f: fn(&P, u32) -> T,
+) -> impl Iterator<Item = (Move, T)> + 'a {
+    let moves = if depth < 1 {
+        Default::default()
+    } else {
+        pos.legal_moves()
+    };
+    moves.into_iter().map(move |m| {
+        let mut child = pos.clone();
+        child.play_unchecked(&m);
+        let result = f(&child, depth - 1);
+        (m, result)
+    })
+}
+
+/// Perft driver with a transposition table, for deep verification runs.
+///
+/// Subtree counts are cached by [`Zobrist64`] hash and remaining depth.
+/// Positions of different types (for example [`Chess`](crate::Chess) and
+/// `Atomic`) can have the same hash but
+/// different subtrees, so [clear](HashedPerft::clear()) the table before
+/// switching.
+///
+/// Hash collisions could in theory lead to wrong results. With 64-bit keys,
+/// this is very unlikely to matter in practice.
+///
+/// # Examples
+///
+/// ```
+/// use shakmaty::{perft::HashedPerft, Chess};
+///
+/// let mut perft = HashedPerft::new(1 << 16);
+/// assert_eq!(perft.perft(&Chess::default(), 4), 197_281);
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+pub struct HashedPerft {
+    table: Vec<Entry>,
+    bulk_counting: bool,
+}
+
+#[cfg(feature = "alloc")]
+#[derive(Copy, Clone, Debug, Default)]
+struct Entry {
+    key: u64,
+    depth: u32,
+    nodes: u64,
+}
+
+#[cfg(feature = "alloc")]
+impl HashedPerft {
+    /// Creates a driver with a transposition table of the given number of
+    /// entries (24 bytes each). With `0` entries, nothing is cached.
+    pub fn new(entries: usize) -> HashedPerft {
+        HashedPerft {
+            table: alloc::vec![Entry::default(); entries],
+            bulk_counting: true,
+        }
+    }
+
+    /// Enables or disables bulk counting (enabled by default).
+    ///
+    /// With bulk counting, the leaf nodes are counted as the number of
+    /// legal moves in the position before, without playing the moves.
+    /// Disable it to also exercise [`Position::play_unchecked()`] for the
+    /// last move of each path.
+    pub fn set_bulk_counting(&mut self, bulk_counting: bool) {
+        self.bulk_counting = bulk_counting;
+    }
+
+    /// Forgets all cached counts.
+    pub fn clear(&mut self) {
+        self.table.fill(Entry::default());
+    }
+
+    /// Counts legal move paths of a given length, like [`perft()`].
+    pub fn perft<P: Position + Clone>(&mut self, pos: &P, depth: u32) -> u64 {
+        hashed_perft(&mut self.table, self.bulk_counting, pos, depth)
+    }
+
+    /// Counts legal move paths of a given length, like [`perft()`], with
+    /// the moves of the root position distributed among `threads` threads.
+    ///
+    /// The transposition table is split evenly between the threads.
+    #[cfg(feature = "std")]
+    pub fn parallel_perft<P: Position + Clone + Send>(
+        &mut self,
+        pos: &P,
+        depth: u32,
+        threads: NonZeroUsize,
+    ) -> u64 {
+        if depth < 2 || threads.get() < 2 {
+            return self.perft(pos, depth);
+        }
+
+        let moves = pos.legal_moves();
+        let next = AtomicUsize::new(0);
+        let bulk_counting = self.bulk_counting;
+        let chunk_len = self.table.len() / threads.get();
+
+        std::thread::scope(|scope| {
+            let mut rest = &mut self.table[..];
+            let workers = (0..threads.get())
+                .map(|_| {
+                    let (table, tail) = core::mem::take(&mut rest).split_at_mut(chunk_len);
+                    rest = tail;
+                    let (pos, moves, next) = (pos.clone(), &moves, &next);
+                    scope.spawn(move || {
+                        let mut nodes = 0;
+                        while let Some(m) = moves.get(next.fetch_add(1, Ordering::Relaxed)) {
+                            let mut child = pos.clone();
+                            child.play_unchecked(m);
+                            nodes += hashed_perft(table, bulk_counting, &child, depth - 1);
+                        }
+                        nodes
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            workers
+                .into_iter()
+                .map(|worker| worker.join().expect("perft worker"))
+                .sum()
+        })
+    }
+}
+
+#[cfg(feature = "alloc")]
+fn hashed_perft<P: Position + Clone>(
+    table: &mut [Entry],
+    bulk_counting: bool,
+    pos: &P,
+    depth: u32,
+) -> u64 {
+    if depth < 1 {
+        return 1;
+    }
+
+    if depth == 1 && bulk_counting {
+        return pos.legal_moves().len() as u64;
+    }
+
+    let slot = if depth >= 2 && !table.is_empty() {
+        let key = pos.zobrist_hash::<Zobrist64>(EnPassantMode::Legal).0;
+        // Maps the key to 0..table.len() without a division.
+        let index = ((u128::from(key) * table.len() as u128) >> 64) as usize;
+        let entry = table[index];
+        if entry.key == key && entry.depth == depth {
+            return entry.nodes;
+        }
+        Some((index, key))
+    } else {
+        None
+    };
+
+    let mut nodes = 0;
+    for m in &pos.legal_moves() {
+        let mut child = pos.clone();
+        child.play_unchecked(m);
+        nodes += hashed_perft(table, bulk_counting, &child, depth - 1);
+    }
+
+    if let Some((index, key)) = slot {
+        table[index] = Entry { key, depth, nodes };
+    }
+    nodes
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::position::Chess;
+    use crate::{fen::Fen, position::Chess, CastlingMode, FromSetup};
+
+    fn position<P: FromSetup>(fen: &str) -> P {
+        Fen::from_ascii(fen.as_bytes())
+            .expect("valid fen")
+            .into_position(CastlingMode::Standard)
+            .expect("legal position")
+    }
 
     #[test]
     fn test_perft() {
@@ -57,4 +421,167 @@ mod tests {
         assert_eq!(perft(&pos, 0), 1);
         assert_eq!(perft(&pos, 1), 20);
     }
+
+    #[test]
+    fn test_perft_stats() {
+        let pos: Chess =
+            position("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -");
+        assert_eq!(
+            perft_stats(&pos, 3),
+            PerftStats {
+                nodes: 97_862,
+                captures: 17_102,
+                en_passants: 45,
+                castles: 3162,
+                promotions: 0,
+                drops: 0,
+                checks: 993,
+                discovered_checks: 0,
+                double_checks: 0,
+                checkmates: 1,
+            }
+        );
+
+        let pos: Chess = position("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - -");
+        assert_eq!(
+            perft_stats(&pos, 4),
+            PerftStats {
+                nodes: 43_238,
+                captures: 3348,
+                en_passants: 123,
+                castles: 0,
+                promotions: 0,
+                drops: 0,
+                checks: 1680,
+                discovered_checks: 106,
+                double_checks: 0,
+                checkmates: 17,
+            }
+        );
+
+        let pos: Chess =
+            position("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1");
+        assert_eq!(
+            perft_stats(&pos, 3),
+            PerftStats {
+                nodes: 9467,
+                captures: 1021,
+                en_passants: 4,
+                castles: 0,
+                promotions: 120,
+                drops: 0,
+                checks: 38,
+                discovered_checks: 2,
+                double_checks: 0,
+                checkmates: 22,
+            }
+        );
+    }
+
+    #[test]
+    fn test_perft_stats_discovered_checks() {
+        // Every knight move discovers check, Nd6 and Nf6 are double checks.
+        let pos: Chess = position("4k3/8/8/8/4N3/8/8/K3R3 w - - 0 1");
+        assert_eq!(
+            perft_stats(&pos, 1),
+            PerftStats {
+                nodes: 19,
+                checks: 8,
+                discovered_checks: 8,
+                double_checks: 2,
+                ..PerftStats::default()
+            }
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_divide() {
+        use alloc::{string::ToString as _, vec::Vec};
+
+        let pos: Chess =
+            position("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -");
+        assert_eq!(divide(&pos, 0).count(), 0);
+        assert_eq!(divide(&pos, 1).count(), 48);
+        assert_eq!(divide(&pos, 3).map(|(_, nodes)| nodes).sum::<u64>(), 97_862);
+        assert_eq!(
+            divide_stats(&pos, 3)
+                .map(|(_, stats)| stats)
+                .sum::<PerftStats>(),
+            perft_stats(&pos, 3)
+        );
+
+        let castles = divide_stats(&pos, 1)
+            .filter(|(m, _)| m.is_castle())
+            .map(|(m, stats)| (m.to_uci(CastlingMode::Standard).to_string(), stats.nodes))
+            .collect::<Vec<_>>();
+        assert_eq!(castles, [("e1g1".into(), 1), ("e1c1".into(), 1)]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_hashed_perft() {
+        let pos: Chess =
+            position("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -");
+        for entries in [0, 1, 7, 1 << 12] {
+            let mut perft = HashedPerft::new(entries);
+            assert_eq!(perft.perft(&pos, 0), 1);
+            assert_eq!(perft.perft(&pos, 1), 48);
+            assert_eq!(perft.perft(&pos, 3), 97_862);
+            assert_eq!(perft.perft(&pos, 3), 97_862); // From the table
+            perft.set_bulk_counting(false);
+            assert_eq!(perft.perft(&pos, 3), 97_862);
+            perft.clear();
+            assert_eq!(perft.perft(&pos, 3), 97_862);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_parallel_perft() {
+        let pos: Chess =
+            position("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -");
+        for threads in [1, 3, 64] {
+            let threads = NonZeroUsize::new(threads).expect("non-zero");
+            for entries in [0, 1 << 12] {
+                let mut perft = HashedPerft::new(entries);
+                assert_eq!(perft.parallel_perft(&pos, 1, threads), 48);
+                assert_eq!(perft.parallel_perft(&pos, 3, threads), 97_862);
+            }
+        }
+    }
+
+    #[cfg(all(feature = "alloc", feature = "variant"))]
+    #[test]
+    fn test_hashed_perft_crazyhouse() {
+        use crate::variant::Crazyhouse;
+
+        let pos: Crazyhouse =
+            position("r1bqk2r/pppp1ppp/2n5/4p3/1b2P3/2N5/PPPP1PPP/R1BQKB1R/Nn w KQkq - 0 1");
+        let mut hashed = HashedPerft::new(1 << 12);
+        assert_eq!(hashed.perft(&pos, 3), perft(&pos, 3));
+    }
+
+    #[cfg(feature = "variant")]
+    #[test]
+    fn test_perft_stats_crazyhouse() {
+        use crate::variant::Crazyhouse;
+
+        let pos: Crazyhouse = position("4k3/8/8/8/8/8/8/4K3[Q] w - - 0 1");
+        let stats = perft_stats(&pos, 1);
+        assert_eq!(stats.nodes, 5 + 62);
+        assert_eq!(stats.drops, 62);
+        assert_eq!(
+            stats.checks,
+            divide(&pos, 1)
+                .filter(|(m, _)| {
+                    let mut child = pos.clone();
+                    child.play_unchecked(m);
+                    child.is_check()
+                })
+                .count() as u64
+        );
+        assert_eq!(stats.discovered_checks, 0);
+        assert_eq!(stats.checkmates, 0);
+    }
 }
@@ -0,0 +1,1471 @@
+//! Read and write Portable Game Notation.
+//!
+//! The reader is a zero-copy tokenizer that reports games to a [`Visitor`].
+//! It does not validate moves, so the visitor decides how much work to do
+//! (for example, only collecting tags, or replaying the mainline).
+//!
+//! # Examples
+//!
+//! Count the mainline moves of each game:
+//!
+//! ```
+//! use shakmaty::{pgn::{Reader, Skip, Visitor}, san::SanPlus};
+//!
+//! struct MoveCounter {
+//!     moves: usize,
+//! }
+//!
+//! impl Visitor for MoveCounter {
+//!     type Result = usize;
+//!
+//!     fn begin_game(&mut self) {
+//!         self.moves = 0;
+//!     }
+//!
+//!     fn san(&mut self, _san_plus: SanPlus) {
+//!         self.moves += 1;
+//!     }
+//!
+//!     fn begin_variation(&mut self) -> Skip {
+//!         Skip(true) // stay in the mainline
+//!     }
+//!
+//!     fn end_game(&mut self) -> usize {
+//!         self.moves
+//!     }
+//! }
+//!
+//! let pgn = b"1. e4 e5 2. Nf3 (2. f4 exf4) 2... Nc6 3. Bb5 1-0
+//!
+//! [Event \"?\"]
+//! [Result \"*\"]
+//!
+//! 1. d4 d5 *";
+//!
+//! let mut reader = Reader::new(&pgn[..]);
+//! let mut counter = MoveCounter { moves: 0 };
+//! assert_eq!(reader.read_game(&mut counter), Some(5));
+//! assert_eq!(reader.read_game(&mut counter), Some(2));
+//! assert_eq!(reader.read_game(&mut counter), None);
+//! ```
+//!
+//! Set up the starting position from the tags and replay the moves:
+//!
+//! ```
+//! use shakmaty::{
+//!     pgn::{RawTag, Reader, SetupTags, Visitor},
+//!     san::SanPlus,
+//!     Chess, Position,
+//! };
+//!
+//! struct LastPosition {
+//!     tags: SetupTags,
+//!     pos: Option<Chess>,
+//! }
+//!
+//! impl Visitor for LastPosition {
+//!     type Result = Option<Chess>;
+//!
+//!     fn begin_tags(&mut self) {
+//!         self.tags = SetupTags::new();
+//!     }
+//!
+//!     fn tag(&mut self, name: &[u8], value: RawTag<'_>) {
+//!         self.tags.tag(name, value);
+//!     }
+//!
+//!     fn end_tags(&mut self) -> shakmaty::pgn::Skip {
+//!         self.pos = self.tags.chess().ok();
+//!         shakmaty::pgn::Skip(self.pos.is_none())
+//!     }
+//!
+//!     fn san(&mut self, san_plus: SanPlus) {
+//!         self.pos = self.pos.take().and_then(|pos| {
+//!             let m = san_plus.san.to_move(&pos).ok()?;
+//!             pos.play(&m).ok()
+//!         });
+//!     }
+//!
+//!     fn end_game(&mut self) -> Option<Chess> {
+//!         self.pos.take()
+//!     }
+//! }
+//!
+//! let pgn = b"[FEN \"7k/6pp/8/8/8/8/8/R6K w - - 0 1\"]\n\n1. Ra8# 1-0";
+//!
+//! let mut visitor = LastPosition { tags: SetupTags::new(), pos: None };
+//! let pos = Reader::new(&pgn[..]).read_game(&mut visitor).flatten().expect("legal");
+//! assert!(pos.is_checkmate());
+//! ```
+//!
+//! Write a game in export format:
+//!
+//! ```
+//! # #[cfg(feature = "alloc")] {
+//! use shakmaty::{pgn::ExportGame, san::San, Chess, Position};
+//!
+//! let mut pos = Chess::default();
+//! let mut moves = Vec::new();
+//! for san in ["f3", "e5", "g4", "Qh4"] {
+//!     let m = san.parse::<San>()?.to_move(&pos)?;
+//!     pos.play_unchecked(&m);
+//!     moves.push(m);
+//! }
+//!
+//! let mut game = ExportGame::from_position(Chess::default(), &moves)?;
+//! game.push_tag("White", "Fool");
+//! assert!(game.to_string().ends_with("\n1. f3 e5 2. g4 Qh4# 0-1\n"));
+//! # }
+//!
+//! # #[derive(Debug)] struct CommonError;
+//! # impl From<shakmaty::san::ParseSanError> for CommonError { fn from(_: shakmaty::san::ParseSanError) -> Self { Self } }
+//! # impl From<shakmaty::san::SanError> for CommonError { fn from(_: shakmaty::san::SanError) -> Self { Self } }
+//! # #[cfg(feature = "alloc")]
+//! # impl From<shakmaty::pgn::ExportError> for CommonError { fn from(_: shakmaty::pgn::ExportError) -> Self { Self } }
+//! # Ok::<_, CommonError>(())
+//! ```
+
+#[cfg(feature = "alloc")]
+use core::num::NonZeroU32;
+use core::{fmt, str::FromStr};
+
+#[cfg(feature = "alloc")]
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString as _},
+    vec::Vec,
+};
+
+#[cfg(feature = "variant")]
+use crate::variant::{ParseVariantError, Variant, VariantPosition};
+use crate::{
+    fen::{Fen, ParseFenError},
+    san::SanPlus,
+    CastlingMode, Chess, FromSetup, Outcome, PositionError, Setup,
+};
+#[cfg(feature = "alloc")]
+use crate::{Color, EnPassantMode, Move, Position, PositionErrorKinds};
+
+/// Tells the [`Reader`] whether to skip the following movetext or variation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Default)]
+pub struct Skip(pub bool);
+
+/// A numeric annotation glyph like `$1` or `!`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Nag(pub u8);
+
+impl Nag {
+    /// `!`
+    pub const GOOD_MOVE: Nag = Nag(1);
+    /// `?`
+    pub const MISTAKE: Nag = Nag(2);
+    /// `!!`
+    pub const BRILLIANT_MOVE: Nag = Nag(3);
+    /// `??`
+    pub const BLUNDER: Nag = Nag(4);
+    /// `!?`
+    pub const SPECULATIVE_MOVE: Nag = Nag(5);
+    /// `?!`
+    pub const DUBIOUS_MOVE: Nag = Nag(6);
+
+    /// Parses a NAG like `$12`, or one of the move suffix annotations
+    /// `!`, `?`, `!!`, `??`, `!?` and `?!`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseNagError`] if `s` is not a valid NAG.
+    pub fn from_ascii(s: &[u8]) -> Result<Nag, ParseNagError> {
+        Ok(match s {
+            b"!" => Nag::GOOD_MOVE,
+            b"?" => Nag::MISTAKE,
+            b"!!" => Nag::BRILLIANT_MOVE,
+            b"??" => Nag::BLUNDER,
+            b"!?" => Nag::SPECULATIVE_MOVE,
+            b"?!" => Nag::DUBIOUS_MOVE,
+            [b'$', digits @ ..] if !digits.is_empty() => {
+                btoi::btou(digits).map(Nag).map_err(|_| ParseNagError)?
+            }
+            _ => return Err(ParseNagError),
+        })
+    }
+}
+
+impl FromStr for Nag {
+    type Err = ParseNagError;
+
+    fn from_str(s: &str) -> Result<Nag, ParseNagError> {
+        Nag::from_ascii(s.as_bytes())
+    }
+}
+
+impl fmt::Display for Nag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${}", self.0)
+    }
+}
+
+/// Error when parsing an invalid NAG.
+#[derive(Clone, Debug)]
+pub struct ParseNagError;
+
+impl fmt::Display for ParseNagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid nag")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseNagError {}
+
+/// The raw value of a tag, with escape sequences still in place.
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct RawTag<'a>(pub &'a [u8]);
+
+impl<'a> RawTag<'a> {
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// Resolves the escape sequences `\\` and `\"`, only allocating if
+    /// there are any.
+    #[cfg(feature = "alloc")]
+    pub fn decode(&self) -> Cow<'a, [u8]> {
+        if !self.0.contains(&b'\\') {
+            return Cow::Borrowed(self.0);
+        }
+        let mut decoded = Vec::with_capacity(self.0.len());
+        let mut escaped = false;
+        for &ch in self.0 {
+            if escaped || ch != b'\\' {
+                decoded.push(ch);
+                escaped = false;
+            } else {
+                escaped = true;
+            }
+        }
+        Cow::Owned(decoded)
+    }
+
+    /// Like [`RawTag::decode()`], but also decodes the value as UTF-8,
+    /// replacing invalid sequences.
+    #[cfg(feature = "alloc")]
+    pub fn decode_utf8_lossy(&self) -> Cow<'a, str> {
+        match self.decode() {
+            Cow::Borrowed(bytes) => String::from_utf8_lossy(bytes),
+            Cow::Owned(bytes) => Cow::Owned(match String::from_utf8(bytes) {
+                Ok(s) => s,
+                Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Debug for RawTag<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RawTag")
+            .field(&core::str::from_utf8(self.0).map_err(|_| self.0))
+            .finish()
+    }
+}
+
+/// The raw contents of a comment, without the enclosing braces (or the
+/// leading semicolon of a rest of line comment).
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct RawComment<'a>(pub &'a [u8]);
+
+impl<'a> RawComment<'a> {
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+}
+
+impl fmt::Debug for RawComment<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RawComment")
+            .field(&core::str::from_utf8(self.0).map_err(|_| self.0))
+            .finish()
+    }
+}
+
+/// Receives the contents of games from a [`Reader`].
+///
+/// All methods except [`Visitor::end_game()`] have default implementations
+/// that ignore their input.
+pub trait Visitor {
+    /// Value produced by [`Visitor::end_game()`].
+    type Result;
+
+    /// Called at the start of each game.
+    fn begin_game(&mut self) {}
+
+    /// Called before the tag pairs, even if there are none.
+    fn begin_tags(&mut self) {}
+
+    /// Called for each tag pair, like `[White "Deep Blue"]`.
+    fn tag(&mut self, _name: &[u8], _value: RawTag<'_>) {}
+
+    /// Called after the tag pairs. Return `Skip(true)` to skip the
+    /// movetext of the game.
+    fn end_tags(&mut self) -> Skip {
+        Skip(false)
+    }
+
+    /// Called for each move. Tokens that are not syntactically valid
+    /// SAN are not reported.
+    fn san(&mut self, _san_plus: SanPlus) {}
+
+    /// Called for each annotation glyph, including move suffix annotations
+    /// like `!?`.
+    fn nag(&mut self, _nag: Nag) {}
+
+    /// Called for each comment.
+    fn comment(&mut self, _comment: RawComment<'_>) {}
+
+    /// Called when a variation starts. Return `Skip(true)` to skip the
+    /// variation, including all of its nested variations.
+    fn begin_variation(&mut self) -> Skip {
+        Skip(false)
+    }
+
+    /// Called when a variation that was not skipped ends.
+    fn end_variation(&mut self) {}
+
+    /// Called with the game termination marker, where `*` is `None`.
+    fn outcome(&mut self, _outcome: Option<Outcome>) {}
+
+    /// Called at the end of each game.
+    fn end_game(&mut self) -> Self::Result;
+}
+
+fn is_space(ch: u8) -> bool {
+    matches!(ch, b' ' | b'\t' | b'\r' | b'\n' | b'\x0b' | b'\x0c')
+}
+
+fn is_token_end(ch: u8) -> bool {
+    is_space(ch)
+        || matches!(
+            ch,
+            b'{' | b'}' | b'(' | b')' | b'[' | b']' | b';' | b'$' | b'!' | b'?'
+        )
+}
+
+/// Reads games from a byte slice without copying.
+///
+/// Games are delimited by game termination markers (`1-0`, `0-1`,
+/// `1/2-1/2` and `*`) or by the start of the next tag section.
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    pgn: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(pgn: &'a [u8]) -> Reader<'a> {
+        Reader {
+            pgn: pgn.strip_prefix(b"\xef\xbb\xbf").unwrap_or(pgn),
+            pos: 0,
+        }
+    }
+
+    /// The part of the input that has not been read yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.pgn[self.pos..]
+    }
+
+    /// Reads the next game, or returns `None` if there are no more games.
+    pub fn read_game<V: Visitor>(&mut self, visitor: &mut V) -> Option<V::Result> {
+        self.skip_whitespace_and_escapes();
+        if self.pos >= self.pgn.len() {
+            return None;
+        }
+
+        visitor.begin_game();
+        visitor.begin_tags();
+        self.read_tags(visitor);
+        let skip = visitor.end_tags();
+        self.read_movetext(visitor, skip);
+        self.skip_whitespace_and_escapes();
+        Some(visitor.end_game())
+    }
+
+    /// Skips the next game, or returns `false` if there are no more games.
+    pub fn skip_game(&mut self) -> bool {
+        struct SkipVisitor;
+
+        impl Visitor for SkipVisitor {
+            type Result = ();
+
+            fn end_tags(&mut self) -> Skip {
+                Skip(true)
+            }
+
+            fn end_game(&mut self) {}
+        }
+
+        self.read_game(&mut SkipVisitor).is_some()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.pgn.get(self.pos).copied()
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.pos == 0 || self.pgn[self.pos - 1] == b'\n'
+    }
+
+    fn skip_line(&mut self) {
+        self.pos = self.pgn[self.pos..]
+            .iter()
+            .position(|&ch| ch == b'\n')
+            .map_or(self.pgn.len(), |n| self.pos + n + 1);
+    }
+
+    fn skip_whitespace_and_escapes(&mut self) {
+        while let Some(ch) = self.peek() {
+            if is_space(ch) {
+                self.pos += 1;
+            } else if ch == b'%' && self.at_line_start() {
+                self.skip_line();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn read_tags<V: Visitor>(&mut self, visitor: &mut V) {
+        loop {
+            self.skip_whitespace_and_escapes();
+            match self.peek() {
+                Some(b'[') => self.read_tag(visitor),
+                Some(b';') => self.skip_line(),
+                _ => break,
+            }
+        }
+    }
+
+    fn read_tag<V: Visitor>(&mut self, visitor: &mut V) {
+        let line_end = self.pgn[self.pos..]
+            .iter()
+            .position(|&ch| ch == b'\n')
+            .map_or(self.pgn.len(), |n| self.pos + n);
+        let line = &self.pgn[self.pos + 1..line_end];
+
+        let name_start = line.iter().position(|&ch| !is_space(ch));
+        let name_len = name_start.map_or(0, |start| {
+            line[start..]
+                .iter()
+                .position(|&ch| is_space(ch) || ch == b'"' || ch == b']')
+                .unwrap_or(line.len() - start)
+        });
+        let name_start = name_start.unwrap_or(0);
+        let name = &line[name_start..name_start + name_len];
+
+        let rest = &line[name_start + name_len..];
+        if let Some(quote) = rest.iter().position(|&ch| ch == b'"') {
+            let value_start = quote + 1;
+            let mut value_end = value_start;
+            while let Some(&ch) = rest.get(value_end) {
+                match ch {
+                    b'\\' => value_end += 2,
+                    b'"' => break,
+                    _ => value_end += 1,
+                }
+            }
+            if value_end < rest.len() && !name.is_empty() {
+                visitor.tag(name, RawTag(&rest[value_start..value_end]));
+
+                // Continue after the closing bracket, allowing further
+                // content on the same line.
+                let closing = name_start + name_len + value_end + 1;
+                if let Some(n) = line[closing..].iter().position(|&ch| ch == b']') {
+                    self.pos += 1 + closing + n + 1;
+                    return;
+                }
+            }
+        }
+
+        // Malformed tag.
+        self.pos = line_end;
+    }
+
+    fn read_movetext<V: Visitor>(&mut self, visitor: &mut V, Skip(skip): Skip) {
+        let mut depth = 0usize;
+        let mut skip_depth = usize::from(skip);
+
+        while let Some(ch) = self.peek() {
+            match ch {
+                _ if is_space(ch) => self.pos += 1,
+                b'%' if self.at_line_start() => self.skip_line(),
+                b'[' => break, // next game
+                b'{' => {
+                    let start = self.pos + 1;
+                    let end = self.pgn[start..]
+                        .iter()
+                        .position(|&ch| ch == b'}')
+                        .map_or(self.pgn.len(), |n| start + n);
+                    if skip_depth == 0 {
+                        visitor.comment(RawComment(&self.pgn[start..end]));
+                    }
+                    self.pos = (end + 1).min(self.pgn.len());
+                }
+                b';' => {
+                    let start = self.pos + 1;
+                    self.skip_line();
+                    if skip_depth == 0 {
+                        let comment = &self.pgn[start..self.pos];
+                        let comment = comment.strip_suffix(b"\n").unwrap_or(comment);
+                        let comment = comment.strip_suffix(b"\r").unwrap_or(comment);
+                        visitor.comment(RawComment(comment));
+                    }
+                }
+                b'(' => {
+                    self.pos += 1;
+                    if skip_depth > 0 {
+                        skip_depth += 1;
+                    } else if visitor.begin_variation().0 {
+                        skip_depth = 1;
+                    } else {
+                        depth += 1;
+                    }
+                }
+                b')' => {
+                    self.pos += 1;
+                    if skip_depth > 0 {
+                        // Stop skipping at the end of a skipped variation, but
+                        // never resume a skipped game.
+                        if skip_depth > 1 || !skip {
+                            skip_depth -= 1;
+                        }
+                    } else if depth > 0 {
+                        depth -= 1;
+                        visitor.end_variation();
+                    }
+                }
+                b'}' | b']' => self.pos += 1,
+                _ => {
+                    let start = self.pos;
+                    self.pos += 1;
+                    while self.peek().is_some_and(|ch| !is_token_end(ch)) {
+                        self.pos += 1;
+                    }
+                    let token = &self.pgn[start..self.pos];
+
+                    if matches!(ch, b'!' | b'?' | b'$') {
+                        if ch != b'$' {
+                            while self.peek().is_some_and(|ch| matches!(ch, b'!' | b'?')) {
+                                self.pos += 1;
+                            }
+                        }
+                        if skip_depth == 0 {
+                            if let Ok(nag) = Nag::from_ascii(&self.pgn[start..self.pos]) {
+                                visitor.nag(nag);
+                            }
+                        }
+                        continue;
+                    }
+
+                    let outcome = match Outcome::from_ascii(token) {
+                        Ok(outcome) => Some(Some(outcome)),
+                        Err(crate::ParseOutcomeError::Unknown) => Some(None),
+                        Err(crate::ParseOutcomeError::Invalid) => None,
+                    };
+                    if let Some(outcome) = outcome {
+                        if depth == 0 && (skip_depth == 0 || (skip && skip_depth == 1)) {
+                            if skip_depth == 0 {
+                                visitor.outcome(outcome);
+                            }
+                            break;
+                        }
+                        continue;
+                    }
+
+                    if skip_depth == 0 {
+                        if let Some(san_plus) = parse_san_token(token) {
+                            visitor.san(san_plus);
+                        }
+                    }
+                }
+            }
+        }
+
+        while depth > 0 {
+            depth -= 1;
+            visitor.end_variation();
+        }
+    }
+}
+
+fn parse_san_token(token: &[u8]) -> Option<SanPlus> {
+    // Strip move numbers like 12. or 12..., which may be attached to
+    // the move itself.
+    let digits = token.iter().take_while(|ch| ch.is_ascii_digit()).count();
+    let dots = token[digits..].iter().take_while(|&&ch| ch == b'.').count();
+    let token = if digits > 0 && dots > 0 {
+        &token[digits + dots..]
+    } else {
+        token
+    };
+
+    match token {
+        b"" => None,
+        b"0-0" | b"0-0+" | b"0-0#" | b"0-0-0" | b"0-0-0+" | b"0-0-0#" => {
+            let mut buf = [0; 6];
+            for (dst, &src) in buf.iter_mut().zip(token) {
+                *dst = if src == b'0' { b'O' } else { src };
+            }
+            SanPlus::from_ascii(&buf[..token.len()]).ok()
+        }
+        _ => SanPlus::from_ascii(token).ok(),
+    }
+}
+
+/// Reads games from a [`std::io::BufRead`], buffering one game at a time.
+///
+/// Game boundaries are found line by line: a game ends before a line
+/// starting with `[` that follows movetext.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct BufferedReader<R> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: usize,
+    pending: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::BufRead> BufferedReader<R> {
+    pub fn new(inner: R) -> BufferedReader<R> {
+        BufferedReader {
+            inner,
+            buf: Vec::new(),
+            pos: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads the next game, or returns `None` if there are no more games.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors of the underlying reader.
+    pub fn read_game<V: Visitor>(&mut self, visitor: &mut V) -> std::io::Result<Option<V::Result>> {
+        self.fill()?;
+        let mut reader = Reader::new(&self.buf[self.pos..]);
+        let result = reader.read_game(visitor);
+        self.pos = self.buf.len() - reader.remaining().len();
+        Ok(result)
+    }
+
+    /// Skips the next game, or returns `false` if there are no more games.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors of the underlying reader.
+    pub fn skip_game(&mut self) -> std::io::Result<bool> {
+        self.fill()?;
+        let mut reader = Reader::new(&self.buf[self.pos..]);
+        let result = reader.skip_game();
+        self.pos = self.buf.len() - reader.remaining().len();
+        Ok(result)
+    }
+
+    fn fill(&mut self) -> std::io::Result<()> {
+        if self.buf[self.pos..].iter().any(|&ch| !is_space(ch)) {
+            return Ok(());
+        }
+
+        self.buf.clear();
+        self.pos = 0;
+        self.buf.append(&mut self.pending);
+
+        let mut in_comment = false;
+        let mut seen_movetext = false;
+        loop {
+            let start = self.buf.len();
+            if self.inner.read_until(b'\n', &mut self.buf)? == 0 {
+                break;
+            }
+            let line = &self.buf[start..];
+            let trimmed = &line[line.iter().take_while(|&&ch| is_space(ch)).count()..];
+
+            if !in_comment {
+                if trimmed.first() == Some(&b'[') {
+                    if seen_movetext {
+                        self.pending.extend_from_slice(line);
+                        self.buf.truncate(start);
+                        break;
+                    }
+                    continue;
+                }
+                if trimmed.is_empty() || line.first() == Some(&b'%') {
+                    continue;
+                }
+            }
+
+            seen_movetext = true;
+            for &ch in line {
+                if in_comment {
+                    in_comment = ch != b'}';
+                } else if ch == b'{' {
+                    in_comment = true;
+                } else if ch == b';' {
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Collects the `FEN`, `SetUp` and `Variant` tags of a game, to set up its
+/// starting position.
+///
+/// The `FEN` tag is used unless the `SetUp` tag is explicitly `0`.
+/// Castling rights are interpreted in [`CastlingMode::Chess960`] if the
+/// variant is Chess960, and otherwise detected using
+/// [`CastlingMode::detect()`].
+#[derive(Debug, Clone, Default)]
+pub struct SetupTags {
+    fen: Option<Result<Fen, ParseFenError>>,
+    set_up: Option<bool>,
+    chess960: bool,
+    #[cfg(feature = "variant")]
+    variant: Option<Result<Variant, ParseVariantError>>,
+}
+
+impl SetupTags {
+    pub fn new() -> SetupTags {
+        SetupTags::default()
+    }
+
+    /// Records the tag if it is relevant for the starting position.
+    pub fn tag(&mut self, name: &[u8], value: RawTag<'_>) {
+        match name {
+            b"FEN" => self.fen = Some(Fen::from_ascii(value.as_bytes())),
+            b"SetUp" => self.set_up = Some(value.as_bytes() != b"0"),
+            b"Variant" => {
+                let value = value.as_bytes();
+                self.chess960 = value.eq_ignore_ascii_case(b"chess960")
+                    || value.eq_ignore_ascii_case(b"chess 960")
+                    || value.eq_ignore_ascii_case(b"fischerandom")
+                    || value.eq_ignore_ascii_case(b"fischer random");
+                #[cfg(feature = "variant")]
+                {
+                    self.variant = Some(if self.chess960 {
+                        Ok(Variant::Chess)
+                    } else {
+                        Variant::from_ascii(value)
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// The setup of the starting position.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseFenError`] if the `FEN` tag is invalid.
+    pub fn setup(&self) -> Result<Option<Setup>, ParseFenError> {
+        match self.fen {
+            Some(Ok(ref fen)) if self.set_up != Some(false) => Ok(Some(fen.as_setup().clone())),
+            Some(Err(ref err)) if self.set_up != Some(false) => Err(err.clone()),
+            _ => Ok(None),
+        }
+    }
+
+    /// The castling mode to use for the starting position.
+    pub fn castling_mode(&self) -> CastlingMode {
+        if self.chess960 {
+            return CastlingMode::Chess960;
+        }
+        match self.setup() {
+            Ok(Some(ref setup)) => CastlingMode::detect(setup),
+            _ => CastlingMode::Standard,
+        }
+    }
+
+    /// The starting position of a standard chess game, ignoring the
+    /// `Variant` tag.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the `FEN` tag is invalid or not a legal position.
+    #[allow(clippy::result_large_err)] // Ok variant is also large
+    pub fn chess(&self) -> Result<Chess, SetupTagsError<Chess>> {
+        self.position()
+    }
+
+    /// The starting position of a standard chess game or variant without
+    /// a specific initial position.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the `FEN` tag is invalid or not a legal position.
+    #[allow(clippy::result_large_err)] // Ok variant is also large
+    pub fn position<P: FromSetup + Default>(&self) -> Result<P, SetupTagsError<P>> {
+        match self.setup().map_err(SetupTagsError::InvalidFen)? {
+            Some(setup) => {
+                P::from_setup(setup, self.castling_mode()).map_err(SetupTagsError::InvalidPosition)
+            }
+            None => Ok(P::default()),
+        }
+    }
+
+    /// The variant, defaulting to standard chess if there is no `Variant`
+    /// tag.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseVariantError`] for unknown variant names.
+    #[cfg(feature = "variant")]
+    pub fn variant(&self) -> Result<Variant, ParseVariantError> {
+        self.variant.clone().unwrap_or(Ok(Variant::Chess))
+    }
+
+    /// The starting position, taking the `Variant` tag into account.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the `Variant` or `FEN` tag is invalid, or if it is not a
+    /// legal position.
+    #[cfg(feature = "variant")]
+    #[allow(clippy::result_large_err)] // Ok variant is also large
+    pub fn variant_position(&self) -> Result<VariantPosition, SetupTagsError<VariantPosition>> {
+        let variant = self.variant().map_err(SetupTagsError::InvalidVariant)?;
+        match self.setup().map_err(SetupTagsError::InvalidFen)? {
+            Some(setup) => VariantPosition::from_setup(variant, setup, self.castling_mode())
+                .map_err(SetupTagsError::InvalidPosition),
+            None => Ok(VariantPosition::new(variant)),
+        }
+    }
+}
+
+/// Error when setting up the starting position from [`SetupTags`].
+#[derive(Debug)]
+pub enum SetupTagsError<P> {
+    InvalidFen(ParseFenError),
+    #[cfg(feature = "variant")]
+    InvalidVariant(ParseVariantError),
+    InvalidPosition(PositionError<P>),
+}
+
+impl<P> fmt::Display for SetupTagsError<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupTagsError::InvalidFen(err) => err.fmt(f),
+            #[cfg(feature = "variant")]
+            SetupTagsError::InvalidVariant(err) => err.fmt(f),
+            SetupTagsError::InvalidPosition(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<P: fmt::Debug> std::error::Error for SetupTagsError<P> {}
+
+/// Error when creating an [`ExportGame`].
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ExportError {
+    /// The starting setup is not a legal position.
+    InvalidPosition(PositionErrorKinds),
+    /// The move at the given index is not legal.
+    IllegalMove { index: usize, m: Move },
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::InvalidPosition(_) => f.write_str("illegal starting position"),
+            ExportError::IllegalMove { index, m } => {
+                write!(f, "illegal move {m} at index {index}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ExportError {}
+
+#[cfg(feature = "alloc")]
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+/// Maximum number of characters per line, so that export format fits into
+/// 80 columns.
+#[cfg(feature = "alloc")]
+const MAX_LINE_LEN: usize = 79;
+
+/// A game to be written in PGN export format.
+///
+/// The tags of the Seven Tag Roster are always written first, in the
+/// standard order, using placeholder values where missing. The `Result`
+/// tag always agrees with the game termination marker. Movetext lines are
+/// wrapped so that they fit into 80 columns.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct ExportGame {
+    tags: Vec<(String, String)>,
+    turn: Color,
+    fullmoves: NonZeroU32,
+    sans: Vec<SanPlus>,
+    outcome: Option<Outcome>,
+}
+
+#[cfg(feature = "alloc")]
+impl ExportGame {
+    /// Sets up the starting position and validates the moves.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the setup is not a legal position of type `P`, or if any
+    /// of the moves is illegal.
+    pub fn from_setup<P: FromSetup + Position + 'static>(
+        setup: Setup,
+        mode: CastlingMode,
+        moves: &[Move],
+    ) -> Result<ExportGame, ExportError> {
+        let pos = setup
+            .position::<P>(mode)
+            .map_err(|err| ExportError::InvalidPosition(err.kinds()))?;
+        ExportGame::from_position(pos, moves)
+    }
+
+    /// Validates the moves, starting from the given position. The `SetUp`
+    /// and `FEN` tags are added if it is not the standard initial position.
+    /// The `Variant` tag is added for variants, and for positions using
+    /// [`CastlingMode::Chess960`].
+    /// The outcome defaults to the outcome of the final position, if any.
+    ///
+    /// # Errors
+    ///
+    /// Errors if any of the moves is illegal.
+    pub fn from_position<P: Position + 'static>(
+        pos: P,
+        moves: &[Move],
+    ) -> Result<ExportGame, ExportError> {
+        let mut pos = pos;
+        let variant = variant_tag(&pos);
+        let turn = pos.turn();
+        let fullmoves = pos.fullmoves();
+        let fen = {
+            let setup = Setup {
+                board: pos.board().clone(),
+                promoted: pos.promoted(),
+                pockets: pos.pockets().copied(),
+                turn,
+                castling_rights: pos.castles().castling_rights(),
+                ep_square: pos.ep_square(EnPassantMode::Legal),
+                remaining_checks: pos.remaining_checks().copied(),
+                halfmoves: pos.halfmoves(),
+                fullmoves,
+            };
+            (setup != Setup::initial()).then(|| Fen(setup).to_string())
+        };
+
+        let mut sans = Vec::with_capacity(moves.len());
+        for (index, m) in moves.iter().enumerate() {
+            if !pos.is_legal(m) {
+                return Err(ExportError::IllegalMove {
+                    index,
+                    m: m.clone(),
+                });
+            }
+            sans.push(SanPlus::from_move_and_play_unchecked(&mut pos, m));
+        }
+
+        let mut game = ExportGame {
+            tags: Vec::new(),
+            turn,
+            fullmoves,
+            sans,
+            outcome: pos.outcome(),
+        };
+        if let Some(variant) = variant {
+            game.push_tag("Variant", variant);
+        }
+        if let Some(fen) = fen {
+            game.push_tag("SetUp", "1");
+            game.push_tag("FEN", fen);
+        }
+        Ok(game)
+    }
+
+    /// Adds a tag, replacing an existing tag with the same name.
+    /// The `Result` tag is ignored in favor of [`ExportGame::set_outcome()`].
+    pub fn push_tag<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) {
+        let name = name.into();
+        let value = value.into();
+        if name == "Result" {
+            return;
+        }
+        match self.tags.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => self.tags.push((name, value)),
+        }
+    }
+
+    pub fn set_outcome(&mut self, outcome: Option<Outcome>) {
+        self.outcome = outcome;
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    pub fn sans(&self) -> &[SanPlus] {
+        &self.sans
+    }
+
+    fn result(&self) -> &'static str {
+        self.outcome.map_or("*", Outcome::as_str)
+    }
+
+    /// Writes the game in export format.
+    ///
+    /// # Errors
+    ///
+    /// Returns errors of the underlying writer.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&self, mut w: W) -> std::io::Result<()> {
+        write!(w, "{self}")
+    }
+}
+
+/// The `Variant` tag for a position, as understood by [`SetupTags`], or
+/// `None` for standard chess.
+#[cfg(feature = "alloc")]
+fn variant_tag<P: Position + 'static>(pos: &P) -> Option<&'static str> {
+    #[cfg(feature = "variant")]
+    {
+        use core::any::Any;
+
+        use crate::variant::{
+            Antichess, Atomic, Crazyhouse, Horde, KingOfTheHill, RacingKings, ThreeCheck,
+        };
+
+        let any: &dyn Any = pos;
+        let variant = if let Some(pos) = any.downcast_ref::<VariantPosition>() {
+            pos.variant()
+        } else if any.is::<Atomic>() {
+            Variant::Atomic
+        } else if any.is::<Antichess>() {
+            Variant::Antichess
+        } else if any.is::<KingOfTheHill>() {
+            Variant::KingOfTheHill
+        } else if any.is::<ThreeCheck>() {
+            Variant::ThreeCheck
+        } else if any.is::<Crazyhouse>() {
+            Variant::Crazyhouse
+        } else if any.is::<RacingKings>() {
+            Variant::RacingKings
+        } else if any.is::<Horde>() {
+            Variant::Horde
+        } else {
+            Variant::Chess
+        };
+
+        match variant {
+            Variant::Chess => (),
+            Variant::Atomic => return Some("Atomic"),
+            Variant::Antichess => return Some("Antichess"),
+            Variant::KingOfTheHill => return Some("King of the Hill"),
+            Variant::ThreeCheck => return Some("Three-check"),
+            Variant::Crazyhouse => return Some("Crazyhouse"),
+            Variant::RacingKings => return Some("Racing Kings"),
+            Variant::Horde => return Some("Horde"),
+        }
+    }
+
+    (pos.castles().mode() == CastlingMode::Chess960).then_some("Chess960")
+}
+
+#[cfg(feature = "alloc")]
+fn append_tag(f: &mut fmt::Formatter<'_>, name: &str, value: &str) -> fmt::Result {
+    use fmt::Write as _;
+
+    f.write_char('[')?;
+    f.write_str(name)?;
+    f.write_str(" \"")?;
+    for ch in value.chars() {
+        if ch == '\\' || ch == '"' {
+            f.write_char('\\')?;
+        }
+        f.write_char(ch)?;
+    }
+    f.write_str("\"]\n")
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for ExportGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use fmt::Write as _;
+
+        for (name, default) in SEVEN_TAG_ROSTER {
+            let value = if name == "Result" {
+                self.result()
+            } else {
+                self.tags
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map_or(default, |(_, v)| v.as_str())
+            };
+            append_tag(f, name, value)?;
+        }
+        for (name, value) in &self.tags {
+            if !SEVEN_TAG_ROSTER.iter().any(|(n, _)| n == name) {
+                append_tag(f, name, value)?;
+            }
+        }
+        f.write_char('\n')?;
+
+        let mut line_len = 0;
+        let mut token = String::new();
+        let mut emit = |f: &mut fmt::Formatter<'_>, token: &str| -> fmt::Result {
+            if line_len > 0 {
+                if line_len + 1 + token.len() > MAX_LINE_LEN {
+                    f.write_char('\n')?;
+                    line_len = 0;
+                } else {
+                    f.write_char(' ')?;
+                    line_len += 1;
+                }
+            }
+            f.write_str(token)?;
+            line_len += token.len();
+            Ok(())
+        };
+
+        let mut turn = self.turn;
+        let mut fullmoves = self.fullmoves.get();
+        for (i, san_plus) in self.sans.iter().enumerate() {
+            token.clear();
+            if turn.is_white() {
+                let _ = write!(token, "{fullmoves}. ");
+            } else if i == 0 {
+                let _ = write!(token, "{fullmoves}... ");
+            }
+            san_plus.append_to_string(&mut token);
+            emit(f, &token)?;
+            if turn.is_black() {
+                fullmoves = fullmoves.saturating_add(1);
+            }
+            turn = !turn;
+        }
+        emit(f, self.result())?;
+        f.write_char('\n')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "alloc")]
+    use alloc::{string::ToString as _, vec};
+
+    use super::*;
+
+    #[cfg(feature = "alloc")]
+    #[derive(Debug, Default)]
+    struct Recorder {
+        events: Vec<String>,
+        skip_variations: bool,
+    }
+
+    #[cfg(feature = "alloc")]
+    impl Visitor for Recorder {
+        type Result = Vec<String>;
+
+        fn begin_game(&mut self) {
+            self.events.clear();
+        }
+
+        fn tag(&mut self, name: &[u8], value: RawTag<'_>) {
+            self.events.push(alloc::format!(
+                "[{} {}]",
+                core::str::from_utf8(name).unwrap(),
+                value.decode_utf8_lossy()
+            ));
+        }
+
+        fn san(&mut self, san_plus: SanPlus) {
+            self.events.push(san_plus.to_string());
+        }
+
+        fn nag(&mut self, nag: Nag) {
+            self.events.push(nag.to_string());
+        }
+
+        fn comment(&mut self, comment: RawComment<'_>) {
+            self.events.push(alloc::format!(
+                "{{{}}}",
+                core::str::from_utf8(comment.as_bytes()).unwrap()
+            ));
+        }
+
+        fn begin_variation(&mut self) -> Skip {
+            if !self.skip_variations {
+                self.events.push("(".to_string());
+            }
+            Skip(self.skip_variations)
+        }
+
+        fn end_variation(&mut self) {
+            self.events.push(")".to_string());
+        }
+
+        fn outcome(&mut self, outcome: Option<Outcome>) {
+            self.events
+                .push(outcome.map_or("*", Outcome::as_str).to_string());
+        }
+
+        fn end_game(&mut self) -> Vec<String> {
+            core::mem::take(&mut self.events)
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    const PGN: &[u8] = b"\xef\xbb\xbf[Event \"Test \\\"quoted\\\"\"]
+[Site \"?\"]
+% escaped line
+[Result \"1-0\"]
+
+1.e4 {open} e5 2. Nf3 $1 (2. f4!? exf4 (2... d5) ; rest of line
+) 2... Nc6 3. Bb5 0-0?! 1-0
+
+1. d4 *
+";
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_read_games() {
+        let mut reader = Reader::new(PGN);
+        let mut visitor = Recorder::default();
+        assert_eq!(
+            reader.read_game(&mut visitor).unwrap(),
+            vec![
+                "[Event Test \"quoted\"]",
+                "[Site ?]",
+                "[Result 1-0]",
+                "e4",
+                "{open}",
+                "e5",
+                "Nf3",
+                "$1",
+                "(",
+                "f4",
+                "$5",
+                "exf4",
+                "(",
+                "d5",
+                ")",
+                "{ rest of line}",
+                ")",
+                "Nc6",
+                "Bb5",
+                "O-O",
+                "$6",
+                "1-0",
+            ]
+        );
+        assert_eq!(reader.read_game(&mut visitor).unwrap(), vec!["d4", "*"]);
+        assert_eq!(reader.read_game(&mut visitor), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_skip() {
+        let mut reader = Reader::new(PGN);
+        let mut visitor = Recorder {
+            skip_variations: true,
+            ..Recorder::default()
+        };
+        let events = reader.read_game(&mut visitor).unwrap();
+        assert_eq!(
+            events[3..],
+            ["e4", "{open}", "e5", "Nf3", "$1", "Nc6", "Bb5", "O-O", "$6", "1-0"]
+        );
+
+        let mut reader = Reader::new(PGN);
+        assert!(reader.skip_game());
+        assert!(reader.skip_game());
+        assert!(!reader.skip_game());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_buffered_reader() {
+        let pgn = b"[Event \"a\"]\n1. e4 {\n[not a tag]\n} e5\n[Event \"b\"]\n\n1. d4\n\n1. c4 *\n";
+        let mut reader = BufferedReader::new(&pgn[..]);
+        let mut visitor = Recorder::default();
+        assert_eq!(
+            reader.read_game(&mut visitor).unwrap().unwrap(),
+            vec!["[Event a]", "e4", "{\n[not a tag]\n}", "e5"]
+        );
+        assert_eq!(
+            reader.read_game(&mut visitor).unwrap().unwrap(),
+            vec!["[Event b]", "d4", "c4", "*"]
+        );
+        assert!(reader.read_game(&mut visitor).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_setup_tags() {
+        let mut tags = SetupTags::new();
+        assert_eq!(tags.chess().unwrap(), Chess::default());
+
+        tags.tag(b"FEN", RawTag(b"4k3/8/8/8/8/8/8/4K2R w K - 0 1"));
+        assert!(tags.chess().is_ok());
+        assert_eq!(tags.setup().unwrap().unwrap().board.occupied().count(), 3);
+
+        tags.tag(b"SetUp", RawTag(b"0"));
+        assert_eq!(tags.chess().unwrap(), Chess::default());
+
+        let mut tags = SetupTags::new();
+        tags.tag(b"Variant", RawTag(b"Chess960"));
+        tags.tag(
+            b"FEN",
+            RawTag(b"bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"),
+        );
+        assert_eq!(tags.castling_mode(), CastlingMode::Chess960);
+        assert!(tags.chess().is_ok());
+
+        tags.tag(b"FEN", RawTag(b"not a fen"));
+        assert!(matches!(tags.chess(), Err(SetupTagsError::InvalidFen(_))));
+    }
+
+    #[cfg(feature = "variant")]
+    #[test]
+    fn test_setup_tags_variant() {
+        let mut tags = SetupTags::new();
+        tags.tag(b"Variant", RawTag(b"Racing Kings"));
+        let pos = tags.variant_position().unwrap();
+        assert_eq!(pos.variant(), Variant::RacingKings);
+
+        tags.tag(b"Variant", RawTag(b"Shogi"));
+        assert!(matches!(
+            tags.variant_position(),
+            Err(SetupTagsError::InvalidVariant(_))
+        ));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_export() {
+        let mut pos = Chess::default();
+        let mut moves = Vec::new();
+        for _ in 0..40 {
+            let m = pos.legal_moves()[0].clone();
+            pos.play_unchecked(&m);
+            moves.push(m);
+        }
+
+        let mut game = ExportGame::from_position(Chess::default(), &moves).unwrap();
+        game.push_tag("Annotator", "\"Quoted\"");
+        game.push_tag("White", "A");
+        let pgn = game.to_string();
+        assert!(pgn.starts_with(
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"A\"]\n[Black \"?\"]\n[Result \"*\"]\n[Annotator \"\\\"Quoted\\\"\"]\n\n1. "
+        ));
+        assert!(pgn.ends_with(" *\n"));
+        assert!(pgn.lines().all(|line| line.len() <= MAX_LINE_LEN));
+
+        // Reading it back yields the same moves.
+        let mut visitor = Recorder::default();
+        let events = Reader::new(pgn.as_bytes()).read_game(&mut visitor).unwrap();
+        let sans: Vec<String> = game.sans().iter().map(|s| s.to_string()).collect();
+        assert_eq!(events[8..events.len() - 1], sans[..]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_export_from_setup() {
+        let setup = Fen::from_ascii(b"7k/8/8/8/8/8/8/R6K b - - 3 12")
+            .unwrap()
+            .into_setup();
+        let m = Move::Normal {
+            role: crate::Role::King,
+            from: crate::Square::H8,
+            capture: None,
+            to: crate::Square::G8,
+            promotion: None,
+        };
+        let game = ExportGame::from_setup::<Chess>(
+            setup,
+            CastlingMode::Standard,
+            core::slice::from_ref(&m),
+        )
+        .unwrap();
+        let pgn = game.to_string();
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"7k/8/8/8/8/8/8/R6K b - - 3 12\"]\n"));
+        assert!(pgn.ends_with("\n12... Kg8 *\n"));
+
+        let illegal = ExportGame::from_position(Chess::default(), &[m]);
+        assert!(matches!(
+            illegal,
+            Err(ExportError::IllegalMove { index: 0, .. })
+        ));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_export_chess960() {
+        let setup = Fen::from_ascii(b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+            .unwrap()
+            .into_setup();
+        let pos: Chess = setup.position(CastlingMode::Chess960).unwrap();
+        let pgn = ExportGame::from_position(pos, &[]).unwrap().to_string();
+        assert!(pgn.contains("[Variant \"Chess960\"]\n"));
+        assert!(!pgn.contains("[FEN "));
+
+        let mut tags = SetupTags::new();
+        tags.tag(b"Variant", RawTag(b"Chess960"));
+        assert_eq!(tags.castling_mode(), CastlingMode::Chess960);
+
+        let pgn = ExportGame::from_position(Chess::default(), &[])
+            .unwrap()
+            .to_string();
+        assert!(!pgn.contains("[Variant "));
+    }
+
+    #[cfg(all(feature = "alloc", feature = "variant"))]
+    #[test]
+    fn test_export_variant() {
+        for variant in Variant::ALL {
+            let pos = VariantPosition::new(variant);
+            let m = pos.legal_moves()[0].clone();
+            let pgn = ExportGame::from_position(pos, &[m]).unwrap().to_string();
+
+            let tag = pgn
+                .lines()
+                .find_map(|line| line.strip_prefix("[Variant \""))
+                .map(|value| value.trim_end_matches("\"]"));
+            assert_eq!(tag.is_some(), variant != Variant::Chess, "{variant}");
+
+            let mut tags = SetupTags::new();
+            if let Some(tag) = tag {
+                tags.tag(b"Variant", RawTag(tag.as_bytes()));
+            }
+            assert_eq!(tags.variant().unwrap(), variant);
+        }
+
+        let pgn = ExportGame::from_position(crate::variant::Atomic::default(), &[])
+            .unwrap()
+            .to_string();
+        assert!(pgn.contains("[Variant \"Atomic\"]\n"));
+    }
+}
@@ -0,0 +1,689 @@
+//! Read and write Polyglot opening books.
+//!
+//! A Polyglot book is a sequence of 16 byte entries, sorted by the
+//! [`Zobrist64`] hash of the position. Each entry suggests a move with a
+//! weight. Hashes are computed with [`EnPassantMode::Legal`], which matches
+//! the Polyglot specification.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "alloc")] {
+//! use shakmaty::{Chess, Position, polyglot::{Book, BookWriter}, san::San};
+//!
+//! // Write a tiny book.
+//! let pos = Chess::default();
+//! let mut writer = BookWriter::new();
+//! for (san, weight) in [("e4", 3), ("d4", 2), ("Nf3", 1)] {
+//!     let m = san.parse::<San>()?.to_move(&pos)?;
+//!     writer.add_move(&pos, &m, weight);
+//! }
+//! let bytes = writer.to_bytes();
+//!
+//! // Look up moves.
+//! let book = Book::new(&bytes[..])?;
+//! let moves: Vec<_> = book
+//!     .moves(&pos)
+//!     .map(|(m, weight)| (San::from_move(&pos, &m).to_string(), weight))
+//!     .collect();
+//! assert_eq!(moves, [("e4".to_owned(), 3), ("d4".to_owned(), 2), ("Nf3".to_owned(), 1)]);
+//!
+//! // Choose a move with probability proportional to its weight, based on a
+//! // uniformly random number.
+//! let m = book.choose_move(&pos, 0x1234_5678_9abc_def0).expect("book move");
+//! # }
+//! # #[derive(Debug)] struct CommonError;
+//! # impl From<shakmaty::san::ParseSanError> for CommonError { fn from(_: shakmaty::san::ParseSanError) -> Self { Self } }
+//! # impl From<shakmaty::san::SanError> for CommonError { fn from(_: shakmaty::san::SanError) -> Self { Self } }
+//! # impl From<shakmaty::polyglot::InvalidBookError> for CommonError { fn from(_: shakmaty::polyglot::InvalidBookError) -> Self { Self } }
+//! # Ok::<_, CommonError>(())
+//! ```
+
+use core::{fmt, iter::FusedIterator, slice::ChunksExact};
+
+use crate::{
+    zobrist::{Zobrist64, ZobristHash},
+    EnPassantMode, Move, MoveList, Position, Role, Square,
+};
+
+/// Error when a book does not consist of whole entries.
+#[derive(Clone, Debug)]
+pub struct InvalidBookError;
+
+impl fmt::Display for InvalidBookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid polyglot book")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidBookError {}
+
+/// Computes the Polyglot key of a position.
+pub fn key<P: Position>(pos: &P) -> Zobrist64 {
+    pos.zobrist_hash(EnPassantMode::Legal)
+}
+
+/// A move in Polyglot encoding.
+///
+/// Castling moves are encoded as the king moving to the square of the
+/// castling rook, e.g., `e1h1`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RawMove(pub u16);
+
+impl RawMove {
+    /// Encodes a move. Returns `None` for piece drops.
+    pub fn from_move(m: &Move) -> Option<RawMove> {
+        let (from, to, promotion) = match *m {
+            Move::Normal {
+                from,
+                to,
+                promotion,
+                ..
+            } => (from, to, promotion),
+            Move::EnPassant { from, to } => (from, to, None),
+            Move::Castle { king, rook } => (king, rook, None),
+            Move::Put { .. } => return None,
+        };
+        Some(RawMove(
+            u16::from(to)
+                | (u16::from(from) << 6)
+                | (promotion.map_or(0, |role| role as u16 - 1) << 12),
+        ))
+    }
+
+    pub fn from(self) -> Square {
+        Square::new(u32::from((self.0 >> 6) & 63))
+    }
+
+    pub fn to(self) -> Square {
+        Square::new(u32::from(self.0 & 63))
+    }
+
+    /// Gets the promotion role, or `None`. Returns `None` for invalid
+    /// promotion codes.
+    pub fn promotion(self) -> Option<Role> {
+        match (self.0 >> 12) & 7 {
+            1 => Some(Role::Knight),
+            2 => Some(Role::Bishop),
+            3 => Some(Role::Rook),
+            4 => Some(Role::Queen),
+            _ => None,
+        }
+    }
+
+    /// Decodes the move in the context of a position. Returns `None` if it
+    /// is not legal.
+    pub fn to_move<P: Position>(self, pos: &P) -> Option<Move> {
+        self.find_in(&pos.legal_moves())
+    }
+
+    fn find_in(self, legals: &MoveList) -> Option<Move> {
+        if (self.0 >> 12) & 7 > 4 {
+            return None;
+        }
+        let (from, to, promotion) = (self.from(), self.to(), self.promotion());
+        legals
+            .iter()
+            .find(|m| match **m {
+                Move::Castle { king, rook } => king == from && rook == to,
+                _ => m.from() == Some(from) && m.to() == to && m.promotion() == promotion,
+            })
+            .cloned()
+    }
+}
+
+/// An entry of a Polyglot book.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Entry {
+    /// Polyglot key of the position, see [`key()`].
+    pub key: Zobrist64,
+    pub raw_move: RawMove,
+    /// Relative weight of the move.
+    pub weight: u16,
+    /// Learning data, not used by most programs.
+    pub learn: u32,
+}
+
+impl Entry {
+    /// Size of an encoded entry in bytes.
+    pub const SIZE: usize = 16;
+
+    pub fn from_bytes(bytes: &[u8; Entry::SIZE]) -> Entry {
+        let [k0, k1, k2, k3, k4, k5, k6, k7, m0, m1, w0, w1, l0, l1, l2, l3] = *bytes;
+        Entry {
+            key: Zobrist64(u64::from_be_bytes([k0, k1, k2, k3, k4, k5, k6, k7])),
+            raw_move: RawMove(u16::from_be_bytes([m0, m1])),
+            weight: u16::from_be_bytes([w0, w1]),
+            learn: u32::from_be_bytes([l0, l1, l2, l3]),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; Entry::SIZE] {
+        let mut bytes = [0; Entry::SIZE];
+        bytes[0..8].copy_from_slice(&self.key.0.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.raw_move.0.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+
+    fn from_slice(bytes: &[u8]) -> Entry {
+        Entry::from_bytes(bytes.try_into().expect("entry size"))
+    }
+}
+
+fn read_key(bytes: &[u8]) -> Zobrist64 {
+    Zobrist64(u64::from_be_bytes(bytes[..8].try_into().expect("key size")))
+}
+
+/// Chooses an item with probability proportional to its weight, given a
+/// uniformly random number.
+fn choose<T, I: Iterator<Item = (T, u16)> + Clone>(items: I, random: u64) -> Option<T> {
+    let total: u64 = items.clone().map(|(_, weight)| u64::from(weight)).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut choice = ((u128::from(random) * u128::from(total)) >> 64) as u64;
+    for (item, weight) in items {
+        match choice.checked_sub(u64::from(weight)) {
+            Some(remaining) => choice = remaining,
+            None => return Some(item),
+        }
+    }
+    None
+}
+
+/// A Polyglot book in memory, e.g., a byte slice or a memory mapped file.
+#[derive(Debug, Clone)]
+pub struct Book<B> {
+    data: B,
+}
+
+impl<B: AsRef<[u8]>> Book<B> {
+    /// Wraps the contents of a book. The entries are expected to be sorted
+    /// by key.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidBookError`] if the length of the data is not a
+    /// multiple of [`Entry::SIZE`].
+    pub fn new(data: B) -> Result<Book<B>, InvalidBookError> {
+        if data.as_ref().len() % Entry::SIZE == 0 {
+            Ok(Book { data })
+        } else {
+            Err(InvalidBookError)
+        }
+    }
+
+    pub fn into_inner(self) -> B {
+        self.data
+    }
+
+    /// Gets the total number of entries in the book.
+    pub fn len(&self) -> usize {
+        self.data.as_ref().len() / Entry::SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.as_ref().is_empty()
+    }
+
+    /// Finds all entries with the given key, using binary search.
+    pub fn entries(&self, key: Zobrist64) -> Entries<'_> {
+        let data = self.data.as_ref();
+        let key_at = |i: usize| read_key(&data[i * Entry::SIZE..]);
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if key_at(mid).0 < key.0 {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        let mut end = lo;
+        while end < self.len() && key_at(end) == key {
+            end += 1;
+        }
+        Entries {
+            inner: data[lo * Entry::SIZE..end * Entry::SIZE].chunks_exact(Entry::SIZE),
+        }
+    }
+
+    /// Gets the legal book moves and their weights in a position, in the
+    /// order of the book. Entries with illegal moves are skipped.
+    pub fn moves<P: Position>(&self, pos: &P) -> Moves<'_> {
+        Moves {
+            entries: self.entries(key(pos)),
+            legals: pos.legal_moves(),
+        }
+    }
+
+    /// Chooses a legal book move, with probability proportional to its
+    /// weight, given a uniformly `random` number. Returns `None` if there
+    /// are no book moves with non-zero weight.
+    pub fn choose_move<P: Position>(&self, pos: &P, random: u64) -> Option<Move> {
+        choose(self.moves(pos), random)
+    }
+}
+
+/// Iterator over the [`Entry`] items of a position in a [`Book`].
+#[derive(Debug, Clone)]
+pub struct Entries<'a> {
+    inner: ChunksExact<'a, u8>,
+}
+
+impl Iterator for Entries<'_> {
+    type Item = Entry;
+
+    fn next(&mut self) -> Option<Entry> {
+        self.inner.next().map(Entry::from_slice)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Entries<'_> {}
+
+impl DoubleEndedIterator for Entries<'_> {
+    fn next_back(&mut self) -> Option<Entry> {
+        self.inner.next_back().map(Entry::from_slice)
+    }
+}
+
+impl FusedIterator for Entries<'_> {}
+
+/// Iterator over the legal moves and weights of a position in a [`Book`].
+#[derive(Debug, Clone)]
+pub struct Moves<'a> {
+    entries: Entries<'a>,
+    legals: MoveList,
+}
+
+impl Iterator for Moves<'_> {
+    type Item = (Move, u16);
+
+    fn next(&mut self) -> Option<(Move, u16)> {
+        self.entries.by_ref().find_map(|entry| {
+            entry
+                .raw_move
+                .find_in(&self.legals)
+                .map(|m| (m, entry.weight))
+        })
+    }
+}
+
+impl FusedIterator for Moves<'_> {}
+
+/// A Polyglot book file that is searched without loading it into memory.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct FileBook<R> {
+    inner: R,
+    len: u64,
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read + std::io::Seek> FileBook<R> {
+    /// Opens a book, e.g., a [`std::fs::File`]. The entries are expected to
+    /// be sorted by key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if seeking fails, or with
+    /// [`std::io::ErrorKind::InvalidData`] if the length is not a multiple
+    /// of [`Entry::SIZE`].
+    pub fn new(mut inner: R) -> std::io::Result<FileBook<R>> {
+        let size = inner.seek(std::io::SeekFrom::End(0))?;
+        if size % Entry::SIZE as u64 != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                InvalidBookError,
+            ));
+        }
+        Ok(FileBook {
+            inner,
+            len: size / Entry::SIZE as u64,
+        })
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Gets the total number of entries in the book.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn read_entry(&mut self, i: u64) -> std::io::Result<Entry> {
+        let mut buf = [0; Entry::SIZE];
+        self.inner
+            .seek(std::io::SeekFrom::Start(i * Entry::SIZE as u64))?;
+        self.inner.read_exact(&mut buf)?;
+        Ok(Entry::from_bytes(&buf))
+    }
+
+    /// Finds all entries with the given key, using binary search.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from the underlying file fails.
+    pub fn entries(&mut self, key: Zobrist64) -> std::io::Result<alloc::vec::Vec<Entry>> {
+        let (mut lo, mut hi) = (0, self.len);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.read_entry(mid)?.key.0 < key.0 {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        let mut entries = alloc::vec::Vec::new();
+        for i in lo..self.len {
+            let entry = self.read_entry(i)?;
+            if entry.key != key {
+                break;
+            }
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Gets the legal book moves and their weights in a position, in the
+    /// order of the book. Entries with illegal moves are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from the underlying file fails.
+    pub fn moves<P: Position>(&mut self, pos: &P) -> std::io::Result<alloc::vec::Vec<(Move, u16)>> {
+        let legals = pos.legal_moves();
+        Ok(self
+            .entries(key(pos))?
+            .into_iter()
+            .filter_map(|entry| entry.raw_move.find_in(&legals).map(|m| (m, entry.weight)))
+            .collect())
+    }
+
+    /// Chooses a legal book move, with probability proportional to its
+    /// weight, given a uniformly `random` number. Returns `None` if there
+    /// are no book moves with non-zero weight.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from the underlying file fails.
+    pub fn choose_move<P: Position>(
+        &mut self,
+        pos: &P,
+        random: u64,
+    ) -> std::io::Result<Option<Move>> {
+        Ok(choose(self.moves(pos)?.into_iter(), random))
+    }
+}
+
+/// Collects entries and writes them as a sorted Polyglot book.
+///
+/// Entries with the same key and move are merged by adding their weights
+/// (saturating at [`u16::MAX`]) and keeping the learning data of the first
+/// entry. Entries of each position are ordered by descending weight.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default)]
+pub struct BookWriter {
+    entries: alloc::vec::Vec<Entry>,
+}
+
+#[cfg(feature = "alloc")]
+impl BookWriter {
+    pub fn new() -> BookWriter {
+        BookWriter::default()
+    }
+
+    pub fn add(&mut self, entry: Entry) {
+        self.entries.push(entry);
+    }
+
+    /// Adds a move in the given position. Piece drops are ignored.
+    pub fn add_move<P: Position>(&mut self, pos: &P, m: &Move, weight: u16) {
+        if let Some(raw_move) = RawMove::from_move(m) {
+            self.add(Entry {
+                key: key(pos),
+                raw_move,
+                weight,
+                learn: 0,
+            });
+        }
+    }
+
+    /// Adds all entries of an existing book.
+    pub fn add_book<B: AsRef<[u8]>>(&mut self, book: &Book<B>) {
+        self.entries.extend(
+            book.data
+                .as_ref()
+                .chunks_exact(Entry::SIZE)
+                .map(Entry::from_slice),
+        );
+    }
+
+    /// Merges and sorts the entries.
+    pub fn entries(&mut self) -> &[Entry] {
+        // Merge duplicates, keeping the first occurrence in place.
+        self.entries
+            .sort_by_key(|entry| (entry.key.0, entry.raw_move.0));
+        self.entries.dedup_by(|entry, kept| {
+            let duplicate = entry.key == kept.key && entry.raw_move == kept.raw_move;
+            if duplicate {
+                kept.weight = kept.weight.saturating_add(entry.weight);
+            }
+            duplicate
+        });
+        self.entries.sort_by(|a, b| {
+            a.key
+                .0
+                .cmp(&b.key.0)
+                .then_with(|| b.weight.cmp(&a.weight))
+                .then_with(|| a.raw_move.0.cmp(&b.raw_move.0))
+        });
+        &self.entries
+    }
+
+    /// Produces the contents of the book file.
+    pub fn to_bytes(&mut self) -> alloc::vec::Vec<u8> {
+        self.entries().iter().flat_map(Entry::to_bytes).collect()
+    }
+
+    /// Writes the book file.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the underlying writer returns an error.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&mut self, mut w: W) -> std::io::Result<()> {
+        for entry in self.entries() {
+            w.write_all(&entry.to_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Extend<Entry> for BookWriter {
+    fn extend<T: IntoIterator<Item = Entry>>(&mut self, iter: T) {
+        self.entries.extend(iter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fen::Fen, CastlingMode, Chess};
+
+    fn setup_fen(fen: &str) -> Chess {
+        fen.parse::<Fen>()
+            .expect("valid fen")
+            .into_position(CastlingMode::Standard)
+            .expect("legal fen")
+    }
+
+    #[test]
+    fn test_raw_move() {
+        // g1f3 as listed in the Polyglot specification.
+        let m = Move::Normal {
+            role: Role::Knight,
+            from: Square::G1,
+            capture: None,
+            to: Square::F3,
+            promotion: None,
+        };
+        assert_eq!(RawMove::from_move(&m), Some(RawMove(0x0195)));
+        assert_eq!(RawMove(0x0195).to_move(&Chess::default()), Some(m));
+
+        let pos = setup_fen("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        for (raw, expected) in [
+            (
+                RawMove(0x0107),
+                Move::Castle {
+                    king: Square::E1,
+                    rook: Square::H1,
+                },
+            ),
+            (
+                RawMove(0x0100),
+                Move::Castle {
+                    king: Square::E1,
+                    rook: Square::A1,
+                },
+            ),
+            (
+                RawMove(0x4c78),
+                Move::Normal {
+                    role: Role::Pawn,
+                    from: Square::B7,
+                    capture: Some(Role::Rook),
+                    to: Square::A8,
+                    promotion: Some(Role::Queen),
+                },
+            ),
+        ] {
+            assert_eq!(raw.to_move(&pos), Some(expected.clone()));
+            assert_eq!(RawMove::from_move(&expected), Some(raw));
+        }
+        assert_eq!(RawMove(0x5c78).to_move(&pos), None);
+    }
+
+    #[test]
+    fn test_entry() {
+        let entry = Entry {
+            key: Zobrist64(0x463b_9618_1691_fc9c),
+            raw_move: RawMove(0x031c),
+            weight: 42,
+            learn: 7,
+        };
+        let bytes = entry.to_bytes();
+        assert_eq!(
+            bytes,
+            [0x46, 0x3b, 0x96, 0x18, 0x16, 0x91, 0xfc, 0x9c, 0x03, 0x1c, 0x00, 42, 0, 0, 0, 7]
+        );
+        assert_eq!(Entry::from_bytes(&bytes), entry);
+        assert_eq!(key(&Chess::default()), entry.key);
+        assert!(Book::new(&bytes[..15]).is_err());
+    }
+
+    #[test]
+    fn test_choose() {
+        let items = [('a', 1), ('b', 0), ('c', 3)];
+        assert_eq!(choose(items.iter().copied(), 0), Some('a'));
+        assert_eq!(choose(items.iter().copied(), u64::MAX / 4 - 1), Some('a'));
+        assert_eq!(choose(items.iter().copied(), u64::MAX / 4 + 1), Some('c'));
+        assert_eq!(choose(items.iter().copied(), u64::MAX), Some('c'));
+        assert_eq!(choose([('a', 0)].iter().copied(), 0), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_write_and_read() {
+        let pos = Chess::default();
+        let e4 = RawMove(0x031c);
+        let d4 = RawMove(0x02db);
+        let nf3 = RawMove(0x0195);
+
+        let mut writer = BookWriter::new();
+        writer.add(Entry {
+            key: key(&pos),
+            raw_move: e4,
+            weight: 1,
+            learn: 0,
+        });
+        writer.add_move(&pos, &nf3.to_move(&pos).expect("legal"), 3);
+        writer.add_move(&pos, &d4.to_move(&pos).expect("legal"), 2);
+        writer.add_move(&pos, &e4.to_move(&pos).expect("legal"), 3);
+        for raw_move in [RawMove(0x0001), RawMove(0x0002)] {
+            writer.add(Entry {
+                key: Zobrist64(1),
+                raw_move,
+                weight: 1,
+                learn: 0,
+            });
+            writer.add(Entry {
+                key: Zobrist64(u64::MAX),
+                raw_move,
+                weight: u16::MAX,
+                learn: 0,
+            });
+        }
+        writer.add(Entry {
+            key: Zobrist64(u64::MAX),
+            raw_move: RawMove(0x0001),
+            weight: 1,
+            learn: 0,
+        });
+        assert_eq!(writer.entries().len(), 7);
+
+        let mut bytes = alloc::vec::Vec::new();
+        writer.write_to(&mut bytes).expect("write to vec");
+        assert_eq!(bytes, writer.to_bytes());
+
+        let book = Book::new(&bytes).expect("valid book");
+        assert_eq!(book.len(), 7);
+        let entries: alloc::vec::Vec<_> = book.entries(key(&pos)).collect();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.raw_move, entry.weight))
+                .collect::<alloc::vec::Vec<_>>(),
+            [(e4, 4), (nf3, 3), (d4, 2)]
+        );
+        assert_eq!(book.moves(&pos).count(), 3);
+        assert_eq!(book.entries(Zobrist64(0)).count(), 0);
+        assert_eq!(book.entries(Zobrist64(1)).count(), 2);
+        assert_eq!(
+            book.entries(Zobrist64(u64::MAX))
+                .map(|entry| entry.weight)
+                .collect::<alloc::vec::Vec<_>>(),
+            [u16::MAX, u16::MAX]
+        );
+        assert_eq!(
+            book.choose_move(&pos, 0),
+            Some(e4.to_move(&pos).expect("legal"))
+        );
+
+        let mut file_book = FileBook::new(std::io::Cursor::new(&bytes)).expect("valid book");
+        assert_eq!(file_book.len(), 7);
+        assert_eq!(file_book.entries(key(&pos)).expect("read"), entries);
+        assert_eq!(
+            file_book.moves(&pos).expect("read"),
+            book.moves(&pos).collect::<alloc::vec::Vec<_>>()
+        );
+        assert_eq!(
+            file_book.choose_move(&pos, u64::MAX).expect("read"),
+            Some(d4.to_move(&pos).expect("legal"))
+        );
+        assert!(FileBook::new(std::io::Cursor::new(&bytes[1..])).is_err());
+    }
+}
@@ -11,7 +11,7 @@ use crate::{
     attacks,
     bitboard::{Bitboard, Direction},
     setup::{Castles, EnPassant, Setup},
-    Board, ByColor, ByRole, CastlingMode, CastlingSide, Color,
+    Board, ByCastlingSide, ByColor, ByRole, CastlingMode, CastlingSide, Color,
     Color::{Black, White},
     EnPassantMode, Move, MoveList, Piece, Rank, RemainingChecks, Role, Square,
 };
@@ -63,6 +63,41 @@ impl fmt::Display for Outcome {
     }
 }
 
+/// Serializes as `1-0`, `0-1` or `1/2-1/2` in human-readable formats, and
+/// otherwise as `1` (white wins), `2` (black wins) or `0` (draw).
+#[cfg(feature = "serde")]
+impl serde::Serialize for Outcome {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(self.as_str())
+        } else {
+            serializer.serialize_u8(match *self {
+                Outcome::Decisive { winner: White } => 1,
+                Outcome::Decisive { winner: Black } => 2,
+                Outcome::Draw => 0,
+            })
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Outcome {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Outcome, D::Error> {
+        if deserializer.is_human_readable() {
+            crate::util::deserialize_ascii(deserializer, "game result", |s| {
+                Outcome::from_ascii(s).ok()
+            })
+        } else {
+            crate::util::deserialize_u8(deserializer, "outcome index", |n| match n {
+                1 => Some(Outcome::Decisive { winner: White }),
+                2 => Some(Outcome::Decisive { winner: Black }),
+                0 => Some(Outcome::Draw),
+                _ => None,
+            })
+        }
+    }
+}
+
 /// Error when parsing the outcome of a game.
 #[derive(Debug, Clone)]
 pub enum ParseOutcomeError {
@@ -118,6 +153,58 @@ impl<P: fmt::Debug> fmt::Display for PlayError<P> {
 #[cfg(feature = "std")]
 impl<P: fmt::Debug> std::error::Error for PlayError<P> {}
 
+/// State needed to take back a move, returned by
+/// [`Position::play_with_undo()`].
+///
+/// Pass it to [`Position::undo()`] together with the same move, on the
+/// position the move was played on.
+#[derive(Clone, Debug)]
+pub struct Undo {
+    rooks: ByColor<ByCastlingSide<Option<Square>>>,
+    ep_square: Option<EnPassant>,
+    halfmoves: u32,
+    fullmoves: NonZeroU32,
+    #[cfg(feature = "variant")]
+    variant: VariantUndo,
+}
+
+impl Undo {
+    fn new(
+        castles: &Castles,
+        ep_square: Option<EnPassant>,
+        halfmoves: u32,
+        fullmoves: NonZeroU32,
+    ) -> Undo {
+        Undo {
+            rooks: castles.rooks(),
+            ep_square,
+            halfmoves,
+            fullmoves,
+            #[cfg(feature = "variant")]
+            variant: VariantUndo::None,
+        }
+    }
+}
+
+/// Variant specific state changed by a move.
+#[cfg(feature = "variant")]
+#[derive(Clone, Debug)]
+enum VariantUndo {
+    None,
+    /// Pieces removed from the squares around the target square, in the
+    /// order of [`attacks::king_attacks()`].
+    Atomic {
+        exploded: [Option<Piece>; 8],
+    },
+    ThreeCheck {
+        remaining_checks: ByColor<RemainingChecks>,
+    },
+    /// Whether the piece on the target square was promoted before the move.
+    Crazyhouse {
+        promoted: bool,
+    },
+}
+
 bitflags! {
     /// Reasons for a [`Setup`] not being a legal [`Position`].
     ///
@@ -393,7 +480,7 @@ pub trait Position {
     /// Generates legal castling moves.
     fn castling_moves(&self, side: CastlingSide) -> MoveList {
         let mut moves = self.legal_moves();
-        moves.retain(|m| m.castling_side().map_or(false, |s| side == s));
+        moves.retain(|m| m.castling_side() == Some(side));
         moves
     }
 
@@ -492,6 +579,51 @@ pub trait Position {
     /// [`Position::play()`] if you cannot guarantee legality.
     fn play_unchecked(&mut self, m: &Move);
 
+    /// Plays a move in place and returns the state needed to take it back
+    /// with [`Position::undo()`]. This avoids cloning the position, for
+    /// example in search.
+    ///
+    /// It is the callers responsibility to ensure the move is legal.
+    ///
+    /// # Panics
+    ///
+    /// Illegal moves can corrupt the state of the position and may
+    /// (or may not) panic or cause panics on future calls.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use shakmaty::{Chess, Color, Move, Position, Role, Square};
+    ///
+    /// let mut pos = Chess::default();
+    /// let m = Move::Normal {
+    ///     role: Role::Pawn,
+    ///     from: Square::E2,
+    ///     to: Square::E4,
+    ///     capture: None,
+    ///     promotion: None,
+    /// };
+    ///
+    /// let undo = pos.play_with_undo(&m);
+    /// assert_eq!(pos.board().piece_at(Square::E4), Some(Color::White.pawn()));
+    /// assert_eq!(pos.turn(), Color::Black);
+    ///
+    /// pos.undo(&m, undo);
+    /// assert_eq!(pos, Chess::default());
+    /// ```
+    fn play_with_undo(&mut self, m: &Move) -> Undo;
+
+    /// Takes back a move played with [`Position::play_with_undo()`].
+    ///
+    /// Moves must be taken back in reverse order, each with the [`Undo`]
+    /// returned when it was played.
+    ///
+    /// # Panics
+    ///
+    /// Undoing any other move can corrupt the state of the position and may
+    /// (or may not) panic or cause panics on future calls.
+    fn undo(&mut self, m: &Move, undo: Undo);
+
     // Implementation note: Trait methods above this comment should be made
     // available for VariantPosition. The provided methods below this comment
     // are never overwritten in implementations, but for simplicity of use
@@ -853,6 +985,30 @@ impl Position for Chess {
         );
     }
 
+    fn play_with_undo(&mut self, m: &Move) -> Undo {
+        let undo = Undo::new(
+            &self.castles,
+            self.ep_square,
+            self.halfmoves,
+            self.fullmoves,
+        );
+        self.play_unchecked(m);
+        undo
+    }
+
+    fn undo(&mut self, m: &Move, undo: Undo) {
+        undo_move(
+            &mut self.board,
+            &mut self.turn,
+            &mut self.castles,
+            &mut self.ep_square,
+            &mut self.halfmoves,
+            &mut self.fullmoves,
+            m,
+            undo,
+        );
+    }
+
     fn legal_moves(&self) -> MoveList {
         let mut moves = MoveList::new();
 
@@ -1076,6 +1232,52 @@ pub(crate) mod variant {
                 },
             }
         }
+
+        /// Plays a move and returns the pieces removed by an explosion from
+        /// the squares around the target square, in the order of
+        /// [`attacks::king_attacks()`].
+        fn play_exploding(&mut self, m: &Move) -> [Option<Piece>; 8] {
+            let mut exploded = [None; 8];
+
+            do_move(
+                &mut self.board,
+                &mut Bitboard(0),
+                &mut self.turn,
+                &mut self.castles,
+                &mut self.ep_square,
+                &mut self.halfmoves,
+                &mut self.fullmoves,
+                m,
+            );
+
+            match *m {
+                Move::Normal {
+                    capture: Some(_),
+                    to,
+                    ..
+                }
+                | Move::EnPassant { to, .. } => {
+                    self.board.discard_piece_at(to);
+
+                    let explosion_radius =
+                        attacks::king_attacks(to) & self.board().occupied() & !self.board.pawns();
+
+                    if (explosion_radius & self.board().kings() & self.us()).any() {
+                        self.castles.discard_color(self.turn());
+                    }
+
+                    for (i, sq) in attacks::king_attacks(to).into_iter().enumerate() {
+                        if explosion_radius.contains(sq) {
+                            exploded[i] = self.board.remove_piece_at(sq);
+                            self.castles.discard_rook(sq);
+                        }
+                    }
+                }
+                _ => (),
+            }
+
+            exploded
+        }
     }
 
     impl Default for Atomic {
@@ -1202,40 +1404,35 @@ pub(crate) mod variant {
         }
 
         fn play_unchecked(&mut self, m: &Move) {
-            do_move(
+            self.play_exploding(m);
+        }
+
+        fn play_with_undo(&mut self, m: &Move) -> Undo {
+            let undo = Undo::new(
+                &self.castles,
+                self.ep_square,
+                self.halfmoves,
+                self.fullmoves,
+            );
+            Undo {
+                variant: VariantUndo::Atomic {
+                    exploded: self.play_exploding(m),
+                },
+                ..undo
+            }
+        }
+
+        fn undo(&mut self, m: &Move, undo: Undo) {
+            undo_move(
                 &mut self.board,
-                &mut Bitboard(0),
                 &mut self.turn,
                 &mut self.castles,
                 &mut self.ep_square,
                 &mut self.halfmoves,
                 &mut self.fullmoves,
                 m,
+                undo,
             );
-
-            match *m {
-                Move::Normal {
-                    capture: Some(_),
-                    to,
-                    ..
-                }
-                | Move::EnPassant { to, .. } => {
-                    self.board.discard_piece_at(to);
-
-                    let explosion_radius =
-                        attacks::king_attacks(to) & self.board().occupied() & !self.board.pawns();
-
-                    if (explosion_radius & self.board().kings() & self.us()).any() {
-                        self.castles.discard_color(self.turn());
-                    }
-
-                    for explosion in explosion_radius {
-                        self.board.discard_piece_at(explosion);
-                        self.castles.discard_rook(explosion);
-                    }
-                }
-                _ => (),
-            }
         }
 
         fn legal_moves(&self) -> MoveList {
@@ -1514,6 +1711,30 @@ pub(crate) mod variant {
             );
         }
 
+        fn play_with_undo(&mut self, m: &Move) -> Undo {
+            let undo = Undo::new(
+                &self.castles,
+                self.ep_square,
+                self.halfmoves,
+                self.fullmoves,
+            );
+            self.play_unchecked(m);
+            undo
+        }
+
+        fn undo(&mut self, m: &Move, undo: Undo) {
+            undo_move(
+                &mut self.board,
+                &mut self.turn,
+                &mut self.castles,
+                &mut self.ep_square,
+                &mut self.halfmoves,
+                &mut self.fullmoves,
+                m,
+                undo,
+            );
+        }
+
         fn en_passant_moves(&self) -> MoveList {
             let mut moves = MoveList::new();
             gen_en_passant(self.board(), self.turn, self.ep_square, &mut moves);
@@ -1672,6 +1893,14 @@ pub(crate) mod variant {
             self.chess.play_unchecked(m);
         }
 
+        fn play_with_undo(&mut self, m: &Move) -> Undo {
+            self.chess.play_with_undo(m)
+        }
+
+        fn undo(&mut self, m: &Move, undo: Undo) {
+            self.chess.undo(m, undo);
+        }
+
         fn legal_moves(&self) -> MoveList {
             if self.is_variant_end() {
                 MoveList::new()
@@ -1818,6 +2047,29 @@ pub(crate) mod variant {
             }
         }
 
+        fn play_with_undo(&mut self, m: &Move) -> Undo {
+            let undo = Undo {
+                variant: VariantUndo::ThreeCheck {
+                    remaining_checks: self.remaining_checks,
+                },
+                ..Undo::new(
+                    &self.chess.castles,
+                    self.chess.ep_square,
+                    self.chess.halfmoves,
+                    self.chess.fullmoves,
+                )
+            };
+            self.play_unchecked(m);
+            undo
+        }
+
+        fn undo(&mut self, m: &Move, undo: Undo) {
+            if let VariantUndo::ThreeCheck { remaining_checks } = undo.variant {
+                self.remaining_checks = remaining_checks;
+            }
+            self.chess.undo(m, undo);
+        }
+
         fn legal_moves(&self) -> MoveList {
             if self.is_variant_end() {
                 MoveList::new()
@@ -2072,6 +2324,56 @@ pub(crate) mod variant {
             );
         }
 
+        fn play_with_undo(&mut self, m: &Move) -> Undo {
+            let promoted = match *m {
+                Move::Normal { to, .. } => self.promoted.contains(to),
+                _ => false,
+            };
+            let undo = Undo {
+                variant: VariantUndo::Crazyhouse { promoted },
+                ..Undo::new(
+                    &self.chess.castles,
+                    self.chess.ep_square,
+                    self.chess.halfmoves,
+                    self.chess.fullmoves,
+                )
+            };
+            self.play_unchecked(m);
+            undo
+        }
+
+        fn undo(&mut self, m: &Move, undo: Undo) {
+            let promoted = match undo.variant {
+                VariantUndo::Crazyhouse { promoted } => promoted,
+                _ => false,
+            };
+            match *m {
+                Move::Normal {
+                    from,
+                    capture,
+                    to,
+                    promotion,
+                    ..
+                } => {
+                    if let Some(capture) = capture {
+                        let capture = if promoted { Role::Pawn } else { capture };
+                        *self.pockets.get_mut(!self.turn()).get_mut(capture) -= 1;
+                    }
+                    let from_promoted = self.promoted.contains(to) && promotion.is_none();
+                    self.promoted.set(from, from_promoted);
+                    self.promoted.set(to, promoted);
+                }
+                Move::EnPassant { .. } => {
+                    self.pockets.get_mut(!self.turn()).pawn -= 1;
+                }
+                Move::Put { role, .. } => {
+                    *self.pockets.get_mut(!self.turn()).get_mut(role) += 1;
+                }
+                Move::Castle { .. } => {}
+            }
+            self.chess.undo(m, undo);
+        }
+
         fn legal_moves(&self) -> MoveList {
             let mut moves = self.chess.legal_moves();
 
@@ -2323,6 +2625,25 @@ pub(crate) mod variant {
             );
         }
 
+        fn play_with_undo(&mut self, m: &Move) -> Undo {
+            let undo = Undo::new(&self.castles, None, self.halfmoves, self.fullmoves);
+            self.play_unchecked(m);
+            undo
+        }
+
+        fn undo(&mut self, m: &Move, undo: Undo) {
+            undo_move(
+                &mut self.board,
+                &mut self.turn,
+                &mut self.castles,
+                &mut None,
+                &mut self.halfmoves,
+                &mut self.fullmoves,
+                m,
+                undo,
+            );
+        }
+
         fn legal_moves(&self) -> MoveList {
             let mut moves = MoveList::new();
 
@@ -2574,6 +2895,30 @@ pub(crate) mod variant {
             );
         }
 
+        fn play_with_undo(&mut self, m: &Move) -> Undo {
+            let undo = Undo::new(
+                &self.castles,
+                self.ep_square,
+                self.halfmoves,
+                self.fullmoves,
+            );
+            self.play_unchecked(m);
+            undo
+        }
+
+        fn undo(&mut self, m: &Move, undo: Undo) {
+            undo_move(
+                &mut self.board,
+                &mut self.turn,
+                &mut self.castles,
+                &mut self.ep_square,
+                &mut self.halfmoves,
+                &mut self.fullmoves,
+                m,
+                undo,
+            );
+        }
+
         fn legal_moves(&self) -> MoveList {
             let mut moves = MoveList::new();
 
@@ -2986,6 +3331,66 @@ fn do_move(
     *turn = !color;
 }
 
+#[allow(clippy::too_many_arguments)] // But typesafe
+fn undo_move(
+    board: &mut Board,
+    turn: &mut Color,
+    castles: &mut Castles,
+    ep_square: &mut Option<EnPassant>,
+    halfmoves: &mut u32,
+    fullmoves: &mut NonZeroU32,
+    m: &Move,
+    undo: Undo,
+) {
+    let color = !*turn;
+
+    match *m {
+        Move::Normal {
+            role,
+            from,
+            capture,
+            to,
+            ..
+        } => {
+            match capture {
+                Some(capture) => board.set_piece_at(to, capture.of(!color)),
+                None => board.discard_piece_at(to),
+            }
+            board.set_piece_at(from, role.of(color));
+        }
+        Move::Castle { king, rook } => {
+            let side = CastlingSide::from_queen_side(rook < king);
+            board.discard_piece_at(Square::from_coords(side.rook_to_file(), rook.rank()));
+            board.discard_piece_at(Square::from_coords(side.king_to_file(), king.rank()));
+            board.set_piece_at(rook, color.rook());
+            board.set_piece_at(king, color.king());
+        }
+        Move::EnPassant { from, to } => {
+            board.discard_piece_at(to);
+            board.set_piece_at(from, color.pawn());
+            board.set_piece_at(Square::from_coords(to.file(), from.rank()), (!color).pawn());
+        }
+        Move::Put { to, .. } => {
+            board.discard_piece_at(to);
+        }
+    }
+
+    #[cfg(feature = "variant")]
+    if let VariantUndo::Atomic { exploded } = undo.variant {
+        for (sq, piece) in attacks::king_attacks(m.to()).into_iter().zip(exploded) {
+            if let Some(piece) = piece {
+                board.set_piece_at(sq, piece);
+            }
+        }
+    }
+
+    castles.restore_rooks(undo.rooks);
+    *ep_square = undo.ep_square;
+    *halfmoves = undo.halfmoves;
+    *fullmoves = undo.fullmoves;
+    *turn = color;
+}
+
 fn validate<P: Position>(pos: &P, ep_square: Option<EnPassant>) -> PositionErrorKinds {
     let mut errors = PositionErrorKinds::empty();
 
@@ -3833,4 +4238,87 @@ mod tests {
             to: Square::D4
         }));
     }
+
+    fn perft_with_undo<P: Position + Clone>(pos: &mut P, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let mut nodes = 0;
+        for m in pos.legal_moves() {
+            let before = pos.clone();
+            let undo = pos.play_with_undo(&m);
+            nodes += perft_with_undo(pos, depth - 1);
+            pos.undo(&m, undo);
+
+            assert_eq!(pos.legal_moves(), before.legal_moves(), "undo {m:?}");
+            assert_eq!(
+                pos.clone().into_setup(EnPassantMode::Always),
+                before.into_setup(EnPassantMode::Always),
+                "undo {m:?}"
+            );
+        }
+        nodes
+    }
+
+    fn assert_play_undo<P: Position + FromSetup + Clone>(fen: &str, depth: u32) {
+        let mut pos: P = setup_fen(fen);
+        assert_eq!(
+            perft_with_undo(&mut pos, depth),
+            crate::perft(&pos, depth),
+            "{fen}"
+        );
+    }
+
+    #[test]
+    fn test_play_with_undo() {
+        assert_play_undo::<Chess>(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            2,
+        );
+        assert_play_undo::<Chess>("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 3);
+        assert_play_undo::<Chess>(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            2,
+        );
+        assert_play_undo::<Chess>("1r2k2r/8/8/8/8/8/8/R2K3R w KQk - 17 40", 2);
+    }
+
+    #[test]
+    fn test_undo_size() {
+        // Only the state changed by a move is stored.
+        assert!(core::mem::size_of::<Undo>() <= 32);
+    }
+
+    #[cfg(feature = "variant")]
+    #[test]
+    fn test_variant_play_with_undo() {
+        use super::variant::*;
+
+        assert_play_undo::<Atomic>(
+            "rnbqkb1r/pp1ppppp/5n2/2p5/4P3/2N5/PPPP1PPP/R1BQKBNR w KQkq c6 0 3",
+            3,
+        );
+        assert_play_undo::<Atomic>("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", 3);
+        assert_play_undo::<Antichess>("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1", 3);
+        assert_play_undo::<KingOfTheHill>(
+            "r1bqkbnr/pppp1ppp/2n5/4p3/3PP3/8/PPP2PPP/RNBQKBNR w KQkq - 1 3",
+            2,
+        );
+        assert_play_undo::<ThreeCheck>(
+            "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 1+2 2 3",
+            3,
+        );
+        assert_play_undo::<Crazyhouse>("2k1r3/4Q~3/8/8/8/8/8/4K3[Nr] b - - 0 1", 3);
+        assert_play_undo::<Crazyhouse>("r3k3/1P6/8/8/8/8/8/4K3[Qp] w q - 0 1", 3);
+        assert_play_undo::<Crazyhouse>(
+            "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/2N2N2/PPPP1PPP/R1BQK2R[] w KQkq - 4 4",
+            2,
+        );
+        assert_play_undo::<RacingKings>("8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1", 3);
+        assert_play_undo::<Horde>(
+            "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1",
+            2,
+        );
+    }
 }
@@ -159,8 +159,38 @@ macro_rules! try_role_from_int_impl {
 
 try_role_from_int_impl! { u8 i8 u16 i16 u32 i32 u64 i64 u128 i128 usize isize }
 
+/// Serializes as a lowercase letter like `n` in human-readable formats, and
+/// as the discriminant (`1` to `6`) otherwise.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Role {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(self.char().encode_utf8(&mut [0; 4]))
+        } else {
+            serializer.serialize_u8(u8::from(*self))
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Role {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Role, D::Error> {
+        if deserializer.is_human_readable() {
+            crate::util::deserialize_ascii(deserializer, "piece letter", |s| match *s {
+                [ch] => Role::from_char(char::from(ch)),
+                _ => None,
+            })
+        } else {
+            crate::util::deserialize_u8(deserializer, "role discriminant", |n| {
+                Role::try_from(n).ok()
+            })
+        }
+    }
+}
+
 /// Container with values for each [`Role`].
 #[derive(Copy, Clone, Default, Eq, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct ByRole<T> {
     pub pawn: T,
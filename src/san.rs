@@ -54,6 +54,8 @@
 
 use core::{fmt, str::FromStr};
 
+use arrayvec::ArrayVec;
+
 use crate::{
     util::AppendAscii, CastlingSide, File, Move, MoveList, Outcome, Position, Rank, Role, Square,
 };
@@ -449,7 +451,7 @@ impl San {
                 }
                 _ => false,
             },
-            San::Castle(side) => m.castling_side().map_or(false, |s| side == s),
+            San::Castle(side) => m.castling_side() == Some(side),
             San::Put { role, to } => match *m {
                 Move::Put { role: r, to: t } => r == role && to == t,
                 _ => false,
@@ -458,7 +460,7 @@ impl San {
         }
     }
 
-    fn append_to<W: AppendAscii>(&self, f: &mut W) -> Result<(), W::Error> {
+    pub(crate) fn append_to<W: AppendAscii>(&self, f: &mut W) -> Result<(), W::Error> {
         match *self {
             San::Normal {
                 role,
@@ -543,6 +545,20 @@ impl fmt::Display for San {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for San {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for San {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<San, D::Error> {
+        crate::util::deserialize_ascii(deserializer, "SAN", |s| San::from_ascii(s).ok())
+    }
+}
+
 /// Check (`+`) or checkmate (`#`) suffix.
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
 pub enum Suffix {
@@ -644,7 +660,7 @@ impl SanPlus {
         }
     }
 
-    fn append_to<W: AppendAscii>(&self, f: &mut W) -> Result<(), W::Error> {
+    pub(crate) fn append_to<W: AppendAscii>(&self, f: &mut W) -> Result<(), W::Error> {
         self.san.append_to(f)?;
         if let Some(suffix) = self.suffix {
             f.append_ascii(suffix.char())?;
@@ -682,6 +698,289 @@ impl fmt::Display for SanPlus {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for SanPlus {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SanPlus {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<SanPlus, D::Error> {
+        crate::util::deserialize_ascii(deserializer, "SAN", |s| SanPlus::from_ascii(s).ok())
+    }
+}
+
+/// Piece letters used to read and write SAN in a particular language.
+///
+/// The letters of pawns are never written, and pawn drops are written as
+/// `@e4`. Letters must not be confused with the rest of the notation, e.g.,
+/// lowercase file letters.
+///
+/// # Examples
+///
+/// ```
+/// use shakmaty::san::{PieceLetters, San};
+///
+/// let san = San::from_localized("Sf3", &PieceLetters::GERMAN)?;
+/// assert_eq!(san, "Nf3".parse()?);
+///
+/// assert_eq!(san.localized(&PieceLetters::FRENCH).to_string(), "Cf3");
+/// assert_eq!(san.localized(&PieceLetters::FIGURINE).to_string(), "♘f3");
+///
+/// let custom = PieceLetters {
+///     knight: "Kn",
+///     ..PieceLetters::ENGLISH
+/// };
+/// assert_eq!(San::from_localized("Knf3", &custom)?, san);
+/// # Ok::<_, shakmaty::san::ParseSanError>(())
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PieceLetters<'a> {
+    pub knight: &'a str,
+    pub bishop: &'a str,
+    pub rook: &'a str,
+    pub queen: &'a str,
+    pub king: &'a str,
+}
+
+impl PieceLetters<'static> {
+    /// `N`, `B`, `R`, `Q`, `K`.
+    pub const ENGLISH: PieceLetters<'static> = PieceLetters {
+        knight: "N",
+        bishop: "B",
+        rook: "R",
+        queen: "Q",
+        king: "K",
+    };
+
+    /// `S`, `L`, `T`, `D`, `K`.
+    pub const GERMAN: PieceLetters<'static> = PieceLetters {
+        knight: "S",
+        bishop: "L",
+        rook: "T",
+        queen: "D",
+        king: "K",
+    };
+
+    /// `C`, `F`, `T`, `D`, `R`.
+    pub const FRENCH: PieceLetters<'static> = PieceLetters {
+        knight: "C",
+        bishop: "F",
+        rook: "T",
+        queen: "D",
+        king: "R",
+    };
+
+    /// `C`, `A`, `T`, `D`, `R`.
+    pub const SPANISH: PieceLetters<'static> = PieceLetters {
+        knight: "C",
+        bishop: "A",
+        rook: "T",
+        queen: "D",
+        king: "R",
+    };
+
+    /// `P`, `L`, `T`, `D`, `K`.
+    pub const DUTCH: PieceLetters<'static> = PieceLetters {
+        knight: "P",
+        bishop: "L",
+        rook: "T",
+        queen: "D",
+        king: "K",
+    };
+
+    /// `К`, `С`, `Л`, `Ф`, `Кр`.
+    pub const RUSSIAN: PieceLetters<'static> = PieceLetters {
+        knight: "К",
+        bishop: "С",
+        rook: "Л",
+        queen: "Ф",
+        king: "Кр",
+    };
+
+    /// Figurines `♘`, `♗`, `♖`, `♕`, `♔`, for either color. When parsing,
+    /// the black figurines `♞`, `♝`, `♜`, `♛`, `♚` are accepted as well.
+    pub const FIGURINE: PieceLetters<'static> = PieceLetters {
+        knight: "♘",
+        bishop: "♗",
+        rook: "♖",
+        queen: "♕",
+        king: "♔",
+    };
+}
+
+impl<'a> PieceLetters<'a> {
+    /// Gets the letter of the given piece type, or an empty string for
+    /// pawns.
+    pub const fn get(&self, role: Role) -> &'a str {
+        match role {
+            Role::Pawn => "",
+            Role::Knight => self.knight,
+            Role::Bishop => self.bishop,
+            Role::Rook => self.rook,
+            Role::Queen => self.queen,
+            Role::King => self.king,
+        }
+    }
+
+    /// Finds the longest piece letter at the start of `s`.
+    fn strip_prefix<'s>(&self, s: &'s str) -> Option<(Role, &'s str)> {
+        let mut best: Option<(Role, &'s str)> = None;
+        for role in [
+            Role::Knight,
+            Role::Bishop,
+            Role::Rook,
+            Role::Queen,
+            Role::King,
+        ] {
+            let letter = self.get(role);
+            if letter.is_empty() {
+                continue;
+            }
+            if let Some(rest) = s
+                .strip_prefix(letter)
+                .or_else(|| s.strip_prefix(black_figurine(letter)?))
+            {
+                if best.map_or(true, |(_, best_rest)| rest.len() < best_rest.len()) {
+                    best = Some((role, rest));
+                }
+            }
+        }
+        best
+    }
+
+    /// Translates localized notation to English SAN.
+    fn translate(&self, mut s: &str) -> Result<ArrayVec<u8, 16>, ParseSanError> {
+        let mut buf = ArrayVec::new();
+        while let Some(ch) = s.chars().next() {
+            let ascii = if let Some((role, rest)) = self.strip_prefix(s) {
+                s = rest;
+                role.upper_char() as u8
+            } else if ch.is_ascii() && (!ch.is_ascii_uppercase() || ch == 'O') {
+                s = &s[1..];
+                ch as u8
+            } else {
+                return Err(ParseSanError);
+            };
+            buf.try_push(ascii).map_err(|_| ParseSanError)?;
+        }
+        Ok(buf)
+    }
+}
+
+/// Gets the black counterpart of a white figurine, so that figurine
+/// notation can be parsed regardless of the color of the figurines.
+fn black_figurine(letter: &str) -> Option<&'static str> {
+    Some(match letter {
+        "♘" => "♞",
+        "♗" => "♝",
+        "♖" => "♜",
+        "♕" => "♛",
+        "♔" => "♚",
+        _ => return None,
+    })
+}
+
+impl Default for PieceLetters<'_> {
+    fn default() -> Self {
+        PieceLetters::ENGLISH
+    }
+}
+
+/// Writes SAN with [`PieceLetters`] in place of the English piece letters.
+struct LocalizedWriter<'w, 'a, W> {
+    inner: &'w mut W,
+    letters: &'w PieceLetters<'a>,
+}
+
+impl<W: fmt::Write> AppendAscii for LocalizedWriter<'_, '_, W> {
+    type Error = fmt::Error;
+
+    fn reserve(&mut self, _additional: usize) {}
+
+    fn append_ascii(&mut self, ascii_char: char) -> fmt::Result {
+        match Role::from_char(ascii_char) {
+            Some(role) if ascii_char.is_ascii_uppercase() => {
+                self.inner.write_str(self.letters.get(role))
+            }
+            _ => self.inner.write_char(ascii_char),
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    fn append_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_str(s)
+    }
+}
+
+/// [`San`] or [`SanPlus`] displayed with localized [`PieceLetters`].
+/// See [`San::localized()`] and [`SanPlus::localized()`].
+#[derive(Debug, Clone)]
+pub struct Localized<'a, T> {
+    inner: &'a T,
+    letters: &'a PieceLetters<'a>,
+}
+
+impl fmt::Display for Localized<'_, San> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.append_to(&mut LocalizedWriter {
+            inner: f,
+            letters: self.letters,
+        })
+    }
+}
+
+impl fmt::Display for Localized<'_, SanPlus> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.append_to(&mut LocalizedWriter {
+            inner: f,
+            letters: self.letters,
+        })
+    }
+}
+
+impl San {
+    /// Parses a SAN with localized piece letters. Ignores a possible check or
+    /// checkmate suffix.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseSanError`] if `san` is not syntactically valid.
+    pub fn from_localized(san: &str, letters: &PieceLetters<'_>) -> Result<San, ParseSanError> {
+        San::from_ascii(&letters.translate(san)?)
+    }
+
+    /// Displays the SAN with localized piece letters.
+    pub fn localized<'a>(&'a self, letters: &'a PieceLetters<'a>) -> Localized<'a, San> {
+        Localized {
+            inner: self,
+            letters,
+        }
+    }
+}
+
+impl SanPlus {
+    /// Parses a SAN with localized piece letters and possible check and
+    /// checkmate suffix.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseSanError`] if `san` is not syntactically valid.
+    pub fn from_localized(san: &str, letters: &PieceLetters<'_>) -> Result<SanPlus, ParseSanError> {
+        SanPlus::from_ascii(&letters.translate(san)?)
+    }
+
+    /// Displays the SAN with localized piece letters.
+    pub fn localized<'a>(&'a self, letters: &'a PieceLetters<'a>) -> Localized<'a, SanPlus> {
+        Localized {
+            inner: self,
+            letters,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[cfg(feature = "alloc")]
@@ -782,4 +1081,34 @@ mod tests {
         );
         assert_eq!(san.to_string(), "6h8");
     }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_localized() {
+        for (letters, localized, english) in [
+            (PieceLetters::GERMAN, "Sxe5+", "Nxe5+"),
+            (PieceLetters::GERMAN, "e8=D#", "e8=Q#"),
+            (PieceLetters::FRENCH, "Rxf2", "Kxf2"),
+            (PieceLetters::SPANISH, "Aab5", "Bab5"),
+            (PieceLetters::DUTCH, "Pd7", "Nd7"),
+            (PieceLetters::RUSSIAN, "Крe2", "Ke2"),
+            (PieceLetters::RUSSIAN, "Кc3", "Nc3"),
+            (PieceLetters::RUSSIAN, "Ф@h7", "Q@h7"),
+            (PieceLetters::FIGURINE, "♖a1a8", "Ra1a8"),
+            (PieceLetters::GERMAN, "O-O-O", "O-O-O"),
+            (PieceLetters::GERMAN, "@e4", "@e4"),
+        ] {
+            let san = SanPlus::from_localized(localized, &letters).expect("valid san");
+            assert_eq!(san.to_string(), english);
+            assert_eq!(san.localized(&letters).to_string(), localized);
+        }
+
+        for (black, english) in [("♞f6", "Nf6"), ("♚xe2+", "Kxe2+"), ("e1=♛#", "e1=Q#")] {
+            let san = SanPlus::from_localized(black, &PieceLetters::FIGURINE).expect("valid san");
+            assert_eq!(san.to_string(), english);
+        }
+
+        assert!(San::from_localized("Nf3", &PieceLetters::GERMAN).is_err());
+        assert!(San::from_localized("e8=Q", &PieceLetters::FRENCH).is_err());
+    }
 }
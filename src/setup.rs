@@ -1,8 +1,10 @@
 use core::{convert::identity, num::NonZeroU32};
 
 use crate::{
-    attacks, Bitboard, Board, ByCastlingSide, ByColor, ByRole, CastlingMode, CastlingSide, Color,
-    File, FromSetup, PositionError, Rank, RemainingChecks, Square,
+    attacks,
+    board::{parse_diagram, Diagram, ParseDiagramError},
+    Bitboard, Board, ByCastlingSide, ByColor, ByRole, CastlingMode, CastlingSide, Color, File,
+    FromSetup, PositionError, Rank, RemainingChecks, Square,
 };
 
 /// A not necessarily legal position.
@@ -110,6 +112,52 @@ impl Setup {
         }
     }
 
+    /// [Chess960](crate::chess960) starting position `n`, with all castling
+    /// rights. Returns `None` if `n >= 960`.
+    ///
+    /// Use [`CastlingMode::Chess960`] to set up positions, unless `n` is
+    /// [`chess960::STANDARD`](crate::chess960::STANDARD).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use shakmaty::{CastlingMode, Chess, Position, Setup, chess960};
+    ///
+    /// let n = chess960::random_number(42);
+    /// let setup = Setup::chess960(n).expect("valid number");
+    /// let pos: Chess = setup.position(CastlingMode::Chess960)?;
+    /// assert_eq!(pos.board().chess960_number(), Some(n));
+    /// # Ok::<_, shakmaty::PositionError<_>>(())
+    /// ```
+    ///
+    /// Variants that allow Chess960 starting positions work the same way:
+    ///
+    /// ```
+    /// # #[cfg(feature = "variant")] {
+    /// use shakmaty::{CastlingMode, Position, Setup, variant::{Variant, VariantPosition}};
+    ///
+    /// let setup = Setup::chess960(0).expect("valid number");
+    /// let pos = VariantPosition::from_setup(Variant::Crazyhouse, setup, CastlingMode::Chess960)
+    ///     .expect("legal position");
+    /// assert!(pos.pockets().is_some());
+    /// # }
+    /// ```
+    pub fn chess960(n: u32) -> Option<Setup> {
+        Setup::double_chess960(ByColor { white: n, black: n })
+    }
+
+    /// Double Fischer Random starting position, where each side has an
+    /// independently numbered [Chess960](crate::chess960) back rank, with
+    /// all castling rights. Returns `None` if any number is `>= 960`.
+    pub fn double_chess960(numbers: ByColor<u32>) -> Option<Setup> {
+        let board = Board::double_chess960(numbers)?;
+        Some(Setup {
+            castling_rights: board.rooks() & Bitboard::BACKRANKS,
+            board,
+            ..Setup::empty()
+        })
+    }
+
     /// Swap turns and discard en passant rights. This is sometimes called
     /// "playing a null move".
     pub fn swap_turn(&mut self) {
@@ -148,6 +196,31 @@ impl Setup {
     pub fn position<P: FromSetup>(self, mode: CastlingMode) -> Result<P, PositionError<P>> {
         P::from_setup(self, mode)
     }
+
+    /// Renders the board as a text diagram, followed by the side to move.
+    /// See [`Diagram`] for options.
+    pub const fn diagram(&self) -> Diagram<'_> {
+        self.board.diagram().turn(Some(self.turn))
+    }
+
+    /// Parses a text diagram, as rendered by [`Setup::diagram()`]. See
+    /// [`Board::from_diagram()`] for the format.
+    ///
+    /// Uses the side to move from a final `white to move` or
+    /// `black to move` line, defaulting to white. The setup has no castling
+    /// rights and no en passant square.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseDiagramError`] if the diagram is invalid.
+    pub fn from_diagram(diagram: &str) -> Result<Setup, ParseDiagramError> {
+        let (board, turn) = parse_diagram(diagram)?;
+        Ok(Setup {
+            board,
+            turn: turn.unwrap_or(Color::White),
+            ..Setup::empty()
+        })
+    }
 }
 
 impl Default for Setup {
@@ -156,12 +229,73 @@ impl Default for Setup {
     }
 }
 
+/// Serializes as a [FEN](crate::fen::Fen) in human-readable formats, and as a
+/// [`PackedSetup`](crate::packed::PackedSetup) otherwise.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Setup {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(&crate::fen::Fen::from_setup(self.clone()))
+        } else {
+            serializer.serialize_bytes(crate::packed::PackedSetup::from_setup(self).as_bytes())
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Setup {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Setup, D::Error> {
+        use core::fmt;
+
+        use serde::de;
+
+        use crate::packed::PackedSetup;
+
+        struct PackedSetupVisitor;
+
+        impl<'de> de::Visitor<'de> for PackedSetupVisitor {
+            type Value = Setup;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("packed setup")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Setup, E> {
+                PackedSetup::from_bytes(v)
+                    .map(|packed| packed.to_setup())
+                    .map_err(|_| E::invalid_value(de::Unexpected::Bytes(v), &self))
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Setup, A::Error> {
+                let mut bytes = arrayvec::ArrayVec::<u8, { PackedSetup::MAX_LEN }>::new();
+                while let Some(byte) = seq.next_element()? {
+                    bytes
+                        .try_push(byte)
+                        .map_err(|_| de::Error::invalid_length(bytes.len() + 1, &self))?;
+                }
+                self.visit_bytes(&bytes)
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            crate::util::deserialize_ascii(deserializer, "FEN", |s| {
+                crate::fen::Fen::from_ascii(s)
+                    .ok()
+                    .map(crate::fen::Fen::into_setup)
+            })
+        } else {
+            deserializer.deserialize_bytes(PackedSetupVisitor)
+        }
+    }
+}
+
 /// Castling paths and unmoved rooks.
 #[derive(Clone, Debug)]
 pub struct Castles {
     mask: Bitboard,
     rook: ByColor<ByCastlingSide<Option<Square>>>,
     path: ByColor<ByCastlingSide<Bitboard>>,
+    king: ByColor<Option<Square>>,
     mode: CastlingMode,
 }
 
@@ -190,6 +324,10 @@ impl Castles {
                     queen_side: Bitboard(0x0000_0000_0000_000e),
                 },
             },
+            king: ByColor {
+                black: Some(Square::E8),
+                white: Some(Square::E1),
+            },
         }
     }
 }
@@ -233,6 +371,10 @@ impl Castles {
                     queen_side: Bitboard::EMPTY,
                 },
             },
+            king: ByColor {
+                black: None,
+                white: None,
+            },
         }
     }
 
@@ -254,6 +396,7 @@ impl Castles {
                     let chess960 = king.file() != File::E || a_side.file() != File::A;
                     if !chess960 || mode.is_chess960() {
                         castles.mask.add(a_side);
+                        *castles.king.get_mut(color) = Some(king);
                         castles.rook.get_mut(color).queen_side = Some(a_side);
                         castles.path.get_mut(color).queen_side =
                             (attacks::between(a_side, rook_to).with(rook_to)
@@ -269,6 +412,7 @@ impl Castles {
                     let chess960 = king.file() != File::E || h_side.file() != File::H;
                     if !chess960 || mode.is_chess960() {
                         castles.mask.add(h_side);
+                        *castles.king.get_mut(color) = Some(king);
                         castles.rook.get_mut(color).king_side = Some(h_side);
                         castles.path.get_mut(color).king_side = (attacks::between(h_side, rook_to)
                             .with(rook_to)
@@ -331,6 +475,18 @@ impl Castles {
         };
     }
 
+    /// Gets the unmoved rooks by color and side. This is the only part that
+    /// changes when playing moves.
+    pub(crate) const fn rooks(&self) -> ByColor<ByCastlingSide<Option<Square>>> {
+        self.rook
+    }
+
+    /// Restores unmoved rooks previously obtained from [`Castles::rooks()`].
+    pub(crate) fn restore_rooks(&mut self, rooks: ByColor<ByCastlingSide<Option<Square>>>) {
+        self.rook = rooks;
+        self.mask = rooks.into_iter().flatten().flatten().collect();
+    }
+
     #[inline]
     pub const fn rook(&self, color: Color, side: CastlingSide) -> Option<Square> {
         *self.rook.get(color).get(side)
@@ -374,6 +530,59 @@ impl Castles {
     }
 }
 
+/// Serialized form of [`Castles`]. The castling paths are not included,
+/// but recomputed from the king and rook squares.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "Castles")]
+struct SerdeCastles {
+    mode: CastlingMode,
+    rooks: Bitboard,
+    kings: ByColor<Option<Square>>,
+}
+
+/// Serializes the castling mode, the unmoved rooks (as a [`Bitboard`]),
+/// and the king squares of players that still have castling rights.
+/// Deserializing validates the castling rights like
+/// [`Castles::from_setup()`].
+#[cfg(feature = "serde")]
+impl serde::Serialize for Castles {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(
+            &SerdeCastles {
+                mode: self.mode,
+                rooks: self.mask,
+                kings: ByColor::new_with(|color| {
+                    self.king.get(color).filter(|_| self.has_color(color))
+                }),
+            },
+            serializer,
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Castles {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Castles, D::Error> {
+        let castles: SerdeCastles = serde::Deserialize::deserialize(deserializer)?;
+
+        let mut setup = Setup::empty();
+        for rook in castles.rooks {
+            let color = Color::from_white(rook.rank() == Rank::First);
+            setup.board.set_piece_at(rook, color.rook());
+        }
+        for color in Color::ALL {
+            if let Some(king) = *castles.kings.get(color) {
+                setup.board.set_piece_at(king, color.king());
+            }
+        }
+        setup.castling_rights = castles.rooks;
+
+        Castles::from_setup(&setup, castles.mode)
+            .map_err(|_| serde::de::Error::custom("invalid castling rights"))
+    }
+}
+
 /// En passant square on the third or sixth rank.
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct EnPassant(pub Square);
@@ -426,3 +635,129 @@ impl EnPassant {
         self.0.xor(Square::A2)
     }
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_castles_serde() {
+        use serde_test::{assert_de_tokens_error, assert_tokens, Configure as _, Token};
+
+        #[derive(Debug, serde::Serialize, serde::Deserialize)]
+        #[serde(transparent)]
+        struct Wrapper(Castles);
+
+        impl PartialEq for Wrapper {
+            fn eq(&self, other: &Wrapper) -> bool {
+                // Paths are only relevant while the castling right remains.
+                self.0.mask == other.0.mask
+                    && self.0.rook == other.0.rook
+                    && self.0.mode == other.0.mode
+                    && Color::ALL.into_iter().all(|color| {
+                        CastlingSide::ALL.into_iter().all(|side| {
+                            !self.0.has(color, side)
+                                || self.0.path(color, side) == other.0.path(color, side)
+                        })
+                    })
+            }
+        }
+
+        let mut castles = Castles::default();
+        castles.discard_color(Color::Black);
+        castles.discard_rook(Square::A1);
+        assert_tokens(
+            &Wrapper(castles).readable(),
+            &[
+                Token::Struct {
+                    name: "Castles",
+                    len: 3,
+                },
+                Token::Str("mode"),
+                Token::UnitVariant {
+                    name: "CastlingMode",
+                    variant: "Standard",
+                },
+                Token::Str("rooks"),
+                Token::U64(0x80),
+                Token::Str("kings"),
+                Token::Struct {
+                    name: "ByColor",
+                    len: 2,
+                },
+                Token::Str("black"),
+                Token::None,
+                Token::Str("white"),
+                Token::Some,
+                Token::Str("e1"),
+                Token::StructEnd,
+                Token::StructEnd,
+            ],
+        );
+
+        let mut setup = Setup::empty();
+        setup.board = Board::from_ascii_board_fen(b"nrkbqrbn/pppppppp/8/8/8/8/PPPPPPPP/NRKBQRBN")
+            .expect("valid board");
+        setup.castling_rights = Bitboard::from(Square::B1) | Square::F1 | Square::B8 | Square::F8;
+        let castles = Castles::from_setup(&setup, CastlingMode::Chess960).expect("valid castles");
+        assert_tokens(
+            &Wrapper(castles).compact(),
+            &[
+                Token::Struct {
+                    name: "Castles",
+                    len: 3,
+                },
+                Token::Str("mode"),
+                Token::UnitVariant {
+                    name: "CastlingMode",
+                    variant: "Chess960",
+                },
+                Token::Str("rooks"),
+                Token::U64(0x2200_0000_0000_0022),
+                Token::Str("kings"),
+                Token::Struct {
+                    name: "ByColor",
+                    len: 2,
+                },
+                Token::Str("black"),
+                Token::Some,
+                Token::U8(58),
+                Token::Str("white"),
+                Token::Some,
+                Token::U8(2),
+                Token::StructEnd,
+                Token::StructEnd,
+            ],
+        );
+
+        // Standard castling with the king on the wrong square.
+        assert_de_tokens_error::<serde_test::Readable<Wrapper>>(
+            &[
+                Token::Struct {
+                    name: "Castles",
+                    len: 3,
+                },
+                Token::Str("mode"),
+                Token::UnitVariant {
+                    name: "CastlingMode",
+                    variant: "Standard",
+                },
+                Token::Str("rooks"),
+                Token::U64(0x01),
+                Token::Str("kings"),
+                Token::Struct {
+                    name: "ByColor",
+                    len: 2,
+                },
+                Token::Str("black"),
+                Token::None,
+                Token::Str("white"),
+                Token::Some,
+                Token::Str("d1"),
+                Token::StructEnd,
+                Token::StructEnd,
+            ],
+            "invalid castling rights",
+        );
+    }
+}
@@ -674,6 +674,32 @@ impl fmt::Display for Square {
     }
 }
 
+/// Serializes as a square name like `e4` in human-readable formats, and
+/// as the square index otherwise.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Square {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u8(u8::from(*self))
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Square {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Square, D::Error> {
+        if deserializer.is_human_readable() {
+            crate::util::deserialize_ascii(deserializer, "square name", |s| {
+                Square::from_ascii(s).ok()
+            })
+        } else {
+            crate::util::deserialize_u8(deserializer, "square index", |n| Square::try_from(n).ok())
+        }
+    }
+}
+
 impl fmt::Debug for Square {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_char(self.file().upper_char())?;
@@ -705,4 +731,21 @@ mod tests {
         Square::H1.hash(&mut hasher);
         assert_eq!(hasher.finish(), 7);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        use serde_test::{assert_de_tokens_error, assert_tokens, Configure as _, Token};
+
+        assert_tokens(&Square::E4.readable(), &[Token::Str("e4")]);
+        assert_tokens(&Square::E4.compact(), &[Token::U8(28)]);
+        assert_de_tokens_error::<serde_test::Readable<Square>>(
+            &[Token::Str("e9")],
+            "invalid value: string \"e9\", expected square name",
+        );
+        assert_de_tokens_error::<serde_test::Compact<Square>>(
+            &[Token::U8(64)],
+            "invalid value: integer `64`, expected square index",
+        );
+    }
 }
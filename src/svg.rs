@@ -0,0 +1,520 @@
+//! Render boards and positions as SVG images.
+//!
+//! Pieces are drawn as Unicode chess symbols, so viewers need a font that
+//! covers them (most system fonts do).
+//!
+//! # Examples
+//!
+//! ```
+//! use shakmaty::{Chess, Position, Square, san::San, svg::{Arrow, Brush, Svg}};
+//!
+//! let mut pos = Chess::default();
+//! let m = "e4".parse::<San>()?.to_move(&pos)?;
+//! pos.play_unchecked(&m);
+//!
+//! let arrows = [Arrow::new(Square::G8, Square::F6, Brush::Blue)];
+//! let svg = Svg::from_position(&pos)
+//!     .coordinates(true)
+//!     .last_move(Some(&m))
+//!     .arrows(&arrows)
+//!     .to_string();
+//!
+//! assert!(svg.starts_with("<svg"));
+//! # #[derive(Debug)] struct CommonError;
+//! # impl From<shakmaty::san::ParseSanError> for CommonError { fn from(_: shakmaty::san::ParseSanError) -> Self { Self } }
+//! # impl From<shakmaty::san::SanError> for CommonError { fn from(_: shakmaty::san::SanError) -> Self { Self } }
+//! # Ok::<_, CommonError>(())
+//! ```
+
+use core::fmt;
+
+use crate::{
+    Board, ByColor, ByRole, Color, File, Move, Piece, Position, Rank, RemainingChecks, Role, Setup,
+    Square,
+};
+
+const SQUARE_SIZE: u32 = 45;
+const MARGIN: u32 = 20;
+
+/// Color of an [`Arrow`] or [`Circle`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Brush {
+    #[default]
+    Green,
+    Red,
+    Blue,
+    Yellow,
+}
+
+impl Brush {
+    const fn color(self) -> &'static str {
+        match self {
+            Brush::Green => "#15781b",
+            Brush::Red => "#882020",
+            Brush::Blue => "#003088",
+            Brush::Yellow => "#e68f00",
+        }
+    }
+}
+
+/// An arrow from one square to another.
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Arrow {
+    pub tail: Square,
+    pub head: Square,
+    pub brush: Brush,
+}
+
+impl Arrow {
+    pub const fn new(tail: Square, head: Square, brush: Brush) -> Arrow {
+        Arrow { tail, head, brush }
+    }
+}
+
+/// A circle around a square.
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Circle {
+    pub square: Square,
+    pub brush: Brush,
+}
+
+impl Circle {
+    pub const fn new(square: Square, brush: Brush) -> Circle {
+        Circle { square, brush }
+    }
+}
+
+/// A configurable SVG image of a board. Implements [`Display`](fmt::Display)
+/// to write the SVG document.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct Svg<'a> {
+    board: &'a Board,
+    orientation: Color,
+    coordinates: bool,
+    last_move: Option<(Option<Square>, Square)>,
+    check: Option<Square>,
+    arrows: &'a [Arrow],
+    circles: &'a [Circle],
+    pockets: Option<&'a ByColor<ByRole<u8>>>,
+    remaining_checks: Option<&'a ByColor<RemainingChecks>>,
+    size: Option<u32>,
+}
+
+impl<'a> Svg<'a> {
+    /// Renders a plain board from white's point of view.
+    pub const fn new(board: &'a Board) -> Svg<'a> {
+        Svg {
+            board,
+            orientation: Color::White,
+            coordinates: false,
+            last_move: None,
+            check: None,
+            arrows: &[],
+            circles: &[],
+            pockets: None,
+            remaining_checks: None,
+            size: None,
+        }
+    }
+
+    /// Renders the board of a setup, including pockets and remaining checks,
+    /// if any.
+    pub const fn from_setup(setup: &'a Setup) -> Svg<'a> {
+        Svg {
+            pockets: setup.pockets.as_ref(),
+            remaining_checks: setup.remaining_checks.as_ref(),
+            ..Svg::new(&setup.board)
+        }
+    }
+
+    /// Renders the board of a position, including pockets and remaining
+    /// checks, if any. Highlights the king of the side to move, if it is in
+    /// check.
+    pub fn from_position<P: Position>(pos: &'a P) -> Svg<'a> {
+        Svg {
+            pockets: pos.pockets(),
+            remaining_checks: pos.remaining_checks(),
+            check: if pos.checkers().any() {
+                pos.board().king_of(pos.turn())
+            } else {
+                None
+            },
+            ..Svg::new(pos.board())
+        }
+    }
+
+    /// Sets the side that is at the bottom of the image.
+    pub const fn orientation(mut self, orientation: Color) -> Svg<'a> {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Enables or disables rank and file labels.
+    pub const fn coordinates(mut self, coordinates: bool) -> Svg<'a> {
+        self.coordinates = coordinates;
+        self
+    }
+
+    /// Highlights the origin and target square of a move. For castling
+    /// moves, these are the squares of the king.
+    pub fn last_move(mut self, m: Option<&Move>) -> Svg<'a> {
+        self.last_move = m.map(|m| match *m {
+            Move::Castle { king, .. } => (
+                Some(king),
+                m.castling_side()
+                    .expect("castling move")
+                    .king_to(Color::from_white(king.rank() == Rank::First)),
+            ),
+            _ => (m.from(), m.to()),
+        });
+        self
+    }
+
+    /// Highlights a king in check, or `None`.
+    pub const fn check(mut self, check: Option<Square>) -> Svg<'a> {
+        self.check = check;
+        self
+    }
+
+    /// Sets arrows to draw on top of the pieces.
+    pub const fn arrows(mut self, arrows: &'a [Arrow]) -> Svg<'a> {
+        self.arrows = arrows;
+        self
+    }
+
+    /// Sets circles to draw on top of the pieces.
+    pub const fn circles(mut self, circles: &'a [Circle]) -> Svg<'a> {
+        self.circles = circles;
+        self
+    }
+
+    /// Sets the Crazyhouse pockets to draw above and below the board, or
+    /// `None`.
+    pub const fn pockets(mut self, pockets: Option<&'a ByColor<ByRole<u8>>>) -> Svg<'a> {
+        self.pockets = pockets;
+        self
+    }
+
+    /// Sets the Three-Check counters to draw above and below the board, or
+    /// `None`.
+    pub const fn remaining_checks(
+        mut self,
+        remaining_checks: Option<&'a ByColor<RemainingChecks>>,
+    ) -> Svg<'a> {
+        self.remaining_checks = remaining_checks;
+        self
+    }
+
+    /// Sets the width of the image in pixels. The height is scaled
+    /// accordingly. Defaults to the natural size of 45 pixels per square.
+    pub const fn size(mut self, size: Option<u32>) -> Svg<'a> {
+        self.size = size;
+        self
+    }
+
+    const fn margin(&self) -> u32 {
+        if self.coordinates {
+            MARGIN
+        } else {
+            0
+        }
+    }
+
+    const fn band(&self) -> u32 {
+        if self.pockets.is_some() || self.remaining_checks.is_some() {
+            SQUARE_SIZE
+        } else {
+            0
+        }
+    }
+
+    /// Top left corner of a square.
+    fn corner(&self, square: Square) -> (u32, u32) {
+        let (col, row) = match self.orientation {
+            Color::White => (u32::from(square.file()), 7 - u32::from(square.rank())),
+            Color::Black => (7 - u32::from(square.file()), u32::from(square.rank())),
+        };
+        (
+            self.margin() + col * SQUARE_SIZE,
+            self.band() + self.margin() + row * SQUARE_SIZE,
+        )
+    }
+
+    fn center(&self, square: Square) -> (f32, f32) {
+        let (x, y) = self.corner(square);
+        (
+            (x + SQUARE_SIZE / 2) as f32 + 0.5,
+            (y + SQUARE_SIZE / 2) as f32 + 0.5,
+        )
+    }
+
+    fn write_piece(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        piece: Piece,
+        (x, y): (u32, u32),
+        size: u32,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "<text x=\"{}\" y=\"{}\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\" stroke=\"#000\" stroke-width=\"1.2\">{}\u{fe0e}</text>",
+            x + SQUARE_SIZE / 2,
+            y + SQUARE_SIZE / 2,
+            size,
+            piece.color.fold_wb("#fff", "#000"),
+            match piece.role {
+                Role::Pawn => '♟',
+                Role::Knight => '♞',
+                Role::Bishop => '♝',
+                Role::Rook => '♜',
+                Role::Queen => '♛',
+                Role::King => '♚',
+            }
+        )
+    }
+
+    fn write_band(&self, f: &mut fmt::Formatter<'_>, color: Color, y: u32) -> fmt::Result {
+        if let Some(pockets) = self.pockets {
+            let pocket = pockets.get(color);
+            for (i, role) in [
+                Role::Pawn,
+                Role::Knight,
+                Role::Bishop,
+                Role::Rook,
+                Role::Queen,
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                let x = self.margin() + i as u32 * SQUARE_SIZE;
+                let count = *pocket.get(role);
+                if count > 0 {
+                    self.write_piece(f, role.of(color), (x, y), SQUARE_SIZE * 2 / 3)?;
+                }
+                write!(
+                    f,
+                    "<text x=\"{}\" y=\"{}\" font-size=\"12\" font-family=\"sans-serif\" text-anchor=\"end\" fill=\"{}\">{}</text>",
+                    x + SQUARE_SIZE - 2,
+                    y + SQUARE_SIZE - 3,
+                    if count > 0 { "#000" } else { "#888" },
+                    count
+                )?;
+            }
+        }
+        if let Some(remaining_checks) = self.remaining_checks {
+            write!(
+                f,
+                "<text x=\"{}\" y=\"{}\" font-size=\"16\" font-family=\"sans-serif\" text-anchor=\"end\" dominant-baseline=\"central\" fill=\"#000\">checks left: {}</text>",
+                self.margin() + 8 * SQUARE_SIZE,
+                y + SQUARE_SIZE / 2,
+                u32::from(*remaining_checks.get(color))
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn sqrt(x: f32) -> f32 {
+    // Not available in core. Arrows are at most a few hundred pixels long,
+    // so a few Newton iterations are plenty.
+    let mut root = x.max(1.0);
+    for _ in 0..16 {
+        root = (root + x / root) / 2.0;
+    }
+    root
+}
+
+impl fmt::Display for Svg<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let margin = self.margin();
+        let band = self.band();
+        let width = 2 * margin + 8 * SQUARE_SIZE;
+        let height = 2 * band + 2 * margin + 8 * SQUARE_SIZE;
+
+        write!(
+            f,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" viewBox=\"0 0 {width} {height}\" width=\"{}\" height=\"{}\">",
+            self.size.unwrap_or(width),
+            self.size.map_or(height, |size| size * height / width),
+        )?;
+
+        // Definitions.
+        f.write_str("<defs><radialGradient id=\"check\"><stop offset=\"0%\" stop-color=\"#f00\" stop-opacity=\"1\"/><stop offset=\"50%\" stop-color=\"#e70000\" stop-opacity=\"1\"/><stop offset=\"100%\" stop-color=\"#9e0000\" stop-opacity=\"0\"/></radialGradient></defs>")?;
+
+        // Coordinates.
+        if self.coordinates {
+            write!(
+                f,
+                "<rect x=\"0\" y=\"{band}\" width=\"{width}\" height=\"{}\" fill=\"#212121\"/>",
+                2 * margin + 8 * SQUARE_SIZE
+            )?;
+            for file in File::ALL {
+                let (x, _) = self.corner(Square::from_coords(file, Rank::First));
+                for y in [band + margin / 2, band + margin * 3 / 2 + 8 * SQUARE_SIZE] {
+                    write!(
+                        f,
+                        "<text x=\"{}\" y=\"{y}\" font-size=\"14\" font-family=\"sans-serif\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"#e5e5e5\">{}</text>",
+                        x + SQUARE_SIZE / 2,
+                        file.char()
+                    )?;
+                }
+            }
+            for rank in Rank::ALL {
+                let (_, y) = self.corner(Square::from_coords(File::A, rank));
+                for x in [margin / 2, margin * 3 / 2 + 8 * SQUARE_SIZE] {
+                    write!(
+                        f,
+                        "<text x=\"{x}\" y=\"{}\" font-size=\"14\" font-family=\"sans-serif\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"#e5e5e5\">{}</text>",
+                        y + SQUARE_SIZE / 2,
+                        rank.char()
+                    )?;
+                }
+            }
+        }
+
+        // Squares.
+        for square in Square::ALL {
+            let (x, y) = self.corner(square);
+            let highlighted = self
+                .last_move
+                .is_some_and(|(from, to)| from == Some(square) || to == square);
+            write!(
+                f,
+                "<rect x=\"{x}\" y=\"{y}\" width=\"{SQUARE_SIZE}\" height=\"{SQUARE_SIZE}\" fill=\"{}\"/>",
+                match (square.is_light(), highlighted) {
+                    (true, false) => "#f0d9b5",
+                    (false, false) => "#b58863",
+                    (true, true) => "#cdd16a",
+                    (false, true) => "#aaa23b",
+                }
+            )?;
+            if self.check == Some(square) {
+                write!(
+                    f,
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"{SQUARE_SIZE}\" height=\"{SQUARE_SIZE}\" fill=\"url(#check)\"/>",
+                )?;
+            }
+        }
+
+        // Pieces.
+        for (square, piece) in self.board.clone() {
+            self.write_piece(f, piece, self.corner(square), SQUARE_SIZE * 4 / 5)?;
+        }
+
+        // Pockets and remaining checks.
+        if band > 0 {
+            self.write_band(f, !self.orientation, 0)?;
+            self.write_band(f, self.orientation, height - band)?;
+        }
+
+        // Circles and arrows.
+        for circle in self.circles {
+            let (x, y) = self.center(circle.square);
+            write!(
+                f,
+                "<circle cx=\"{x}\" cy=\"{y}\" r=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" opacity=\"0.8\"/>",
+                SQUARE_SIZE as f32 / 2.0 - 2.5,
+                circle.brush.color(),
+                SQUARE_SIZE / 15,
+            )?;
+        }
+        for arrow in self.arrows {
+            let (x0, y0) = self.center(arrow.tail);
+            let (x1, y1) = self.center(arrow.head);
+            let (dx, dy) = (x1 - x0, y1 - y0);
+            let len = sqrt(dx * dx + dy * dy);
+            if len < 1.0 {
+                continue;
+            }
+            let (ux, uy) = (dx / len, dy / len);
+            let head_len = SQUARE_SIZE as f32 * 0.45;
+            let head_width = SQUARE_SIZE as f32 * 0.5;
+            let (bx, by) = (x1 - ux * head_len, y1 - uy * head_len);
+            let (px, py) = (-uy * head_width / 2.0, ux * head_width / 2.0);
+            write!(
+                f,
+                "<g fill=\"{color}\" stroke=\"{color}\" opacity=\"0.8\"><line x1=\"{x0:.1}\" y1=\"{y0:.1}\" x2=\"{bx:.1}\" y2=\"{by:.1}\" stroke-width=\"{}\" stroke-linecap=\"butt\"/><polygon points=\"{x1:.1},{y1:.1} {:.1},{:.1} {:.1},{:.1}\" stroke=\"none\"/></g>",
+                SQUARE_SIZE as f32 * 0.2,
+                bx + px,
+                by + py,
+                bx - px,
+                by - py,
+                color = arrow.brush.color(),
+            )?;
+        }
+
+        f.write_str("</svg>")
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use alloc::string::ToString as _;
+
+    use super::*;
+    use crate::{fen::Fen, CastlingMode, Chess, EnPassantMode};
+
+    #[test]
+    fn test_svg() {
+        let pos: Chess = "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"
+            .parse::<Fen>()
+            .expect("valid fen")
+            .into_position(CastlingMode::Standard)
+            .expect("legal fen");
+
+        let svg = Svg::from_position(&pos).to_string();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<text").count(), 32);
+        assert_eq!(svg.matches("url(#check)").count(), 1);
+        assert!(svg.contains("viewBox=\"0 0 360 360\""));
+
+        let circles = [Circle::new(Square::E1, Brush::Red)];
+        let arrows = [
+            Arrow::new(Square::H4, Square::E1, Brush::Red),
+            Arrow::new(Square::G1, Square::F3, Brush::Green),
+        ];
+        let svg = Svg::from_position(&pos)
+            .orientation(Color::Black)
+            .coordinates(true)
+            .circles(&circles)
+            .arrows(&arrows)
+            .size(Some(200))
+            .to_string();
+        assert_eq!(svg.matches("<text").count(), 32 + 4 * 8);
+        assert_eq!(svg.matches("<circle").count(), 1);
+        assert_eq!(svg.matches("<polygon").count(), 2);
+        assert!(svg.contains("viewBox=\"0 0 400 400\" width=\"200\" height=\"200\""));
+
+        let setup = Setup {
+            pockets: Some(ByColor {
+                white: ByRole {
+                    knight: 2,
+                    ..ByRole::default()
+                },
+                black: ByRole::default(),
+            }),
+            remaining_checks: Some(ByColor::default()),
+            ..pos.into_setup(EnPassantMode::Legal)
+        };
+        let svg = Svg::from_setup(&setup).to_string();
+        assert!(svg.contains("viewBox=\"0 0 360 450\""));
+        assert!(svg.contains("checks left: 3"));
+        assert_eq!(svg.matches("url(#check)").count(), 0);
+    }
+
+    #[test]
+    fn test_sqrt() {
+        for x in [0.0, 1.0, 2.0, 45.0, 100_000.0] {
+            let root = sqrt(x);
+            let error = root * root - x;
+            assert!(
+                -0.01 * x.max(1.0) < error && error < 0.01 * x.max(1.0),
+                "{x}"
+            );
+        }
+    }
+}
@@ -0,0 +1,2615 @@
+//! Probe Syzygy endgame tablebases.
+//!
+//! Syzygy tables store win/draw/loss information ([`Wdl`]) and the distance
+//! to the next zeroing move ([`Dtz`]) for positions with few pieces. Tables
+//! are looked up by the material on the board (e.g. `KQvK`) and read through
+//! a [`Filesystem`], which defaults to the local file system but can be
+//! replaced, for example to serve tables from memory.
+//!
+//! Supported variants are [`Chess`] (`.rtbw` and `.rtbz` files) and, with the
+//! `variant` feature, `Atomic` (`.atbw` and `.atbz` files) and `Antichess`
+//! (`.gtbw` and `.gtbz` files for pawnless tables, `.stbw` and `.stbz` files
+//! otherwise).
+//!
+//! Positions with castling rights are never covered by the tables. The
+//! half-move clock is taken into account by [`Tablebase::probe_wdl()`] and
+//! [`Tablebase::best_moves()`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use shakmaty::{fen::Fen, CastlingMode, Chess, syzygy::{Tablebase, Wdl}};
+//!
+//! let mut tables = Tablebase::new();
+//! tables.add_directory("tables/chess")?;
+//!
+//! let pos: Chess = "8/8/8/8/B7/N7/K2k4/8 b - - 0 1"
+//!     .parse::<Fen>()?
+//!     .into_position(CastlingMode::Standard)?;
+//!
+//! assert_eq!(tables.probe_wdl_after_zeroing(&pos)?, Wdl::Loss);
+//!
+//! if let Some((m, dtz)) = tables.best_move(&pos)? {
+//!     println!("{m} (dtz {})", dtz.0);
+//! }
+//! # #[derive(Debug)] struct CommonError;
+//! # impl From<std::io::Error> for CommonError { fn from(_: std::io::Error) -> Self { Self } }
+//! # impl From<shakmaty::fen::ParseFenError> for CommonError { fn from(_: shakmaty::fen::ParseFenError) -> Self { Self } }
+//! # impl<P> From<shakmaty::PositionError<P>> for CommonError { fn from(_: shakmaty::PositionError<P>) -> Self { Self } }
+//! # impl From<shakmaty::syzygy::SyzygyError> for CommonError { fn from(_: shakmaty::syzygy::SyzygyError) -> Self { Self } }
+//! # Ok::<_, CommonError>(())
+//! ```
+
+use alloc::{boxed::Box, sync::Arc, vec, vec::Vec};
+use core::{fmt, marker::PhantomData, ops::Neg};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crate::{
+    material::MaterialKey, Bitboard, Board, ByColor, ByRole, Chess, Color, Move, Outcome, Piece,
+    Position, Role, Square,
+};
+
+/// Win/draw/loss from the point of view of the side to move.
+///
+/// Cursed wins and blessed losses are wins and losses that can not be
+/// converted before the 50-move rule applies.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Wdl {
+    Loss = -2,
+    BlessedLoss = -1,
+    Draw = 0,
+    CursedWin = 1,
+    Win = 2,
+}
+
+impl Wdl {
+    /// Gets the value of a finished game from the point of view of `pov`.
+    pub fn from_outcome(outcome: Outcome, pov: Color) -> Wdl {
+        match outcome {
+            Outcome::Decisive { winner } if winner == pov => Wdl::Win,
+            Outcome::Decisive { .. } => Wdl::Loss,
+            Outcome::Draw => Wdl::Draw,
+        }
+    }
+
+    const fn signum(self) -> i32 {
+        (self as i32).signum()
+    }
+
+    const fn from_value(value: u16) -> Option<Wdl> {
+        Some(match value {
+            0 => Wdl::Loss,
+            1 => Wdl::BlessedLoss,
+            2 => Wdl::Draw,
+            3 => Wdl::CursedWin,
+            4 => Wdl::Win,
+            _ => return None,
+        })
+    }
+
+    /// The DTZ of a position where the best move is zeroing.
+    const fn before_zeroing(self) -> i32 {
+        match self {
+            Wdl::Loss => -1,
+            Wdl::BlessedLoss => -101,
+            Wdl::Draw => 0,
+            Wdl::CursedWin => 101,
+            Wdl::Win => 1,
+        }
+    }
+}
+
+impl Neg for Wdl {
+    type Output = Wdl;
+
+    fn neg(self) -> Wdl {
+        match self {
+            Wdl::Loss => Wdl::Win,
+            Wdl::BlessedLoss => Wdl::CursedWin,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::CursedWin => Wdl::BlessedLoss,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+}
+
+/// Distance to zeroing in plies, from the point of view of the side to move.
+///
+/// Positive for wins, negative for losses and zero for draws. A zeroing
+/// move is a capture or a pawn move (or a checkmate), which resets the
+/// half-move clock. Cursed wins and blessed losses are offset by 100 plies.
+///
+/// Some tables store distances in full moves, so the value may be one ply
+/// larger (in absolute terms) than the true distance.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Dtz(pub i32);
+
+impl Neg for Dtz {
+    type Output = Dtz;
+
+    fn neg(self) -> Dtz {
+        Dtz(-self.0)
+    }
+}
+
+/// Kind of table.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Metric {
+    /// Win/draw/loss table.
+    Wdl,
+    /// Distance to zeroing table.
+    Dtz,
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Metric::Wdl => "wdl",
+            Metric::Dtz => "dtz",
+        })
+    }
+}
+
+/// Error when probing tablebases.
+#[derive(Debug)]
+pub enum SyzygyError {
+    /// The position has castling rights, which are not covered by the
+    /// tables.
+    Castling,
+    /// The position has more pieces than supported by the variant.
+    TooManyPieces,
+    /// No table for the material configuration has been added.
+    MissingTable {
+        metric: Metric,
+        material: MaterialKey,
+    },
+    /// A table could not be read or is corrupted.
+    ProbeFailed {
+        metric: Metric,
+        material: MaterialKey,
+        error: io::Error,
+    },
+}
+
+impl fmt::Display for SyzygyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyzygyError::Castling => {
+                f.write_str("syzygy tables do not contain positions with castling rights")
+            }
+            SyzygyError::TooManyPieces => f.write_str("too many pieces for syzygy tables"),
+            SyzygyError::MissingTable { metric, material } => {
+                write!(f, "required {metric} table not found: {material}")
+            }
+            SyzygyError::ProbeFailed {
+                metric,
+                material,
+                error,
+            } => write!(f, "failed to probe {metric} table {material}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for SyzygyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SyzygyError::ProbeFailed { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// A `Result` type for tablebase probes.
+pub type SyzygyResult<T> = Result<T, SyzygyError>;
+
+/// Access to table files.
+///
+/// Implement this to read tables from somewhere other than the local file
+/// system, for example from memory in tests.
+pub trait Filesystem: Send + Sync {
+    /// Gets the size of a regular file. Fails for missing files and
+    /// directories.
+    fn regular_file_size(&self, path: &Path) -> io::Result<u64>;
+
+    /// Lists the entries of a directory.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Opens a file for reading.
+    fn open(&self, path: &Path) -> io::Result<Box<dyn RandomAccessFile>>;
+}
+
+/// A table file opened by a [`Filesystem`].
+pub trait RandomAccessFile: Send + Sync {
+    /// Reads exactly `buf.len()` bytes starting at `offset`.
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()>;
+}
+
+/// The local file system.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct OsFilesystem;
+
+impl Filesystem for OsFilesystem {
+    fn regular_file_size(&self, path: &Path) -> io::Result<u64> {
+        let meta = fs::metadata(path)?;
+        if !meta.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not a regular file",
+            ));
+        }
+        Ok(meta.len())
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn RandomAccessFile>> {
+        let file = fs::File::open(path)?;
+        #[cfg(not(any(unix, windows)))]
+        let file = std::sync::Mutex::new(file);
+        Ok(Box::new(OsFile { file }))
+    }
+}
+
+struct OsFile {
+    #[cfg(any(unix, windows))]
+    file: fs::File,
+    #[cfg(not(any(unix, windows)))]
+    file: std::sync::Mutex<fs::File>,
+}
+
+impl RandomAccessFile for OsFile {
+    #[cfg(unix)]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        std::os::unix::fs::FileExt::read_exact_at(&self.file, buf, offset)
+    }
+
+    #[cfg(windows)]
+    fn read_exact_at(&self, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+        use std::os::windows::fs::FileExt;
+        while !buf.is_empty() {
+            match self.file.seek_read(buf, offset) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => {
+                    buf = &mut buf[n..];
+                    offset += n as u64;
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        use std::io::{Read, Seek, SeekFrom};
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "poisoned file lock"))?;
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(buf)
+    }
+}
+
+/// File extension and magic header bytes of a kind of table file.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct TableType {
+    pub ext: &'static str,
+    pub magic: [u8; 4],
+}
+
+/// A variant with Syzygy tables.
+pub trait Syzygy: Position + Clone {
+    /// WDL tables.
+    const TBW: TableType;
+    /// DTZ tables.
+    const TBZ: TableType;
+    /// WDL tables without pawns, if different from [`Syzygy::TBW`].
+    const PAWNLESS_TBW: Option<TableType>;
+    /// DTZ tables without pawns, if different from [`Syzygy::TBZ`].
+    const PAWNLESS_TBZ: Option<TableType>;
+    /// Whether each side always has exactly one king.
+    const ONE_KING: bool;
+    /// Whether the kings may stand on adjacent squares.
+    const CONNECTED_KINGS: bool;
+    /// The maximum number of pieces in the tables.
+    const MAX_PIECES: usize;
+}
+
+impl Syzygy for Chess {
+    const TBW: TableType = TableType {
+        ext: "rtbw",
+        magic: [0x71, 0xe8, 0x23, 0x5d],
+    };
+    const TBZ: TableType = TableType {
+        ext: "rtbz",
+        magic: [0xd7, 0x66, 0x0c, 0xa5],
+    };
+    const PAWNLESS_TBW: Option<TableType> = None;
+    const PAWNLESS_TBZ: Option<TableType> = None;
+    const ONE_KING: bool = true;
+    const CONNECTED_KINGS: bool = false;
+    const MAX_PIECES: usize = 7;
+}
+
+#[cfg(feature = "variant")]
+impl Syzygy for crate::variant::Atomic {
+    const TBW: TableType = TableType {
+        ext: "atbw",
+        magic: [0x55, 0x8d, 0xa4, 0x49],
+    };
+    const TBZ: TableType = TableType {
+        ext: "atbz",
+        magic: [0x91, 0xa9, 0x5e, 0xeb],
+    };
+    const PAWNLESS_TBW: Option<TableType> = None;
+    const PAWNLESS_TBZ: Option<TableType> = None;
+    const ONE_KING: bool = true;
+    const CONNECTED_KINGS: bool = true;
+    const MAX_PIECES: usize = 6;
+}
+
+#[cfg(feature = "variant")]
+impl Syzygy for crate::variant::Antichess {
+    const TBW: TableType = TableType {
+        ext: "stbw",
+        magic: [0x7b, 0xf6, 0x93, 0x15],
+    };
+    const TBZ: TableType = TableType {
+        ext: "stbz",
+        magic: [0xe4, 0xcf, 0xe7, 0x23],
+    };
+    const PAWNLESS_TBW: Option<TableType> = Some(TableType {
+        ext: "gtbw",
+        magic: [0xbc, 0x55, 0xbc, 0x21],
+    });
+    const PAWNLESS_TBZ: Option<TableType> = Some(TableType {
+        ext: "gtbz",
+        magic: [0xd6, 0xf5, 0x1b, 0x50],
+    });
+    const ONE_KING: bool = false;
+    const CONNECTED_KINGS: bool = true;
+    const MAX_PIECES: usize = 6;
+}
+
+type Material = ByColor<ByRole<u8>>;
+
+fn has_pawns(material: &Material) -> bool {
+    material.white.pawn > 0 || material.black.pawn > 0
+}
+
+fn unique_pieces(material: &Material) -> usize {
+    material
+        .iter()
+        .flat_map(|side| side.iter())
+        .filter(|&&n| n == 1)
+        .count()
+}
+
+/// Smallest number of identical pieces, among pieces that are not unique.
+fn min_like_man(material: &Material) -> usize {
+    material
+        .iter()
+        .flat_map(|side| side.iter())
+        .filter(|&&n| n >= 2)
+        .min()
+        .map_or(0, |&n| usize::from(n))
+}
+
+/// Encodes a group of like pieces, if the piece that comes first in the
+/// twisted order is in the a1-d1-d4 triangle.
+fn like_index(squares: &[usize]) -> Option<u64> {
+    let mut twisted: Vec<u64> = squares.iter().map(|&sq| MULT_TWIST[sq]).collect();
+    twisted.sort_unstable();
+    let (&lead, others) = twisted.split_last()?;
+    let lead = INV_TRIANGLE.iter().position(|&sq| MULT_TWIST[sq] == lead)?;
+    Some(
+        MULT_IDX[others.len()][lead]
+            + others
+                .iter()
+                .enumerate()
+                .map(|(i, &t)| binomial(t, i as u64 + 1))
+                .sum::<u64>(),
+    )
+}
+
+const fn binomial(n: u64, k: u64) -> u64 {
+    if k > n {
+        return 0;
+    }
+    let mut result = 1;
+    let mut i = 0;
+    while i < k {
+        result = result * (n - i) / (i + 1);
+        i += 1;
+    }
+    result
+}
+
+const fn file_of(sq: usize) -> usize {
+    sq & 7
+}
+
+const fn rank_of(sq: usize) -> usize {
+    sq >> 3
+}
+
+/// Distance of a square from the a1-h8 diagonal, positive above it.
+const fn off_diagonal(sq: usize) -> i32 {
+    rank_of(sq) as i32 - file_of(sq) as i32
+}
+
+const fn flip_diagonal(sq: usize) -> usize {
+    ((sq >> 3) | (sq << 3)) & 63
+}
+
+/// Applies one of the 8 symmetries of the board: mirror the file (bit 0),
+/// mirror the rank (bit 1), then flip at the a1-h8 diagonal (bit 2).
+const fn transform(sq: usize, symmetry: usize) -> usize {
+    let mut sq =
+        sq ^ if symmetry & 1 != 0 { 7 } else { 0 } ^ if symmetry & 2 != 0 { 56 } else { 0 };
+    if symmetry & 4 != 0 {
+        sq = flip_diagonal(sq);
+    }
+    sq
+}
+
+/// Maps squares in the a1-d1-d4 triangle to 0..10, with the diagonal last.
+const MAP_A1D1D4: [u64; 64] = {
+    let mut map = [0; 64];
+    let mut code = 0;
+    let mut sq = 0;
+    while sq <= 27 {
+        if off_diagonal(sq) < 0 && file_of(sq) <= 3 {
+            map[sq] = code;
+            code += 1;
+        }
+        sq += 1;
+    }
+    let mut sq = 0;
+    while sq <= 27 {
+        if off_diagonal(sq) == 0 && file_of(sq) <= 3 {
+            map[sq] = code;
+            code += 1;
+        }
+        sq += 1;
+    }
+    map
+};
+
+/// Maps squares below the a1-h8 diagonal to 0..28.
+const MAP_B1H1H7: [u64; 64] = {
+    let mut map = [0; 64];
+    let mut code = 0;
+    let mut sq = 0;
+    while sq < 64 {
+        if off_diagonal(sq) < 0 {
+            map[sq] = code;
+            code += 1;
+        }
+        sq += 1;
+    }
+    map
+};
+
+/// Squares of the a1-d1-d4 triangle, in the order of [`MAP_A1D1D4`].
+const INV_TRIANGLE: [usize; 10] = [1, 2, 3, 10, 11, 19, 0, 9, 18, 27];
+
+/// Orders squares by their image in the a1-d1-d4 triangle, and decreasing
+/// within each group, so that the triangle square comes first.
+const MULT_TWIST: [u64; 64] = [
+    15, 63, 55, 47, 40, 48, 56, 12, //
+    62, 11, 39, 31, 24, 32, 8, 57, //
+    54, 38, 7, 23, 16, 4, 33, 49, //
+    46, 30, 22, 3, 0, 17, 25, 41, //
+    45, 29, 21, 2, 1, 18, 26, 42, //
+    53, 37, 6, 20, 19, 5, 34, 50, //
+    61, 10, 36, 28, 27, 35, 9, 58, //
+    14, 60, 52, 44, 43, 51, 59, 13, //
+];
+
+/// Index offsets for a group of like pieces, by number of pieces minus one
+/// and triangle code of the leading piece.
+const MULT_IDX: [[u64; 10]; 5] = {
+    let mut table = [[0; 10]; 5];
+    let mut i = 0;
+    while i < 5 {
+        let mut s = 0;
+        let mut j = 0;
+        while j < 10 {
+            table[i][j] = s;
+            s += if i == 0 {
+                1
+            } else {
+                binomial(MULT_TWIST[INV_TRIANGLE[j]], i as u64)
+            };
+            j += 1;
+        }
+        i += 1;
+    }
+    table
+};
+
+/// Number of placements of a group of like pieces, by number of pieces
+/// minus one.
+const MULT_FACTOR: [u64; 5] = {
+    let mut table = [0; 5];
+    let mut i = 0;
+    while i < 5 {
+        table[i] = MULT_IDX[i][9]
+            + if i == 0 {
+                1
+            } else {
+                binomial(MULT_TWIST[INV_TRIANGLE[9]], i as u64)
+            };
+        i += 1;
+    }
+    table
+};
+
+/// Finds the canonical placement of two like pieces: the symmetry and
+/// whether to swap the pieces, such that the first piece is in the a1-d1-d4
+/// triangle, with the lowest triangle code and then the lowest square of
+/// the second piece.
+const fn canonical_pair(s0: usize, s1: usize) -> (usize, bool) {
+    let mut best = (usize::MAX, false);
+    let mut best_key = (usize::MAX, usize::MAX);
+    let mut symmetry = 0;
+    while symmetry < 8 {
+        let mut swap = 0;
+        while swap < 2 {
+            let (a, b) = if swap == 0 { (s0, s1) } else { (s1, s0) };
+            let (a, b) = (transform(a, symmetry), transform(b, symmetry));
+            let mut i = 0;
+            while i < 10 {
+                if INV_TRIANGLE[i] == a && (i < best_key.0 || (i == best_key.0 && b < best_key.1)) {
+                    best = (symmetry, swap != 0);
+                    best_key = (i, b);
+                }
+                i += 1;
+            }
+            swap += 1;
+        }
+        symmetry += 1;
+    }
+    best
+}
+
+/// Maps the 278 placements of two like pieces, by triangle code of the
+/// first piece and square of the second piece. Only canonical placements
+/// are mapped.
+const MAP_PP: [[u64; 64]; 10] = {
+    let mut map = [[u64::MAX; 64]; 10];
+    let mut code = 0;
+    let mut i = 0;
+    while i < 10 {
+        let mut s1 = 0;
+        while s1 < 64 {
+            if s1 != INV_TRIANGLE[i] {
+                let (symmetry, swap) = canonical_pair(INV_TRIANGLE[i], s1);
+                if symmetry == 0 && !swap {
+                    map[i][s1] = code;
+                    code += 1;
+                }
+            }
+            s1 += 1;
+        }
+        i += 1;
+    }
+    map
+};
+
+/// Maps the 462 placements of two non-adjacent kings, with the first king
+/// in the a1-d1-d4 triangle, and the second king not above the diagonal if
+/// the first king is on it.
+const MAP_KK: [[u64; 64]; 10] = {
+    let mut map = [[0; 64]; 10];
+    let mut both_on_diagonal = [(0, 0); 32];
+    let mut num_both_on_diagonal = 0;
+    let mut code = 0;
+    let mut idx = 0;
+    while idx < 10 {
+        let mut s1 = 0;
+        while s1 <= 27 {
+            if MAP_A1D1D4[s1] == idx as u64 && (idx != 0 || s1 == 1) {
+                let mut s2 = 0;
+                while s2 < 64 {
+                    let adjacent = file_of(s1).abs_diff(file_of(s2)) <= 1
+                        && rank_of(s1).abs_diff(rank_of(s2)) <= 1;
+                    if adjacent || (off_diagonal(s1) == 0 && off_diagonal(s2) > 0) {
+                        // Illegal or mirrored.
+                    } else if off_diagonal(s1) == 0 && off_diagonal(s2) == 0 {
+                        both_on_diagonal[num_both_on_diagonal] = (idx, s2);
+                        num_both_on_diagonal += 1;
+                    } else {
+                        map[idx][s2] = code;
+                        code += 1;
+                    }
+                    s2 += 1;
+                }
+            }
+            s1 += 1;
+        }
+        idx += 1;
+    }
+    let mut i = 0;
+    while i < num_both_on_diagonal {
+        let (idx, s2) = both_on_diagonal[i];
+        map[idx][s2] = code;
+        code += 1;
+        i += 1;
+    }
+    map
+};
+
+/// Maps pawn squares a2-h7 to 0..48, such that the leading pawn (closest to
+/// the edge, then lowest rank) has the highest value.
+const MAP_PAWNS: [u64; 64] = {
+    let mut map = [0; 64];
+    let mut available: u64 = 47;
+    let mut file = 0;
+    while file < 4 {
+        let mut rank = 1;
+        while rank < 7 {
+            let sq = rank * 8 + file;
+            map[sq] = available;
+            map[sq ^ 7] = available - 1;
+            available = available.wrapping_sub(2);
+            rank += 1;
+        }
+        file += 1;
+    }
+    map
+};
+
+/// Index offsets for a group of leading pawns, by number of pawns and
+/// square of the leading pawn.
+const LEAD_PAWN_IDX: [[u64; 64]; 7] = {
+    let mut table = [[0; 64]; 7];
+    let mut num = 1;
+    while num < 7 {
+        let mut file = 0;
+        while file < 4 {
+            let mut idx = 0;
+            let mut rank = 1;
+            while rank < 7 {
+                let sq = rank * 8 + file;
+                table[num][sq] = idx;
+                idx += binomial(MAP_PAWNS[sq], num as u64 - 1);
+                rank += 1;
+            }
+            file += 1;
+        }
+        num += 1;
+    }
+    table
+};
+
+/// Number of placements of a group of leading pawns, by number of pawns
+/// and file of the leading pawn.
+const LEAD_PAWNS_SIZE: [[u64; 4]; 7] = {
+    let mut table = [[0; 4]; 7];
+    let mut num = 1;
+    while num < 7 {
+        let mut file = 0;
+        while file < 4 {
+            let mut rank = 1;
+            while rank < 7 {
+                table[num][file] += binomial(MAP_PAWNS[rank * 8 + file], num as u64 - 1);
+                rank += 1;
+            }
+            file += 1;
+        }
+        num += 1;
+    }
+    table
+};
+
+mod flags {
+    pub const STM: u8 = 1;
+    pub const MAPPED: u8 = 2;
+    pub const WIN_PLIES: u8 = 4;
+    pub const LOSS_PLIES: u8 = 8;
+    pub const WIDE: u8 = 16;
+    pub const SINGLE_VALUE: u8 = 128;
+}
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn read_bytes<const N: usize>(file: &dyn RandomAccessFile, offset: u64) -> io::Result<[u8; N]> {
+    let mut buf = [0; N];
+    file.read_exact_at(&mut buf, offset)?;
+    Ok(buf)
+}
+
+fn read_u8(file: &dyn RandomAccessFile, offset: u64) -> io::Result<u8> {
+    read_bytes::<1>(file, offset).map(|[b]| b)
+}
+
+fn read_u16_le(file: &dyn RandomAccessFile, offset: u64) -> io::Result<u16> {
+    read_bytes(file, offset).map(u16::from_le_bytes)
+}
+
+fn read_u32_le(file: &dyn RandomAccessFile, offset: u64) -> io::Result<u32> {
+    read_bytes(file, offset).map(u32::from_le_bytes)
+}
+
+/// Compressed values for one side to move and (with pawns) one file of the
+/// leading pawn.
+#[derive(Debug, Default)]
+struct PairsData {
+    pieces: Vec<Piece>,
+    group_len: Vec<usize>,
+    group_idx: Vec<u64>,
+    flags: u8,
+    min_sym_len: u8,
+    block_size: u64,
+    span: u64,
+    sparse_index: u64,
+    sparse_index_size: u64,
+    block_lengths: u64,
+    block_lengths_size: u64,
+    blocks: u64,
+    num_blocks: u64,
+    lowest_sym: Vec<u16>,
+    base64: Vec<u64>,
+    symlen: Vec<u8>,
+    btree: Vec<[u8; 3]>,
+    /// Offsets of the DTZ value maps for win, loss, cursed win and blessed
+    /// loss.
+    dtz_map: [u64; 4],
+}
+
+impl PairsData {
+    fn left(&self, sym: usize) -> usize {
+        let [a, b, _] = self.btree[sym];
+        usize::from(b & 0xf) << 8 | usize::from(a)
+    }
+
+    fn right(&self, sym: usize) -> usize {
+        let [_, b, c] = self.btree[sym];
+        usize::from(c) << 4 | usize::from(b >> 4)
+    }
+
+    fn tb_size(&self) -> u64 {
+        self.group_idx[self.group_len.len()]
+    }
+
+    fn set_groups<S: Syzygy>(
+        &mut self,
+        material: &Material,
+        order: [u8; 2],
+        file: usize,
+    ) -> io::Result<()> {
+        let pawns = has_pawns(material);
+        let unique = unique_pieces(material);
+        let like_man = min_like_man(material);
+        let mut first_len: usize = if pawns {
+            0
+        } else if unique >= 3 {
+            3
+        } else if unique == 2 {
+            2
+        } else if (2..=MULT_FACTOR.len()).contains(&like_man) {
+            like_man
+        } else {
+            return Err(invalid_data("unsupported table encoding"));
+        };
+
+        self.group_len = vec![1];
+        for i in 1..self.pieces.len() {
+            first_len = first_len.saturating_sub(1);
+            if first_len > 0 || self.pieces[i] == self.pieces[i - 1] {
+                *self.group_len.last_mut().expect("group") += 1;
+            } else {
+                self.group_len.push(1);
+            }
+        }
+
+        let n = self.group_len.len();
+        let both_pawns = material.white.pawn > 0 && material.black.pawn > 0;
+        if both_pawns && n < 2 {
+            return Err(invalid_data("missing pawn group"));
+        }
+        let mut next = if both_pawns { 2 } else { 1 };
+        let mut free_squares =
+            64 - self.group_len[0] - if both_pawns { self.group_len[1] } else { 0 };
+        let mut idx: u64 = 1;
+        self.group_idx = vec![0; n + 1];
+
+        let mut k = 0;
+        while next < n || k == order[0] || k == order[1] {
+            if k == order[0] {
+                self.group_idx[0] = idx;
+                idx *= if pawns {
+                    LEAD_PAWNS_SIZE
+                        .get(self.group_len[0])
+                        .ok_or_else(|| invalid_data("too many leading pawns"))?[file]
+                } else if unique >= 3 {
+                    31332
+                } else if unique == 2 {
+                    if S::CONNECTED_KINGS {
+                        518
+                    } else {
+                        462
+                    }
+                } else if like_man == 2 {
+                    278
+                } else {
+                    MULT_FACTOR[like_man - 1]
+                };
+            } else if k == order[1] {
+                self.group_idx[1] = idx;
+                idx *= binomial(48 - self.group_len[0] as u64, self.group_len[1] as u64);
+            } else {
+                self.group_idx[next] = idx;
+                idx *= binomial(free_squares as u64, self.group_len[next] as u64);
+                free_squares -= self.group_len[next];
+                next += 1;
+            }
+            k += 1;
+            if k > 15 {
+                return Err(invalid_data("invalid group order"));
+            }
+        }
+        self.group_idx[n] = idx;
+        Ok(())
+    }
+
+    fn set_sizes(&mut self, file: &dyn RandomAccessFile, mut offset: u64) -> io::Result<u64> {
+        self.flags = read_u8(file, offset)?;
+        offset += 1;
+
+        if self.flags & flags::SINGLE_VALUE != 0 {
+            self.min_sym_len = read_u8(file, offset)?;
+            return Ok(offset + 1);
+        }
+
+        let [block_size, span, padding] = read_bytes(file, offset)?;
+        offset += 3;
+        if block_size > 32 || span > 32 {
+            return Err(invalid_data("invalid block size"));
+        }
+        self.block_size = 1 << block_size;
+        self.span = 1 << span;
+        self.sparse_index_size = self.tb_size().div_ceil(self.span);
+        self.num_blocks = u64::from(read_u32_le(file, offset)?);
+        offset += 4;
+        self.block_lengths_size = self.num_blocks + u64::from(padding);
+
+        let [max_sym_len, min_sym_len] = read_bytes(file, offset)?;
+        offset += 2;
+        if min_sym_len > max_sym_len || max_sym_len > 32 {
+            return Err(invalid_data("invalid symbol lengths"));
+        }
+        self.min_sym_len = min_sym_len;
+
+        let h = usize::from(max_sym_len - min_sym_len) + 1;
+        let mut lowest_sym = vec![0; 2 * h];
+        file.read_exact_at(&mut lowest_sym, offset)?;
+        offset += 2 * h as u64;
+        self.lowest_sym = lowest_sym
+            .chunks_exact(2)
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect();
+
+        // Canonical Huffman code: longer symbols have lower values.
+        self.base64 = vec![0; h];
+        for i in (0..h - 1).rev() {
+            self.base64[i] = self.base64[i + 1]
+                .wrapping_add(u64::from(self.lowest_sym[i]))
+                .wrapping_sub(u64::from(self.lowest_sym[i + 1]))
+                / 2;
+        }
+        for (i, base) in self.base64.iter_mut().enumerate() {
+            *base = base
+                .checked_shl(64 - i as u32 - u32::from(min_sym_len))
+                .unwrap_or(0);
+        }
+
+        let num_syms = usize::from(read_u16_le(file, offset)?);
+        offset += 2;
+        let mut btree = vec![0; 3 * num_syms];
+        file.read_exact_at(&mut btree, offset)?;
+        offset += 3 * num_syms as u64 + (num_syms as u64 & 1);
+        self.btree = btree
+            .chunks_exact(3)
+            .map(|chunk| [chunk[0], chunk[1], chunk[2]])
+            .collect();
+
+        self.set_symlen()?;
+
+        Ok(offset)
+    }
+
+    /// Computes how many values each symbol expands to (minus one), without
+    /// recursion.
+    fn set_symlen(&mut self) -> io::Result<()> {
+        let n = self.btree.len();
+        self.symlen = vec![0; n];
+        let mut visited = vec![false; n];
+        let mut stack = Vec::new();
+
+        for sym in 0..n {
+            stack.push(sym);
+            while let Some(&s) = stack.last() {
+                if visited[s] {
+                    stack.pop();
+                    continue;
+                }
+                if self.right(s) == 0xfff {
+                    visited[s] = true;
+                    stack.pop();
+                    continue;
+                }
+                let (left, right) = (self.left(s), self.right(s));
+                if left >= n || right >= n || stack.len() > n {
+                    return Err(invalid_data("invalid symbol tree"));
+                }
+                if !visited[left] {
+                    stack.push(left);
+                } else if !visited[right] {
+                    stack.push(right);
+                } else {
+                    self.symlen[s] = self.symlen[left]
+                        .wrapping_add(self.symlen[right])
+                        .wrapping_add(1);
+                    visited[s] = true;
+                    stack.pop();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn block_length(&self, file: &dyn RandomAccessFile, block: u64) -> io::Result<i64> {
+        if block >= self.block_lengths_size {
+            return Err(invalid_data("block out of range"));
+        }
+        read_u16_le(file, self.block_lengths + 2 * block).map(i64::from)
+    }
+
+    /// Reads the value at index `idx`.
+    fn decompress(&self, file: &dyn RandomAccessFile, idx: u64) -> io::Result<u16> {
+        if self.flags & flags::SINGLE_VALUE != 0 {
+            return Ok(u16::from(self.min_sym_len));
+        }
+
+        // Find the block using the sparse index, which points to the middle
+        // of every span.
+        let k = idx / self.span;
+        if k >= self.sparse_index_size {
+            return Err(invalid_data("index out of range"));
+        }
+        let entry = read_bytes::<6>(file, self.sparse_index + 6 * k)?;
+        let mut block = u64::from(u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]));
+        let mut offset = i64::from(u16::from_le_bytes([entry[4], entry[5]]));
+        offset += (idx % self.span) as i64 - (self.span / 2) as i64;
+
+        while offset < 0 {
+            block = block
+                .checked_sub(1)
+                .ok_or_else(|| invalid_data("block out of range"))?;
+            offset += self.block_length(file, block)? + 1;
+        }
+        loop {
+            let len = self.block_length(file, block)?;
+            if offset <= len {
+                break;
+            }
+            offset -= len + 1;
+            block += 1;
+        }
+
+        if block >= self.num_blocks {
+            return Err(invalid_data("block out of range"));
+        }
+        let mut data = vec![0; self.block_size as usize];
+        file.read_exact_at(&mut data, self.blocks + block * self.block_size)?;
+        let read_u32_be = |ptr: usize| -> io::Result<u64> {
+            data.get(ptr..ptr + 4)
+                .map(|chunk| {
+                    u64::from(u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                })
+                .ok_or_else(|| invalid_data("unexpected end of block"))
+        };
+
+        // Decode Huffman symbols until reaching the one containing the
+        // value.
+        let mut buf64 = read_u32_be(0)? << 32 | read_u32_be(4)?;
+        let mut ptr = 8;
+        let mut buf64_size = 64;
+        let min_sym_len = u32::from(self.min_sym_len);
+        let mut sym;
+        loop {
+            let mut len = 0;
+            while buf64 < self.base64[len] {
+                len += 1;
+                if len >= self.base64.len() {
+                    return Err(invalid_data("invalid symbol"));
+                }
+            }
+
+            sym = ((buf64 - self.base64[len])
+                .checked_shr(64 - len as u32 - min_sym_len)
+                .unwrap_or(0)) as usize
+                + usize::from(self.lowest_sym[len]);
+            let symlen = i64::from(
+                *self
+                    .symlen
+                    .get(sym)
+                    .ok_or_else(|| invalid_data("invalid symbol"))?,
+            );
+            if offset < symlen + 1 {
+                break;
+            }
+            offset -= symlen + 1;
+
+            let len = len as u32 + min_sym_len;
+            buf64 = buf64.checked_shl(len).unwrap_or(0);
+            buf64_size -= len;
+            if buf64_size <= 32 {
+                buf64_size += 32;
+                buf64 |= read_u32_be(ptr)? << (64 - buf64_size);
+                ptr += 4;
+            }
+        }
+
+        // Expand the symbol (recursive pairing) to find the value.
+        while self.symlen[sym] != 0 {
+            let left = self.left(sym);
+            let left_len = i64::from(self.symlen[left]);
+            if offset < left_len + 1 {
+                sym = left;
+            } else {
+                offset -= left_len + 1;
+                sym = self.right(sym);
+            }
+            if sym >= self.symlen.len() {
+                return Err(invalid_data("invalid symbol"));
+            }
+        }
+
+        Ok(self.left(sym) as u16)
+    }
+}
+
+/// An opened table file.
+struct Table {
+    file: Box<dyn RandomAccessFile>,
+    metric: Metric,
+    /// Material as in the table name, with the stronger side as white.
+    material: MaterialKey,
+    symmetric: bool,
+    has_pawns: bool,
+    /// Number of like pieces in the leading group, if there are less than
+    /// two unique pieces (only in Antichess). Otherwise `0`.
+    like_man: usize,
+    /// Indexed by file of the leading pawn (only one without pawns) and
+    /// side to move (only one for DTZ and symmetric tables).
+    files: Vec<Vec<PairsData>>,
+}
+
+impl fmt::Debug for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Table")
+            .field("metric", &self.metric)
+            .field("material", &self.material)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Table {
+    fn open<S: Syzygy>(
+        file: Box<dyn RandomAccessFile>,
+        metric: Metric,
+        key: MaterialKey,
+        table_type: TableType,
+    ) -> io::Result<Table> {
+        let material = key.material();
+        let symmetric = key.is_symmetric();
+        let pawns = key.has_pawns();
+        let num_pieces = key.count();
+        let both_pawns = material.white.pawn > 0 && material.black.pawn > 0;
+
+        if read_bytes::<4>(&*file, 0)? != table_type.magic {
+            return Err(invalid_data("invalid magic header bytes"));
+        }
+
+        let header = read_u8(&*file, 4)?;
+        if (header & 2 != 0) != pawns || (header & 1 != 0) == symmetric {
+            return Err(invalid_data("table header does not match material"));
+        }
+        let mut offset = 5;
+
+        let sides = if metric == Metric::Wdl && !symmetric {
+            2
+        } else {
+            1
+        };
+        let num_files = if pawns { 4 } else { 1 };
+
+        let mut files = Vec::with_capacity(num_files);
+        for tb_file in 0..num_files {
+            let order_byte = read_u8(&*file, offset)?;
+            let pawn_order_byte = if both_pawns {
+                read_u8(&*file, offset + 1)?
+            } else {
+                0xff
+            };
+            offset += 1 + u64::from(both_pawns);
+            let orders = [
+                [order_byte & 0xf, pawn_order_byte & 0xf],
+                [order_byte >> 4, pawn_order_byte >> 4],
+            ];
+
+            let mut sides_data: Vec<PairsData> = (0..sides).map(|_| PairsData::default()).collect();
+            for _ in 0..num_pieces {
+                let byte = read_u8(&*file, offset)?;
+                offset += 1;
+                for (side, data) in sides_data.iter_mut().enumerate() {
+                    let nibble = if side == 0 { byte & 0xf } else { byte >> 4 };
+                    let role =
+                        Role::try_from(nibble & 7).map_err(|_| invalid_data("invalid piece"))?;
+                    let color = Color::from_white(nibble & 8 == 0);
+                    data.pieces.push(role.of(color));
+                }
+            }
+
+            for (side, data) in sides_data.iter_mut().enumerate() {
+                let mut pieces_material = Material::default();
+                for piece in &data.pieces {
+                    *pieces_material.get_mut(piece.color).get_mut(piece.role) += 1;
+                }
+                if pieces_material != material {
+                    return Err(invalid_data("table pieces do not match material"));
+                }
+                data.set_groups::<S>(&material, orders[side], tb_file)?;
+            }
+
+            files.push(sides_data);
+        }
+
+        offset += offset & 1;
+
+        for sides_data in &mut files {
+            for data in sides_data {
+                offset = data.set_sizes(&*file, offset)?;
+            }
+        }
+
+        if metric == Metric::Dtz {
+            for sides_data in &mut files {
+                let data = &mut sides_data[0];
+                if data.flags & flags::MAPPED != 0 {
+                    if data.flags & flags::WIDE != 0 {
+                        offset += offset & 1;
+                        for map in &mut data.dtz_map {
+                            *map = offset + 2;
+                            offset += 2 * u64::from(read_u16_le(&*file, offset)?) + 2;
+                        }
+                    } else {
+                        for map in &mut data.dtz_map {
+                            *map = offset + 1;
+                            offset += u64::from(read_u8(&*file, offset)?) + 1;
+                        }
+                    }
+                }
+            }
+            offset += offset & 1;
+        }
+
+        for sides_data in &mut files {
+            for data in sides_data {
+                data.sparse_index = offset;
+                offset += 6 * data.sparse_index_size;
+            }
+        }
+
+        for sides_data in &mut files {
+            for data in sides_data {
+                data.block_lengths = offset;
+                offset += 2 * data.block_lengths_size;
+            }
+        }
+
+        for sides_data in &mut files {
+            for data in sides_data {
+                offset = (offset + 0x3f) & !0x3f;
+                data.blocks = offset;
+                offset += data.num_blocks * data.block_size;
+            }
+        }
+
+        Ok(Table {
+            file,
+            metric,
+            material: key,
+            symmetric,
+            has_pawns: pawns,
+            like_man: if !pawns && unique_pieces(&material) < 2 {
+                min_like_man(&material)
+            } else {
+                0
+            },
+            files,
+        })
+    }
+
+    /// Computes the index of a position and returns it together with the
+    /// compressed data it belongs to, or `None` if the position has the
+    /// wrong side to move for a one-sided DTZ table.
+    fn encode<S: Syzygy>(
+        &self,
+        board: &Board,
+        turn: Color,
+    ) -> io::Result<Option<(&PairsData, u64)>> {
+        // Tables are stored with the stronger side as white. Mirror the
+        // position if black is stronger, or if the material is symmetric and
+        // black is to move.
+        let flip =
+            board.material_key() != self.material || (self.symmetric && turn == Color::Black);
+        let flip_square = |sq: Square| usize::from(sq) ^ if flip { 56 } else { 0 };
+        let flip_color = |color: Color| if flip { !color } else { color };
+        let stm = flip_color(turn);
+
+        let mut squares = Vec::with_capacity(8);
+        let mut pieces = Vec::with_capacity(8);
+
+        // Place the leading pawns first, with the leading pawn (closest to
+        // the edge, lowest rank) at the front.
+        let mut lead_pawns = Bitboard::EMPTY;
+        let mut tb_file = 0;
+        if self.has_pawns {
+            let lead = self.files[0][0].pieces[0];
+            lead_pawns = board.by_piece(lead.role.of(flip_color(lead.color)));
+            for sq in lead_pawns {
+                squares.push(flip_square(sq));
+                pieces.push(lead);
+            }
+            let (max, _) = squares
+                .iter()
+                .enumerate()
+                .max_by_key(|&(_, &sq)| MAP_PAWNS[sq])
+                .ok_or_else(|| invalid_data("missing leading pawns"))?;
+            squares.swap(0, max);
+            tb_file = file_of(squares[0]).min(7 - file_of(squares[0]));
+        }
+
+        let sides_data = &self.files[tb_file];
+        if self.metric == Metric::Dtz
+            && (sides_data[0].flags & flags::STM != 0) != (stm == Color::Black)
+            && (self.has_pawns || !self.symmetric)
+        {
+            return Ok(None);
+        }
+        let data = &sides_data[if self.metric == Metric::Wdl {
+            usize::from(stm == Color::Black).min(sides_data.len() - 1)
+        } else {
+            0
+        }];
+
+        for sq in board.occupied() & !lead_pawns {
+            let piece = board.piece_at(sq).expect("occupied");
+            squares.push(flip_square(sq));
+            pieces.push(piece.role.of(flip_color(piece.color)));
+        }
+
+        if squares.len() != data.pieces.len() {
+            return Err(invalid_data("table pieces do not match position"));
+        }
+
+        // Order the pieces as in the table.
+        let lead_pawns_count = lead_pawns.count();
+        for i in lead_pawns_count..squares.len() {
+            if pieces[i] == data.pieces[i] {
+                continue;
+            }
+            if let Some(j) = (i + 1..squares.len()).find(|&j| pieces[j] == data.pieces[i]) {
+                pieces.swap(i, j);
+                squares.swap(i, j);
+            }
+        }
+
+        // Mirror so that the leading piece is on the a-d files.
+        if file_of(squares[0]) > 3 {
+            for sq in &mut squares {
+                *sq ^= 7;
+            }
+        }
+
+        let mut idx;
+        if self.has_pawns {
+            idx = LEAD_PAWN_IDX[lead_pawns_count][squares[0]];
+            squares[1..lead_pawns_count].sort_by_key(|&sq| MAP_PAWNS[sq]);
+            for (i, &sq) in squares.iter().enumerate().take(lead_pawns_count).skip(1) {
+                idx += binomial(MAP_PAWNS[sq], i as u64);
+            }
+        } else if self.like_man == 2 {
+            // Use the canonical placement of the two like pieces.
+            let (symmetry, swap) = canonical_pair(squares[0], squares[1]);
+            for sq in &mut squares {
+                *sq = transform(*sq, symmetry);
+            }
+            if swap {
+                squares.swap(0, 1);
+            }
+            idx = MAP_PP[MAP_A1D1D4[squares[0]] as usize][squares[1]];
+        } else if self.like_man > 2 {
+            // Use the symmetry with the lowest index of the like pieces.
+            let like = self.like_man;
+            let (symmetry, like_idx) = (0..8)
+                .filter_map(|symmetry| {
+                    let mirrored: Vec<usize> = squares[..like]
+                        .iter()
+                        .map(|&sq| transform(sq, symmetry))
+                        .collect();
+                    like_index(&mirrored).map(|idx| (symmetry, idx))
+                })
+                .min_by_key(|&(_, idx)| idx)
+                .expect("every square has an image in the triangle");
+            for sq in &mut squares {
+                *sq = transform(*sq, symmetry);
+            }
+            idx = like_idx;
+        } else {
+            // Mirror so that the leading piece is on ranks 1-4, and the
+            // first piece off the diagonal is below it.
+            if rank_of(squares[0]) > 3 {
+                for sq in &mut squares {
+                    *sq ^= 56;
+                }
+            }
+            for i in 0..data.group_len[0] {
+                let off = off_diagonal(squares[i]);
+                if off == 0 {
+                    continue;
+                }
+                if off > 0 {
+                    for sq in &mut squares[i..] {
+                        *sq = flip_diagonal(*sq);
+                    }
+                }
+                break;
+            }
+
+            let sq = |i: usize| squares[i] as u64;
+            let adjust1 = u64::from(squares[1] > squares[0]);
+            idx = if data.group_len[0] == 3 {
+                let adjust2 =
+                    u64::from(squares[2] > squares[0]) + u64::from(squares[2] > squares[1]);
+                if off_diagonal(squares[0]) != 0 {
+                    (MAP_A1D1D4[squares[0]] * 63 + (sq(1) - adjust1)) * 62 + sq(2) - adjust2
+                } else if off_diagonal(squares[1]) != 0 {
+                    (6 * 63 + rank_of(squares[0]) as u64 * 28 + MAP_B1H1H7[squares[1]]) * 62 + sq(2)
+                        - adjust2
+                } else if off_diagonal(squares[2]) != 0 {
+                    6 * 63 * 62
+                        + 4 * 28 * 62
+                        + rank_of(squares[0]) as u64 * 7 * 28
+                        + (rank_of(squares[1]) as u64 - adjust1) * 28
+                        + MAP_B1H1H7[squares[2]]
+                } else {
+                    6 * 63 * 62
+                        + 4 * 28 * 62
+                        + 4 * 7 * 28
+                        + rank_of(squares[0]) as u64 * 6 * 7
+                        + (rank_of(squares[1]) as u64 - adjust1) * 6
+                        + (rank_of(squares[2]) as u64 - adjust2)
+                }
+            } else if S::CONNECTED_KINGS {
+                if off_diagonal(squares[0]) != 0 {
+                    MAP_A1D1D4[squares[0]] * 63 + (sq(1) - adjust1)
+                } else if off_diagonal(squares[1]) != 0 {
+                    6 * 63 + rank_of(squares[0]) as u64 * 28 + MAP_B1H1H7[squares[1]]
+                } else {
+                    6 * 63
+                        + 4 * 28
+                        + rank_of(squares[0]) as u64 * 7
+                        + (rank_of(squares[1]) as u64 - adjust1)
+                }
+            } else {
+                MAP_KK[MAP_A1D1D4[squares[0]] as usize][squares[1]]
+            };
+        }
+
+        // Encode the remaining groups, each relative to the squares not
+        // occupied by previous groups.
+        idx *= data.group_idx[0];
+        let mut remaining_pawns = self.has_pawns
+            && self.material.side(Color::White).pawn > 0
+            && self.material.side(Color::Black).pawn > 0;
+        let mut group_start = data.group_len[0];
+        for (next, &len) in data.group_len.iter().enumerate().skip(1) {
+            squares[group_start..group_start + len].sort_unstable();
+            let mut n = 0;
+            for i in 0..len {
+                let sq = squares[group_start + i];
+                let adjust = squares[..group_start].iter().filter(|&&s| sq > s).count();
+                let sq = sq - adjust - if remaining_pawns { 8 } else { 0 };
+                n += binomial(sq as u64, i as u64 + 1);
+            }
+            remaining_pawns = false;
+            idx += n * data.group_idx[next];
+            group_start += len;
+        }
+
+        Ok(Some((data, idx)))
+    }
+
+    fn probe_wdl<S: Syzygy>(&self, board: &Board, turn: Color) -> io::Result<Wdl> {
+        let (data, idx) = self
+            .encode::<S>(board, turn)?
+            .expect("wdl tables have both sides");
+        let value = data.decompress(&*self.file, idx)?;
+        Wdl::from_value(value).ok_or_else(|| invalid_data("invalid wdl value"))
+    }
+
+    fn probe_dtz<S: Syzygy>(
+        &self,
+        board: &Board,
+        turn: Color,
+        wdl: Wdl,
+    ) -> io::Result<Option<i32>> {
+        let Some((data, idx)) = self.encode::<S>(board, turn)? else {
+            return Ok(None);
+        };
+        let mut value = data.decompress(&*self.file, idx)?;
+
+        if data.flags & flags::MAPPED != 0 {
+            let map = data.dtz_map[match wdl {
+                Wdl::Win | Wdl::Draw => 0,
+                Wdl::Loss => 1,
+                Wdl::CursedWin => 2,
+                Wdl::BlessedLoss => 3,
+            }];
+            value = if data.flags & flags::WIDE != 0 {
+                read_u16_le(&*self.file, map + 2 * u64::from(value))?
+            } else {
+                u16::from(read_u8(&*self.file, map + u64::from(value))?)
+            };
+        }
+
+        let mut value = i32::from(value);
+        if (wdl == Wdl::Win && data.flags & flags::WIN_PLIES == 0)
+            || (wdl == Wdl::Loss && data.flags & flags::LOSS_PLIES == 0)
+            || wdl == Wdl::CursedWin
+            || wdl == Wdl::BlessedLoss
+        {
+            value *= 2;
+        }
+        Ok(Some(value + 1))
+    }
+}
+
+/// A table file, opened lazily on first use.
+#[derive(Debug)]
+struct TableEntry {
+    path: PathBuf,
+    material: MaterialKey,
+    table_type: TableType,
+    table: OnceLock<Table>,
+}
+
+const MAX_DTZ: i32 = 1 << 18;
+
+/// A collection of tables.
+pub struct Tablebase<S> {
+    filesystem: Arc<dyn Filesystem>,
+    wdl: HashMap<MaterialKey, Arc<TableEntry>>,
+    dtz: HashMap<MaterialKey, Arc<TableEntry>>,
+    max_pieces: usize,
+    variant: PhantomData<fn() -> S>,
+}
+
+impl<S> fmt::Debug for Tablebase<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tablebase")
+            .field("wdl", &self.wdl.len())
+            .field("dtz", &self.dtz.len())
+            .field("max_pieces", &self.max_pieces)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Syzygy> Default for Tablebase<S> {
+    fn default() -> Self {
+        Tablebase::new()
+    }
+}
+
+impl<S: Syzygy> Tablebase<S> {
+    /// Creates an empty collection of tables, read from the local file
+    /// system.
+    pub fn new() -> Tablebase<S> {
+        Tablebase::with_filesystem(Arc::new(OsFilesystem))
+    }
+
+    /// Creates an empty collection of tables, read from `filesystem`.
+    pub fn with_filesystem(filesystem: Arc<dyn Filesystem>) -> Tablebase<S> {
+        Tablebase {
+            filesystem,
+            wdl: HashMap::new(),
+            dtz: HashMap::new(),
+            max_pieces: 0,
+            variant: PhantomData,
+        }
+    }
+
+    /// Gets the maximum number of pieces of the added tables.
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    /// Adds all tables of the variant in a directory, and returns how many
+    /// were added. Other files are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the directory can not be listed.
+    pub fn add_directory<P: AsRef<Path>>(&mut self, path: P) -> io::Result<usize> {
+        let mut added = 0;
+        for path in self.filesystem.read_dir(path.as_ref())? {
+            if self.add_file(path).is_ok() {
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    /// Adds a table file. The file is opened when it is first needed.
+    ///
+    /// # Errors
+    ///
+    /// Errors with [`io::ErrorKind::InvalidInput`] if the file name is not
+    /// a table name of the variant (like `KQvK.rtbw`), or if the file is
+    /// not a regular file.
+    pub fn add_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let invalid_input =
+            || io::Error::new(io::ErrorKind::InvalidInput, "not a syzygy table file");
+
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or_else(invalid_input)?;
+        let ext = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .ok_or_else(invalid_input)?;
+        let material: MaterialKey = stem.parse().map_err(|_| invalid_input())?;
+        let pieces = material.count();
+        if pieces < 2
+            || pieces > S::MAX_PIECES
+            || (S::ONE_KING && Color::ALL.iter().any(|&c| material.side(c).king != 1))
+        {
+            return Err(invalid_input());
+        }
+
+        let pawns = material.has_pawns();
+        let (tbw, tbz) = match (pawns, S::PAWNLESS_TBW, S::PAWNLESS_TBZ) {
+            (false, Some(tbw), Some(tbz)) => (tbw, tbz),
+            _ => (S::TBW, S::TBZ),
+        };
+        let (map, table_type) = if ext == tbw.ext {
+            (&mut self.wdl, tbw)
+        } else if ext == tbz.ext {
+            (&mut self.dtz, tbz)
+        } else {
+            return Err(invalid_input());
+        };
+
+        self.filesystem.regular_file_size(path)?;
+
+        let entry = Arc::new(TableEntry {
+            path: path.to_path_buf(),
+            material,
+            table_type,
+            table: OnceLock::new(),
+        });
+        map.insert(material.into_mirrored(), Arc::clone(&entry));
+        map.insert(material, entry);
+        self.max_pieces = self.max_pieces.max(pieces);
+        Ok(())
+    }
+
+    fn table(&self, metric: Metric, board: &Board) -> SyzygyResult<&Table> {
+        let material = board.material_key();
+        let entry = match metric {
+            Metric::Wdl => self.wdl.get(&material),
+            Metric::Dtz => self.dtz.get(&material),
+        }
+        .ok_or(SyzygyError::MissingTable { metric, material })?;
+
+        if let Some(table) = entry.table.get() {
+            return Ok(table);
+        }
+        let table = self
+            .filesystem
+            .open(&entry.path)
+            .and_then(|file| Table::open::<S>(file, metric, entry.material, entry.table_type))
+            .map_err(|error| SyzygyError::ProbeFailed {
+                metric,
+                material: entry.material,
+                error,
+            })?;
+        Ok(entry.table.get_or_init(|| table))
+    }
+
+    fn probe_failed(metric: Metric, board: &Board, error: io::Error) -> SyzygyError {
+        SyzygyError::ProbeFailed {
+            metric,
+            material: board.material_key(),
+            error,
+        }
+    }
+
+    fn probe_wdl_table(&self, pos: &S) -> SyzygyResult<Wdl> {
+        self.table(Metric::Wdl, pos.board())?
+            .probe_wdl::<S>(pos.board(), pos.turn())
+            .map_err(|error| Self::probe_failed(Metric::Wdl, pos.board(), error))
+    }
+
+    fn probe_dtz_table(&self, pos: &S, wdl: Wdl) -> SyzygyResult<Option<i32>> {
+        self.table(Metric::Dtz, pos.board())?
+            .probe_dtz::<S>(pos.board(), pos.turn(), wdl)
+            .map_err(|error| Self::probe_failed(Metric::Dtz, pos.board(), error))
+    }
+
+    fn check(pos: &S) -> SyzygyResult<()> {
+        if pos.castles().any() {
+            return Err(SyzygyError::Castling);
+        }
+        if pos.board().occupied().count() > S::MAX_PIECES {
+            return Err(SyzygyError::TooManyPieces);
+        }
+        Ok(())
+    }
+
+    /// Resolves captures (and pawn moves if `zeroing`), which the tables
+    /// may not store correctly, and probes the WDL table. Also returns
+    /// whether the best move is zeroing, in which case the DTZ table can not
+    /// be used.
+    fn search(&self, pos: &S, zeroing: bool) -> SyzygyResult<(Wdl, bool)> {
+        if let Some(outcome) = pos.outcome() {
+            return Ok((Wdl::from_outcome(outcome, pos.turn()), true));
+        }
+
+        let moves = pos.legal_moves();
+        let mut best = Wdl::Loss;
+        let mut searched = 0;
+        for m in &moves {
+            if !(m.is_capture() || (zeroing && m.role() == Role::Pawn)) {
+                continue;
+            }
+            searched += 1;
+
+            let mut after = pos.clone();
+            after.play_unchecked(m);
+            let (value, _) = self.search(&after, false)?;
+            let value = -value;
+            if value > best {
+                best = value;
+                if value == Wdl::Win {
+                    return Ok((value, true));
+                }
+            }
+        }
+
+        let no_more_moves = searched > 0 && searched == moves.len();
+        let value = if no_more_moves {
+            best
+        } else {
+            self.probe_wdl_table(pos)?
+        };
+
+        Ok(if best >= value {
+            (best, best > Wdl::Draw || no_more_moves)
+        } else {
+            (value, false)
+        })
+    }
+
+    fn dtz(&self, pos: &S) -> SyzygyResult<i32> {
+        let (wdl, zeroing) = self.search(pos, true)?;
+        if wdl == Wdl::Draw {
+            return Ok(0);
+        }
+        if zeroing {
+            return Ok(wdl.before_zeroing());
+        }
+
+        if let Some(dtz) = self.probe_dtz_table(pos, wdl)? {
+            let cursed = matches!(wdl, Wdl::CursedWin | Wdl::BlessedLoss);
+            return Ok((dtz + if cursed { 100 } else { 0 }) * wdl.signum());
+        }
+
+        // The table only stores the other side to move, so look one ply
+        // ahead.
+        let mut min_dtz = None;
+        for m in &pos.legal_moves() {
+            let mut after = pos.clone();
+            after.play_unchecked(m);
+
+            let zeroing = m.is_zeroing();
+            let mut dtz = if zeroing {
+                -self.search(&after, false)?.0.before_zeroing()
+            } else {
+                -self.dtz(&after)?
+            };
+            if dtz == 1 && after.is_checkmate() {
+                min_dtz = Some(1);
+            }
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+
+            if dtz.signum() == wdl.signum() && min_dtz.map_or(true, |min| dtz < min) {
+                min_dtz = Some(dtz);
+            }
+        }
+
+        Ok(min_dtz.unwrap_or(-1))
+    }
+
+    /// Probes the WDL value of a position, assuming the half-move clock
+    /// has just been reset.
+    ///
+    /// # Errors
+    ///
+    /// See [`SyzygyError`].
+    pub fn probe_wdl_after_zeroing(&self, pos: &S) -> SyzygyResult<Wdl> {
+        Self::check(pos)?;
+        self.search(pos, false).map(|(wdl, _)| wdl)
+    }
+
+    /// Probes the WDL value of a position, taking the half-move clock into
+    /// account: wins and losses that can not be converted before the
+    /// 50-move rule applies are reported as cursed wins and blessed losses.
+    ///
+    /// This requires DTZ tables unless the half-move clock is zero. Since
+    /// DTZ values may be rounded, positions very close to the limit can be
+    /// misjudged.
+    ///
+    /// # Errors
+    ///
+    /// See [`SyzygyError`].
+    pub fn probe_wdl(&self, pos: &S) -> SyzygyResult<Wdl> {
+        let wdl = self.probe_wdl_after_zeroing(pos)?;
+        let halfmoves = i64::from(pos.halfmoves());
+        if halfmoves == 0 || !matches!(wdl, Wdl::Win | Wdl::Loss) {
+            return Ok(wdl);
+        }
+        let dtz = i64::from(self.dtz(pos)?);
+        Ok(match wdl {
+            Wdl::Win if dtz + halfmoves > 100 => Wdl::CursedWin,
+            Wdl::Loss if -dtz + halfmoves > 100 => Wdl::BlessedLoss,
+            wdl => wdl,
+        })
+    }
+
+    /// Probes the distance to zeroing of a position.
+    ///
+    /// # Errors
+    ///
+    /// See [`SyzygyError`].
+    pub fn probe_dtz(&self, pos: &S) -> SyzygyResult<Dtz> {
+        Self::check(pos)?;
+        self.dtz(pos).map(Dtz)
+    }
+
+    /// Gets the best moves, together with the distance to zeroing from the
+    /// current position.
+    ///
+    /// Moves are ranked considering the half-move clock: wins that can be
+    /// converted before the 50-move rule applies come first (fastest
+    /// first), and losses that can be drawn by the 50-move rule are
+    /// preferred over other losses (slowest first).
+    ///
+    /// # Errors
+    ///
+    /// See [`SyzygyError`].
+    pub fn best_moves(&self, pos: &S) -> SyzygyResult<Vec<(Move, Dtz)>> {
+        Self::check(pos)?;
+
+        let halfmoves = i32::try_from(pos.halfmoves()).unwrap_or(i32::MAX);
+        let mut ranked = Vec::new();
+        for m in &pos.legal_moves() {
+            let mut after = pos.clone();
+            after.play_unchecked(m);
+
+            let mut dtz = if m.is_zeroing() {
+                (-self.search(&after, false)?.0).before_zeroing()
+            } else if after.halfmoves() >= 100 && !after.is_checkmate() {
+                0
+            } else {
+                let dtz = -self.dtz(&after)?;
+                dtz + dtz.signum()
+            };
+            if dtz == 2 && after.is_checkmate() {
+                dtz = 1;
+            }
+
+            let rank = if dtz > 0 {
+                if dtz.saturating_add(halfmoves) <= 99 {
+                    MAX_DTZ - dtz
+                } else {
+                    MAX_DTZ / 2 - dtz.saturating_add(halfmoves).min(MAX_DTZ / 2)
+                }
+            } else if dtz < 0 {
+                if (-dtz * 2).saturating_add(halfmoves) < 100 {
+                    -MAX_DTZ - dtz
+                } else {
+                    -MAX_DTZ / 2 + (-dtz).saturating_add(halfmoves).min(MAX_DTZ / 2)
+                }
+            } else {
+                0
+            };
+            ranked.push((m.clone(), Dtz(dtz), rank));
+        }
+
+        let best = ranked.iter().map(|&(_, _, rank)| rank).max();
+        Ok(ranked
+            .into_iter()
+            .filter(|&(_, _, rank)| Some(rank) == best)
+            .map(|(m, dtz, _)| (m, dtz))
+            .collect())
+    }
+
+    /// Gets a best move (see [`Tablebase::best_moves()`]), or `None` if
+    /// there are no legal moves.
+    ///
+    /// # Errors
+    ///
+    /// See [`SyzygyError`].
+    pub fn best_move(&self, pos: &S) -> SyzygyResult<Option<(Move, Dtz)>> {
+        Ok(self.best_moves(pos)?.into_iter().next())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString as _;
+
+    use super::*;
+    use crate::{fen::Fen, CastlingMode};
+
+    #[derive(Default)]
+    struct MemoryFilesystem {
+        files: HashMap<PathBuf, Arc<Vec<u8>>>,
+    }
+
+    struct MemoryFile(Arc<Vec<u8>>);
+
+    impl Filesystem for MemoryFilesystem {
+        fn regular_file_size(&self, path: &Path) -> io::Result<u64> {
+            self.files
+                .get(path)
+                .map(|data| data.len() as u64)
+                .ok_or_else(|| io::ErrorKind::NotFound.into())
+        }
+
+        fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+            Ok(self
+                .files
+                .keys()
+                .filter(|file| file.parent() == Some(path))
+                .cloned()
+                .collect())
+        }
+
+        fn open(&self, path: &Path) -> io::Result<Box<dyn RandomAccessFile>> {
+            self.files
+                .get(path)
+                .map(|data| Box::new(MemoryFile(Arc::clone(data))) as Box<dyn RandomAccessFile>)
+                .ok_or_else(|| io::ErrorKind::NotFound.into())
+        }
+    }
+
+    impl RandomAccessFile for MemoryFile {
+        fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+            let start = usize::try_from(offset).map_err(|_| io::ErrorKind::UnexpectedEof)?;
+            let data = self
+                .0
+                .get(start..start + buf.len())
+                .ok_or(io::ErrorKind::UnexpectedEof)?;
+            buf.copy_from_slice(data);
+            Ok(())
+        }
+    }
+
+    /// Builds a table where every position of a side to move has the same
+    /// value.
+    fn single_value_table(
+        table_type: TableType,
+        split: bool,
+        has_pawns: bool,
+        pieces: &[u8],
+        values: &[u8],
+    ) -> Vec<u8> {
+        let mut data = table_type.magic.to_vec();
+        data.push(u8::from(split) | if has_pawns { 2 } else { 0 });
+        let num_files = if has_pawns { 4 } else { 1 };
+        for _ in 0..num_files {
+            data.push(0);
+            data.extend(pieces.iter().map(|&p| p | p << 4));
+        }
+        if data.len() % 2 != 0 {
+            data.push(0);
+        }
+        for _ in 0..num_files {
+            for &value in values {
+                data.extend([flags::SINGLE_VALUE, value]);
+            }
+        }
+        data
+    }
+
+    /// Builds a table for pawnless, asymmetric material, compressing the
+    /// values of each side to move like the generator does: symbols for
+    /// single values, for runs of the most common value and for the most
+    /// common pair of different values, canonical Huffman codes, and small
+    /// blocks that are found with a sparse index.
+    fn compressed_table(
+        table_type: TableType,
+        pieces: &[u8],
+        sides: &[(u8, Vec<u16>)],
+        dtz_map: Option<&[Vec<u8>; 4]>,
+    ) -> Vec<u8> {
+        const BLOCK_SIZE: usize = 32;
+        const SPAN: usize = 16;
+        const LEAF: usize = 0xfff;
+
+        let mut sizes = Vec::new();
+        let mut sparse_index = Vec::new();
+        let mut block_lengths = Vec::new();
+        let mut blocks = Vec::new();
+
+        for (flags, values) in sides {
+            let mut distinct = values.clone();
+            distinct.sort_unstable();
+            distinct.dedup();
+            let common = *distinct
+                .iter()
+                .max_by_key(|&&v| values.iter().filter(|&&w| w == v).count())
+                .expect("values");
+
+            // Symbols as (left, right), where leaves are (value, LEAF).
+            let mut syms: Vec<(usize, usize)> =
+                distinct.iter().map(|&v| (usize::from(v), LEAF)).collect();
+            let leaf = |v: u16| distinct.binary_search(&v).expect("leaf");
+            let pair = syms.len();
+            syms.push((leaf(common), leaf(common)));
+            let quad = syms.len();
+            syms.push((pair, pair));
+            let mut pairs = alloc::collections::BTreeMap::new();
+            for w in values.windows(2).filter(|w| w[0] != w[1]) {
+                *pairs.entry((w[0], w[1])).or_insert(0) += 1;
+            }
+            let (&(a, b), _) = pairs
+                .iter()
+                .max_by_key(|&(_, &count)| count)
+                .expect("distinct values");
+            let mixed = syms.len();
+            syms.push((leaf(a), leaf(b)));
+
+            let mut tokens = Vec::new();
+            let mut i = 0;
+            while i < values.len() {
+                let run = values[i..]
+                    .iter()
+                    .take(4)
+                    .take_while(|&&v| v == common)
+                    .count();
+                let token = match run {
+                    4 => (quad, 4),
+                    2 | 3 => (pair, 2),
+                    _ if values[i..].starts_with(&[a, b]) => (mixed, 2),
+                    _ => (leaf(values[i]), 1),
+                };
+                tokens.push(token);
+                i += token.1;
+            }
+
+            // Huffman code lengths.
+            let mut lengths = vec![0; syms.len()];
+            let mut nodes: Vec<(usize, Vec<usize>)> = (0..syms.len())
+                .map(|sym| (1 + tokens.iter().filter(|t| t.0 == sym).count(), vec![sym]))
+                .collect();
+            while nodes.len() > 1 {
+                nodes.sort_by_key(|&(weight, _)| core::cmp::Reverse(weight));
+                let (a_weight, a) = nodes.pop().expect("node");
+                let (b_weight, b) = nodes.pop().expect("node");
+                for &sym in a.iter().chain(&b) {
+                    lengths[sym] += 1;
+                }
+                nodes.push((a_weight + b_weight, [a, b].concat()));
+            }
+
+            // Canonical numbering: longer codes have lower symbols and
+            // lower values.
+            let mut order: Vec<usize> = (0..syms.len()).collect();
+            order.sort_by_key(|&sym| (core::cmp::Reverse(lengths[sym]), sym));
+            let mut number = vec![0; syms.len()];
+            for (n, &sym) in order.iter().enumerate() {
+                number[sym] = n;
+            }
+            let min_len = *lengths.iter().min().expect("symbols");
+            let max_len = *lengths.iter().max().expect("symbols");
+            let h = max_len - min_len + 1;
+            let count = |i: usize| lengths.iter().filter(|&&len| len == min_len + i).count();
+            let mut lowest_sym = vec![0; h];
+            let mut base = vec![0; h];
+            for i in (0..h - 1).rev() {
+                lowest_sym[i] = lowest_sym[i + 1] + count(i + 1);
+                base[i] = (base[i + 1] + count(i + 1)) / 2;
+            }
+            let code = |sym: usize| {
+                let i = lengths[sym] - min_len;
+                (lengths[sym], base[i] + number[sym] - lowest_sym[i])
+            };
+
+            sizes.push(*flags);
+            sizes.extend([5, 4, 0]); // log2 of BLOCK_SIZE and SPAN
+            let mut block_tokens = vec![Vec::new()];
+            let mut used = 0;
+            for &token in &tokens {
+                // Leave room for the lookahead of the decoder.
+                if used + lengths[token.0] > 8 * BLOCK_SIZE - 64 {
+                    block_tokens.push(Vec::new());
+                    used = 0;
+                }
+                used += lengths[token.0];
+                block_tokens.last_mut().expect("block").push(token);
+            }
+            sizes.extend(
+                u32::try_from(block_tokens.len())
+                    .expect("blocks")
+                    .to_le_bytes(),
+            );
+            sizes.extend([max_len as u8, min_len as u8]);
+            for &lowest in &lowest_sym {
+                sizes.extend(u16::try_from(lowest).expect("symbol").to_le_bytes());
+            }
+            sizes.extend(u16::try_from(syms.len()).expect("symbols").to_le_bytes());
+            for &sym in &order {
+                let (left, right) = match syms[sym] {
+                    (value, LEAF) => (value, LEAF),
+                    (left, right) => (number[left], number[right]),
+                };
+                sizes.extend([
+                    left as u8,
+                    (left >> 8) as u8 | (right << 4) as u8,
+                    (right >> 4) as u8,
+                ]);
+            }
+            if syms.len() % 2 != 0 {
+                sizes.push(0);
+            }
+
+            let mut data = Vec::new();
+            let mut starts = Vec::new();
+            let mut start = 0;
+            for block in &block_tokens {
+                let mut bytes = [0; BLOCK_SIZE];
+                let mut pos = 0;
+                for &(sym, _) in block {
+                    let (len, code) = code(sym);
+                    for bit in (0..len).rev() {
+                        if code >> bit & 1 != 0 {
+                            bytes[pos / 8] |= 0x80 >> (pos % 8);
+                        }
+                        pos += 1;
+                    }
+                }
+                data.extend(bytes);
+                let len: usize = block.iter().map(|&(_, n)| n).sum();
+                block_lengths.extend(u16::try_from(len - 1).expect("block length").to_le_bytes());
+                starts.push(start);
+                start += len;
+            }
+            blocks.push(data);
+
+            // Each entry points to the middle of its span.
+            for k in 0..values.len().div_ceil(SPAN) {
+                let idx = k * SPAN + SPAN / 2;
+                let block = starts.partition_point(|&start| start <= idx) - 1;
+                sparse_index.extend(u32::try_from(block).expect("block").to_le_bytes());
+                sparse_index.extend(
+                    u16::try_from(idx - starts[block])
+                        .expect("offset")
+                        .to_le_bytes(),
+                );
+            }
+        }
+
+        let mut table = table_type.magic.to_vec();
+        table.extend([1, 0]);
+        table.extend(pieces.iter().map(|&p| p | p << 4));
+        if table.len() % 2 != 0 {
+            table.push(0);
+        }
+        table.extend(sizes);
+        if let Some(dtz_map) = dtz_map {
+            for map in dtz_map {
+                table.push(u8::try_from(map.len()).expect("map length"));
+                table.extend(map);
+            }
+            if table.len() % 2 != 0 {
+                table.push(0);
+            }
+        }
+        table.extend(sparse_index);
+        table.extend(block_lengths);
+        for data in blocks {
+            table.resize((table.len() + 0x3f) & !0x3f, 0);
+            table.extend(data);
+        }
+        table
+    }
+
+    fn kqvk() -> Tablebase<Chess> {
+        let mut fs = MemoryFilesystem::default();
+        // White king, white queen, black king.
+        let pieces = [6, 5, 14];
+        fs.files.insert(
+            PathBuf::from("tables/KQvK.rtbw"),
+            Arc::new(single_value_table(
+                Chess::TBW,
+                true,
+                false,
+                &pieces,
+                &[4, 0],
+            )),
+        );
+        fs.files.insert(
+            PathBuf::from("tables/KQvK.rtbz"),
+            Arc::new(single_value_table(Chess::TBZ, true, false, &pieces, &[5])),
+        );
+        fs.files
+            .insert(PathBuf::from("tables/README.txt"), Arc::new(Vec::new()));
+
+        let mut tables = Tablebase::with_filesystem(Arc::new(fs));
+        assert_eq!(tables.add_directory("tables").expect("read dir"), 2);
+        assert_eq!(tables.max_pieces(), 3);
+        tables
+    }
+
+    fn setup_fen(fen: &str) -> Chess {
+        fen.parse::<Fen>()
+            .expect("valid fen")
+            .into_position(CastlingMode::Standard)
+            .expect("legal position")
+    }
+
+    #[test]
+    fn test_tables() {
+        assert_eq!(MAP_KK.iter().flatten().max(), Some(&461));
+        assert_eq!(MAP_PAWNS[usize::from(Square::A2)], 47);
+        assert_eq!(MAP_PAWNS[usize::from(Square::E7)], 0);
+        assert_eq!(LEAD_PAWNS_SIZE[1], [6, 6, 6, 6]);
+        assert_eq!(
+            MAP_PP
+                .iter()
+                .flatten()
+                .filter(|&&code| code != u64::MAX)
+                .max(),
+            Some(&277)
+        );
+        assert_eq!(MAP_PP[0][usize::from(Square::A7)], u64::MAX);
+        assert_eq!(MAP_PP[1][usize::from(Square::H1)], 66);
+        assert_eq!(MULT_FACTOR[..3], [10, 294, 6162]);
+    }
+
+    #[test]
+    fn test_probe() {
+        let tables = kqvk();
+
+        let pos = setup_fen("8/8/8/4k3/8/8/8/KQ6 w - - 0 1");
+        assert_eq!(
+            tables.probe_wdl_after_zeroing(&pos).expect("probe"),
+            Wdl::Win
+        );
+        assert_eq!(tables.probe_dtz(&pos).expect("probe"), Dtz(11));
+
+        let pos = setup_fen("8/8/8/4k3/8/8/8/KQ6 b - - 0 1");
+        assert_eq!(
+            tables.probe_wdl_after_zeroing(&pos).expect("probe"),
+            Wdl::Loss
+        );
+        assert_eq!(tables.probe_dtz(&pos).expect("probe"), Dtz(-12));
+
+        // Mirrored colors.
+        let pos = setup_fen("kq6/8/8/8/4K3/8/8/8 b - - 0 1");
+        assert_eq!(
+            tables.probe_wdl_after_zeroing(&pos).expect("probe"),
+            Wdl::Win
+        );
+        assert_eq!(tables.probe_dtz(&pos).expect("probe"), Dtz(11));
+
+        // The only legal move captures the queen.
+        let pos = setup_fen("8/8/8/8/8/8/kQ6/7K b - - 0 1");
+        assert_eq!(
+            tables.probe_wdl_after_zeroing(&pos).expect("probe"),
+            Wdl::Draw
+        );
+        assert_eq!(tables.probe_dtz(&pos).expect("probe"), Dtz(0));
+
+        // 50-move rule.
+        let pos = setup_fen("8/8/8/4k3/8/8/8/KQ6 w - - 95 100");
+        assert_eq!(tables.probe_wdl(&pos).expect("probe"), Wdl::CursedWin);
+        let pos = setup_fen("8/8/8/4k3/8/8/8/KQ6 w - - 80 100");
+        assert_eq!(tables.probe_wdl(&pos).expect("probe"), Wdl::Win);
+    }
+
+    #[test]
+    fn test_compressed() {
+        fn for_each_position(step: usize, mut f: impl FnMut(&Board)) {
+            for white_king in Square::ALL.into_iter().step_by(step) {
+                for queen in Square::ALL.into_iter().filter(|&sq| sq != white_king) {
+                    for black_king in Square::ALL
+                        .into_iter()
+                        .filter(|&sq| sq != white_king && sq != queen)
+                    {
+                        let mut board = Board::empty();
+                        board.set_piece_at(white_king, Color::White.king());
+                        board.set_piece_at(queen, Color::White.queen());
+                        board.set_piece_at(black_king, Color::Black.king());
+                        f(&board);
+                    }
+                }
+            }
+        }
+
+        // Values that depend on the position, but not on its symmetries.
+        fn distances(board: &Board) -> (u16, u16) {
+            let black_king = board.king_of(Color::Black).expect("black king");
+            let white_king = board.king_of(Color::White).expect("white king");
+            let queen = board.by_piece(Color::White.queen()).first().expect("queen");
+            (
+                white_king.distance(black_king) as u16,
+                queen.distance(black_king) as u16,
+            )
+        }
+        fn wdl(board: &Board, turn: Color) -> u16 {
+            let (kings, queen) = distances(board);
+            match turn {
+                Color::White => (kings + queen) % 5,
+                Color::Black => queen % 3 * 2,
+            }
+        }
+        fn dtz(board: &Board) -> u16 {
+            let (kings, queen) = distances(board);
+            kings + queen
+        }
+
+        let material: MaterialKey = "KQvK".parse().expect("material");
+        let pieces = [6, 5, 14];
+        let open = |table_type: TableType, metric: Metric, data: Vec<u8>| {
+            Table::open::<Chess>(
+                Box::new(MemoryFile(Arc::new(data))),
+                metric,
+                material,
+                table_type,
+            )
+            .expect("open")
+        };
+
+        // Index all positions, and use the last value for unused indexes.
+        let index = open(
+            Chess::TBW,
+            Metric::Wdl,
+            single_value_table(Chess::TBW, true, false, &pieces, &[4, 0]),
+        );
+        let size = index.files[0][0].tb_size() as usize;
+        let mut wdl_values = [vec![None; size], vec![None; size]];
+        let mut dtz_values = vec![None; size];
+        for_each_position(1, |board| {
+            for turn in Color::ALL {
+                let (_, idx) = index
+                    .encode::<Chess>(board, turn)
+                    .expect("encode")
+                    .expect("wdl");
+                let idx = idx as usize;
+                let slot = &mut wdl_values[usize::from(turn == Color::Black)][idx];
+                assert!(slot.map_or(true, |v| v == wdl(board, turn)), "{board:?}");
+                *slot = Some(wdl(board, turn));
+                if turn == Color::White {
+                    dtz_values[idx] = Some(dtz(board));
+                }
+            }
+        });
+        let fill = |values: Vec<Option<u16>>| -> Vec<u16> {
+            values
+                .into_iter()
+                .scan(0, |last, v| {
+                    *last = v.unwrap_or(*last);
+                    Some(*last)
+                })
+                .collect()
+        };
+        let [white, black] = wdl_values;
+        let wdl_table = compressed_table(
+            Chess::TBW,
+            &pieces,
+            &[(0, fill(white)), (0, fill(black))],
+            None,
+        );
+        let dtz_map: [Vec<u8>; 4] =
+            core::array::from_fn(|map| (0..15).map(|v| v * (map as u8 + 1)).collect());
+        let dtz_table = compressed_table(
+            Chess::TBZ,
+            &pieces,
+            &[(flags::MAPPED | flags::WIN_PLIES, fill(dtz_values))],
+            Some(&dtz_map),
+        );
+
+        let table = open(Chess::TBW, Metric::Wdl, wdl_table.clone());
+        assert!(table.files[0].iter().all(|data| data.num_blocks > 100));
+        for_each_position(3, |board| {
+            let mirrored = board.clone().into_mirrored();
+            for turn in Color::ALL {
+                let expected = Wdl::from_value(wdl(board, turn));
+                assert_eq!(table.probe_wdl::<Chess>(board, turn).ok(), expected);
+                assert_eq!(table.probe_wdl::<Chess>(&mirrored, !turn).ok(), expected);
+            }
+        });
+
+        let table = open(Chess::TBZ, Metric::Dtz, dtz_table.clone());
+        for_each_position(3, |board| {
+            let value = i32::from(dtz(board));
+            let probe =
+                |turn: Color, wdl: Wdl| table.probe_dtz::<Chess>(board, turn, wdl).expect("probe");
+            assert_eq!(probe(Color::White, Wdl::Win), Some(value + 1));
+            assert_eq!(probe(Color::White, Wdl::Loss), Some(4 * value + 1));
+            assert_eq!(probe(Color::White, Wdl::CursedWin), Some(6 * value + 1));
+            assert_eq!(probe(Color::Black, Wdl::Win), None);
+        });
+
+        let mut fs = MemoryFilesystem::default();
+        fs.files
+            .insert(PathBuf::from("KQvK.rtbw"), Arc::new(wdl_table));
+        fs.files
+            .insert(PathBuf::from("KQvK.rtbz"), Arc::new(dtz_table));
+        let mut tables = Tablebase::<Chess>::with_filesystem(Arc::new(fs));
+        tables.add_file("KQvK.rtbw").expect("add file");
+        tables.add_file("KQvK.rtbz").expect("add file");
+
+        let pos = setup_fen("8/8/8/4k3/8/8/8/KQ6 w - - 0 1");
+        assert_eq!(
+            tables.probe_wdl_after_zeroing(&pos).expect("probe"),
+            Wdl::CursedWin
+        );
+        assert_eq!(tables.probe_dtz(&pos).expect("probe"), Dtz(149));
+        let pos = setup_fen("8/8/8/4k3/8/8/8/KQ6 b - - 0 1");
+        assert_eq!(
+            tables.probe_wdl_after_zeroing(&pos).expect("probe"),
+            Wdl::Draw
+        );
+        assert_eq!(tables.probe_dtz(&pos).expect("probe"), Dtz(0));
+    }
+
+    #[test]
+    fn test_best_moves() {
+        let tables = kqvk();
+
+        let pos = setup_fen("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1");
+        let best = tables.best_moves(&pos).expect("probe");
+        assert_eq!(best.len(), 1);
+        for (m, dtz) in best {
+            assert_eq!(dtz, Dtz(1));
+            assert!(pos.clone().play(&m).expect("legal").is_checkmate());
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let tables = kqvk();
+
+        assert!(matches!(
+            tables.probe_wdl(&Chess::default()),
+            Err(SyzygyError::Castling)
+        ));
+        assert!(matches!(
+            tables.probe_wdl(&setup_fen(
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"
+            )),
+            Err(SyzygyError::TooManyPieces)
+        ));
+        match tables.probe_wdl(&setup_fen("8/8/8/4k3/8/8/8/KR6 w - - 0 1")) {
+            Err(SyzygyError::MissingTable { metric, material }) => {
+                assert_eq!(metric, Metric::Wdl);
+                assert_eq!(material.to_string(), "KRvK");
+            }
+            res => panic!("unexpected {res:?}"),
+        }
+
+        let mut fs = MemoryFilesystem::default();
+        fs.files
+            .insert(PathBuf::from("KRvK.rtbw"), Arc::new(vec![0; 16]));
+        let mut tables = Tablebase::<Chess>::with_filesystem(Arc::new(fs));
+        tables.add_file("KRvK.rtbw").expect("add file");
+        assert!(tables.add_file("KRvK.atbw").is_err());
+        assert!(tables.add_file("KvKN.rtbw").is_err());
+        assert!(matches!(
+            tables.probe_wdl(&setup_fen("8/8/8/4k3/8/8/8/KR6 w - - 0 1")),
+            Err(SyzygyError::ProbeFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_encode() {
+        /// Checks that all placements of the remaining pieces are encoded
+        /// within the size of the table.
+        fn check<S: Syzygy>(table: &Table, board: &mut Board, pieces: &[(Piece, &[Square])]) {
+            let Some(&(piece, squares)) = pieces.first() else {
+                let (data, idx) = table
+                    .encode::<S>(board, Color::White)
+                    .expect("encode")
+                    .expect("wdl");
+                assert!(idx < data.tb_size(), "{idx} out of range for {board:?}");
+                return;
+            };
+            for &sq in squares {
+                if board.piece_at(sq).is_none() {
+                    board.set_piece_at(sq, piece);
+                    check::<S>(table, board, &pieces[1..]);
+                    board.discard_piece_at(sq);
+                }
+            }
+        }
+
+        let open = |name: &str, split: bool, has_pawns: bool, pieces: &[u8]| {
+            let material = name.parse().expect("material");
+            let data = single_value_table(Chess::TBW, split, has_pawns, pieces, &[4, 0]);
+            Table::open::<Chess>(
+                Box::new(MemoryFile(Arc::new(data))),
+                Metric::Wdl,
+                material,
+                Chess::TBW,
+            )
+            .expect("open")
+        };
+
+        let every =
+            |step: usize| -> Vec<Square> { Square::ALL.iter().copied().step_by(step).collect() };
+        let all = every(1);
+        let some = every(3);
+        let pawn_squares: Vec<Square> = Square::ALL[8..56].iter().copied().step_by(3).collect();
+
+        let table = open("KQvK", true, false, &[6, 5, 14]);
+        check::<Chess>(
+            &table,
+            &mut Board::empty(),
+            &[
+                (Color::White.king(), &all),
+                (Color::White.queen(), &some),
+                (Color::Black.king(), &all),
+            ],
+        );
+
+        let table = open("KRRvK", true, false, &[6, 14, 4, 4]);
+        check::<Chess>(
+            &table,
+            &mut Board::empty(),
+            &[
+                (Color::White.king(), &all),
+                (Color::Black.king(), &some),
+                (Color::White.rook(), &some),
+                (Color::White.rook(), &some),
+            ],
+        );
+
+        let table = open("KPvK", true, true, &[1, 6, 14]);
+        check::<Chess>(
+            &table,
+            &mut Board::empty(),
+            &[
+                (Color::White.pawn(), &pawn_squares),
+                (Color::White.king(), &all),
+                (Color::Black.king(), &some),
+            ],
+        );
+    }
+
+    #[cfg(feature = "variant")]
+    #[test]
+    fn test_encode_like_pieces() {
+        use std::collections::HashSet;
+
+        use crate::variant::Antichess;
+
+        let table_type = Antichess::PAWNLESS_TBW.expect("pawnless tables");
+        let open = |name: &str, pieces: &[u8]| {
+            let data = single_value_table(table_type, true, false, pieces, &[4, 0]);
+            Table::open::<Antichess>(
+                Box::new(MemoryFile(Arc::new(data))),
+                Metric::Wdl,
+                name.parse().expect("material"),
+                table_type,
+            )
+            .expect("open")
+        };
+
+        /// Checks that the index is in range, and that the index of the
+        /// leading group is the same for all symmetries of the board.
+        fn encode(table: &Table, board: &Board) -> u64 {
+            let factor = table.files[0][0].group_idx[1];
+            let mut indexes = (0..8).map(|symmetry| {
+                let mut mirrored = Board::empty();
+                for (sq, piece) in board.clone() {
+                    let sq = transform(usize::from(sq), symmetry);
+                    mirrored.set_piece_at(Square::new(sq as u32), piece);
+                }
+                let (data, idx) = table
+                    .encode::<Antichess>(&mirrored, Color::White)
+                    .expect("encode")
+                    .expect("wdl");
+                assert!(idx < data.tb_size(), "{idx} out of range for {mirrored:?}");
+                idx % factor
+            });
+            let idx = indexes.next().expect("identity");
+            assert!(indexes.all(|other| other == idx), "{board:?}");
+            idx
+        }
+
+        let place = |board: &mut Board, piece: Piece, squares: &[Square]| {
+            for &sq in squares {
+                board.set_piece_at(sq, piece);
+            }
+        };
+
+        // All placements of two like pieces use exactly the 278 indexes of
+        // the leading group.
+        let table = open("RRvBB", &[4, 4, 11, 11]);
+        let mut leading = HashSet::new();
+        for a in Square::ALL {
+            for b in Square::ALL.into_iter().filter(|&b| b > a) {
+                let mut board = Board::empty();
+                place(&mut board, Color::White.rook(), &[a, b]);
+                let free: Vec<Square> = (!board.occupied()).into_iter().take(2).collect();
+                place(&mut board, Color::Black.bishop(), &free);
+                leading.insert(encode(&table, &board));
+            }
+        }
+        assert_eq!(leading.len(), 278);
+
+        for (rooks, bishops) in [
+            ([Square::B1, Square::G7], [Square::A1, Square::H8]),
+            ([Square::D4, Square::E5], [Square::C3, Square::F6]),
+            ([Square::A1, Square::H8], [Square::D4, Square::E5]),
+        ] {
+            let mut board = Board::empty();
+            place(&mut board, Color::White.rook(), &rooks);
+            place(&mut board, Color::Black.bishop(), &bishops);
+            encode(&table, &board);
+        }
+
+        // Three like pieces.
+        let table = open("RRRvBBB", &[4, 4, 4, 11, 11, 11]);
+        for a in Square::ALL.into_iter().step_by(5) {
+            for b in Square::ALL.into_iter().step_by(7).filter(|&b| b > a) {
+                for c in Square::ALL.into_iter().step_by(3).filter(|&c| c > b) {
+                    let mut board = Board::empty();
+                    place(&mut board, Color::White.rook(), &[a, b, c]);
+                    let free: Vec<Square> = (!board.occupied()).into_iter().rev().take(3).collect();
+                    place(&mut board, Color::Black.bishop(), &free);
+                    encode(&table, &board);
+                }
+            }
+        }
+    }
+}
@@ -230,8 +230,199 @@ impl Display for Move {
     }
 }
 
+/// Lossless notation of a [`Move`] for serialization. Like [`Display`], but
+/// also names captured pieces like reversible algebraic notation, e.g.
+/// `Bd3xNh7` or `e4xPd5`, and castling squares, e.g. `O-O/e1h1`.
+#[cfg(feature = "serde")]
+struct SerdeMove<'a>(&'a Move);
+
+#[cfg(feature = "serde")]
+impl Display for SerdeMove<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self.0 {
+            Move::Normal {
+                role,
+                from,
+                capture,
+                to,
+                promotion,
+            } => {
+                if role != Role::Pawn {
+                    f.write_char(role.upper_char())?;
+                }
+                write!(f, "{from}")?;
+                match capture {
+                    Some(capture) => write!(f, "x{}", capture.upper_char())?,
+                    None => f.write_char('-')?,
+                }
+                write!(f, "{to}")?;
+                if let Some(p) = promotion {
+                    write!(f, "={}", p.upper_char())?;
+                }
+                Ok(())
+            }
+            Move::Castle { king, rook } => write!(
+                f,
+                "{}/{king}{rook}",
+                if king < rook { "O-O" } else { "O-O-O" }
+            ),
+            Move::EnPassant { .. } | Move::Put { .. } => self.0.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Move {
+    fn from_serde_ascii(s: &[u8]) -> Option<Move> {
+        fn upper_role(ch: u8) -> Option<Role> {
+            if ch.is_ascii_uppercase() {
+                Role::from_char(char::from(ch))
+            } else {
+                None
+            }
+        }
+
+        let castle = |s: &[u8], queen_side: bool| {
+            let king = Square::from_ascii(s.get(..2)?).ok()?;
+            let rook = Square::from_ascii(&s[2..]).ok()?;
+            (queen_side != (king < rook)).then_some(Move::Castle { king, rook })
+        };
+        if let Some(s) = s.strip_prefix(b"O-O-O/") {
+            return castle(s, true);
+        } else if let Some(s) = s.strip_prefix(b"O-O/") {
+            return castle(s, false);
+        }
+
+        let (role, s) = match s.split_first() {
+            Some((&ch, rest)) if ch != b'P' && upper_role(ch).is_some() => (upper_role(ch)?, rest),
+            _ => (Role::Pawn, s),
+        };
+
+        if let Some(to) = s.strip_prefix(b"@") {
+            return Some(Move::Put {
+                role,
+                to: Square::from_ascii(to).ok()?,
+            });
+        }
+
+        let from = Square::from_ascii(s.get(..2)?).ok()?;
+        let (capture, s) = match s[2..] {
+            [b'-', ref rest @ ..] => (None, rest),
+            [b'x', ch, ref rest @ ..] if ch.is_ascii_uppercase() => (Some(upper_role(ch)?), rest),
+            [b'x', ref rest @ ..] if role == Role::Pawn => {
+                return Some(Move::EnPassant {
+                    from,
+                    to: Square::from_ascii(rest).ok()?,
+                });
+            }
+            _ => return None,
+        };
+
+        let to = Square::from_ascii(s.get(..2)?).ok()?;
+        let promotion = match s[2..] {
+            [] => None,
+            [b'=', ch] => Some(upper_role(ch)?),
+            _ => return None,
+        };
+
+        Some(Move::Normal {
+            role,
+            from,
+            capture,
+            to,
+            promotion,
+        })
+    }
+
+    /// Packs the move into 23 bits: the destination (or rook) square, the
+    /// origin (or king) square, the roles of the moved, captured and
+    /// promoted pieces (`0` if none), and the kind of move.
+    fn to_compact(&self) -> u32 {
+        let role = |role: Option<Role>| role.map_or(0, u32::from);
+        let (kind, from, to, moved, capture, promotion) = match *self {
+            Move::Normal {
+                role: moved,
+                from,
+                capture,
+                to,
+                promotion,
+            } => (0, from, to, Some(moved), capture, promotion),
+            Move::EnPassant { from, to } => (1, from, to, None, None, None),
+            Move::Castle { king, rook } => (2, king, rook, None, None, None),
+            Move::Put { role, to } => (3, Square::A1, to, Some(role), None, None),
+        };
+        u32::from(to)
+            | u32::from(from) << 6
+            | role(moved) << 12
+            | role(capture) << 15
+            | role(promotion) << 18
+            | kind << 21
+    }
+
+    fn from_compact(n: u32) -> Option<Move> {
+        let square = |shift: u32| Square::new((n >> shift) & 63);
+        let role = |shift: u32| match (n >> shift) & 7 {
+            0 => Ok(None),
+            r => Role::try_from(r).map(Some),
+        };
+        let (from, to) = (square(6), square(0));
+        let m = match n >> 21 {
+            0 => Move::Normal {
+                role: role(12).ok()??,
+                from,
+                capture: role(15).ok()?,
+                to,
+                promotion: role(18).ok()?,
+            },
+            1 => Move::EnPassant { from, to },
+            2 => Move::Castle {
+                king: from,
+                rook: to,
+            },
+            3 => Move::Put {
+                role: role(12).ok()??,
+                to,
+            },
+            _ => return None,
+        };
+        // Reject non-canonical encodings.
+        (m.to_compact() == n).then_some(m)
+    }
+}
+
+/// Serializes in a lossless variant of long algebraic notation like `Nb1-c3`,
+/// `Bd3xNh7`, `e5xd6` (en passant), `O-O/e1h1` or `Q@g8` in human-readable
+/// formats, and as a packed integer otherwise.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Move {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(&SerdeMove(self))
+        } else {
+            serializer.serialize_u32(self.to_compact())
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Move {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Move, D::Error> {
+        use serde::{de, de::Error as _};
+
+        if deserializer.is_human_readable() {
+            crate::util::deserialize_ascii(deserializer, "move", Move::from_serde_ascii)
+        } else {
+            let n = u32::deserialize(deserializer)?;
+            Move::from_compact(n).ok_or_else(|| {
+                D::Error::invalid_value(de::Unexpected::Unsigned(u64::from(n)), &"packed move")
+            })
+        }
+    }
+}
+
 /// `Standard` or `Chess960`.
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CastlingMode {
     /// Castling notation and validity requirements for standard chess.
     ///
@@ -317,6 +508,113 @@ mod tests {
     fn test_move_size() {
         assert!(mem::size_of::<Move>() <= 8);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_move_serde() {
+        use serde_test::{assert_de_tokens_error, assert_tokens, Configure as _, Token};
+
+        for (m, readable, compact) in [
+            (
+                Move::Normal {
+                    role: Role::Knight,
+                    from: Square::B1,
+                    capture: None,
+                    to: Square::C3,
+                    promotion: None,
+                },
+                "Nb1-c3",
+                0x2052,
+            ),
+            (
+                Move::Normal {
+                    role: Role::Pawn,
+                    from: Square::E7,
+                    capture: Some(Role::Rook),
+                    to: Square::D8,
+                    promotion: Some(Role::Queen),
+                },
+                "e7xRd8=Q",
+                0x16_1d3b,
+            ),
+            (
+                Move::Normal {
+                    role: Role::Bishop,
+                    from: Square::D3,
+                    capture: Some(Role::Pawn),
+                    to: Square::H7,
+                    promotion: None,
+                },
+                "Bd3xPh7",
+                0xb4f7,
+            ),
+            (
+                Move::EnPassant {
+                    from: Square::E5,
+                    to: Square::D6,
+                },
+                "e5xd6",
+                0x20_092b,
+            ),
+            (
+                Move::Castle {
+                    king: Square::E1,
+                    rook: Square::A1,
+                },
+                "O-O-O/e1a1",
+                0x40_0100,
+            ),
+            (
+                Move::Castle {
+                    king: Square::E8,
+                    rook: Square::H8,
+                },
+                "O-O/e8h8",
+                0x40_0f3f,
+            ),
+            (
+                Move::Put {
+                    role: Role::Pawn,
+                    to: Square::E4,
+                },
+                "@e4",
+                0x60_101c,
+            ),
+            (
+                Move::Put {
+                    role: Role::Queen,
+                    to: Square::G8,
+                },
+                "Q@g8",
+                0x60_503e,
+            ),
+        ] {
+            assert_tokens(&m.clone().readable(), &[Token::Str(readable)]);
+            assert_tokens(&m.compact(), &[Token::U32(compact)]);
+        }
+
+        for (invalid, error) in [
+            ("Pe2-e4", "invalid value: string \"Pe2-e4\", expected move"),
+            ("e2e4", "invalid value: string \"e2e4\", expected move"),
+            ("Ng1xg3", "invalid value: string \"Ng1xg3\", expected move"),
+            (
+                "O-O/e1a1",
+                "invalid value: string \"O-O/e1a1\", expected move",
+            ),
+            ("e2-e4=", "invalid value: string \"e2-e4=\", expected move"),
+            ("Q@g9", "invalid value: string \"Q@g9\", expected move"),
+        ] {
+            assert_de_tokens_error::<serde_test::Readable<Move>>(&[Token::Str(invalid)], error);
+        }
+        assert_de_tokens_error::<serde_test::Compact<Move>>(
+            &[Token::U32(0x80_0000)],
+            "invalid value: integer `8388608`, expected packed move",
+        );
+        assert_de_tokens_error::<serde_test::Compact<Move>>(
+            &[Token::U32(0x20_192b)],
+            "invalid value: integer `2103595`, expected packed move",
+        );
+    }
 }
 
 /// The number of checks the respective side needs to give in order to win
@@ -62,10 +62,81 @@
 //! assert_eq!(uci.to_string(), "b1c3");
 //! ```
 //!
+//! # Protocol
+//!
+//! With the `alloc` feature, `UciCommand` and `UciResponse` model the
+//! commands sent from the GUI to the engine and the engine's responses.
+//! Both can be parsed and written. With the `std` feature, `UciEngine`
+//! drives an engine from the GUI side.
+//!
+//! On the engine side:
+//!
+//! ```
+//! # #[cfg(all(feature = "alloc", feature = "variant"))] {
+//! use core::time::Duration;
+//!
+//! use shakmaty::{
+//!     uci::{UciCommand, UciResponse},
+//!     variant::Variant,
+//!     CastlingMode, Position,
+//! };
+//!
+//! let mut variant = Variant::Chess;
+//! let mut mode = CastlingMode::Standard;
+//! let mut pos = None;
+//!
+//! for line in [
+//!     "setoption name UCI_Variant value kingofthehill",
+//!     "setoption name UCI_Chess960 value true",
+//!     "position startpos moves e2e4 e7e5 g1f3 b8c6 f1c4 g8f6",
+//!     "go movetime 1000",
+//! ] {
+//!     match line.parse::<UciCommand>() {
+//!         Ok(UciCommand::SetOption(option)) => {
+//!             variant = option.variant().unwrap_or(variant);
+//!             mode = option.castling_mode().unwrap_or(mode);
+//!         }
+//!         Ok(UciCommand::Position(position)) => {
+//!             pos = position.to_position(variant, mode).ok();
+//!         }
+//!         Ok(UciCommand::Go(go)) => {
+//!             assert_eq!(go.movetime, Some(Duration::from_secs(1)));
+//!             let pos = pos.as_ref().expect("position");
+//!             assert_eq!(pos.variant(), Variant::KingOfTheHill);
+//!
+//!             // Castling moves are written according to UCI_Chess960.
+//!             let legals = pos.legal_moves();
+//!             let m = legals.iter().find(|m| m.is_castle()).expect("castling");
+//!             let response = UciResponse::BestMove {
+//!                 m: Some(m.to_uci(mode)),
+//!                 ponder: None,
+//!             };
+//!             assert_eq!(response.to_string(), "bestmove e1h1");
+//!         }
+//!         _ => (),
+//!     }
+//! }
+//! # }
+//! ```
+//!
 //! [`Move`]: super::Move
 
+#[cfg(feature = "std")]
+mod engine;
+#[cfg(feature = "alloc")]
+mod protocol;
+
 use core::{fmt, str::FromStr};
 
+#[cfg(feature = "std")]
+pub use self::engine::{Handshake, Search, SearchInfo, UciEngine, UciEngineError};
+#[cfg(all(feature = "alloc", feature = "variant"))]
+pub use self::protocol::UciPositionError;
+#[cfg(feature = "alloc")]
+pub use self::protocol::{
+    Go, IdKey, Info, ParseUciCommandError, ParseUciResponseError, Score, ScoreBound, SetOption,
+    UciCommand, UciOption, UciOptionKind, UciPosition, UciResponse,
+};
 use crate::{util::AppendAscii, CastlingMode, CastlingSide, Move, Position, Rank, Role, Square};
 
 /// Error when parsing an invalid UCI move.
@@ -132,6 +203,20 @@ impl fmt::Display for UciMove {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for UciMove {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for UciMove {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<UciMove, D::Error> {
+        crate::util::deserialize_ascii(deserializer, "UCI move", |s| UciMove::from_ascii(s).ok())
+    }
+}
+
 impl UciMove {
     /// Parses a move in UCI notation.
     ///
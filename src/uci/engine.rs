@@ -0,0 +1,540 @@
+use std::{
+    error::Error,
+    fmt,
+    io::{self, BufRead, Write},
+    string::String,
+    time::{Duration, Instant},
+    vec::Vec,
+};
+
+use super::{
+    Go, IdKey, Info, Score, SetOption, UciCommand, UciMove, UciOption, UciPosition, UciResponse,
+};
+use crate::{CastlingMode, FromSetup, Move, Position, PositionError, Setup};
+
+/// Error when communicating with a [`UciEngine`].
+#[derive(Debug)]
+pub enum UciEngineError {
+    /// Reading from or writing to the engine failed, or the engine closed
+    /// its output.
+    Io(io::Error),
+    /// The start position is not legal.
+    Position(PositionError<()>),
+    /// One of the moves played from the start position is not legal.
+    IllegalMove(Move),
+    /// The engine sent an illegal best move.
+    IllegalBestMove(UciMove),
+}
+
+impl fmt::Display for UciEngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UciEngineError::Io(error) => write!(f, "uci engine i/o error: {error}"),
+            UciEngineError::Position(error) => error.fmt(f),
+            UciEngineError::IllegalMove(m) => write!(f, "illegal move: {m}"),
+            UciEngineError::IllegalBestMove(uci) => write!(f, "illegal best move: {uci}"),
+        }
+    }
+}
+
+impl Error for UciEngineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UciEngineError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for UciEngineError {
+    fn from(error: io::Error) -> UciEngineError {
+        UciEngineError::Io(error)
+    }
+}
+
+/// What the engine announced in response to `uci`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Handshake {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub options: Vec<UciOption>,
+}
+
+/// An `info` line sent during a search.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SearchInfo {
+    pub info: Info,
+    /// The principal variation of [`Info::pv`], converted to legal moves. It
+    /// is cut off at the first move that is not legal.
+    pub pv: Vec<Move>,
+}
+
+/// The result of [`UciEngine::search()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Search {
+    /// The best move, or `None` if the engine found no legal moves.
+    pub best_move: Option<Move>,
+    /// The expected reply, if the engine sent a legal one.
+    pub ponder: Option<Move>,
+    /// All `info` lines sent during the search.
+    pub infos: Vec<SearchInfo>,
+    /// The time from sending `go` until receiving `bestmove`.
+    pub elapsed: Duration,
+}
+
+impl Search {
+    /// Gets the last score reported for the main line.
+    pub fn score(&self) -> Option<Score> {
+        self.infos
+            .iter()
+            .rev()
+            .filter(|search_info| search_info.info.multipv.unwrap_or(1) == 1)
+            .find_map(|search_info| search_info.info.score)
+    }
+}
+
+/// Drives a UCI engine from the GUI side, over any pair of reader and writer.
+///
+/// # Examples
+///
+/// Running an engine in a child process:
+///
+/// ```no_run
+/// use std::{
+///     io::BufReader,
+///     process::{Command, Stdio},
+///     time::Duration,
+/// };
+///
+/// use shakmaty::{
+///     uci::{Go, UciEngine},
+///     CastlingMode, Chess, Setup,
+/// };
+///
+/// let mut child = Command::new("stockfish")
+///     .stdin(Stdio::piped())
+///     .stdout(Stdio::piped())
+///     .spawn()?;
+/// let stdout = BufReader::new(child.stdout.take().expect("stdout"));
+/// let stdin = child.stdin.take().expect("stdin");
+///
+/// let mut engine = UciEngine::new(stdout, stdin);
+/// let handshake = engine.handshake()?;
+/// println!("playing against {:?}", handshake.name);
+/// engine.set_option("Threads", Some("2"))?;
+/// engine.new_game()?;
+///
+/// let search = engine.search::<Chess>(
+///     &Setup::default(),
+///     &[],
+///     CastlingMode::Standard,
+///     &Go {
+///         movetime: Some(Duration::from_millis(100)),
+///         ..Go::default()
+///     },
+/// )?;
+/// println!("best move: {:?}, score: {:?}", search.best_move, search.score());
+///
+/// engine.quit()?;
+/// child.wait()?;
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct UciEngine<R, W> {
+    reader: R,
+    writer: W,
+    line: String,
+}
+
+impl<R: BufRead, W: Write> UciEngine<R, W> {
+    /// Creates a driver that reads the engine's responses from `reader` and
+    /// writes commands to `writer`.
+    pub fn new(reader: R, writer: W) -> UciEngine<R, W> {
+        UciEngine {
+            reader,
+            writer,
+            line: String::new(),
+        }
+    }
+
+    /// Gets back the reader and writer.
+    pub fn into_inner(self) -> (R, W) {
+        (self.reader, self.writer)
+    }
+
+    /// Sends a command and flushes the writer.
+    ///
+    /// # Errors
+    ///
+    /// Errors if writing to the engine fails.
+    pub fn send(&mut self, command: &UciCommand) -> io::Result<()> {
+        writeln!(self.writer, "{command}")?;
+        self.writer.flush()
+    }
+
+    /// Waits for the next response, skipping lines that can not be parsed.
+    ///
+    /// # Errors
+    ///
+    /// Errors if reading from the engine fails, or with
+    /// [`io::ErrorKind::UnexpectedEof`] if the engine closed its output.
+    pub fn recv(&mut self) -> io::Result<UciResponse> {
+        loop {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "engine closed its output",
+                ));
+            }
+            if let Ok(response) = self.line.parse() {
+                return Ok(response);
+            }
+        }
+    }
+
+    /// Sends `uci` and collects the engine's name, author and options until
+    /// `uciok`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if communicating with the engine fails.
+    pub fn handshake(&mut self) -> io::Result<Handshake> {
+        self.send(&UciCommand::Uci)?;
+        let mut handshake = Handshake::default();
+        loop {
+            match self.recv()? {
+                UciResponse::Id {
+                    key: IdKey::Name,
+                    value,
+                } => handshake.name = Some(value),
+                UciResponse::Id {
+                    key: IdKey::Author,
+                    value,
+                } => handshake.author = Some(value),
+                UciResponse::Option(option) => handshake.options.push(option),
+                UciResponse::UciOk => return Ok(handshake),
+                _ => (),
+            }
+        }
+    }
+
+    /// Sends `setoption`. The value is `None` for buttons.
+    ///
+    /// # Errors
+    ///
+    /// Errors if writing to the engine fails.
+    pub fn set_option(&mut self, name: &str, value: Option<&str>) -> io::Result<()> {
+        self.send(&UciCommand::SetOption(SetOption {
+            name: name.into(),
+            value: value.map(Into::into),
+        }))
+    }
+
+    /// Sends `isready` and waits for `readyok`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if communicating with the engine fails.
+    pub fn is_ready(&mut self) -> io::Result<()> {
+        self.send(&UciCommand::IsReady)?;
+        while self.recv()? != UciResponse::ReadyOk {}
+        Ok(())
+    }
+
+    /// Sends `ucinewgame` and waits until the engine is ready.
+    ///
+    /// # Errors
+    ///
+    /// Errors if communicating with the engine fails.
+    pub fn new_game(&mut self) -> io::Result<()> {
+        self.send(&UciCommand::UciNewGame)?;
+        self.is_ready()
+    }
+
+    /// Sends the position reached by playing `moves` from `setup`, runs
+    /// `go` and waits for `bestmove`.
+    ///
+    /// Castling moves are sent according to `mode`, which should match the
+    /// `UCI_Chess960` option of the engine. The best move, ponder move and
+    /// principal variations are converted back to legal moves of `P`.
+    ///
+    /// Searches with [`Go::infinite`] or [`Go::ponder`] only finish after
+    /// `stop` or `ponderhit`, so use [`UciEngine::send()`] and
+    /// [`UciEngine::recv()`] for those instead.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the start position or any of the moves is illegal, if
+    /// communicating with the engine fails, or if the engine sends an illegal
+    /// best move.
+    pub fn search<P>(
+        &mut self,
+        setup: &Setup,
+        moves: &[Move],
+        mode: CastlingMode,
+        go: &Go,
+    ) -> Result<Search, UciEngineError>
+    where
+        P: FromSetup + Position + Clone,
+    {
+        let mut pos = P::from_setup(setup.clone(), mode).map_err(|err| {
+            UciEngineError::Position(PositionError {
+                errors: err.kinds(),
+                pos: (),
+            })
+        })?;
+        for m in moves {
+            if !pos.is_legal(m) {
+                return Err(UciEngineError::IllegalMove(m.clone()));
+            }
+            pos.play_unchecked(m);
+        }
+
+        self.send(&UciCommand::Position(UciPosition::from_setup(
+            setup.clone(),
+            moves,
+            mode,
+        )))?;
+        self.send(&UciCommand::Go(go.clone()))?;
+        let start = Instant::now();
+
+        let mut infos = Vec::new();
+        loop {
+            match self.recv()? {
+                UciResponse::Info(info) => {
+                    let pv = legal_moves(&pos, &info.pv);
+                    infos.push(SearchInfo { info, pv });
+                }
+                UciResponse::BestMove { m, ponder } => {
+                    let elapsed = start.elapsed();
+                    let best_move = match m {
+                        None | Some(UciMove::Null) => None,
+                        Some(uci) => Some(
+                            uci.to_move(&pos)
+                                .map_err(|_| UciEngineError::IllegalBestMove(uci))?,
+                        ),
+                    };
+                    let ponder = match (&best_move, ponder) {
+                        (Some(best_move), Some(ponder)) => {
+                            let mut after = pos.clone();
+                            after.play_unchecked(best_move);
+                            ponder.to_move(&after).ok()
+                        }
+                        _ => None,
+                    };
+                    return Ok(Search {
+                        best_move,
+                        ponder,
+                        infos,
+                        elapsed,
+                    });
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Sends `quit`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if writing to the engine fails.
+    pub fn quit(mut self) -> io::Result<()> {
+        self.send(&UciCommand::Quit)
+    }
+}
+
+/// Converts moves played in sequence from `pos`, up to the first illegal
+/// move.
+fn legal_moves<P: Position + Clone>(pos: &P, ucis: &[UciMove]) -> Vec<Move> {
+    let mut pos = pos.clone();
+    let mut moves = Vec::with_capacity(ucis.len());
+    for uci in ucis {
+        let Ok(m) = uci.to_move(&pos) else {
+            break;
+        };
+        pos.play_unchecked(&m);
+        moves.push(m);
+    }
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec;
+
+    use super::*;
+    use crate::{uci::UciOptionKind, Chess, Square};
+
+    fn fake_engine(output: &str) -> UciEngine<&[u8], Vec<u8>> {
+        UciEngine::new(output.as_bytes(), Vec::new())
+    }
+
+    fn sent(engine: UciEngine<&[u8], Vec<u8>>) -> String {
+        String::from_utf8(engine.into_inner().1).expect("utf-8")
+    }
+
+    #[test]
+    fn test_handshake() {
+        let mut engine = fake_engine(
+            "Fake engine by someone\n\
+             id name Fake 1.0\n\
+             id author Some One\n\
+             option name Hash type spin default 16 min 1 max 1024\n\
+             option name Clear Hash type button\n\
+             uciok\n\
+             readyok\n",
+        );
+        let handshake = engine.handshake().expect("handshake");
+        assert_eq!(handshake.name.as_deref(), Some("Fake 1.0"));
+        assert_eq!(handshake.author.as_deref(), Some("Some One"));
+        assert_eq!(
+            handshake.options,
+            vec![
+                UciOption {
+                    name: "Hash".into(),
+                    kind: UciOptionKind::Spin {
+                        default: 16,
+                        min: 1,
+                        max: 1024
+                    },
+                },
+                UciOption {
+                    name: "Clear Hash".into(),
+                    kind: UciOptionKind::Button,
+                },
+            ]
+        );
+        engine.set_option("Hash", Some("64")).expect("set option");
+        engine.set_option("Clear Hash", None).expect("set option");
+        engine.new_game().expect("new game");
+        assert_eq!(
+            engine.recv().expect_err("eof").kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        assert_eq!(
+            sent(engine),
+            "uci\n\
+             setoption name Hash value 64\n\
+             setoption name Clear Hash\n\
+             ucinewgame\n\
+             isready\n"
+        );
+    }
+
+    #[test]
+    fn test_search() {
+        let mut engine = fake_engine(
+            "info depth 1 score cp 30 pv e2e4 e7e5\n\
+             info string starting depth 2\n\
+             info depth 2 multipv 2 score cp 10 pv d2d4\n\
+             info depth 2 multipv 1 score mate 3 lowerbound nodes 100 pv e2e4 e7e5 e1e3 g1f3\n\
+             bestmove e2e4 ponder e7e5\n",
+        );
+        let search = engine
+            .search::<Chess>(
+                &Setup::default(),
+                &[],
+                CastlingMode::Standard,
+                &Go {
+                    movetime: Some(Duration::from_millis(100)),
+                    ..Go::default()
+                },
+            )
+            .expect("search");
+
+        assert_eq!(search.best_move.as_ref().map(Move::to), Some(Square::E4));
+        assert_eq!(search.ponder.as_ref().map(Move::to), Some(Square::E5));
+        assert_eq!(search.score(), Some(Score::Mate(3)));
+        assert_eq!(search.infos.len(), 4);
+        assert_eq!(search.infos[0].pv.len(), 2);
+        assert_eq!(search.infos[3].info.pv.len(), 4);
+        assert_eq!(search.infos[3].pv.len(), 2);
+
+        assert_eq!(
+            sent(engine),
+            "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\n\
+             go movetime 100\n"
+        );
+    }
+
+    #[test]
+    fn test_search_castling() {
+        let moves: Vec<Move> = {
+            let mut pos = Chess::default();
+            ["e2e4", "e7e5", "g1f3", "g8f6", "f1c4", "f8c5", "e1g1"]
+                .into_iter()
+                .map(|uci| {
+                    let m = uci
+                        .parse::<UciMove>()
+                        .expect("valid uci")
+                        .to_move(&pos)
+                        .expect("legal move");
+                    pos.play_unchecked(&m);
+                    m
+                })
+                .collect()
+        };
+
+        let mut engine = fake_engine("bestmove e8h8\n");
+        let search = engine
+            .search::<Chess>(
+                &Setup::default(),
+                &moves,
+                CastlingMode::Chess960,
+                &Go::default(),
+            )
+            .expect("search");
+        assert!(search.best_move.expect("best move").is_castle());
+        assert_eq!(search.ponder, None);
+        assert!(sent(engine).ends_with(" moves e2e4 e7e5 g1f3 g8f6 f1c4 f8c5 e1h1\ngo\n"));
+
+        let mut engine = fake_engine("bestmove e1g1\n");
+        assert!(matches!(
+            engine.search::<Chess>(
+                &Setup::default(),
+                &moves[..6],
+                CastlingMode::Standard,
+                &Go::default(),
+            ),
+            Ok(Search {
+                best_move: Some(Move::Castle { .. }),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_search_errors() {
+        let mut engine = fake_engine("bestmove e2e5\n");
+        assert!(matches!(
+            engine.search::<Chess>(
+                &Setup::default(),
+                &[],
+                CastlingMode::Standard,
+                &Go::default()
+            ),
+            Err(UciEngineError::IllegalBestMove(_))
+        ));
+
+        let mut engine = fake_engine("bestmove (none)\n");
+        assert!(matches!(
+            engine.search::<Chess>(&Setup::empty(), &[], CastlingMode::Standard, &Go::default()),
+            Err(UciEngineError::Position(_))
+        ));
+        assert_eq!(sent(engine), "");
+
+        let mut engine = fake_engine("info depth 1\n");
+        assert!(matches!(
+            engine.search::<Chess>(
+                &Setup::default(),
+                &[],
+                CastlingMode::Standard,
+                &Go::default()
+            ),
+            Err(UciEngineError::Io(_))
+        ));
+    }
+}
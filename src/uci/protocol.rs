@@ -0,0 +1,1014 @@
+use alloc::{string::String, vec::Vec};
+use core::{fmt, iter::Peekable, str::FromStr, str::SplitAsciiWhitespace, time::Duration};
+
+use super::UciMove;
+use crate::{fen::Fen, CastlingMode, Color, Move, Setup};
+#[cfg(feature = "variant")]
+use crate::{
+    variant::{Variant, VariantPosition},
+    EnPassantMode, Position, PositionError,
+};
+
+type Tokens<'a> = Peekable<SplitAsciiWhitespace<'a>>;
+
+/// Error when parsing an invalid UCI command.
+#[derive(Clone, Debug)]
+pub struct ParseUciCommandError;
+
+impl fmt::Display for ParseUciCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid uci command")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseUciCommandError {}
+
+/// Error when parsing an invalid UCI response.
+#[derive(Clone, Debug)]
+pub struct ParseUciResponseError;
+
+impl fmt::Display for ParseUciResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid uci response")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseUciResponseError {}
+
+/// A command sent from the GUI to the engine.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum UciCommand {
+    /// `uci`, to be answered with [`UciResponse::Id`], the supported
+    /// options and [`UciResponse::UciOk`].
+    Uci,
+    /// `debug on` or `debug off`.
+    Debug(bool),
+    /// `isready`, to be answered with [`UciResponse::ReadyOk`].
+    IsReady,
+    /// `setoption name <id> [value <x>]`.
+    SetOption(SetOption),
+    /// `ucinewgame`.
+    UciNewGame,
+    /// `position [startpos | fen <fen>] [moves <move>...]`.
+    Position(UciPosition),
+    /// `go ...`, to start searching.
+    Go(Go),
+    /// `stop`, to be answered with [`UciResponse::BestMove`].
+    Stop,
+    /// `ponderhit`, when the opponent played the expected move.
+    PonderHit,
+    /// `quit`.
+    Quit,
+}
+
+impl FromStr for UciCommand {
+    type Err = ParseUciCommandError;
+
+    /// Parses a line sent by the GUI. Unknown tokens before the command are
+    /// skipped, as recommended by the protocol.
+    fn from_str(line: &str) -> Result<UciCommand, ParseUciCommandError> {
+        let mut tokens = line.split_ascii_whitespace().peekable();
+        while let Some(token) = tokens.next() {
+            return match token {
+                "uci" => Some(UciCommand::Uci),
+                "debug" => match tokens.next() {
+                    Some("on") => Some(UciCommand::Debug(true)),
+                    Some("off") => Some(UciCommand::Debug(false)),
+                    _ => None,
+                },
+                "isready" => Some(UciCommand::IsReady),
+                "setoption" => SetOption::parse(&mut tokens).map(UciCommand::SetOption),
+                "ucinewgame" => Some(UciCommand::UciNewGame),
+                "position" => UciPosition::parse(&mut tokens).map(UciCommand::Position),
+                "go" => Go::parse(&mut tokens).map(UciCommand::Go),
+                "stop" => Some(UciCommand::Stop),
+                "ponderhit" => Some(UciCommand::PonderHit),
+                "quit" => Some(UciCommand::Quit),
+                _ => continue,
+            }
+            .ok_or(ParseUciCommandError);
+        }
+        Err(ParseUciCommandError)
+    }
+}
+
+impl fmt::Display for UciCommand {
+    /// Writes the command as a single line, without the line terminator.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UciCommand::Uci => f.write_str("uci"),
+            UciCommand::Debug(on) => f.write_str(if *on { "debug on" } else { "debug off" }),
+            UciCommand::IsReady => f.write_str("isready"),
+            UciCommand::SetOption(option) => option.fmt(f),
+            UciCommand::UciNewGame => f.write_str("ucinewgame"),
+            UciCommand::Position(position) => position.fmt(f),
+            UciCommand::Go(go) => go.fmt(f),
+            UciCommand::Stop => f.write_str("stop"),
+            UciCommand::PonderHit => f.write_str("ponderhit"),
+            UciCommand::Quit => f.write_str("quit"),
+        }
+    }
+}
+
+/// Arguments of the `setoption` command.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetOption {
+    pub name: String,
+    /// The value, or `None` for buttons.
+    pub value: Option<String>,
+}
+
+impl SetOption {
+    fn parse(tokens: &mut Tokens<'_>) -> Option<SetOption> {
+        if tokens.next() != Some("name") {
+            return None;
+        }
+        let name = join_until(tokens, &["value"]);
+        if name.is_empty() {
+            return None;
+        }
+        let value = tokens.next().map(|_| join_until(tokens, &[]));
+        Some(SetOption { name, value })
+    }
+
+    /// Checks if this sets the option `name`. Option names are not case
+    /// sensitive.
+    pub fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    /// Gets the castling mode selected with the `UCI_Chess960` option, or
+    /// `None` if this sets a different option or the value is invalid.
+    pub fn castling_mode(&self) -> Option<CastlingMode> {
+        if !self.is("UCI_Chess960") {
+            return None;
+        }
+        match self.value.as_deref() {
+            Some("true") => Some(CastlingMode::Chess960),
+            Some("false") => Some(CastlingMode::Standard),
+            _ => None,
+        }
+    }
+
+    /// Gets the variant selected with the `UCI_Variant` option, or `None`
+    /// if this sets a different option or the variant is unknown.
+    #[cfg(feature = "variant")]
+    pub fn variant(&self) -> Option<Variant> {
+        if !self.is("UCI_Variant") {
+            return None;
+        }
+        Variant::from_uci(self.value.as_deref()?).ok()
+    }
+}
+
+impl fmt::Display for SetOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "setoption name {}", self.name)?;
+        if let Some(ref value) = self.value {
+            write!(f, " value {value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Arguments of the `position` command.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct UciPosition {
+    /// The starting position, or `None` for `startpos`.
+    pub fen: Option<Fen>,
+    /// Moves played from the starting position.
+    pub moves: Vec<UciMove>,
+}
+
+impl UciPosition {
+    fn parse(tokens: &mut Tokens<'_>) -> Option<UciPosition> {
+        let fen = match tokens.next()? {
+            "startpos" => None,
+            "fen" => Some(join_until(tokens, &["moves"]).parse().ok()?),
+            _ => return None,
+        };
+        let mut moves = Vec::new();
+        if tokens.next() == Some("moves") {
+            for token in tokens {
+                moves.push(token.parse().ok()?);
+            }
+        }
+        Some(UciPosition { fen, moves })
+    }
+
+    /// Builds the arguments for the position reached by playing `moves`
+    /// from `setup`. Castling moves are written according to `mode`, as in
+    /// [`UciMove::from_move()`].
+    pub fn from_setup(setup: Setup, moves: &[Move], mode: CastlingMode) -> UciPosition {
+        UciPosition {
+            fen: Some(Fen::from_setup(setup)),
+            moves: moves.iter().map(|m| UciMove::from_move(m, mode)).collect(),
+        }
+    }
+
+    /// Sets up the position of the given variant, as selected with the
+    /// `UCI_Variant` option, and plays the moves.
+    ///
+    /// Castling moves are accepted in both standard and Chess960 notation,
+    /// but `mode` determines the castling rights of the position, like the
+    /// `UCI_Chess960` option. Use [`Move::to_uci()`](crate::Move::to_uci)
+    /// with the same mode to write moves in responses.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`UciPositionError`] if the position is not legal in the
+    /// variant, or if one of the moves is illegal.
+    #[cfg(feature = "variant")]
+    #[allow(clippy::result_large_err)] // Ok variant is also large
+    pub fn to_position(
+        &self,
+        variant: Variant,
+        mode: CastlingMode,
+    ) -> Result<VariantPosition, UciPositionError> {
+        let setup = match self.fen {
+            Some(ref fen) => fen.as_setup().clone(),
+            None => VariantPosition::new(variant).into_setup(EnPassantMode::Legal),
+        };
+        let mut pos = VariantPosition::from_setup(variant, setup, mode)
+            .map_err(UciPositionError::Position)?;
+        for uci in &self.moves {
+            let m = uci
+                .to_move(&pos)
+                .map_err(|_| UciPositionError::IllegalMove(uci.clone()))?;
+            pos.play_unchecked(&m);
+        }
+        Ok(pos)
+    }
+}
+
+impl fmt::Display for UciPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.fen {
+            Some(ref fen) => write!(f, "position fen {fen}")?,
+            None => f.write_str("position startpos")?,
+        }
+        if !self.moves.is_empty() {
+            f.write_str(" moves")?;
+            for m in &self.moves {
+                write!(f, " {m}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Error when setting up the position of a `position` command.
+#[cfg(feature = "variant")]
+#[derive(Debug)]
+pub enum UciPositionError {
+    /// The starting position is not legal.
+    Position(PositionError<VariantPosition>),
+    /// A move is not legal.
+    IllegalMove(UciMove),
+}
+
+#[cfg(feature = "variant")]
+impl fmt::Display for UciPositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UciPositionError::Position(err) => err.fmt(f),
+            UciPositionError::IllegalMove(uci) => write!(f, "illegal uci move: {uci}"),
+        }
+    }
+}
+
+#[cfg(all(feature = "variant", feature = "std"))]
+impl std::error::Error for UciPositionError {}
+
+/// Arguments of the `go` command.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Go {
+    /// Restrict the search to these moves.
+    pub searchmoves: Vec<UciMove>,
+    /// Search in pondering mode.
+    pub ponder: bool,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    /// Moves until the next time control.
+    pub movestogo: Option<u32>,
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    /// Search for a mate in this many moves.
+    pub mate: Option<u32>,
+    pub movetime: Option<Duration>,
+    /// Search until `stop`.
+    pub infinite: bool,
+}
+
+impl Go {
+    fn parse(tokens: &mut Tokens<'_>) -> Option<Go> {
+        let mut go = Go::default();
+        while let Some(token) = tokens.next() {
+            match token {
+                "searchmoves" => go.searchmoves = moves(tokens),
+                "ponder" => go.ponder = true,
+                "wtime" => go.wtime = Some(millis(tokens.next()?)?),
+                "btime" => go.btime = Some(millis(tokens.next()?)?),
+                "winc" => go.winc = Some(millis(tokens.next()?)?),
+                "binc" => go.binc = Some(millis(tokens.next()?)?),
+                "movestogo" => go.movestogo = Some(tokens.next()?.parse().ok()?),
+                "depth" => go.depth = Some(tokens.next()?.parse().ok()?),
+                "nodes" => go.nodes = Some(tokens.next()?.parse().ok()?),
+                "mate" => go.mate = Some(tokens.next()?.parse().ok()?),
+                "movetime" => go.movetime = Some(millis(tokens.next()?)?),
+                "infinite" => go.infinite = true,
+                _ => (),
+            }
+        }
+        Some(go)
+    }
+
+    /// Gets the remaining time on the clock of `color`.
+    pub fn time(&self, color: Color) -> Option<Duration> {
+        color.fold_wb(self.wtime, self.btime)
+    }
+
+    /// Gets the increment per move of `color`.
+    pub fn inc(&self, color: Color) -> Option<Duration> {
+        color.fold_wb(self.winc, self.binc)
+    }
+}
+
+impl fmt::Display for Go {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("go")?;
+        if self.ponder {
+            f.write_str(" ponder")?;
+        }
+        for (key, time) in [
+            ("wtime", self.wtime),
+            ("btime", self.btime),
+            ("winc", self.winc),
+            ("binc", self.binc),
+        ] {
+            if let Some(time) = time {
+                write!(f, " {key} {}", time.as_millis())?;
+            }
+        }
+        if let Some(movestogo) = self.movestogo {
+            write!(f, " movestogo {movestogo}")?;
+        }
+        if let Some(depth) = self.depth {
+            write!(f, " depth {depth}")?;
+        }
+        if let Some(nodes) = self.nodes {
+            write!(f, " nodes {nodes}")?;
+        }
+        if let Some(mate) = self.mate {
+            write!(f, " mate {mate}")?;
+        }
+        if let Some(movetime) = self.movetime {
+            write!(f, " movetime {}", movetime.as_millis())?;
+        }
+        if self.infinite {
+            f.write_str(" infinite")?;
+        }
+        if !self.searchmoves.is_empty() {
+            f.write_str(" searchmoves")?;
+            for m in &self.searchmoves {
+                write!(f, " {m}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A response sent from the engine to the GUI.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum UciResponse {
+    /// `id name <name>` or `id author <author>`.
+    Id { key: IdKey, value: String },
+    /// `uciok`.
+    UciOk,
+    /// `readyok`.
+    ReadyOk,
+    /// `bestmove <move> [ponder <move>]`, or `bestmove (none)` if there are
+    /// no legal moves.
+    BestMove {
+        m: Option<UciMove>,
+        ponder: Option<UciMove>,
+    },
+    /// `info ...`.
+    Info(Info),
+    /// `option name <id> type <type> ...`.
+    Option(UciOption),
+}
+
+impl FromStr for UciResponse {
+    type Err = ParseUciResponseError;
+
+    /// Parses a line sent by the engine. Unknown tokens before the response
+    /// are skipped, as recommended by the protocol.
+    fn from_str(line: &str) -> Result<UciResponse, ParseUciResponseError> {
+        let mut tokens = line.split_ascii_whitespace().peekable();
+        while let Some(token) = tokens.next() {
+            return match token {
+                "id" => {
+                    let key = match tokens.next() {
+                        Some("name") => Some(IdKey::Name),
+                        Some("author") => Some(IdKey::Author),
+                        _ => None,
+                    };
+                    key.map(|key| UciResponse::Id {
+                        key,
+                        value: join_until(&mut tokens, &[]),
+                    })
+                }
+                "uciok" => Some(UciResponse::UciOk),
+                "readyok" => Some(UciResponse::ReadyOk),
+                "bestmove" => best_move(&mut tokens),
+                "info" => Info::parse(&mut tokens).map(UciResponse::Info),
+                "option" => UciOption::parse(&mut tokens).map(UciResponse::Option),
+                _ => continue,
+            }
+            .ok_or(ParseUciResponseError);
+        }
+        Err(ParseUciResponseError)
+    }
+}
+
+impl fmt::Display for UciResponse {
+    /// Writes the response as a single line, without the line terminator.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UciResponse::Id { key, value } => write!(f, "id {key} {value}"),
+            UciResponse::UciOk => f.write_str("uciok"),
+            UciResponse::ReadyOk => f.write_str("readyok"),
+            UciResponse::BestMove { m, ponder } => {
+                match m {
+                    Some(m) => write!(f, "bestmove {m}")?,
+                    None => f.write_str("bestmove (none)")?,
+                }
+                if let Some(ponder) = ponder {
+                    write!(f, " ponder {ponder}")?;
+                }
+                Ok(())
+            }
+            UciResponse::Info(info) => info.fmt(f),
+            UciResponse::Option(option) => option.fmt(f),
+        }
+    }
+}
+
+/// Key of [`UciResponse::Id`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum IdKey {
+    Name,
+    Author,
+}
+
+impl fmt::Display for IdKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IdKey::Name => "name",
+            IdKey::Author => "author",
+        })
+    }
+}
+
+/// A score from the point of view of the engine.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Score {
+    /// Centipawns.
+    Cp(i32),
+    /// Mate in this many moves, or getting mated if negative.
+    Mate(i32),
+}
+
+/// Bound of a score that is not exact.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ScoreBound {
+    Lower,
+    Upper,
+}
+
+/// Search information sent with `info`. All fields are optional.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Info {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    pub multipv: Option<u32>,
+    pub score: Option<Score>,
+    /// Set if the score is only a bound.
+    pub bound: Option<ScoreBound>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    /// Hash table usage in permill.
+    pub hashfull: Option<u32>,
+    pub tbhits: Option<u64>,
+    /// CPU usage in permill.
+    pub cpuload: Option<u32>,
+    pub time: Option<Duration>,
+    pub currmove: Option<UciMove>,
+    pub currmovenumber: Option<u32>,
+    /// Principal variation.
+    pub pv: Vec<UciMove>,
+    /// Free-form text, which extends to the end of the line.
+    pub string: Option<String>,
+}
+
+impl Info {
+    fn parse(tokens: &mut Tokens<'_>) -> Option<Info> {
+        let mut info = Info::default();
+        while let Some(token) = tokens.next() {
+            match token {
+                "depth" => info.depth = Some(tokens.next()?.parse().ok()?),
+                "seldepth" => info.seldepth = Some(tokens.next()?.parse().ok()?),
+                "multipv" => info.multipv = Some(tokens.next()?.parse().ok()?),
+                "score" => {
+                    info.score = Some(match tokens.next()? {
+                        "cp" => Score::Cp(tokens.next()?.parse().ok()?),
+                        "mate" => Score::Mate(tokens.next()?.parse().ok()?),
+                        _ => return None,
+                    });
+                    info.bound = match tokens.peek() {
+                        Some(&"lowerbound") => Some(ScoreBound::Lower),
+                        Some(&"upperbound") => Some(ScoreBound::Upper),
+                        _ => None,
+                    };
+                    if info.bound.is_some() {
+                        tokens.next();
+                    }
+                }
+                "nodes" => info.nodes = Some(tokens.next()?.parse().ok()?),
+                "nps" => info.nps = Some(tokens.next()?.parse().ok()?),
+                "hashfull" => info.hashfull = Some(tokens.next()?.parse().ok()?),
+                "tbhits" => info.tbhits = Some(tokens.next()?.parse().ok()?),
+                "cpuload" => info.cpuload = Some(tokens.next()?.parse().ok()?),
+                "time" => info.time = Some(millis(tokens.next()?)?),
+                "currmove" => info.currmove = Some(tokens.next()?.parse().ok()?),
+                "currmovenumber" => info.currmovenumber = Some(tokens.next()?.parse().ok()?),
+                "pv" => info.pv = moves(tokens),
+                "string" => info.string = Some(join_until(tokens, &[])),
+                _ => (),
+            }
+        }
+        Some(info)
+    }
+}
+
+impl fmt::Display for Info {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("info")?;
+        if let Some(depth) = self.depth {
+            write!(f, " depth {depth}")?;
+        }
+        if let Some(seldepth) = self.seldepth {
+            write!(f, " seldepth {seldepth}")?;
+        }
+        if let Some(multipv) = self.multipv {
+            write!(f, " multipv {multipv}")?;
+        }
+        if let Some(score) = self.score {
+            match score {
+                Score::Cp(cp) => write!(f, " score cp {cp}")?,
+                Score::Mate(mate) => write!(f, " score mate {mate}")?,
+            }
+            match self.bound {
+                Some(ScoreBound::Lower) => f.write_str(" lowerbound")?,
+                Some(ScoreBound::Upper) => f.write_str(" upperbound")?,
+                None => (),
+            }
+        }
+        if let Some(nodes) = self.nodes {
+            write!(f, " nodes {nodes}")?;
+        }
+        if let Some(nps) = self.nps {
+            write!(f, " nps {nps}")?;
+        }
+        if let Some(hashfull) = self.hashfull {
+            write!(f, " hashfull {hashfull}")?;
+        }
+        if let Some(tbhits) = self.tbhits {
+            write!(f, " tbhits {tbhits}")?;
+        }
+        if let Some(cpuload) = self.cpuload {
+            write!(f, " cpuload {cpuload}")?;
+        }
+        if let Some(time) = self.time {
+            write!(f, " time {}", time.as_millis())?;
+        }
+        if let Some(ref currmove) = self.currmove {
+            write!(f, " currmove {currmove}")?;
+        }
+        if let Some(currmovenumber) = self.currmovenumber {
+            write!(f, " currmovenumber {currmovenumber}")?;
+        }
+        if !self.pv.is_empty() {
+            f.write_str(" pv")?;
+            for m in &self.pv {
+                write!(f, " {m}")?;
+            }
+        }
+        if let Some(ref string) = self.string {
+            write!(f, " string {string}")?;
+        }
+        Ok(())
+    }
+}
+
+/// An option supported by the engine, as announced after `uci`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UciOption {
+    pub name: String,
+    pub kind: UciOptionKind,
+}
+
+impl UciOption {
+    fn parse(tokens: &mut Tokens<'_>) -> Option<UciOption> {
+        const KEYS: [&str; 4] = ["default", "min", "max", "var"];
+
+        if tokens.next()? != "name" {
+            return None;
+        }
+        let name = join_until(tokens, &["type"]);
+        if name.is_empty() || tokens.next()? != "type" {
+            return None;
+        }
+        let kind = tokens.next()?;
+
+        let (mut default, mut min, mut max, mut vars) = (None, None, None, Vec::new());
+        while let Some(key) = tokens.next() {
+            let value = join_until(tokens, &KEYS);
+            match key {
+                "default" => default = Some(value),
+                "min" => min = Some(value),
+                "max" => max = Some(value),
+                "var" => vars.push(value),
+                _ => return None,
+            }
+        }
+
+        let kind = match kind {
+            "check" => UciOptionKind::Check {
+                default: match default?.as_str() {
+                    "true" => true,
+                    "false" => false,
+                    _ => return None,
+                },
+            },
+            "spin" => UciOptionKind::Spin {
+                default: default?.parse().ok()?,
+                min: min?.parse().ok()?,
+                max: max?.parse().ok()?,
+            },
+            "combo" => UciOptionKind::Combo {
+                default: default?,
+                vars,
+            },
+            "button" => UciOptionKind::Button,
+            "string" => UciOptionKind::String {
+                default: default
+                    .filter(|default| default != "<empty>")
+                    .unwrap_or_default(),
+            },
+            _ => return None,
+        };
+        Some(UciOption { name, kind })
+    }
+}
+
+impl fmt::Display for UciOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "option name {} type ", self.name)?;
+        match self.kind {
+            UciOptionKind::Check { default } => write!(f, "check default {default}"),
+            UciOptionKind::Spin { default, min, max } => {
+                write!(f, "spin default {default} min {min} max {max}")
+            }
+            UciOptionKind::Combo {
+                ref default,
+                ref vars,
+            } => {
+                write!(f, "combo default {default}")?;
+                for var in vars {
+                    write!(f, " var {var}")?;
+                }
+                Ok(())
+            }
+            UciOptionKind::Button => f.write_str("button"),
+            UciOptionKind::String { ref default } => {
+                if default.is_empty() {
+                    f.write_str("string default <empty>")
+                } else {
+                    write!(f, "string default {default}")
+                }
+            }
+        }
+    }
+}
+
+/// Type and default value of a [`UciOption`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum UciOptionKind {
+    /// `true` or `false`.
+    Check { default: bool },
+    /// An integer in the range `min..=max`.
+    Spin { default: i64, min: i64, max: i64 },
+    /// One of the given values.
+    Combo { default: String, vars: Vec<String> },
+    /// An action without value.
+    Button,
+    /// Free-form text.
+    String { default: String },
+}
+
+/// Joins tokens with single spaces, up to (and not including) any of the
+/// tokens in `end`.
+fn join_until(tokens: &mut Tokens<'_>, end: &[&str]) -> String {
+    let mut joined = String::new();
+    while let Some(token) = tokens.next_if(|token| !end.contains(token)) {
+        if !joined.is_empty() {
+            joined.push(' ');
+        }
+        joined.push_str(token);
+    }
+    joined
+}
+
+/// Parses a time in milliseconds. Negative times, as sent by some GUIs
+/// when the clock has run out, are treated as zero.
+fn millis(token: &str) -> Option<Duration> {
+    let millis: i64 = token.parse().ok()?;
+    Some(Duration::from_millis(millis.max(0) as u64))
+}
+
+/// Parses moves, up to the first token that is not a move.
+fn moves(tokens: &mut Tokens<'_>) -> Vec<UciMove> {
+    let mut moves = Vec::new();
+    while let Some(uci) = tokens.peek().and_then(|token| token.parse().ok()) {
+        tokens.next();
+        moves.push(uci);
+    }
+    moves
+}
+
+fn best_move(tokens: &mut Tokens<'_>) -> Option<UciResponse> {
+    fn optional_move(token: &str) -> Option<Option<UciMove>> {
+        match token {
+            "(none)" => Some(None),
+            _ => token.parse().ok().map(Some),
+        }
+    }
+
+    let m = optional_move(tokens.next()?)?;
+    let ponder = match tokens.next() {
+        Some("ponder") => optional_move(tokens.next()?)?,
+        _ => None,
+    };
+    Some(UciResponse::BestMove { m, ponder })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString as _, vec};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!("uci".parse::<UciCommand>().ok(), Some(UciCommand::Uci));
+        assert_eq!(
+            "  joho debug on\n".parse::<UciCommand>().ok(),
+            Some(UciCommand::Debug(true))
+        );
+        assert!("debug".parse::<UciCommand>().is_err());
+        assert!("".parse::<UciCommand>().is_err());
+
+        assert_eq!(
+            "setoption name Clear Hash".parse::<UciCommand>().ok(),
+            Some(UciCommand::SetOption(SetOption {
+                name: "Clear Hash".to_string(),
+                value: None,
+            }))
+        );
+        assert_eq!(
+            "setoption name SyzygyPath value /tmp/my  tables"
+                .parse::<UciCommand>()
+                .ok(),
+            Some(UciCommand::SetOption(SetOption {
+                name: "SyzygyPath".to_string(),
+                value: Some("/tmp/my tables".to_string()),
+            }))
+        );
+
+        assert_eq!(
+            "position startpos moves e2e4 e7e5"
+                .parse::<UciCommand>()
+                .ok(),
+            Some(UciCommand::Position(UciPosition {
+                fen: None,
+                moves: vec![
+                    "e2e4".parse().expect("valid move"),
+                    "e7e5".parse().expect("valid move"),
+                ],
+            }))
+        );
+        match "position fen 8/8/8/8/8/8/8/K1k5 w - - 0 1".parse::<UciCommand>() {
+            Ok(UciCommand::Position(UciPosition {
+                fen: Some(fen),
+                moves,
+            })) => {
+                assert_eq!(fen.to_string(), "8/8/8/8/8/8/8/K1k5 w - - 0 1");
+                assert!(moves.is_empty());
+            }
+            res => panic!("unexpected {res:?}"),
+        }
+        assert!("position startpos moves e2e9"
+            .parse::<UciCommand>()
+            .is_err());
+        assert!("position".parse::<UciCommand>().is_err());
+
+        match "go wtime -10 btime 2000 winc 10 binc 20 movestogo 5 depth 8 searchmoves e2e4 d2d4 ponder"
+            .parse::<UciCommand>()
+        {
+            Ok(UciCommand::Go(go)) => {
+                assert_eq!(go.time(Color::White), Some(Duration::ZERO));
+                assert_eq!(go.time(Color::Black), Some(Duration::from_secs(2)));
+                assert_eq!(go.inc(Color::Black), Some(Duration::from_millis(20)));
+                assert_eq!(go.movestogo, Some(5));
+                assert_eq!(go.depth, Some(8));
+                assert_eq!(go.searchmoves.len(), 2);
+                assert!(go.ponder);
+                assert!(!go.infinite);
+            }
+            res => panic!("unexpected {res:?}"),
+        }
+        assert!("go depth".parse::<UciCommand>().is_err());
+    }
+
+    #[test]
+    fn test_set_option() {
+        let option = SetOption {
+            name: "uci_chess960".to_string(),
+            value: Some("true".to_string()),
+        };
+        assert_eq!(option.castling_mode(), Some(CastlingMode::Chess960));
+        assert!(option.is("UCI_Chess960"));
+    }
+
+    #[cfg(feature = "variant")]
+    #[test]
+    fn test_position() {
+        use crate::Square;
+
+        let option: SetOption = match "setoption name UCI_Variant value atomic".parse() {
+            Ok(UciCommand::SetOption(option)) => option,
+            res => panic!("unexpected {res:?}"),
+        };
+        assert_eq!(option.variant(), Some(Variant::Atomic));
+        assert_eq!(option.castling_mode(), None);
+
+        let position = UciPosition {
+            fen: None,
+            moves: vec![
+                "e2e4".parse().expect("valid move"),
+                "e7e5".parse().expect("valid move"),
+                "g1f3".parse().expect("valid move"),
+                "b8c6".parse().expect("valid move"),
+                "f1c4".parse().expect("valid move"),
+                "g8f6".parse().expect("valid move"),
+                "e1h1".parse().expect("valid move"),
+            ],
+        };
+        for mode in [CastlingMode::Standard, CastlingMode::Chess960] {
+            let pos = position
+                .to_position(Variant::Chess, mode)
+                .expect("legal position");
+            assert_eq!(pos.board().king_of(Color::White), Some(Square::G1));
+            assert_eq!(pos.castles().mode(), mode);
+        }
+
+        let pos = UciPosition {
+            fen: None,
+            moves: vec!["e4e5".parse().expect("valid move")],
+        }
+        .to_position(Variant::Horde, CastlingMode::Standard)
+        .expect("legal position");
+        assert_eq!(pos.variant(), Variant::Horde);
+
+        assert!(matches!(
+            UciPosition {
+                fen: None,
+                moves: vec!["e2e5".parse().expect("valid move")],
+            }
+            .to_position(Variant::Chess, CastlingMode::Standard),
+            Err(UciPositionError::IllegalMove(_))
+        ));
+    }
+
+    #[test]
+    fn test_write_response() {
+        assert_eq!(
+            UciResponse::Id {
+                key: IdKey::Name,
+                value: "Engine 1.0".to_string(),
+            }
+            .to_string(),
+            "id name Engine 1.0"
+        );
+        assert_eq!(
+            UciResponse::BestMove {
+                m: Some("e2e4".parse().expect("valid move")),
+                ponder: Some("e7e5".parse().expect("valid move")),
+            }
+            .to_string(),
+            "bestmove e2e4 ponder e7e5"
+        );
+        assert_eq!(
+            UciResponse::BestMove {
+                m: None,
+                ponder: None
+            }
+            .to_string(),
+            "bestmove (none)"
+        );
+        assert_eq!(
+            UciResponse::Info(Info {
+                depth: Some(10),
+                score: Some(Score::Mate(-3)),
+                bound: Some(ScoreBound::Upper),
+                time: Some(Duration::from_millis(1500)),
+                pv: vec![
+                    "e2e4".parse().expect("valid move"),
+                    "e7e5".parse().expect("valid move"),
+                ],
+                string: Some("hello world".to_string()),
+                ..Info::default()
+            })
+            .to_string(),
+            "info depth 10 score mate -3 upperbound time 1500 pv e2e4 e7e5 string hello world"
+        );
+        assert_eq!(
+            UciResponse::Option(UciOption {
+                name: "Style".to_string(),
+                kind: UciOptionKind::Combo {
+                    default: "Normal".to_string(),
+                    vars: vec!["Solid".to_string(), "Normal".to_string()],
+                },
+            })
+            .to_string(),
+            "option name Style type combo default Normal var Solid var Normal"
+        );
+        assert_eq!(
+            UciResponse::Option(UciOption {
+                name: "Hash".to_string(),
+                kind: UciOptionKind::Spin {
+                    default: 16,
+                    min: 1,
+                    max: 1024,
+                },
+            })
+            .to_string(),
+            "option name Hash type spin default 16 min 1 max 1024"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for line in [
+            "uci",
+            "debug off",
+            "setoption name Clear Hash",
+            "setoption name UCI_Chess960 value true",
+            "position startpos",
+            "position fen 8/8/8/8/8/8/8/K1k5 w - - 0 1 moves a1a2",
+            "go ponder wtime 1000 btime 0 movestogo 3 infinite searchmoves e2e4 d2d4",
+            "ponderhit",
+        ] {
+            let command: UciCommand = line.parse().expect("valid command");
+            assert_eq!(command.to_string(), line);
+        }
+
+        for line in [
+            "id author Some One",
+            "uciok",
+            "bestmove e7e8q ponder (none)",
+            "bestmove (none)",
+            "info depth 3 seldepth 5 multipv 1 score cp -12 lowerbound nodes 1000 nps 2000 hashfull 10 tbhits 0 time 500 pv g1f3 g8f6",
+            "info currmove e2e4 currmovenumber 1",
+            "info string NNUE  evaluation enabled",
+            "option name Threads type spin default 1 min 1 max 512",
+            "option name UCI_Chess960 type check default false",
+            "option name Debug Log File type string default <empty>",
+        ] {
+            let response: UciResponse = line.parse().expect("valid response");
+            assert_eq!(response.to_string(), line.replace("  ", " ").replace(" ponder (none)", ""));
+        }
+
+        assert!("bestmove".parse::<UciResponse>().is_err());
+        assert!("info depth x".parse::<UciResponse>().is_err());
+        assert!("option name Foo type spin default 1"
+            .parse::<UciResponse>()
+            .is_err());
+    }
+}
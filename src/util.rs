@@ -25,6 +25,11 @@ pub(crate) trait AppendAscii {
     fn append_ascii(&mut self, ascii_char: char) -> Result<(), Self::Error>;
     fn reserve(&mut self, additional: usize);
 
+    /// Appends a string, which unlike individual characters need not be
+    /// ASCII.
+    #[cfg(feature = "alloc")]
+    fn append_str(&mut self, s: &str) -> Result<(), Self::Error>;
+
     fn append_u32(&mut self, n: u32) -> Result<(), Self::Error> {
         if n >= 1_000_000_000 {
             self.append_ascii(char::from(b'0' + ((n / 1_000_000_000) % 10) as u8))?;
@@ -65,6 +70,11 @@ impl AppendAscii for fmt::Formatter<'_> {
     fn append_ascii(&mut self, ascii_char: char) -> Result<(), Self::Error> {
         self.write_char(ascii_char)
     }
+
+    #[cfg(feature = "alloc")]
+    fn append_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.write_str(s)
+    }
 }
 
 #[cfg(feature = "alloc")]
@@ -79,6 +89,11 @@ impl AppendAscii for alloc::string::String {
         self.push(ascii_char);
         Ok(())
     }
+
+    fn append_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.push_str(s);
+        Ok(())
+    }
 }
 
 #[cfg(feature = "alloc")]
@@ -93,6 +108,11 @@ impl AppendAscii for alloc::vec::Vec<u8> {
         self.push(ascii_char as u8);
         Ok(())
     }
+
+    fn append_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
 }
 
 #[cfg(feature = "std")]
@@ -108,4 +128,63 @@ impl<W: std::io::Write> AppendAscii for WriteAscii<W> {
         let buf = [ascii_char as u8];
         self.0.write_all(&buf[..])
     }
+
+    fn append_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.0.write_all(s.as_bytes())
+    }
+}
+
+/// Deserializes a value from its ASCII notation, given as a string or as
+/// bytes.
+#[cfg(feature = "serde")]
+pub(crate) fn deserialize_ascii<'de, D, T>(
+    deserializer: D,
+    expecting: &'static str,
+    parse: fn(&[u8]) -> Option<T>,
+) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de;
+
+    struct AsciiVisitor<T> {
+        expecting: &'static str,
+        parse: fn(&[u8]) -> Option<T>,
+    }
+
+    impl<'de, T> de::Visitor<'de> for AsciiVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.expecting)
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+            (self.parse)(v.as_bytes())
+                .ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<T, E> {
+            (self.parse)(v).ok_or_else(|| E::invalid_value(de::Unexpected::Bytes(v), &self))
+        }
+    }
+
+    deserializer.deserialize_str(AsciiVisitor { expecting, parse })
+}
+
+/// Deserializes a value from a compact integer representation.
+#[cfg(feature = "serde")]
+pub(crate) fn deserialize_u8<'de, D, T>(
+    deserializer: D,
+    expecting: &'static str,
+    convert: fn(u8) -> Option<T>,
+) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::{de, de::Error as _, Deserialize as _};
+
+    let n = u8::deserialize(deserializer)?;
+    convert(n)
+        .ok_or_else(|| D::Error::invalid_value(de::Unexpected::Unsigned(u64::from(n)), &expecting))
 }
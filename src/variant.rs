@@ -12,7 +12,7 @@ pub use crate::position::{
 use crate::{
     Bitboard, Board, ByColor, ByRole, Castles, CastlingMode, CastlingSide, Color, EnPassantMode,
     FromSetup, Move, MoveList, Outcome, Position, PositionError, RemainingChecks, Role, Setup,
-    Square,
+    Square, Undo,
 };
 
 /// Discriminant of [`VariantPosition`].
@@ -107,6 +107,34 @@ impl fmt::Display for Variant {
     }
 }
 
+/// Serializes as the UCI name like `kingofthehill` in human-readable formats,
+/// and as the index in [`Variant::ALL`] otherwise.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Variant {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(self.uci())
+        } else {
+            serializer.serialize_u8(*self as u8)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Variant {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Variant, D::Error> {
+        if deserializer.is_human_readable() {
+            crate::util::deserialize_ascii(deserializer, "variant name", |s| {
+                Variant::from_ascii(s).ok()
+            })
+        } else {
+            crate::util::deserialize_u8(deserializer, "variant index", |n| {
+                Variant::ALL.get(usize::from(n)).copied()
+            })
+        }
+    }
+}
+
 /// Error when parsing an unknown variant name.
 #[derive(Clone, Debug)]
 pub struct ParseVariantError;
@@ -404,6 +432,14 @@ impl Position for VariantPosition {
     fn play_unchecked(&mut self, m: &Move) {
         self.borrow_mut().play_unchecked(m);
     }
+
+    fn play_with_undo(&mut self, m: &Move) -> Undo {
+        self.borrow_mut().play_with_undo(m)
+    }
+
+    fn undo(&mut self, m: &Move, undo: Undo) {
+        self.borrow_mut().undo(m, undo);
+    }
 }
 
 #[cfg(test)]
@@ -424,4 +460,21 @@ mod tests {
             .expect("legal move");
         assert_eq!(pos.variant(), Variant::Chess);
     }
+
+    #[test]
+    fn test_variant_position_play_with_undo() {
+        let mut pos = VariantPosition::new(Variant::Crazyhouse);
+        let m = Move::Normal {
+            role: Role::Pawn,
+            from: Square::E2,
+            to: Square::E4,
+            capture: None,
+            promotion: None,
+        };
+        let undo = pos.play_with_undo(&m);
+        assert_eq!(pos.variant(), Variant::Crazyhouse);
+        assert_eq!(pos.turn(), Color::Black);
+        pos.undo(&m, undo);
+        assert_eq!(pos, VariantPosition::new(Variant::Crazyhouse));
+    }
 }
@@ -0,0 +1,702 @@
+//! Parse and write commands of the Chess Engine Communication Protocol, also
+//! known as the XBoard or WinBoard protocol.
+//!
+//! [`XBoardCommand`] models the commands sent from the GUI to the engine,
+//! and [`XBoardResponse`] the engine's replies. Both can be parsed and
+//! written, one line at a time.
+//!
+//! Moves are sent in coordinate notation like `e2e4` or, if the engine
+//! requested it with `feature san=1`, in SAN. [`XBoardMove`] accepts both.
+//!
+//! # Examples
+//!
+//! ```
+//! # use shakmaty::xboard::{IllegalXBoardMoveError, ParseXBoardCommandError, ParseXBoardMoveError};
+//! use shakmaty::{
+//!     xboard::{XBoardCommand, XBoardMove, XBoardResponse},
+//!     CastlingMode, Chess, Position,
+//! };
+//!
+//! let mut pos = Chess::default();
+//!
+//! let command: XBoardCommand = "usermove Nf3".parse()?;
+//! if let XBoardCommand::UserMove(m) = command {
+//!     let m = m.to_move(&pos)?;
+//!     pos.play_unchecked(&m);
+//! }
+//!
+//! // Reply in coordinate notation.
+//! let reply = "Nf6".parse::<XBoardMove>()?.to_move(&pos)?;
+//! let response = XBoardResponse::Move(XBoardMove::from_move(&reply, CastlingMode::Standard));
+//! assert_eq!(response.to_string(), "move g8f6");
+//!
+//! # #[derive(Debug)] struct CommonError;
+//! # impl From<ParseXBoardCommandError> for CommonError { fn from(_: ParseXBoardCommandError) -> Self { Self } }
+//! # impl From<ParseXBoardMoveError> for CommonError { fn from(_: ParseXBoardMoveError) -> Self { Self } }
+//! # impl From<IllegalXBoardMoveError> for CommonError { fn from(_: IllegalXBoardMoveError) -> Self { Self } }
+//! # Ok::<_, CommonError>(())
+//! ```
+
+use alloc::{string::String, vec::Vec};
+use core::{fmt, str::FromStr, time::Duration};
+
+#[cfg(feature = "variant")]
+use crate::variant::Variant;
+use crate::{fen::Fen, san::San, uci::UciMove, CastlingMode, Move, Outcome, Position};
+
+/// Error when parsing an invalid XBoard move.
+#[derive(Clone, Debug)]
+pub struct ParseXBoardMoveError;
+
+impl fmt::Display for ParseXBoardMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid xboard move")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseXBoardMoveError {}
+
+/// Error when an XBoard move is illegal.
+#[derive(Clone, Debug)]
+pub struct IllegalXBoardMoveError;
+
+impl fmt::Display for IllegalXBoardMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("illegal xboard move")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IllegalXBoardMoveError {}
+
+/// Error when parsing an invalid XBoard command.
+#[derive(Clone, Debug)]
+pub struct ParseXBoardCommandError;
+
+impl fmt::Display for ParseXBoardCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid xboard command")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseXBoardCommandError {}
+
+/// Error when parsing an invalid XBoard response.
+#[derive(Clone, Debug)]
+pub struct ParseXBoardResponseError;
+
+impl fmt::Display for ParseXBoardResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid xboard response")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseXBoardResponseError {}
+
+/// A move as sent in the XBoard protocol.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum XBoardMove {
+    /// Coordinate notation like `e2e4`, `e7e8q` or `P@f7`.
+    Coordinate(UciMove),
+    /// Standard Algebraic Notation like `Nf3` or `O-O`.
+    San(San),
+}
+
+impl XBoardMove {
+    /// Parses a move in coordinate notation or SAN.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseXBoardMoveError`] if `s` is neither.
+    pub fn from_ascii(s: &[u8]) -> Result<XBoardMove, ParseXBoardMoveError> {
+        UciMove::from_ascii(s)
+            .map(XBoardMove::Coordinate)
+            .or_else(|_| San::from_ascii(s).map(XBoardMove::San))
+            .map_err(|_| ParseXBoardMoveError)
+    }
+
+    /// Converts a move to coordinate notation.
+    ///
+    /// In Chess960, castling moves are written as `O-O` and `O-O-O`, as
+    /// expected by XBoard. Otherwise, they are written as two-square king
+    /// moves like `e1g1`.
+    pub fn from_move(m: &Move, mode: CastlingMode) -> XBoardMove {
+        match (m.castling_side(), mode) {
+            (Some(side), CastlingMode::Chess960) => XBoardMove::San(San::Castle(side)),
+            _ => XBoardMove::Coordinate(UciMove::from_standard(m)),
+        }
+    }
+
+    /// Converts a move to SAN, for engines that requested `feature san=1`.
+    pub fn from_move_san<P: Position>(pos: &P, m: &Move) -> XBoardMove {
+        XBoardMove::San(San::from_move(pos, m))
+    }
+
+    /// Tries to convert the move to a legal [`Move`] in the context of a
+    /// position.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IllegalXBoardMoveError`] if the move is not legal.
+    pub fn to_move<P: Position>(&self, pos: &P) -> Result<Move, IllegalXBoardMoveError> {
+        match self {
+            XBoardMove::Coordinate(uci) => uci.to_move(pos).map_err(|_| IllegalXBoardMoveError),
+            XBoardMove::San(san) => san.to_move(pos).map_err(|_| IllegalXBoardMoveError),
+        }
+    }
+}
+
+impl FromStr for XBoardMove {
+    type Err = ParseXBoardMoveError;
+
+    fn from_str(s: &str) -> Result<XBoardMove, ParseXBoardMoveError> {
+        XBoardMove::from_ascii(s.as_bytes())
+    }
+}
+
+impl fmt::Display for XBoardMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XBoardMove::Coordinate(uci) => uci.fmt(f),
+            XBoardMove::San(san) => san.fmt(f),
+        }
+    }
+}
+
+/// Selects a variant and castling mode based on the name used by the
+/// `variant` command, like `fischerandom` or `kingofthehill`.
+#[cfg(feature = "variant")]
+pub fn variant_from_name(name: &str) -> Option<(Variant, CastlingMode)> {
+    Some(match name {
+        "normal" => (Variant::Chess, CastlingMode::Standard),
+        "fischerandom" => (Variant::Chess, CastlingMode::Chess960),
+        "atomic" => (Variant::Atomic, CastlingMode::Standard),
+        "giveaway" => (Variant::Antichess, CastlingMode::Standard),
+        "kingofthehill" => (Variant::KingOfTheHill, CastlingMode::Standard),
+        "3check" => (Variant::ThreeCheck, CastlingMode::Standard),
+        "crazyhouse" => (Variant::Crazyhouse, CastlingMode::Standard),
+        "racingkings" => (Variant::RacingKings, CastlingMode::Standard),
+        "horde" => (Variant::Horde, CastlingMode::Standard),
+        _ => return None,
+    })
+}
+
+/// Gets the name of a variant as used by the `variant` command, or `None`
+/// if the protocol has no name for it.
+#[cfg(feature = "variant")]
+pub fn variant_name(variant: Variant, mode: CastlingMode) -> Option<&'static str> {
+    Some(match (variant, mode) {
+        (Variant::Chess, CastlingMode::Standard) => "normal",
+        (Variant::Chess, CastlingMode::Chess960) => "fischerandom",
+        (Variant::Atomic, CastlingMode::Standard) => "atomic",
+        (Variant::Antichess, CastlingMode::Standard) => "giveaway",
+        (Variant::KingOfTheHill, CastlingMode::Standard) => "kingofthehill",
+        (Variant::ThreeCheck, CastlingMode::Standard) => "3check",
+        (Variant::Crazyhouse, CastlingMode::Standard) => "crazyhouse",
+        (Variant::RacingKings, CastlingMode::Standard) => "racingkings",
+        (Variant::Horde, CastlingMode::Standard) => "horde",
+        _ => return None,
+    })
+}
+
+/// A command sent from the GUI to the engine.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum XBoardCommand {
+    /// `xboard`, to switch the engine to XBoard mode.
+    XBoard,
+    /// `protover <n>`, to be answered with [`XBoardResponse::Feature`].
+    Protover(u32),
+    /// `accepted <feature>`.
+    Accepted(String),
+    /// `rejected <feature>`.
+    Rejected(String),
+    /// `new`, to reset to the starting position with the engine playing
+    /// black.
+    New,
+    /// `variant <name>`. See `variant_from_name()` (with the `variant`
+    /// feature).
+    Variant(String),
+    /// `quit`.
+    Quit,
+    /// `force`, to stop the engine from playing either side.
+    Force,
+    /// `go`, to let the engine play the side to move.
+    Go,
+    /// `setboard <fen>`.
+    SetBoard(Fen),
+    /// `usermove <move>`, or just `<move>` in older versions of the
+    /// protocol.
+    UserMove(XBoardMove),
+    /// `level <mps> <base> <inc>`, to set a conventional or incremental
+    /// time control. `mps` is 0 for incremental time controls.
+    Level {
+        mps: u32,
+        base: Duration,
+        inc: Duration,
+    },
+    /// `st <seconds>`, to set a fixed time per move.
+    St(Duration),
+    /// `sd <depth>`, to limit the search depth.
+    Sd(u32),
+    /// `time <centiseconds>`, the remaining time of the engine.
+    Time(Duration),
+    /// `otim <centiseconds>`, the remaining time of the opponent.
+    Otim(Duration),
+    /// `result <result> {<comment>}`, when the game has ended.
+    /// `outcome` is `None` for `*`.
+    Result {
+        outcome: Option<Outcome>,
+        comment: Option<String>,
+    },
+    /// `ping <n>`, to be answered with [`XBoardResponse::Pong`].
+    Ping(u32),
+}
+
+impl FromStr for XBoardCommand {
+    type Err = ParseXBoardCommandError;
+
+    fn from_str(line: &str) -> Result<XBoardCommand, ParseXBoardCommandError> {
+        let (command, args) = split_token(line);
+        match command {
+            "xboard" => Some(XBoardCommand::XBoard),
+            "protover" => args.parse().ok().map(XBoardCommand::Protover),
+            "accepted" => Some(XBoardCommand::Accepted(args.into())),
+            "rejected" => Some(XBoardCommand::Rejected(args.into())),
+            "new" => Some(XBoardCommand::New),
+            "variant" if !args.is_empty() => Some(XBoardCommand::Variant(args.into())),
+            "quit" => Some(XBoardCommand::Quit),
+            "force" => Some(XBoardCommand::Force),
+            "go" => Some(XBoardCommand::Go),
+            "setboard" => args.parse().ok().map(XBoardCommand::SetBoard),
+            "usermove" => args.parse().ok().map(XBoardCommand::UserMove),
+            "level" => parse_level(args),
+            "st" => seconds(args).map(XBoardCommand::St),
+            "sd" => args.parse().ok().map(XBoardCommand::Sd),
+            "time" => centiseconds(args).map(XBoardCommand::Time),
+            "otim" => centiseconds(args).map(XBoardCommand::Otim),
+            "result" => parse_result(args)
+                .map(|(outcome, comment)| XBoardCommand::Result { outcome, comment }),
+            "ping" => args.parse().ok().map(XBoardCommand::Ping),
+            _ if args.is_empty() => command.parse().ok().map(XBoardCommand::UserMove),
+            _ => None,
+        }
+        .ok_or(ParseXBoardCommandError)
+    }
+}
+
+impl fmt::Display for XBoardCommand {
+    /// Writes the command as a single line, without the line terminator.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XBoardCommand::XBoard => f.write_str("xboard"),
+            XBoardCommand::Protover(n) => write!(f, "protover {n}"),
+            XBoardCommand::Accepted(feature) => write!(f, "accepted {feature}"),
+            XBoardCommand::Rejected(feature) => write!(f, "rejected {feature}"),
+            XBoardCommand::New => f.write_str("new"),
+            XBoardCommand::Variant(name) => write!(f, "variant {name}"),
+            XBoardCommand::Quit => f.write_str("quit"),
+            XBoardCommand::Force => f.write_str("force"),
+            XBoardCommand::Go => f.write_str("go"),
+            XBoardCommand::SetBoard(fen) => write!(f, "setboard {fen}"),
+            XBoardCommand::UserMove(m) => write!(f, "usermove {m}"),
+            XBoardCommand::Level { mps, base, inc } => {
+                let base = base.as_secs();
+                write!(f, "level {mps} {}", base / 60)?;
+                if base % 60 != 0 {
+                    write!(f, ":{:02}", base % 60)?;
+                }
+                f.write_str(" ")?;
+                write_seconds(f, *inc)
+            }
+            XBoardCommand::St(time) => {
+                f.write_str("st ")?;
+                write_seconds(f, *time)
+            }
+            XBoardCommand::Sd(depth) => write!(f, "sd {depth}"),
+            XBoardCommand::Time(time) => write!(f, "time {}", time.as_millis() / 10),
+            XBoardCommand::Otim(time) => write!(f, "otim {}", time.as_millis() / 10),
+            XBoardCommand::Result { outcome, comment } => {
+                f.write_str("result ")?;
+                write_result(f, *outcome, comment.as_deref())
+            }
+            XBoardCommand::Ping(n) => write!(f, "ping {n}"),
+        }
+    }
+}
+
+/// A response sent from the engine to the GUI.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum XBoardResponse {
+    /// `feature <name>=<value> ...`, to announce supported features.
+    Feature(Vec<Feature>),
+    /// `move <move>`.
+    Move(XBoardMove),
+    /// `pong <n>`.
+    Pong(u32),
+    /// `resign`.
+    Resign,
+    /// `<result> {<comment>}`, to claim the end of the game. `outcome` is
+    /// `None` for `*`.
+    Result {
+        outcome: Option<Outcome>,
+        comment: Option<String>,
+    },
+}
+
+impl FromStr for XBoardResponse {
+    type Err = ParseXBoardResponseError;
+
+    fn from_str(line: &str) -> Result<XBoardResponse, ParseXBoardResponseError> {
+        let (response, args) = split_token(line);
+        match response {
+            "feature" => Feature::parse_all(args).map(XBoardResponse::Feature),
+            "move" => args.parse().ok().map(XBoardResponse::Move),
+            "pong" => args.parse().ok().map(XBoardResponse::Pong),
+            "resign" => Some(XBoardResponse::Resign),
+            _ => parse_result(line.trim())
+                .map(|(outcome, comment)| XBoardResponse::Result { outcome, comment }),
+        }
+        .ok_or(ParseXBoardResponseError)
+    }
+}
+
+impl fmt::Display for XBoardResponse {
+    /// Writes the response as a single line, without the line terminator.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XBoardResponse::Feature(features) => {
+                f.write_str("feature")?;
+                for feature in features {
+                    write!(f, " {feature}")?;
+                }
+                Ok(())
+            }
+            XBoardResponse::Move(m) => write!(f, "move {m}"),
+            XBoardResponse::Pong(n) => write!(f, "pong {n}"),
+            XBoardResponse::Resign => f.write_str("resign"),
+            XBoardResponse::Result { outcome, comment } => {
+                write_result(f, *outcome, comment.as_deref())
+            }
+        }
+    }
+}
+
+/// A feature like `san=1` or `myname="Engine 1.0"`, as announced by the
+/// engine.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Feature {
+    pub name: String,
+    pub value: FeatureValue,
+}
+
+impl Feature {
+    fn parse_all(mut s: &str) -> Option<Vec<Feature>> {
+        let mut features = Vec::new();
+        loop {
+            s = s.trim_start();
+            if s.is_empty() {
+                return Some(features);
+            }
+            let (name, rest) = s.split_once('=')?;
+            let value = if let Some(quoted) = rest.strip_prefix('"') {
+                let (value, rest) = quoted.split_once('"')?;
+                s = rest;
+                FeatureValue::String(value.into())
+            } else {
+                let (value, rest) = split_token(rest);
+                s = rest;
+                match value.parse() {
+                    Ok(n) => FeatureValue::Int(n),
+                    Err(_) => FeatureValue::String(value.into()),
+                }
+            };
+            features.push(Feature {
+                name: name.into(),
+                value,
+            });
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            FeatureValue::Int(value) => write!(f, "{}={}", self.name, value),
+            FeatureValue::String(ref value) => write!(f, "{}=\"{}\"", self.name, value),
+        }
+    }
+}
+
+/// The value of a [`Feature`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum FeatureValue {
+    /// A number, also used for flags like `san=1`.
+    Int(i64),
+    /// A string like `"normal,fischerandom"`.
+    String(String),
+}
+
+/// Splits off the first token, returning it and the trimmed remainder.
+fn split_token(line: &str) -> (&str, &str) {
+    let line = line.trim();
+    match line.split_once(|ch: char| ch.is_ascii_whitespace()) {
+        Some((token, rest)) => (token, rest.trim_start()),
+        None => (line, ""),
+    }
+}
+
+fn seconds(s: &str) -> Option<Duration> {
+    Duration::try_from_secs_f64(s.parse().ok()?).ok()
+}
+
+fn centiseconds(s: &str) -> Option<Duration> {
+    // Clocks that have run out may be sent as negative times.
+    let centis: i64 = s.parse().ok()?;
+    Some(Duration::from_millis(centis.max(0) as u64 * 10))
+}
+
+fn write_seconds(f: &mut fmt::Formatter<'_>, time: Duration) -> fmt::Result {
+    if time.subsec_nanos() == 0 {
+        write!(f, "{}", time.as_secs())
+    } else {
+        write!(f, "{}", time.as_secs_f64())
+    }
+}
+
+fn parse_level(args: &str) -> Option<XBoardCommand> {
+    let mut args = args.split_ascii_whitespace();
+    let mps = args.next()?.parse().ok()?;
+    let base = args.next()?;
+    let base = match base.split_once(':') {
+        Some((minutes, secs)) => minutes.parse::<u64>().ok()? * 60 + secs.parse::<u64>().ok()?,
+        None => base.parse::<u64>().ok()? * 60,
+    };
+    let inc = seconds(args.next()?)?;
+    if args.next().is_some() {
+        return None;
+    }
+    Some(XBoardCommand::Level {
+        mps,
+        base: Duration::from_secs(base),
+        inc,
+    })
+}
+
+fn parse_result(args: &str) -> Option<(Option<Outcome>, Option<String>)> {
+    let (result, comment) = split_token(args);
+    let outcome = match result {
+        "*" => None,
+        _ => Some(result.parse().ok()?),
+    };
+    let comment = match comment {
+        "" => None,
+        _ => Some(comment.strip_prefix('{')?.strip_suffix('}')?.trim().into()),
+    };
+    Some((outcome, comment))
+}
+
+fn write_result(
+    f: &mut fmt::Formatter<'_>,
+    outcome: Option<Outcome>,
+    comment: Option<&str>,
+) -> fmt::Result {
+    match outcome {
+        Some(outcome) => f.write_str(outcome.as_str())?,
+        None => f.write_str("*")?,
+    }
+    if let Some(comment) = comment {
+        write!(f, " {{{comment}}}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString as _, vec};
+
+    use super::*;
+    use crate::{Chess, Color, Square};
+
+    #[test]
+    fn test_command_roundtrip() {
+        for line in [
+            "xboard",
+            "protover 2",
+            "accepted setboard",
+            "new",
+            "variant fischerandom",
+            "force",
+            "go",
+            "setboard rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            "usermove e7e8q",
+            "usermove Nf3",
+            "usermove O-O-O",
+            "level 40 5 0",
+            "level 0 2:30 1.5",
+            "st 30",
+            "sd 12",
+            "time 6000",
+            "otim 0",
+            "result 1-0 {White mates}",
+            "result *",
+            "ping 7",
+        ] {
+            let command: XBoardCommand = line.parse().expect("valid command");
+            assert_eq!(command.to_string(), line);
+        }
+
+        assert_eq!(
+            "level 0 2:30 1.5".parse::<XBoardCommand>().ok(),
+            Some(XBoardCommand::Level {
+                mps: 0,
+                base: Duration::from_secs(150),
+                inc: Duration::from_millis(1500),
+            })
+        );
+        assert_eq!(
+            "otim -30".parse::<XBoardCommand>().ok(),
+            Some(XBoardCommand::Otim(Duration::ZERO))
+        );
+        assert_eq!(
+            "e2e4".parse::<XBoardCommand>().ok(),
+            Some(XBoardCommand::UserMove(XBoardMove::Coordinate(
+                "e2e4".parse().expect("valid uci")
+            )))
+        );
+        assert!("level 40 5".parse::<XBoardCommand>().is_err());
+        assert!("result 1-0 White mates".parse::<XBoardCommand>().is_err());
+        assert!("bogus command".parse::<XBoardCommand>().is_err());
+    }
+
+    #[test]
+    fn test_response_roundtrip() {
+        let line = "feature ping=1 setboard=1 san=0 myname=\"Engine 1.0\" variants=\"normal,fischerandom\" done=1";
+        let response: XBoardResponse = line.parse().expect("valid response");
+        match response {
+            XBoardResponse::Feature(ref features) => {
+                assert_eq!(features.len(), 6);
+                assert_eq!(
+                    features[3],
+                    Feature {
+                        name: "myname".into(),
+                        value: FeatureValue::String("Engine 1.0".into()),
+                    }
+                );
+                assert_eq!(features[5].value, FeatureValue::Int(1));
+            }
+            _ => panic!("unexpected {response:?}"),
+        }
+        assert_eq!(response.to_string(), line);
+
+        for line in [
+            "move e2e4",
+            "move O-O",
+            "pong 7",
+            "resign",
+            "1/2-1/2 {Stalemate}",
+        ] {
+            let response: XBoardResponse = line.parse().expect("valid response");
+            assert_eq!(response.to_string(), line);
+        }
+        assert_eq!(
+            "0-1 {Black mates}".parse::<XBoardResponse>().ok(),
+            Some(XBoardResponse::Result {
+                outcome: Some(Outcome::Decisive {
+                    winner: Color::Black
+                }),
+                comment: Some("Black mates".into()),
+            })
+        );
+        assert_eq!(
+            "feature".parse::<XBoardResponse>().ok(),
+            Some(XBoardResponse::Feature(vec![]))
+        );
+        assert!("feature myname=\"unterminated"
+            .parse::<XBoardResponse>()
+            .is_err());
+        assert!("hello".parse::<XBoardResponse>().is_err());
+    }
+
+    #[test]
+    fn test_moves() {
+        let pos: Chess = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"
+            .parse::<Fen>()
+            .expect("valid fen")
+            .into_position(CastlingMode::Chess960)
+            .expect("legal position");
+
+        for (notation, to) in [
+            ("O-O", Square::H1),
+            ("e1g1", Square::H1),
+            ("e1h1", Square::H1),
+            ("e1c1", Square::A1),
+        ] {
+            let m = notation
+                .parse::<XBoardMove>()
+                .expect("valid move")
+                .to_move(&pos)
+                .expect("legal move");
+            assert_eq!(
+                m,
+                Move::Castle {
+                    king: Square::E1,
+                    rook: to
+                }
+            );
+        }
+
+        let castle = Move::Castle {
+            king: Square::E1,
+            rook: Square::A1,
+        };
+        assert_eq!(
+            XBoardMove::from_move(&castle, CastlingMode::Chess960).to_string(),
+            "O-O-O"
+        );
+        assert_eq!(
+            XBoardMove::from_move(&castle, CastlingMode::Standard).to_string(),
+            "e1c1"
+        );
+        assert_eq!(
+            XBoardMove::from_move_san(&pos, &castle).to_string(),
+            "O-O-O"
+        );
+
+        let m = "Rb1".parse::<XBoardMove>().expect("valid san");
+        assert!(matches!(m, XBoardMove::San(_)));
+        assert_eq!(
+            m.to_move(&pos)
+                .expect("legal move")
+                .to_uci(CastlingMode::Standard)
+                .to_string(),
+            "a1b1"
+        );
+        assert!("Rb2"
+            .parse::<XBoardMove>()
+            .expect("valid san")
+            .to_move(&pos)
+            .is_err());
+        assert!("e1e9".parse::<XBoardMove>().is_err());
+    }
+
+    #[cfg(feature = "variant")]
+    #[test]
+    fn test_variant_names() {
+        assert_eq!(
+            variant_from_name("fischerandom"),
+            Some((Variant::Chess, CastlingMode::Chess960))
+        );
+        assert_eq!(variant_from_name("suicide"), None);
+        for variant in Variant::ALL {
+            let name = variant_name(variant, CastlingMode::Standard).expect("named variant");
+            assert_eq!(
+                variant_from_name(name),
+                Some((variant, CastlingMode::Standard))
+            );
+        }
+        assert_eq!(variant_name(Variant::Horde, CastlingMode::Chess960), None);
+    }
+}
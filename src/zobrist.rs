@@ -6,6 +6,11 @@
 //! hash values is considered a semver breaking change and will be noted in the
 //! changelog.
 //!
+//! # Incremental updates
+//!
+//! [`ZobristHash::zobrist_hash()`] computes the hash from scratch. Wrap a
+//! position in [`Zobrist`] to keep the hash up to date as moves are played.
+//!
 //! # Warning: Forged collisions
 //!
 //! Zobrist hashes have excellent collision resistance, but can be
@@ -23,12 +28,13 @@
 use core::{
     fmt,
     hash::{Hash, Hasher},
+    num::NonZeroU32,
     ops::{BitXor, BitXorAssign},
 };
 
 use crate::{
-    Board, ByColor, ByRole, CastlingSide, Color, EnPassantMode, File, Piece, Position,
-    RemainingChecks, Role, Square,
+    attacks, Bitboard, Board, ByColor, ByRole, Castles, CastlingSide, Color, EnPassantMode, File,
+    Move, MoveList, Outcome, Piece, Position, RemainingChecks, Role, Setup, Square, Undo,
 };
 
 /// Integer type that can be returned as a Zobrist hash.
@@ -1340,27 +1346,306 @@ impl<P: Position> ZobristHash for P {
             zobrist ^= V::zobrist_for_white_turn();
         }
 
-        let castles = self.castles();
-        for color in Color::ALL {
-            for side in CastlingSide::ALL {
-                if castles.has(color, side) {
-                    zobrist ^= V::zobrist_for_castling_right(color, side);
+        zobrist ^= hash_castles(self.castles());
+
+        if let Some(sq) = self.ep_square(mode) {
+            zobrist ^= V::zobrist_for_en_passant_file(sq.file());
+        }
+
+        if let Some(remaining_checks) = self.remaining_checks() {
+            zobrist ^= hash_remaining_checks(remaining_checks);
+        }
+
+        zobrist
+    }
+}
+
+/// A position that keeps its Zobrist hash up to date as moves are played,
+/// instead of recomputing it from scratch like
+/// [`ZobristHash::zobrist_hash()`].
+///
+/// Implements [`Position`] by delegating to the wrapped position.
+///
+/// # Examples
+///
+/// ```
+/// use shakmaty::{Chess, EnPassantMode, Position, Square, Role, Move};
+/// use shakmaty::zobrist::{Zobrist, Zobrist64, ZobristHash};
+///
+/// let mut pos: Zobrist<Chess, Zobrist64> = Zobrist::new(Chess::default(), EnPassantMode::Legal);
+/// assert_eq!(pos.zobrist(), Zobrist64(0x463b96181691fc9c));
+///
+/// pos.play_unchecked(&Move::Normal {
+///     role: Role::Pawn,
+///     from: Square::E2,
+///     to: Square::E4,
+///     capture: None,
+///     promotion: None,
+/// });
+/// assert_eq!(pos.zobrist(), Zobrist64(0x823c9b50fd114196));
+/// assert_eq!(pos.zobrist(), pos.inner().zobrist_hash(EnPassantMode::Legal));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Zobrist<P, V> {
+    pos: P,
+    mode: EnPassantMode,
+    zobrist: V,
+    ep_square: Option<Square>,
+}
+
+impl<P: Position, V: ZobristValue> Zobrist<P, V> {
+    /// Wraps a position, computing its initial hash from scratch.
+    /// `mode` decides when en passant squares are included in the hash,
+    /// just like for [`ZobristHash::zobrist_hash()`].
+    pub fn new(pos: P, mode: EnPassantMode) -> Zobrist<P, V> {
+        let zobrist = pos.zobrist_hash(mode);
+        let ep_square = pos.ep_square(mode);
+        Zobrist {
+            pos,
+            mode,
+            zobrist,
+            ep_square,
+        }
+    }
+
+    /// The current hash of the position.
+    pub fn zobrist(&self) -> V {
+        self.zobrist
+    }
+
+    /// The en passant mode used for hashing.
+    pub fn mode(&self) -> EnPassantMode {
+        self.mode
+    }
+
+    /// The wrapped position.
+    pub fn inner(&self) -> &P {
+        &self.pos
+    }
+
+    /// Unwraps the position.
+    pub fn into_inner(self) -> P {
+        self.pos
+    }
+
+    /// Plays or takes back `m` with `f`, updating the hash by comparing
+    /// only the parts of the position that `m` can change.
+    fn update<R>(&mut self, m: &Move, f: impl FnOnce(&mut P) -> R) -> R {
+        let changed = changed_squares(m);
+        let mut pieces = [None; MAX_CHANGED_SQUARES];
+        for (sq, piece) in changed.into_iter().zip(&mut pieces) {
+            *piece = self.pos.board().piece_at(sq);
+        }
+        let promoted = self.pos.promoted();
+        let pockets = self.pos.pockets().copied();
+        let castles: V = hash_castles(self.pos.castles());
+        let remaining_checks = self.pos.remaining_checks().copied();
+
+        let result = f(&mut self.pos);
+
+        let pos = &self.pos;
+        let mut zobrist = self.zobrist;
+
+        for (sq, before) in changed.into_iter().zip(pieces) {
+            let after = pos.board().piece_at(sq);
+            if before != after {
+                if let Some(piece) = before {
+                    zobrist ^= V::zobrist_for_piece(sq, piece);
+                }
+                if let Some(piece) = after {
+                    zobrist ^= V::zobrist_for_piece(sq, piece);
                 }
             }
         }
 
-        if let Some(sq) = self.ep_square(mode) {
+        for sq in promoted ^ pos.promoted() {
+            zobrist ^= V::zobrist_for_promoted(sq);
+        }
+
+        if let (Some(before), Some(after)) = (pockets, pos.pockets()) {
+            for color in Color::ALL {
+                for role in Role::ALL {
+                    let (before, after) =
+                        (*before.get(color).get(role), *after.get(color).get(role));
+                    if before != after {
+                        zobrist ^= V::zobrist_for_pocket(color, role, before);
+                        zobrist ^= V::zobrist_for_pocket(color, role, after);
+                    }
+                }
+            }
+        }
+
+        zobrist ^= V::zobrist_for_white_turn();
+
+        zobrist ^= castles;
+        zobrist ^= hash_castles(pos.castles());
+
+        let ep_square = pos.ep_square(self.mode);
+        if let Some(sq) = self.ep_square {
+            zobrist ^= V::zobrist_for_en_passant_file(sq.file());
+        }
+        if let Some(sq) = ep_square {
             zobrist ^= V::zobrist_for_en_passant_file(sq.file());
         }
+        self.ep_square = ep_square;
 
-        if let Some(remaining_checks) = self.remaining_checks() {
-            for (color, remaining) in remaining_checks.as_ref().zip_color() {
-                zobrist ^= V::zobrist_for_remaining_checks(color, *remaining);
+        if let (Some(before), Some(after)) = (remaining_checks, pos.remaining_checks()) {
+            zobrist ^= hash_remaining_checks(&before);
+            zobrist ^= hash_remaining_checks(after);
+        }
+
+        self.zobrist = zobrist;
+        result
+    }
+}
+
+/// Upper bound for the number of [`changed_squares()`].
+const MAX_CHANGED_SQUARES: usize = 10;
+
+/// Squares where pieces may change when `m` is played or taken back. This
+/// includes the squares around the target square of captures, where pieces
+/// explode in Atomic chess.
+fn changed_squares(m: &Move) -> Bitboard {
+    match *m {
+        Move::Normal {
+            from,
+            capture: Some(_),
+            to,
+            ..
+        } => attacks::king_attacks(to).with(from).with(to),
+        Move::Normal { from, to, .. } => Bitboard::from(from).with(to),
+        Move::EnPassant { from, to } => attacks::king_attacks(to).with(from).with(to),
+        Move::Castle { king, rook } => {
+            let side = CastlingSide::from_queen_side(rook < king);
+            Bitboard::from(king)
+                .with(rook)
+                .with(Square::from_coords(side.king_to_file(), king.rank()))
+                .with(Square::from_coords(side.rook_to_file(), rook.rank()))
+        }
+        Move::Put { to, .. } => Bitboard::from(to),
+    }
+}
+
+impl<P: Position, V: ZobristValue + PartialEq + fmt::Debug> Position for Zobrist<P, V> {
+    fn board(&self) -> &Board {
+        self.pos.board()
+    }
+
+    fn promoted(&self) -> Bitboard {
+        self.pos.promoted()
+    }
+
+    fn pockets(&self) -> Option<&ByColor<ByRole<u8>>> {
+        self.pos.pockets()
+    }
+
+    fn turn(&self) -> Color {
+        self.pos.turn()
+    }
+
+    fn castles(&self) -> &Castles {
+        self.pos.castles()
+    }
+
+    fn maybe_ep_square(&self) -> Option<Square> {
+        self.pos.maybe_ep_square()
+    }
+
+    fn remaining_checks(&self) -> Option<&ByColor<RemainingChecks>> {
+        self.pos.remaining_checks()
+    }
+
+    fn halfmoves(&self) -> u32 {
+        self.pos.halfmoves()
+    }
+
+    fn fullmoves(&self) -> NonZeroU32 {
+        self.pos.fullmoves()
+    }
+
+    fn into_setup(self, mode: EnPassantMode) -> Setup {
+        self.pos.into_setup(mode)
+    }
+
+    fn legal_moves(&self) -> MoveList {
+        self.pos.legal_moves()
+    }
+
+    fn san_candidates(&self, role: Role, to: Square) -> MoveList {
+        self.pos.san_candidates(role, to)
+    }
+
+    fn castling_moves(&self, side: CastlingSide) -> MoveList {
+        self.pos.castling_moves(side)
+    }
+
+    fn en_passant_moves(&self) -> MoveList {
+        self.pos.en_passant_moves()
+    }
+
+    fn capture_moves(&self) -> MoveList {
+        self.pos.capture_moves()
+    }
+
+    fn promotion_moves(&self) -> MoveList {
+        self.pos.promotion_moves()
+    }
+
+    fn is_irreversible(&self, m: &Move) -> bool {
+        self.pos.is_irreversible(m)
+    }
+
+    fn king_attackers(&self, square: Square, attacker: Color, occupied: Bitboard) -> Bitboard {
+        self.pos.king_attackers(square, attacker, occupied)
+    }
+
+    fn is_variant_end(&self) -> bool {
+        self.pos.is_variant_end()
+    }
+
+    fn has_insufficient_material(&self, color: Color) -> bool {
+        self.pos.has_insufficient_material(color)
+    }
+
+    fn variant_outcome(&self) -> Option<Outcome> {
+        self.pos.variant_outcome()
+    }
+
+    fn play_unchecked(&mut self, m: &Move) {
+        self.update(m, |pos| pos.play_unchecked(m));
+        debug_assert_eq!(self.zobrist, self.pos.zobrist_hash(self.mode));
+    }
+
+    fn play_with_undo(&mut self, m: &Move) -> Undo {
+        let undo = self.update(m, |pos| pos.play_with_undo(m));
+        debug_assert_eq!(self.zobrist, self.pos.zobrist_hash(self.mode));
+        undo
+    }
+
+    fn undo(&mut self, m: &Move, undo: Undo) {
+        self.update(m, |pos| pos.undo(m, undo));
+        debug_assert_eq!(self.zobrist, self.pos.zobrist_hash(self.mode));
+    }
+}
+
+fn hash_castles<V: ZobristValue>(castles: &Castles) -> V {
+    let mut zobrist = V::default();
+    for color in Color::ALL {
+        for side in CastlingSide::ALL {
+            if castles.has(color, side) {
+                zobrist ^= V::zobrist_for_castling_right(color, side);
             }
         }
+    }
+    zobrist
+}
 
-        zobrist
+fn hash_remaining_checks<V: ZobristValue>(remaining_checks: &ByColor<RemainingChecks>) -> V {
+    let mut zobrist = V::default();
+    for (color, remaining) in remaining_checks.as_ref().zip_color() {
+        zobrist ^= V::zobrist_for_remaining_checks(color, *remaining);
     }
+    zobrist
 }
 
 fn hash_board<V: ZobristValue>(board: &Board) -> V {
@@ -1380,7 +1665,7 @@ fn hash_board<V: ZobristValue>(board: &Board) -> V {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{fen::Fen, CastlingMode, Chess};
+    use crate::{fen::Fen, CastlingMode, Chess, FromSetup};
 
     #[test]
     fn test_polyglot() {
@@ -1453,6 +1738,79 @@ mod tests {
         assert_eq!(chess, king_of_the_hill);
     }
 
+    fn assert_incremental<P: Position + Clone>(pos: &mut Zobrist<P, Zobrist64>, depth: u32) {
+        assert_eq!(pos.zobrist(), pos.inner().zobrist_hash(pos.mode()));
+        if depth == 0 {
+            return;
+        }
+        for m in pos.legal_moves() {
+            let before = pos.zobrist();
+            let undo = pos.play_with_undo(&m);
+            assert_incremental(pos, depth - 1);
+            pos.undo(&m, undo);
+            assert_eq!(pos.zobrist(), before, "undo {m:?}");
+
+            let mut child = pos.clone();
+            child.play_unchecked(&m);
+            assert_eq!(child.zobrist(), child.inner().zobrist_hash(child.mode()));
+        }
+    }
+
+    fn assert_incremental_fen<P: Position + FromSetup + Clone>(fen: &str, depth: u32) {
+        let pos: P = fen
+            .parse::<Fen>()
+            .expect("valid fen")
+            .into_position(CastlingMode::Chess960)
+            .expect("legal position");
+        for mode in [
+            EnPassantMode::Legal,
+            EnPassantMode::PseudoLegal,
+            EnPassantMode::Always,
+        ] {
+            assert_incremental(&mut Zobrist::new(pos.clone(), mode), depth);
+        }
+    }
+
+    #[test]
+    fn test_incremental() {
+        assert_incremental_fen::<Chess>(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            2,
+        );
+        assert_incremental_fen::<Chess>("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 2);
+        assert_incremental_fen::<Chess>(
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            2,
+        );
+        assert_incremental_fen::<Chess>(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            2,
+        );
+    }
+
+    #[cfg(feature = "variant")]
+    #[test]
+    fn test_incremental_variants() {
+        use crate::variant::*;
+
+        assert_incremental_fen::<Atomic>(
+            "rnbqkb1r/pp1ppppp/5n2/2p5/4P3/2N5/PPPP1PPP/R1BQKBNR w KQkq c6 0 3",
+            2,
+        );
+        assert_incremental_fen::<Atomic>("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", 2);
+        assert_incremental_fen::<ThreeCheck>(
+            "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 1+2 2 3",
+            2,
+        );
+        assert_incremental_fen::<Crazyhouse>("2k1r3/4Q~3/8/8/8/8/8/4K3[Nr] b - - 0 1", 2);
+        assert_incremental_fen::<Crazyhouse>("4k3/1P6/8/8/8/8/8/4K3[] w - - 0 1", 3);
+        assert_incremental_fen::<Horde>(
+            "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1",
+            2,
+        );
+        assert_incremental_fen::<RacingKings>("8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1", 2);
+    }
+
     #[test]
     fn test_full_pockets() {
         // 8/8/8/7k/8/8/3K4/8[ppppppppppppppppnnnnbbbbrrrrqq] w - - 0 54
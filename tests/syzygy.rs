@@ -0,0 +1,137 @@
+//! Tests against real Syzygy tables. Put the 3-piece tables (`KQvK.rtbw`,
+//! `KQvK.rtbz`, `KRvK.rtbw`, `KRvK.rtbz`) into `tables/chess` and run
+//! `cargo test --test syzygy -- --ignored`.
+
+#![cfg(feature = "std")]
+
+use shakmaty::{
+    syzygy::{Dtz, Tablebase, Wdl},
+    Board, CastlingMode, Chess, Color, Piece, Position, Setup, Square,
+};
+
+fn tables() -> Tablebase<Chess> {
+    let mut tables = Tablebase::new();
+    assert_eq!(tables.add_directory("tables/chess").expect("read dir"), 4);
+    tables
+}
+
+/// Positions with a white king, a white `piece` and a black king, with the
+/// white king in the a1-d1-d4 triangle.
+fn positions(piece: Piece) -> impl Iterator<Item = Chess> {
+    let triangle = [
+        Square::A1,
+        Square::B1,
+        Square::C1,
+        Square::D1,
+        Square::B2,
+        Square::C2,
+        Square::D2,
+        Square::C3,
+        Square::D3,
+        Square::D4,
+    ];
+    triangle.into_iter().flat_map(move |white_king| {
+        Square::ALL.into_iter().flat_map(move |sq| {
+            Square::ALL.into_iter().flat_map(move |black_king| {
+                Color::ALL.into_iter().filter_map(move |turn| {
+                    let mut board = Board::empty();
+                    board.set_piece_at(white_king, Color::White.king());
+                    if board.piece_at(sq).is_some() {
+                        return None;
+                    }
+                    board.set_piece_at(sq, piece);
+                    if board.piece_at(black_king).is_some() {
+                        return None;
+                    }
+                    board.set_piece_at(black_king, Color::Black.king());
+                    let mut setup = Setup::empty();
+                    setup.board = board;
+                    setup.turn = turn;
+                    setup.position(CastlingMode::Standard).ok()
+                })
+            })
+        })
+    })
+}
+
+/// Checks the probed values of all positions against the values after
+/// each legal move, and returns the longest win.
+fn check_consistency(tables: &Tablebase<Chess>, piece: Piece) -> i32 {
+    let mut longest_win = 0;
+    for pos in positions(piece) {
+        let wdl = tables.probe_wdl_after_zeroing(&pos).expect("probe wdl");
+        let Dtz(dtz) = tables.probe_dtz(&pos).expect("probe dtz");
+
+        if pos.is_checkmate() {
+            assert_eq!((wdl, dtz), (Wdl::Loss, -1), "{pos:?}");
+            continue;
+        }
+        if pos.is_stalemate() {
+            assert_eq!((wdl, dtz), (Wdl::Draw, 0), "{pos:?}");
+            continue;
+        }
+
+        let mut best = Wdl::Loss;
+        let mut fastest_win = None;
+        let mut slowest_loss = 0;
+        for m in &pos.legal_moves() {
+            let after = pos.clone().play(m).expect("legal move");
+            if m.is_capture() {
+                // Only the bare kings are left.
+                best = best.max(Wdl::Draw);
+                continue;
+            }
+            let value = -tables.probe_wdl_after_zeroing(&after).expect("probe wdl");
+            best = best.max(value);
+            let plies = if after.is_checkmate() {
+                1
+            } else {
+                1 + tables.probe_dtz(&after).expect("probe dtz").0.abs()
+            };
+            if value == Wdl::Win {
+                fastest_win = Some(fastest_win.map_or(plies, |fastest: i32| fastest.min(plies)));
+            } else if value == Wdl::Loss {
+                slowest_loss = slowest_loss.max(plies);
+            }
+        }
+        assert_eq!(wdl, best, "{pos:?}");
+
+        // Tables may store distances in full moves, so allow one ply of
+        // rounding.
+        match wdl {
+            Wdl::Win => {
+                let expected = fastest_win.expect("winning move");
+                assert!(
+                    dtz > 0 && dtz.abs_diff(expected) <= 1,
+                    "{pos:?}: {dtz} vs {expected}"
+                );
+                longest_win = longest_win.max(dtz);
+            }
+            Wdl::Loss => {
+                assert!(
+                    dtz < 0 && dtz.abs_diff(-slowest_loss) <= 1,
+                    "{pos:?}: {dtz} vs {}",
+                    -slowest_loss
+                );
+            }
+            _ => assert_eq!(dtz, 0, "{pos:?}"),
+        }
+    }
+    longest_win
+}
+
+#[test]
+#[ignore = "requires tables in tables/chess"]
+fn test_kqvk() {
+    // The longest win is a mate in 10.
+    let longest_win = check_consistency(&tables(), Color::White.queen());
+    assert!((19..=20).contains(&longest_win), "{longest_win}");
+}
+
+#[test]
+#[ignore = "requires tables in tables/chess"]
+fn test_krvk() {
+    // The longest win is a mate in 16.
+    let longest_win = check_consistency(&tables(), Color::White.rook());
+    assert!((31..=32).contains(&longest_win), "{longest_win}");
+}